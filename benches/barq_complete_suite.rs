@@ -64,9 +64,11 @@ fn barq_agentic_workload(c: &mut Criterion) {
 
                 // 2. Create 3000 edges
                 let edges = generate_scale_free_edges(1000, 3);
-                for (from, to) in edges {
-                    let _ = db.add_edge(from, to, "connects");
-                }
+                let edges: Vec<_> = edges
+                    .into_iter()
+                    .map(|(from, to)| (from, to, "connects"))
+                    .collect();
+                db.add_edges(&edges).unwrap();
 
                 // 3. Perform 10 hybrid queries
                 for _ in 0..10 {
@@ -171,9 +173,11 @@ fn barq_read_throughput(c: &mut Criterion) {
     }
 
     let edges = generate_scale_free_edges(10000, 3);
-    for (from, to) in edges {
-        let _ = db.add_edge(from, to, "connects");
-    }
+    let edges: Vec<_> = edges
+        .into_iter()
+        .map(|(from, to)| (from, to, "connects"))
+        .collect();
+    db.add_edges(&edges).unwrap();
 
     group.bench_function("mixed_reads_10k", |b| {
         b.iter(|| {
@@ -29,9 +29,11 @@ fn benchmark_bfs_traversal(c: &mut Criterion) {
                         }
 
                         let edges_data = generate_scale_free_edges(n, e / n);
-                        for (from, to) in edges_data {
-                            let _ = db.add_edge(from, to, "connects");
-                        }
+                        let edges_data: Vec<_> = edges_data
+                            .into_iter()
+                            .map(|(from, to)| (from, to, "connects"))
+                            .collect();
+                        db.add_edges(&edges_data).unwrap();
                         (dir, db)
                     },
                     |(_, db)| {
@@ -62,9 +64,11 @@ fn benchmark_neighbors(c: &mut Criterion) {
             }
 
             let edges = generate_scale_free_edges(n, 5);
-            for (from, to) in edges {
-                let _ = db.add_edge(from, to, "connects");
-            }
+            let edges: Vec<_> = edges
+                .into_iter()
+                .map(|(from, to)| (from, to, "connects"))
+                .collect();
+            db.add_edges(&edges).unwrap();
 
             b.iter(|| {
                 let id = rand::random::<u64>() % n as u64;
@@ -29,9 +29,11 @@ fn benchmark_hybrid_query(c: &mut Criterion) {
             }
 
             let edges = generate_scale_free_edges(n, 3);
-            for (from, to) in edges {
-                let _ = db.add_edge(from, to, "connects");
-            }
+            let edges: Vec<_> = edges
+                .into_iter()
+                .map(|(from, to)| (from, to, "connects"))
+                .collect();
+            db.add_edges(&edges).unwrap();
 
             let params = HybridParams::new(0.7, 0.3);
 
@@ -63,9 +65,11 @@ fn benchmark_hybrid_varying_hops(c: &mut Criterion) {
     }
 
     let edges = generate_scale_free_edges(3000, 3);
-    for (from, to) in edges {
-        let _ = db.add_edge(from, to, "connects");
-    }
+    let edges: Vec<_> = edges
+        .into_iter()
+        .map(|(from, to)| (from, to, "connects"))
+        .collect();
+    db.add_edges(&edges).unwrap();
 
     for hops in [1, 2, 3, 4].iter() {
         group.bench_with_input(BenchmarkId::from_parameter(hops), hops, |b, &h| {
@@ -98,9 +102,11 @@ fn benchmark_hybrid_params(c: &mut Criterion) {
     }
 
     let edges = generate_scale_free_edges(2000, 3);
-    for (from, to) in edges {
-        let _ = db.add_edge(from, to, "connects");
-    }
+    let edges: Vec<_> = edges
+        .into_iter()
+        .map(|(from, to)| (from, to, "connects"))
+        .collect();
+    db.add_edges(&edges).unwrap();
 
     for (alpha, beta) in [(1.0, 0.0), (0.7, 0.3), (0.5, 0.5), (0.3, 0.7), (0.0, 1.0)].iter() {
         group.bench_with_input(
@@ -136,9 +142,11 @@ fn benchmark_hybrid_varying_k(c: &mut Criterion) {
     }
 
     let edges = generate_scale_free_edges(2000, 3);
-    for (from, to) in edges {
-        let _ = db.add_edge(from, to, "connects");
-    }
+    let edges: Vec<_> = edges
+        .into_iter()
+        .map(|(from, to)| (from, to, "connects"))
+        .collect();
+    db.add_edges(&edges).unwrap();
 
     for k in [5, 10, 25, 50].iter() {
         group.bench_with_input(BenchmarkId::from_parameter(k), k, |b, &k_val| {
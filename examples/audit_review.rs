@@ -0,0 +1,93 @@
+//! Audit review: agents record decisions against a policy graph, then a
+//! reviewer replays the trail after a restart and flags weak decisions.
+//!
+//! Run with `cargo run --example audit_review [db_path]`; without a path
+//! the database lives in a temporary directory.
+
+mod common;
+
+use anyhow::ensure;
+use barq_graphdb::agent::DecisionRecord;
+use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+use barq_graphdb::{Node, NodeId};
+
+/// Decisions scoring below this are sent for manual review.
+const REVIEW_THRESHOLD: f32 = 0.5;
+
+fn add_node(db: &mut BarqGraphDb, label: &str, tag: &str) -> anyhow::Result<NodeId> {
+    let id = db.allocate_node_id()?;
+    let mut node = Node::new(id, label.to_string());
+    node.embedding = common::embed(label);
+    node.rule_tags = vec![tag.to_string()];
+    db.append_node(node)?;
+    Ok(id)
+}
+
+fn main() -> anyhow::Result<()> {
+    let (mut db, _dir) = common::open_db()?;
+
+    // Requests link to the cases they cite, cases to the policy that governs them
+    let refunds = add_node(&mut db, "Refunds over 500 need manager approval", "policy")?;
+    let privacy = add_node(
+        &mut db,
+        "Customer data is never shared with vendors",
+        "policy",
+    )?;
+    let case_a = add_node(&mut db, "Refund of 800 approved by manager", "case")?;
+    let case_b = add_node(&mut db, "Vendor asked for customer data export", "case")?;
+    let request = add_node(&mut db, "Customer requests refund of 650", "request")?;
+    db.add_edge(case_a, refunds, "GOVERNED_BY")?;
+    db.add_edge(case_b, privacy, "GOVERNED_BY")?;
+    db.add_edge(request, case_a, "CITES")?;
+
+    // Two agents act on the request; the second skips the policy
+    db.record_decision(
+        DecisionRecord::new(1, 1, request, vec![request, case_a, refunds], 0.92)
+            .with_notes("escalated refund to manager".to_string()),
+    )?;
+    db.record_decision(
+        DecisionRecord::new(2, 2, request, vec![request], 0.31)
+            .with_notes("approved refund directly".to_string()),
+    )?;
+    db.checkpoint()?;
+    let path = db.path().clone();
+    drop(db);
+
+    // Review: reopen from disk and check the trail end to end
+    let db = BarqGraphDb::open(DbOptions::new(path))?;
+    let report = db.verify()?;
+    ensure!(report.is_ok(), "audit trail failed verification");
+    println!(
+        "verified {} records: {} nodes, {} decisions",
+        report.records, report.nodes, report.decisions
+    );
+
+    let mut flagged = 0;
+    for decision in db.list_all_decisions() {
+        let cited_policy = decision.path.iter().any(|id| {
+            db.get_node(*id)
+                .is_some_and(|n| n.rule_tags.iter().any(|t| t == "policy"))
+        });
+        let needs_review = decision.score < REVIEW_THRESHOLD || !cited_policy;
+        flagged += needs_review as usize;
+        println!(
+            "decision {} by agent {}: score {:.2}, policy cited: {}{}",
+            decision.id,
+            decision.agent_id,
+            decision.score,
+            cited_policy,
+            if needs_review { "  -> REVIEW" } else { "" }
+        );
+    }
+    ensure!(flagged == 1, "expected exactly one flagged decision");
+
+    // Show the reviewer which policy the flagged request should have followed
+    let query = common::embed("Customer requests refund of 650");
+    let chains = db.evidence_chains(&query, "policy", 3, 3);
+    ensure!(!chains.is_empty(), "no evidence chain reaches a policy");
+    let target = db
+        .get_node(chains[0].target())
+        .map_or("?", |n| n.label.as_str());
+    println!("\nsupporting policy: {}", target);
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Code graph: index functions and their calls, then answer the
+//! questions a coding agent asks before touching a function.
+//!
+//! Run with `cargo run --example code_graph [db_path]`; without a path
+//! the database lives in a temporary directory.
+
+mod common;
+
+use std::collections::HashMap;
+
+use anyhow::ensure;
+use barq_graphdb::agent::DecisionRecord;
+use barq_graphdb::hybrid::HybridParams;
+use barq_graphdb::storage::Connectivity;
+use barq_graphdb::{Node, NodeId};
+
+/// Function name, doc summary and callees.
+const FUNCTIONS: &[(&str, &str, &[&str])] = &[
+    (
+        "main",
+        "entry point that loads config and starts the server",
+        &["load_config", "start_server"],
+    ),
+    (
+        "load_config",
+        "read and parse the config file from disk",
+        &["read_file", "parse_toml"],
+    ),
+    (
+        "start_server",
+        "bind the listener and serve requests",
+        &["handle_request"],
+    ),
+    (
+        "handle_request",
+        "route a request and write the response",
+        &["parse_toml"],
+    ),
+    ("read_file", "read a file from disk into a string", &[]),
+    ("parse_toml", "parse toml text into a config value", &[]),
+    (
+        "legacy_export",
+        "old csv export no longer called",
+        &["read_file_v1"],
+    ),
+    ("read_file_v1", "deprecated file reader", &[]),
+];
+
+fn main() -> anyhow::Result<()> {
+    let (mut db, _dir) = common::open_db()?;
+
+    let mut ids: HashMap<&str, NodeId> = HashMap::new();
+    for (name, doc, _) in FUNCTIONS {
+        let id = db.allocate_node_id()?;
+        let mut node = Node::new(id, name.to_string());
+        node.embedding = common::embed(&format!("{} {}", name.replace('_', " "), doc));
+        node.rule_tags = vec!["function".to_string()];
+        db.append_node(node)?;
+        ids.insert(name, id);
+    }
+    for (name, _, callees) in FUNCTIONS {
+        for callee in *callees {
+            db.add_edge(ids[name], ids[callee], "CALLS")?;
+        }
+    }
+    let name_of = |id: NodeId| db.get_node(id).map_or("?".to_string(), |n| n.label.clone());
+
+    // Impact analysis: everything that transitively calls parse_toml
+    let mut callers: Vec<String> = db
+        .reverse_bfs_hops(ids["parse_toml"], 5)
+        .into_iter()
+        .filter(|&id| id != ids["parse_toml"])
+        .map(name_of)
+        .collect();
+    callers.sort();
+    println!("changing parse_toml affects: {}", callers.join(", "));
+    ensure!(callers.contains(&"main".to_string()));
+
+    // Dead code: weak components other than the one holding main
+    let components = db.connected_components(Connectivity::Weak);
+    let main_label = components.label(ids["main"]);
+    let dead: Vec<String> = (0..components.count())
+        .filter(|&label| Some(label) != main_label)
+        .flat_map(|label| components.members(label))
+        .map(name_of)
+        .collect();
+    println!("unreachable from main: {}", dead.join(", "));
+    ensure!(dead.len() == 2);
+
+    // Find where to make a change: search near main for config parsing
+    let query = common::embed("parse config file");
+    let results = db.hybrid_query(&query, ids["main"], 3, 3, HybridParams::new(0.6, 0.4));
+    ensure!(!results.is_empty(), "hybrid query returned no functions");
+    println!("\nbest places to change config parsing:");
+    for result in &results {
+        let path: Vec<String> = result.path.iter().map(|&id| name_of(id)).collect();
+        println!("  [{:.3}] {}", result.score, path.join(" -> "));
+    }
+
+    let best = &results[0];
+    let notes = format!("edit {}", name_of(best.id));
+    db.record_decision(
+        DecisionRecord::new(1, 3, ids["main"], best.path.clone(), best.score).with_notes(notes),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! Helpers shared by the examples.
+
+// Each example uses a different subset
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+use barq_graphdb::NodeId;
+
+/// Dimension of the toy embeddings.
+pub const DIMS: usize = 16;
+
+/// Opens the database at the first command-line argument, or in a fresh
+/// temporary directory that is removed when the returned guard drops.
+pub fn open_db() -> anyhow::Result<(BarqGraphDb, Option<tempfile::TempDir>)> {
+    match std::env::args().nth(1) {
+        Some(path) => Ok((
+            BarqGraphDb::open(DbOptions::new(PathBuf::from(path)))?,
+            None,
+        )),
+        None => {
+            let dir = tempfile::tempdir()?;
+            let db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf()))?;
+            Ok((db, Some(dir)))
+        }
+    }
+}
+
+/// Embeds text as a normalized bag of hashed words.
+///
+/// Stands in for a real embedding model so the examples run offline;
+/// texts sharing words land close together.
+pub fn embed(text: &str) -> Vec<f32> {
+    let mut vec = [0.0; DIMS];
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+    {
+        let hash = word
+            .to_lowercase()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+                (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+            });
+        vec[(hash % DIMS as u64) as usize] += 1.0;
+    }
+    let norm = vec
+        .iter()
+        .map(|x| x * x)
+        .sum::<f32>()
+        .sqrt()
+        .max(f32::EPSILON);
+    vec.iter().map(|x| x / norm).collect()
+}
+
+/// Links every embedded node to its `k` nearest neighbors with
+/// `SIMILAR` edges weighted by vector distance.
+///
+/// Returns the number of edges added.
+pub fn build_similarity_edges(db: &mut BarqGraphDb, k: usize) -> anyhow::Result<usize> {
+    let mut ids: Vec<NodeId> = db.list_nodes().iter().map(|n| n.id).collect();
+    ids.sort_unstable();
+    let mut added = 0;
+    for id in ids {
+        let Some(embedding) = db.get_embedding(id).map(<[f32]>::to_vec) else {
+            continue;
+        };
+        for (neighbor, distance) in db.knn_search(&embedding, k + 1) {
+            if neighbor != id {
+                db.add_weighted_edge(id, neighbor, "SIMILAR", distance.max(0.01))?;
+                added += 1;
+            }
+        }
+    }
+    Ok(added)
+}
@@ -0,0 +1,92 @@
+//! Retrieval-augmented agent: ingest documents, link similar chunks,
+//! answer a question with a hybrid query and record the decision.
+//!
+//! Run with `cargo run --example rag_agent [db_path]`; without a path
+//! the database lives in a temporary directory.
+
+mod common;
+
+use anyhow::ensure;
+use barq_graphdb::agent::DecisionRecord;
+use barq_graphdb::hybrid::HybridParams;
+use barq_graphdb::Node;
+
+const AGENT_ID: u64 = 7;
+
+const DOCUMENTS: &[(&str, &[&str])] = &[
+    (
+        "Deployment guide",
+        &[
+            "Deploy the server behind a reverse proxy with TLS enabled.",
+            "Set the checkpoint interval so the WAL stays small between restarts.",
+            "Back up the snapshot and WAL files together for a consistent restore.",
+        ],
+    ),
+    (
+        "Query tuning",
+        &[
+            "Hybrid queries blend vector distance with graph hops from a start node.",
+            "Raise alpha to favor vector similarity over graph proximity.",
+            "Limit max hops to keep hybrid queries fast on dense graphs.",
+        ],
+    ),
+    (
+        "Agent memory",
+        &[
+            "Agents record decisions with the path of nodes they visited.",
+            "Decision paths let reviewers audit why an agent chose an answer.",
+        ],
+    ),
+];
+
+fn main() -> anyhow::Result<()> {
+    let (mut db, _dir) = common::open_db()?;
+
+    // Ingest: one node per document, one embedded node per chunk
+    let mut chunks = 0;
+    for (title, paragraphs) in DOCUMENTS {
+        let doc_id = db.allocate_node_id()?;
+        let mut doc = Node::new(doc_id, title.to_string());
+        doc.rule_tags = vec!["document".to_string()];
+        db.append_node(doc)?;
+        for text in *paragraphs {
+            let id = db.allocate_node_id()?;
+            let mut chunk = Node::new(id, text.to_string());
+            chunk.embedding = common::embed(text);
+            chunk.rule_tags = vec!["chunk".to_string()];
+            db.append_node(chunk)?;
+            db.add_edge(doc_id, id, "CONTAINS")?;
+            db.add_edge(id, doc_id, "PART_OF")?;
+            chunks += 1;
+        }
+    }
+    let similar = common::build_similarity_edges(&mut db, 2)?;
+    println!(
+        "ingested {} documents, {} chunks, {} similarity edges",
+        DOCUMENTS.len(),
+        chunks,
+        similar
+    );
+
+    // Retrieve: seed on the nearest chunk, then expand through the graph
+    let question = "How do I make hybrid queries faster?";
+    let query = common::embed(question);
+    let (seed, _) = db.knn_search(&query, 1)[0];
+    let results = db.hybrid_query(&query, seed, 3, 3, HybridParams::new(0.7, 0.3));
+    ensure!(!results.is_empty(), "hybrid query returned no context");
+
+    println!("\nQ: {}", question);
+    for result in &results {
+        let label = db.get_node(result.id).map_or("?", |n| n.label.as_str());
+        println!("  [{:.3}] {}", result.score, label);
+    }
+
+    // Record which context the answer was grounded in
+    let path: Vec<_> = results.iter().map(|r| r.id).collect();
+    let decision = DecisionRecord::new(1, AGENT_ID, seed, path, results[0].score)
+        .with_notes(format!("answered: {}", question));
+    db.record_decision(decision)?;
+    ensure!(db.list_decisions_for_agent(AGENT_ID).len() == 1);
+    println!("\nrecorded decision for agent {}", AGENT_ID);
+    Ok(())
+}
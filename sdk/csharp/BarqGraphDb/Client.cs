@@ -10,9 +10,22 @@ public class BarqException : Exception
 {
     public int StatusCode { get; }
 
-    public BarqException(string message, int statusCode = 0) : base(message)
+    /// <summary>
+    /// Machine-readable error code such as "node_not_found".
+    /// </summary>
+    public string ErrorCode { get; }
+
+    /// <summary>
+    /// Whether the request may succeed if retried with backoff.
+    /// </summary>
+    public bool Retryable { get; }
+
+    public BarqException(string message, int statusCode = 0, string errorCode = "", bool retryable = false)
+        : base(message)
     {
         StatusCode = statusCode;
+        ErrorCode = errorCode;
+        Retryable = retryable;
     }
 }
 
@@ -72,7 +85,19 @@ public class BarqClient : IDisposable
         if (!response.IsSuccessStatusCode)
         {
             var content = await response.Content.ReadAsStringAsync();
-            throw new BarqException(content, (int)response.StatusCode);
+            try
+            {
+                using var doc = JsonDocument.Parse(content);
+                var root = doc.RootElement;
+                var message = root.TryGetProperty("error", out var e) ? e.GetString() ?? content : content;
+                var code = root.TryGetProperty("error_code", out var c) ? c.GetString() ?? "" : "";
+                var retryable = root.TryGetProperty("retryable", out var r) && r.ValueKind == JsonValueKind.True;
+                throw new BarqException(message, (int)response.StatusCode, code, retryable);
+            }
+            catch (JsonException)
+            {
+                throw new BarqException(content, (int)response.StatusCode);
+            }
         }
     }
 
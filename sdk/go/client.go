@@ -103,6 +103,10 @@ type HealthResponse struct {
 type Error struct {
 	Message    string `json:"error"`
 	StatusCode int    `json:"code"`
+	// ErrorCode is a machine-readable code such as "node_not_found".
+	ErrorCode string `json:"error_code"`
+	// Retryable reports whether the request may succeed if retried with backoff.
+	Retryable bool `json:"retryable"`
 }
 
 func (e *Error) Error() string {
@@ -22,11 +22,17 @@ import type {
  */
 export class BarqError extends Error {
     public readonly statusCode: number;
+    /** Machine-readable code such as `node_not_found`. */
+    public readonly errorCode: string;
+    /** Whether the request may succeed if retried with backoff. */
+    public readonly retryable: boolean;
 
-    constructor(message: string, statusCode: number = 0) {
+    constructor(message: string, statusCode: number = 0, errorCode: string = '', retryable: boolean = false) {
         super(message);
         this.name = 'BarqError';
         this.statusCode = statusCode;
+        this.errorCode = errorCode;
+        this.retryable = retryable;
     }
 }
 
@@ -86,13 +92,17 @@ export class BarqClient {
             if (!response.ok) {
                 const text = await response.text();
                 let message = text;
+                let errorCode = '';
+                let retryable = false;
                 try {
                     const json = JSON.parse(text);
                     message = json.error || text;
+                    errorCode = json.error_code || '';
+                    retryable = json.retryable === true;
                 } catch {
                     // Use text as-is
                 }
-                throw new BarqError(message, response.status);
+                throw new BarqError(message, response.status, errorCode, retryable);
             }
 
             return (await response.json()) as T;
@@ -102,7 +112,7 @@ export class BarqClient {
                 throw error;
             }
             if (error instanceof Error && error.name === 'AbortError') {
-                throw new BarqError('Request timeout');
+                throw new BarqError('Request timeout', 0, 'timeout', true);
             }
             throw new BarqError(`Request failed: ${error}`);
         }
@@ -21,9 +21,17 @@ except ImportError:
 class BarqError(Exception):
     """Exception raised for Barq-GraphDB API errors."""
     
-    def __init__(self, message: str, status_code: int = 0):
+    def __init__(
+        self,
+        message: str,
+        status_code: int = 0,
+        error_code: str = "",
+        retryable: bool = False,
+    ):
         self.message = message
         self.status_code = status_code
+        self.error_code = error_code
+        self.retryable = retryable
         super().__init__(self.message)
 
 
@@ -80,11 +88,16 @@ class BarqClient:
         except requests.exceptions.HTTPError as e:
             try:
                 error_data = e.response.json()
-                raise BarqError(error_data.get("error", str(e)), e.response.status_code)
+                raise BarqError(
+                    error_data.get("error", str(e)),
+                    e.response.status_code,
+                    error_data.get("error_code", ""),
+                    error_data.get("retryable", False),
+                )
             except ValueError:
                 raise BarqError(str(e), e.response.status_code if e.response else 0)
         except requests.exceptions.RequestException as e:
-            raise BarqError(f"Connection error: {e}")
+            raise BarqError(f"Connection error: {e}", retryable=True)
 
     def health(self) -> dict:
         """
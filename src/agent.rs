@@ -5,8 +5,19 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::redaction::DEFAULT_MASK;
 use crate::NodeId;
 
+/// Edge type from a decision node to a node it passed through.
+pub const VISITED: &str = "VISITED";
+
+/// Edge type from a decision node to the node where it was made (the
+/// last node of its path).
+pub const DECIDED_AT: &str = "DECIDED_AT";
+
+/// Rule tag carried by decision nodes.
+pub const DECISION_TAG: &str = "decision";
+
 /// A record of an agent's decision, including the reasoning path.
 ///
 /// Decision records capture the path an agent took through the graph,
@@ -27,6 +38,76 @@ pub struct DecisionRecord {
     pub score: f32,
     /// Optional human-readable notes about the decision.
     pub notes: Option<String>,
+    /// Node materializing this decision in the graph, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<NodeId>,
+    /// Agent run this decision belongs to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Decision this one was taken under (e.g. the plan of a subtask).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_decision_id: Option<u64>,
+    /// Embedding of the decision's goal or notes, for semantic search.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embedding: Vec<f32>,
+    /// Categories of the decision (e.g. "tool_call", "retrieval", "plan").
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Reasoning behind each node of the path, if recorded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<ReasoningStep>,
+    /// Model tokens the decision consumed, if recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<u64>,
+    /// Time the decision took in milliseconds, if recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// What the decision cost (in the operator's currency), if recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+/// One step of a decision's reasoning: what the agent did at a node,
+/// why, and how confident it was.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReasoningStep {
+    /// Node the step was taken at.
+    pub node_id: NodeId,
+    /// What the agent did there (e.g. "retrieve", "compare", "select").
+    pub action: String,
+    /// Why the agent took this step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rationale: Option<String>,
+    /// Confidence in this step.
+    pub score: f32,
+}
+
+impl ReasoningStep {
+    /// Creates a step without a rationale.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_id` - Node the step was taken at
+    /// * `action` - What the agent did there
+    /// * `score` - Confidence in the step
+    pub fn new(node_id: NodeId, action: impl Into<String>, score: f32) -> Self {
+        Self {
+            node_id,
+            action: action.into(),
+            rationale: None,
+            score,
+        }
+    }
+
+    /// Explains the step.
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn with_rationale(mut self, rationale: impl Into<String>) -> Self {
+        self.rationale = Some(rationale.into());
+        self
+    }
 }
 
 impl DecisionRecord {
@@ -57,6 +138,15 @@ impl DecisionRecord {
             path,
             score,
             notes: None,
+            node_id: None,
+            session_id: None,
+            parent_decision_id: None,
+            embedding: Vec::new(),
+            tags: Vec::new(),
+            steps: Vec::new(),
+            tokens: None,
+            latency_ms: None,
+            cost: None,
         }
     }
 
@@ -90,6 +180,15 @@ impl DecisionRecord {
             path,
             score,
             notes: None,
+            node_id: None,
+            session_id: None,
+            parent_decision_id: None,
+            embedding: Vec::new(),
+            tags: Vec::new(),
+            steps: Vec::new(),
+            tokens: None,
+            latency_ms: None,
+            cost: None,
         }
     }
 
@@ -106,6 +205,153 @@ impl DecisionRecord {
         self.notes = Some(notes);
         self
     }
+
+    /// Assigns the decision to an agent run.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - Identifier shared by the decisions of one run
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Places the decision under a parent decision, so hierarchical
+    /// plans (plan, subtask, action) form a tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id` - ID of the parent decision
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn with_parent(mut self, parent_id: u64) -> Self {
+        self.parent_decision_id = Some(parent_id);
+        self
+    }
+
+    /// Attaches an embedding of the decision's goal or notes, so it can
+    /// be found by `BarqGraphDb::search_decisions`.
+    ///
+    /// # Arguments
+    ///
+    /// * `embedding` - Vector in the embedding space queries will use
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn with_embedding(mut self, embedding: Vec<f32>) -> Self {
+        self.embedding = embedding;
+        self
+    }
+
+    /// Categorizes the decision, so audits can list decisions of one
+    /// kind with `BarqGraphDb::list_decisions_with_tag`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tags` - Categories such as "tool_call", "retrieval" or "plan"
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Records the reasoning step by step, replacing the path with the
+    /// steps' nodes so path-based lookups, validation and statistics
+    /// see the same sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - Steps in the order they were taken
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn with_steps(mut self, steps: Vec<ReasoningStep>) -> Self {
+        self.path = steps.iter().map(|s| s.node_id).collect();
+        self.steps = steps;
+        self
+    }
+
+    /// Records the model tokens the decision consumed.
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn with_tokens(mut self, tokens: u64) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Records how long the decision took, in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn with_latency_ms(mut self, latency_ms: u64) -> Self {
+        self.latency_ms = Some(latency_ms);
+        self
+    }
+
+    /// Records what the decision cost, so spend can be aggregated with
+    /// `BarqGraphDb::daily_usage`.
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn with_cost(mut self, cost: f64) -> Self {
+        self.cost = Some(cost);
+        self
+    }
+
+    /// Replaces the decision's free-text content with the redaction
+    /// marker: its notes and step rationales, and its embedding (which
+    /// encodes the notes or goal). With `include_path`, the path and
+    /// steps are cleared as well.
+    pub(crate) fn redact(&mut self, include_path: bool) {
+        self.notes = Some(DEFAULT_MASK.to_string());
+        self.embedding.clear();
+        if include_path {
+            self.path.clear();
+            self.steps.clear();
+        }
+        for step in &mut self.steps {
+            if step.rationale.is_some() {
+                step.rationale = Some(DEFAULT_MASK.to_string());
+            }
+        }
+    }
+
+    /// Materializes the decision as graph node `node_id` when recorded.
+    ///
+    /// The node links to every node of the path, so the decision takes
+    /// part in traversals and hybrid queries (see
+    /// `BarqGraphDb::record_decision`).
+    ///
+    /// # Arguments
+    ///
+    /// * `node_id` - ID for the decision node; must not be in use
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining.
+    pub fn as_node(mut self, node_id: NodeId) -> Self {
+        self.node_id = Some(node_id);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -151,4 +397,25 @@ mod tests {
 
         assert_eq!(record, deserialized);
     }
+
+    #[test]
+    fn test_decision_record_with_steps() {
+        let steps = vec![
+            ReasoningStep::new(100, "retrieve", 0.9).with_rationale("Closest to the query"),
+            ReasoningStep::new(103, "select", 0.6),
+        ];
+        let record = DecisionRecord::with_timestamp(1, 42, 1000, 100, vec![100], 0.75)
+            .with_steps(steps.clone());
+        assert_eq!(record.path, vec![100, 103]);
+
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains(r#""action":"select""#));
+        let deserialized: DecisionRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.steps, steps);
+
+        // Records written before steps existed still load
+        let legacy = r#"{"id":1,"agent_id":42,"created_at":1000,"root_node":100,"path":[100],"score":0.5,"notes":null}"#;
+        let legacy: DecisionRecord = serde_json::from_str(legacy).unwrap();
+        assert!(legacy.steps.is_empty());
+    }
 }
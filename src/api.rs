@@ -3,25 +3,409 @@
 //! This module provides HTTP endpoint handlers for the REST API,
 //! implementing JSON request/response handling for all database operations.
 
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    async_trait,
+    extract::{FromRequestParts, Path, Query, State},
+    http::{request::Parts, StatusCode},
     response::{IntoResponse, Response},
-    Json,
+    Extension, Json,
 };
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 
-use crate::agent::DecisionRecord;
-use crate::hybrid::HybridParams;
+use crate::agent::{DecisionRecord, ReasoningStep};
+use crate::counterfactual::Counterfactual;
+use crate::error::classify;
+use crate::graph::{Centrality, Direction};
+use crate::hybrid::{
+    GraphProximity, HybridFilter, HybridParams, HybridQuery, VectorAggregation,
+    VectorNormalization, WeightedVector,
+};
+use crate::redaction::RedactionPolicy;
+use crate::sort::SortSpec;
 use crate::storage::BarqGraphDb;
-use crate::Node;
+use crate::template::{QueryTemplate, TemplateParams};
+use crate::vector_expr::VectorExpr;
+use crate::{Edge, Node};
 
 /// Shared database state for HTTP handlers.
 pub type DbState = Arc<Mutex<BarqGraphDb>>;
 
+/// Readiness flag reported by the `/ready` endpoint.
+///
+/// Cloned into the router as an extension; the server marks it once
+/// startup warmup has finished.
+#[derive(Debug, Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    /// Creates a flag that starts out not warmed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the server as warmed and ready for traffic.
+    pub fn mark_warmed(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Returns true once warmup has finished.
+    pub fn is_warmed(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Header carrying the caller's API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Redaction to apply to the current request's response.
+///
+/// Extracted from the `RedactionPolicy` extension (if the server
+/// installed one) and the caller's `x-api-key` header. Holds no policy
+/// for privileged callers or when no policy is configured.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor(Option<Arc<RedactionPolicy>>);
+
+impl Redactor {
+    /// Masks fields of a serialized record according to the policy.
+    pub fn apply(&self, tags: &[String], record: &mut serde_json::Value) {
+        if let Some(policy) = &self.0 {
+            policy.apply(tags, record);
+        }
+    }
+
+    /// Returns true if records are masked for this caller.
+    fn is_active(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Redactor {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let api_key = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok());
+        let policy = parts
+            .extensions
+            .get::<Arc<RedactionPolicy>>()
+            .filter(|p| !p.is_privileged(api_key))
+            .cloned();
+        Ok(Self(policy))
+    }
+}
+
+/// Caps on the size of list and query responses.
+///
+/// Installed by the server as an extension; handlers fall back to the
+/// defaults when it is missing. A response that hits either cap is cut
+/// short with `"truncated": true` and a `next_cursor` to resume from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResultLimits {
+    /// Maximum number of items in one response.
+    pub max_results: usize,
+    /// Approximate maximum size of the serialized items, in bytes.
+    pub max_bytes: usize,
+}
+
+impl Default for ResultLimits {
+    fn default() -> Self {
+        Self {
+            max_results: 10_000,
+            max_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ResultLimits {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<ResultLimits>()
+            .copied()
+            .unwrap_or_default())
+    }
+}
+
+/// Ranked query results kept between pages.
+///
+/// Installed by the server as an extension. When a hybrid or kNN response
+/// is truncated, the full ranking is stored here and `next_cursor` points
+/// into it, so later pages are sliced from that snapshot instead of
+/// re-running the query. A cursor whose entry has expired or been evicted,
+/// or that was stored redacted differently than the current caller needs,
+/// falls back to re-running the query. Handlers fall back to a disabled
+/// cache when the extension is missing.
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    entries: Arc<parking_lot::Mutex<HashMap<u64, CachedResults>>>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+#[derive(Debug)]
+struct CachedResults {
+    records: Arc<Vec<serde_json::Value>>,
+    /// Fields added to every page, such as a query's `partial` flag.
+    extra: serde_json::Value,
+    /// Whether the records were masked for a non-privileged caller.
+    redacted: bool,
+    stored_at: Instant,
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self::new(256, Duration::from_secs(300))
+    }
+}
+
+impl ResultCache {
+    /// Creates a cache holding up to `capacity` result lists, each for `ttl`.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: Arc::default(),
+            capacity,
+            ttl,
+        }
+    }
+
+    /// A cache that stores nothing, so every page re-runs its query.
+    pub fn disabled() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+
+    /// Stores a ranking and returns its token, evicting expired entries
+    /// and then the oldest one when full. Returns `None` when disabled.
+    fn insert(
+        &self,
+        records: Vec<serde_json::Value>,
+        extra: serde_json::Value,
+        redacted: bool,
+    ) -> Option<u64> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut entries = self.entries.lock();
+        entries.retain(|_, e| e.stored_at.elapsed() < self.ttl);
+        if entries.len() >= self.capacity {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, e)| e.stored_at)
+                .map(|(t, _)| *t);
+            entries.remove(&oldest?);
+        }
+        let token = loop {
+            let token = rand::random::<u64>();
+            if !entries.contains_key(&token) {
+                break token;
+            }
+        };
+        entries.insert(
+            token,
+            CachedResults {
+                records: Arc::new(records),
+                extra,
+                redacted,
+                stored_at: Instant::now(),
+            },
+        );
+        Some(token)
+    }
+
+    /// Returns a stored ranking and its extra fields, unless expired or
+    /// redacted differently than requested.
+    fn get(
+        &self,
+        token: u64,
+        redacted: bool,
+    ) -> Option<(Arc<Vec<serde_json::Value>>, serde_json::Value)> {
+        let entries = self.entries.lock();
+        entries
+            .get(&token)
+            .filter(|e| e.stored_at.elapsed() < self.ttl && e.redacted == redacted)
+            .map(|e| (Arc::clone(&e.records), e.extra.clone()))
+    }
+
+    /// Pages through a freshly computed ranking, storing it with the
+    /// response's `extra` fields when more pages follow so their cursors
+    /// can be served from the cache to callers redacted the same way.
+    fn page(
+        &self,
+        records: Vec<serde_json::Value>,
+        extra: serde_json::Value,
+        redactor: &Redactor,
+        offset: usize,
+        limit: Option<usize>,
+        limits: ResultLimits,
+    ) -> Page {
+        let page = paginate(records.iter().cloned(), offset, limit, limits);
+        match page
+            .truncated
+            .then(|| self.insert(records, extra, redactor.is_active()))
+            .flatten()
+        {
+            Some(token) => page.resumable_from(token),
+            None => page,
+        }
+    }
+
+    /// Serves a page from the ranking behind a cursor, if still cached
+    /// and redacted as this caller requires.
+    fn cached_page(
+        &self,
+        cursor: QueryCursor,
+        redactor: &Redactor,
+        limit: Option<usize>,
+        limits: ResultLimits,
+    ) -> Option<(Page, serde_json::Value)> {
+        let token = cursor.token?;
+        let (records, extra) = self.get(token, redactor.is_active())?;
+        let page = paginate(records.iter().cloned(), cursor.offset, limit, limits);
+        Some((page.resumable_from(token), extra))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ResultCache {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<ResultCache>()
+            .cloned()
+            .unwrap_or_else(ResultCache::disabled))
+    }
+}
+
+/// Continuation cursor of a ranked query: `<token>:<offset>` into a
+/// cached ranking, or a plain offset to re-run the query from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct QueryCursor {
+    token: Option<u64>,
+    offset: usize,
+}
+
+impl QueryCursor {
+    fn parse(cursor: Option<&str>) -> Result<Self, AppError> {
+        let invalid = |c: &str| AppError::bad_request(format!("Invalid cursor: {}", c));
+        match cursor.map(|c| (c, c.split_once(':'))) {
+            None => Ok(Self {
+                token: None,
+                offset: 0,
+            }),
+            Some((c, Some((token, offset)))) => Ok(Self {
+                token: Some(u64::from_str_radix(token, 16).map_err(|_| invalid(c))?),
+                offset: offset.parse().map_err(|_| invalid(c))?,
+            }),
+            Some((c, None)) => Ok(Self {
+                token: None,
+                offset: parse_cursor(Some(c))?,
+            }),
+        }
+    }
+}
+
+/// One page of a possibly truncated result list.
+#[derive(Debug)]
+struct Page {
+    items: Vec<serde_json::Value>,
+    truncated: bool,
+    next_cursor: Option<String>,
+}
+
+impl Page {
+    /// Adds the page's items and truncation metadata to a response body.
+    fn into_json(self, key: &str) -> serde_json::Value {
+        serde_json::json!({
+            key: self.items,
+            "count": self.items.len(),
+            "truncated": self.truncated,
+            "next_cursor": self.next_cursor
+        })
+    }
+
+    /// Like `into_json`, also adding the fields of the `extra` object.
+    fn into_json_with(self, key: &str, extra: serde_json::Value) -> serde_json::Value {
+        let mut response = self.into_json(key);
+        if let (Some(body), serde_json::Value::Object(extra)) = (response.as_object_mut(), extra) {
+            body.extend(extra);
+        }
+        response
+    }
+
+    /// Points `next_cursor` into the cached ranking behind `token`.
+    fn resumable_from(mut self, token: u64) -> Self {
+        self.next_cursor = self.next_cursor.map(|c| format!("{:x}:{}", token, c));
+        self
+    }
+}
+
+/// Number of records a page may hold: `limit`, capped by the server.
+fn page_size(limit: Option<usize>, limits: ResultLimits) -> usize {
+    limit.map_or(limits.max_results, |l| l.min(limits.max_results))
+}
+
+/// Parses a continuation cursor (an offset into the full result list).
+fn parse_cursor(cursor: Option<&str>) -> Result<usize, AppError> {
+    cursor.map_or(Ok(0), |c| {
+        c.parse()
+            .map_err(|_| AppError::bad_request(format!("Invalid cursor: {}", c)))
+    })
+}
+
+/// Takes one page of records starting at `offset`.
+///
+/// Stops before the page exceeds either limit, but always returns at
+/// least one record so a single oversized item can't stall pagination.
+/// Records are pulled lazily, so nothing past the page is serialized.
+fn paginate(
+    records: impl IntoIterator<Item = serde_json::Value>,
+    offset: usize,
+    limit: Option<usize>,
+    limits: ResultLimits,
+) -> Page {
+    page_at(records.into_iter().skip(offset), offset, limit, limits)
+}
+
+/// Takes one page from records that already start at `offset`.
+fn page_at(
+    records: impl IntoIterator<Item = serde_json::Value>,
+    offset: usize,
+    limit: Option<usize>,
+    limits: ResultLimits,
+) -> Page {
+    let max_results = page_size(limit, limits);
+    let mut records = records.into_iter().peekable();
+    let mut items = Vec::new();
+    let mut bytes = 0;
+    while let Some(record) = records.peek() {
+        let size = serde_json::to_vec(record).map_or(0, |v| v.len());
+        if items.len() >= max_results || (!items.is_empty() && bytes + size > limits.max_bytes) {
+            break;
+        }
+        bytes += size;
+        items.extend(records.next());
+    }
+    let truncated = records.peek().is_some();
+    Page {
+        next_cursor: truncated.then(|| (offset + items.len()).to_string()),
+        items,
+        truncated,
+    }
+}
+
 /// Custom error type for API responses.
 #[derive(Debug)]
 pub struct AppError {
@@ -29,14 +413,25 @@ pub struct AppError {
     pub code: StatusCode,
     /// Error message.
     pub message: String,
+    /// Machine-readable error code (see `BarqError::code`).
+    pub error_code: &'static str,
+    /// Whether clients should retry the request.
+    pub retryable: bool,
 }
 
 impl AppError {
     /// Creates a new API error.
     pub fn new(code: StatusCode, message: impl Into<String>) -> Self {
+        let error_code = match code {
+            StatusCode::BAD_REQUEST => "bad_request",
+            StatusCode::NOT_FOUND => "not_found",
+            _ => "internal",
+        };
         Self {
             code,
             message: message.into(),
+            error_code,
+            retryable: false,
         }
     }
 
@@ -51,11 +446,32 @@ impl AppError {
     }
 }
 
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        let class = classify(&err);
+        let code = match class.code {
+            "node_not_found" | "template_not_found" => StatusCode::NOT_FOUND,
+            "node_already_exists" => StatusCode::CONFLICT,
+            "invalid_operation" | "serialization_error" => StatusCode::BAD_REQUEST,
+            "memory_budget_exceeded" | "read_only" => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            code,
+            message: format!("{:#}", err),
+            error_code: class.code,
+            retryable: class.retryable,
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let body = serde_json::json!({
             "error": self.message,
-            "code": self.code.as_u16()
+            "code": self.code.as_u16(),
+            "error_code": self.error_code,
+            "retryable": self.retryable
         });
         (self.code, Json(body)).into_response()
     }
@@ -82,6 +498,27 @@ pub struct CreateEdgeRequest {
     pub from: u64,
     pub to: u64,
     pub edge_type: String,
+    /// Optional traversal cost used by weighted shortest paths.
+    #[serde(default)]
+    pub weight: Option<f32>,
+    /// Optional edge metadata.
+    #[serde(default)]
+    pub properties: HashMap<String, serde_json::Value>,
+}
+
+/// Query parameters for looking up edges between two nodes.
+#[derive(Debug, Deserialize)]
+pub struct EdgesBetweenQuery {
+    pub from: u64,
+    pub to: u64,
+}
+
+/// Query parameters identifying an edge to remove.
+#[derive(Debug, Deserialize)]
+pub struct RemoveEdgeQuery {
+    pub from: u64,
+    pub to: u64,
+    pub edge_type: String,
 }
 
 /// Request to set an embedding.
@@ -94,16 +531,192 @@ pub struct SetEmbeddingRequest {
 /// Request for hybrid query.
 #[derive(Debug, Deserialize)]
 pub struct HybridQueryRequest {
-    pub start: u64,
+    /// Node to explore from; when omitted, the graph is explored from
+    /// the query's `seeds` nearest vector matches instead.
+    #[serde(default)]
+    pub start: Option<u64>,
+    /// Number of vector matches to explore from when `start` is omitted.
+    #[serde(default = "default_seeds")]
+    pub seeds: usize,
     pub max_hops: usize,
     pub k: usize,
+    #[serde(default)]
     pub query_embedding: Vec<f32>,
+    /// Query vector computed from stored embeddings, used instead of
+    /// `query_embedding` when present.
+    #[serde(default)]
+    pub query_vector: Option<VectorExpr>,
     #[serde(default = "default_alpha")]
     pub alpha: f32,
     #[serde(default = "default_beta")]
     pub beta: f32,
+    /// Optional latency budget in milliseconds; results may be partial.
+    #[serde(default)]
+    pub budget_ms: Option<u64>,
+    /// Maximum number of nodes to explore; results may be truncated.
+    #[serde(default)]
+    pub max_candidates: Option<usize>,
+    /// Edge direction to traverse: `outgoing` (default), `incoming` or `both`.
+    #[serde(default)]
+    pub direction: Direction,
+    /// Tag, agent and timestamp conditions results must meet.
+    #[serde(default)]
+    pub filter: HybridFilter,
+    /// How vector distances become similarities (default `clamp`).
+    #[serde(default)]
+    pub normalization: VectorNormalization,
+    /// Measures graph proximity by accumulated edge weight.
+    #[serde(default)]
+    pub weighted_edges: bool,
+    /// Adds a score breakdown to every result.
+    #[serde(default)]
+    pub explain: bool,
+    /// Adds label, rule tags, timestamp and agent of each result's node.
+    #[serde(default)]
+    pub include_nodes: bool,
+    /// Adds the types of the edges along each result's path.
+    #[serde(default)]
+    pub include_edge_types: bool,
+    /// Expands only this many best-scoring nodes per hop (beam search).
+    #[serde(default)]
+    pub beam_width: Option<usize>,
+    /// Further query vectors, weighted against `query_embedding` (1.0).
+    #[serde(default)]
+    pub query_vectors: Vec<WeightedVector>,
+    /// How similarities to several query vectors are combined (default `max`).
+    #[serde(default)]
+    pub aggregation: VectorAggregation,
+    /// How graph proximity is measured (default `hops`).
+    #[serde(default)]
+    pub graph_proximity: GraphProximity,
+    /// Drops results scoring below this, so fewer than `k` may be returned.
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Page size, capped by the server's result limit.
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Continuation cursor from a previous truncated response.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Request for a kNN similarity search.
+#[derive(Debug, Deserialize)]
+pub struct KnnRequest {
+    #[serde(default)]
+    pub query_embedding: Vec<f32>,
+    /// Query vector computed from stored embeddings, used instead of
+    /// `query_embedding` when present.
+    #[serde(default)]
+    pub query_vector: Option<VectorExpr>,
+    #[serde(default = "default_k")]
+    pub k: usize,
+    /// Diversifies results by maximal marginal relevance with this
+    /// relevance weight (see `BarqGraphDb::knn_search_mmr`).
+    #[serde(default)]
+    pub mmr_lambda: Option<f32>,
+    /// Adds label, rule tags, timestamp and agent of each result's node,
+    /// dropping results without a node.
+    #[serde(default)]
+    pub include_nodes: bool,
+    /// Vectors of subjects to steer results away from (see
+    /// `BarqGraphDb::knn_search_avoiding`).
+    #[serde(default)]
+    pub avoid: Vec<Vec<f32>>,
+    /// Strength of the steering away from `avoid`.
+    #[serde(default = "default_avoid_weight")]
+    pub avoid_weight: f32,
+    /// Drops results farther than this from the query, so fewer than `k`
+    /// may be returned.
+    #[serde(default)]
+    pub max_distance: Option<f32>,
+    /// Page size, capped by the server's result limit.
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Continuation cursor from a previous truncated response.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Request to route a query to its closest tag centroids.
+#[derive(Debug, Deserialize)]
+pub struct RouteQueryRequest {
+    #[serde(default)]
+    pub query_embedding: Vec<f32>,
+    /// Query vector computed from stored embeddings, used instead of
+    /// `query_embedding` when present.
+    #[serde(default)]
+    pub query_vector: Option<VectorExpr>,
+    #[serde(default = "default_route_n")]
+    pub n: usize,
+}
+
+fn default_route_n() -> usize {
+    3
+}
+
+/// Request for evidence chain retrieval.
+#[derive(Debug, Deserialize)]
+pub struct EvidenceChainsRequest {
+    pub query_embedding: Vec<f32>,
+    pub target_tag: String,
+    #[serde(default = "default_max_hops")]
+    pub max_hops: usize,
+    #[serde(default = "default_k")]
+    pub k: usize,
+}
+
+/// Request for a query under hypothetical node/edge removals.
+#[derive(Debug, Deserialize)]
+pub struct WhatIfRequest {
+    #[serde(flatten)]
+    pub counterfactual: Counterfactual,
+    pub query: WhatIfQuery,
 }
 
+/// Query evaluated by a what-if request.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WhatIfQuery {
+    Reachability {
+        start: u64,
+        #[serde(default = "default_max_hops")]
+        max_hops: usize,
+        #[serde(default)]
+        direction: Direction,
+    },
+    ShortestPath {
+        from: u64,
+        to: u64,
+    },
+    Hybrid {
+        start: u64,
+        query_embedding: Vec<f32>,
+        #[serde(default = "default_max_hops")]
+        max_hops: usize,
+        #[serde(default = "default_k")]
+        k: usize,
+        #[serde(default = "default_alpha")]
+        alpha: f32,
+        #[serde(default = "default_beta")]
+        beta: f32,
+        #[serde(default)]
+        direction: Direction,
+    },
+}
+
+fn default_max_hops() -> usize {
+    3
+}
+fn default_k() -> usize {
+    10
+}
+fn default_seeds() -> usize {
+    5
+}
+fn default_avoid_weight() -> f32 {
+    1.0
+}
 fn default_alpha() -> f32 {
     0.5
 }
@@ -116,16 +729,195 @@ fn default_beta() -> f32 {
 pub struct RecordDecisionRequest {
     pub agent_id: u64,
     pub root_node: u64,
+    /// Nodes visited; may be left out when `steps` are given.
+    #[serde(default)]
     pub path: Vec<u64>,
     pub score: f32,
     #[serde(default)]
     pub notes: Option<String>,
+    /// Also adds the decision to the graph as this node, linked to every
+    /// node of its path.
+    #[serde(default)]
+    pub node_id: Option<u64>,
+    /// Agent run the decision belongs to.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Decision this one was taken under.
+    #[serde(default)]
+    pub parent_decision_id: Option<u64>,
+    /// Embedding of the decision's goal or notes, for `/decisions/search`.
+    #[serde(default)]
+    pub embedding: Vec<f32>,
+    /// Categories of the decision, e.g. `tool_call` or `plan`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Reasoning behind each step; replaces `path` with the steps' nodes.
+    #[serde(default)]
+    pub steps: Vec<ReasoningStep>,
+    /// Model tokens the decision consumed.
+    #[serde(default)]
+    pub tokens: Option<u64>,
+    /// Time the decision took in milliseconds.
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    /// What the decision cost.
+    #[serde(default)]
+    pub cost: Option<f64>,
+}
+
+/// Request to find past decisions similar to a query.
+#[derive(Debug, Deserialize)]
+pub struct SearchDecisionsRequest {
+    pub query_embedding: Vec<f32>,
+    #[serde(default = "default_k")]
+    pub k: usize,
 }
 
 /// Query parameters for listing decisions.
 #[derive(Debug, Deserialize)]
 pub struct ListDecisionsQuery {
-    pub agent_id: u64,
+    /// Only list this agent's decisions; all decisions when omitted.
+    #[serde(default)]
+    pub agent_id: Option<u64>,
+    /// Only list decisions carrying this tag.
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Optional sort specification, e.g. `score:desc,timestamp`.
+    #[serde(default)]
+    pub sort: Option<String>,
+    /// Maximum number of decisions to return (capped by the server).
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Continuation cursor from a previous truncated response.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Request to store a memory for an agent.
+#[derive(Debug, Deserialize)]
+pub struct RememberRequest {
+    pub text: String,
+    pub embedding: Vec<f32>,
+    /// Nodes the memory is about, e.g. the session node.
+    #[serde(default)]
+    pub links: Vec<u64>,
+}
+
+/// Request to recall an agent's memories.
+#[derive(Debug, Deserialize)]
+pub struct RecallRequest {
+    pub query_embedding: Vec<f32>,
+    #[serde(default = "default_k")]
+    pub k: usize,
+}
+
+/// Query parameters for daily decision usage.
+#[derive(Debug, Default, Deserialize)]
+pub struct UsageQuery {
+    /// Earliest decision timestamp, inclusive.
+    #[serde(default)]
+    pub since: Option<u64>,
+    /// Latest decision timestamp, inclusive.
+    #[serde(default)]
+    pub until: Option<u64>,
+    /// Only total this agent's decisions.
+    #[serde(default)]
+    pub agent_id: Option<u64>,
+}
+
+/// Query parameters for comparing two agents.
+#[derive(Debug, Deserialize)]
+pub struct CompareAgentsQuery {
+    pub agent_a: u64,
+    pub agent_b: u64,
+    /// Root node of the compared decisions.
+    pub root: u64,
+}
+
+/// Query parameters for redacting a decision.
+#[derive(Debug, Default, Deserialize)]
+pub struct RedactDecisionQuery {
+    /// Also clear the decision's path and steps.
+    #[serde(default)]
+    pub path: bool,
+}
+
+/// Query parameters for getting a node.
+#[derive(Debug, Default, Deserialize)]
+pub struct GetNodeQuery {
+    /// Comma-separated optional fields to add; only `embedding`.
+    #[serde(default)]
+    pub include: Option<String>,
+}
+
+/// Query parameters for listing nodes.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListNodesQuery {
+    /// Optional sort specification, e.g. `degree:desc,label`.
+    #[serde(default)]
+    pub sort: Option<String>,
+    /// Maximum number of nodes to return (capped by the server).
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Continuation cursor from a previous truncated response.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Query parameters for a BFS traversal.
+#[derive(Debug, Deserialize)]
+pub struct BfsQuery {
+    #[serde(default = "default_max_hops")]
+    pub max_hops: usize,
+    /// Edge direction to traverse: `outgoing` (default), `incoming` or `both`.
+    #[serde(default)]
+    pub direction: Direction,
+    /// Only visit nodes created at or after this Unix timestamp.
+    #[serde(default)]
+    pub since: Option<u64>,
+    /// Only visit nodes created at or before this Unix timestamp.
+    #[serde(default)]
+    pub until: Option<u64>,
+    /// Maximum number of nodes to return (capped by the server).
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Continuation cursor from a previous truncated response.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Query parameters for ranking nodes by centrality.
+#[derive(Debug, Deserialize)]
+pub struct CentralityQuery {
+    /// Measure to rank by: `degree` (default), `betweenness` or `closeness`.
+    #[serde(default)]
+    pub measure: Centrality,
+    #[serde(default = "default_k")]
+    pub k: usize,
+    /// Optional number of BFS sources used to approximate the measure.
+    #[serde(default)]
+    pub samples: Option<usize>,
+}
+
+/// Query parameters for listing the most connected nodes.
+#[derive(Debug, Deserialize)]
+pub struct HubsQuery {
+    #[serde(default = "default_k")]
+    pub k: usize,
+    /// Degree to rank by: `outgoing`, `incoming` or `both` (default).
+    #[serde(default = "default_hub_direction")]
+    pub direction: Direction,
+}
+
+fn default_hub_direction() -> Direction {
+    Direction::Both
+}
+
+/// Parses an optional sort specification from a query string.
+fn parse_sort(sort: Option<&str>) -> Result<SortSpec, AppError> {
+    sort.map_or(Ok(SortSpec::default()), |s| {
+        s.parse().map_err(AppError::bad_request)
+    })
 }
 
 /// Generic success response.
@@ -154,6 +946,25 @@ pub async fn health_check() -> impl IntoResponse {
     }))
 }
 
+/// Readiness endpoint.
+///
+/// Returns 503 until startup warmup has finished.
+pub async fn readiness(Extension(ready): Extension<Readiness>) -> impl IntoResponse {
+    let warmed = ready.is_warmed();
+    let code = if warmed {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        code,
+        Json(serde_json::json!({
+            "status": if warmed { "ready" } else { "warming" },
+            "warmed": warmed
+        })),
+    )
+}
+
 /// Creates a new node.
 pub async fn create_node(
     State(db): State<DbState>,
@@ -166,8 +977,7 @@ pub async fn create_node(
     node.agent_id = payload.agent_id;
     node.rule_tags = payload.rule_tags;
 
-    db.append_node(node)
-        .map_err(|e| AppError::internal(e.to_string()))?;
+    db.append_node(node).map_err(AppError::from)?;
 
     Ok((
         StatusCode::CREATED,
@@ -185,8 +995,10 @@ pub async fn create_edge(
 ) -> Result<impl IntoResponse, AppError> {
     let mut db = db.lock().await;
 
-    db.add_edge(payload.from, payload.to, &payload.edge_type)
-        .map_err(|e| AppError::internal(e.to_string()))?;
+    let mut edge = Edge::new(payload.from, payload.to, payload.edge_type);
+    edge.weight = payload.weight;
+    edge.properties = payload.properties;
+    db.add_edge_data(edge).map_err(AppError::from)?;
 
     Ok((
         StatusCode::CREATED,
@@ -198,54 +1010,357 @@ pub async fn create_edge(
     ))
 }
 
-/// Sets an embedding for a node.
-pub async fn set_embedding(
+/// Lists all edges between two nodes, including weights and properties.
+pub async fn edges_between(
     State(db): State<DbState>,
-    Json(payload): Json<SetEmbeddingRequest>,
+    Query(query): Query<EdgesBetweenQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    let mut db = db.lock().await;
-
-    db.set_embedding(payload.id, payload.embedding)
-        .map_err(|e| AppError::internal(e.to_string()))?;
+    let db = db.lock().await;
+    let edges = db.edges_between(query.from, query.to);
 
     Ok(Json(serde_json::json!({
-        "status": "ok",
-        "node_id": payload.id
+        "edges": edges,
+        "count": edges.len()
     })))
 }
 
-/// Performs a hybrid query.
+/// Removes an edge between two nodes.
+pub async fn remove_edge(
+    State(db): State<DbState>,
+    Query(query): Query<RemoveEdgeQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut db = db.lock().await;
+
+    if !db
+        .remove_edge(query.from, query.to, &query.edge_type)
+        .map_err(AppError::from)?
+    {
+        return Err(AppError::new(
+            StatusCode::NOT_FOUND,
+            format!(
+                "Edge {} -[{}]-> {} not found",
+                query.from, query.edge_type, query.to
+            ),
+        ));
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "from": query.from,
+        "to": query.to
+    })))
+}
+
+/// Sets an embedding for a node.
+pub async fn set_embedding(
+    State(db): State<DbState>,
+    Json(payload): Json<SetEmbeddingRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut db = db.lock().await;
+
+    db.set_embedding(payload.id, payload.embedding)
+        .map_err(AppError::from)?;
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "node_id": payload.id
+    })))
+}
+
+/// Returns the query vector of a request, resolving an expression if given.
+fn query_vector(
+    db: &BarqGraphDb,
+    embedding: Vec<f32>,
+    expr: Option<&VectorExpr>,
+) -> Result<Vec<f32>, AppError> {
+    match expr {
+        Some(expr) => db.resolve_vector(expr).map_err(AppError::from),
+        None => Ok(embedding),
+    }
+}
+
+/// Performs a kNN similarity search, one page at a time.
+///
+/// Later pages are served from the result cache while it still holds
+/// the first page's ranking.
+pub async fn knn_query(
+    State(db): State<DbState>,
+    redactor: Redactor,
+    limits: ResultLimits,
+    cache: ResultCache,
+    Json(payload): Json<KnnRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let cursor = QueryCursor::parse(payload.cursor.as_deref())?;
+    if let Some((page, _)) = cache.cached_page(cursor, &redactor, payload.limit, limits) {
+        return Ok(Json(page.into_json("results")));
+    }
+
+    let (query, searcher) = {
+        let db = db.lock().await;
+        let query = query_vector(&db, payload.query_embedding, payload.query_vector.as_ref())?;
+        (query, db.searcher())
+    };
+
+    // Plain searches run off the lock; reranking also reads stored embeddings
+    let results = match (payload.mmr_lambda, payload.avoid.is_empty()) {
+        (Some(_), false) => {
+            return Err(AppError::bad_request(
+                "mmr_lambda and avoid can't be combined",
+            ))
+        }
+        (Some(lambda), true) => db.lock().await.knn_search_mmr(&query, payload.k, lambda),
+        (None, false) => db.lock().await.knn_search_avoiding(
+            &query,
+            &payload.avoid,
+            payload.k,
+            payload.avoid_weight,
+        ),
+        (None, true) => searcher
+            .knn_async(query, payload.k)
+            .await
+            .map_err(AppError::from)?,
+    };
+    let results: Vec<_> = results
+        .into_iter()
+        .filter(|&(_, distance)| payload.max_distance.is_none_or(|max| distance <= max))
+        .collect();
+    let records: Vec<_> = if payload.include_nodes {
+        let matches = db.lock().await.join_nodes(results);
+        matches
+            .iter()
+            .map(|m| {
+                let mut record = serde_json::json!(m);
+                redactor.apply(&m.rule_tags, &mut record);
+                record
+            })
+            .collect()
+    } else {
+        results
+            .into_iter()
+            .map(|(id, distance)| serde_json::json!({"id": id, "distance": distance}))
+            .collect()
+    };
+
+    let page = cache.page(
+        records,
+        serde_json::json!({}),
+        &redactor,
+        cursor.offset,
+        payload.limit,
+        limits,
+    );
+    Ok(Json(page.into_json("results")))
+}
+
+/// Routes a query to the rule tags with the closest centroids.
+pub async fn route_query(
+    State(db): State<DbState>,
+    Json(payload): Json<RouteQueryRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let query = query_vector(&db, payload.query_embedding, payload.query_vector.as_ref())?;
+
+    let response: Vec<_> = db
+        .route_query(&query, payload.n)
+        .into_iter()
+        .map(|(tag, distance)| serde_json::json!({"tag": tag, "distance": distance}))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "results": response
+    })))
+}
+
+/// Lists the maintained tag centroids.
+pub async fn list_centroids(State(db): State<DbState>) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let centroids = db.tag_centroids();
+
+    Ok(Json(serde_json::json!({
+        "centroids": centroids,
+        "count": centroids.len()
+    })))
+}
+
+/// Performs a hybrid query, one page at a time.
+///
+/// Later pages are served from the result cache while it still holds
+/// the first page's ranking, skipping the traversal and scoring.
 pub async fn hybrid_query(
     State(db): State<DbState>,
+    redactor: Redactor,
+    limits: ResultLimits,
+    cache: ResultCache,
     Json(payload): Json<HybridQueryRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    let cursor = QueryCursor::parse(payload.cursor.as_deref())?;
+    if let Some((page, extra)) = cache.cached_page(cursor, &redactor, payload.limit, limits) {
+        return Ok(Json(page.into_json_with("results", extra)));
+    }
+
     let db = db.lock().await;
+    let query = query_vector(&db, payload.query_embedding, payload.query_vector.as_ref())?;
+    if payload
+        .query_vectors
+        .iter()
+        .any(|v| v.embedding.len() != query.len())
+    {
+        return Err(AppError::bad_request(
+            "query_vectors must have the dimension of the query embedding",
+        ));
+    }
+    // Without a cache, never rank more results than this page can return
+    let k = if cache.capacity == 0 {
+        let page_size = payload
+            .limit
+            .map_or(limits.max_results, |l| l.min(limits.max_results));
+        payload
+            .k
+            .min(cursor.offset.saturating_add(page_size).saturating_add(1))
+    } else {
+        payload.k
+    };
+
+    let mut hybrid = HybridQuery::new(query)
+        .seeds(payload.seeds)
+        .max_hops(payload.max_hops)
+        .k(k)
+        .alpha(payload.alpha)
+        .beta(payload.beta)
+        .direction(payload.direction)
+        .filter(payload.filter)
+        .normalization(payload.normalization)
+        .weighted_edges(payload.weighted_edges)
+        .graph_proximity(payload.graph_proximity)
+        .explain(payload.explain)
+        .edge_types(payload.include_edge_types)
+        .include_nodes(payload.include_nodes);
+    if let Some(start) = payload.start {
+        hybrid = hybrid.start(start);
+    }
+    if let Some(max) = payload.max_candidates {
+        hybrid = hybrid.max_candidates(max);
+    }
+    if let Some(width) = payload.beam_width {
+        hybrid = hybrid.beam_width(width);
+    }
+    if let Some(min) = payload.min_score {
+        hybrid = hybrid.min_score(min);
+    }
+    if !payload.query_vectors.is_empty() {
+        hybrid = hybrid.query_vectors(payload.query_vectors, payload.aggregation);
+    }
+    if let Some(ms) = payload.budget_ms {
+        hybrid = hybrid.timeout(Duration::from_millis(ms));
+    }
+    let outcome = hybrid.run(&db);
+
+    let records = outcome.results.iter().map(|r| {
+        let mut record = serde_json::json!({
+            "id": r.id,
+            "score": r.score,
+            "vector_distance": r.vector_distance,
+            "graph_distance": r.graph_distance,
+            "graph_cost": r.graph_cost,
+            "path": r.path
+        });
+        if let Some(explanation) = &r.explanation {
+            record["explanation"] = serde_json::json!(explanation);
+        }
+        if !r.edge_types.is_empty() {
+            record["edge_types"] = serde_json::json!(r.edge_types);
+        }
+        if !r.query_distances.is_empty() {
+            record["query_distances"] = serde_json::json!(r.query_distances);
+        }
+        if let Some(node) = &r.node {
+            record["label"] = node.label.clone().into();
+            record["rule_tags"] = serde_json::json!(node.rule_tags);
+            record["timestamp"] = node.timestamp.into();
+            record["agent_id"] = serde_json::json!(node.agent_id);
+            redactor.apply(&node.rule_tags, &mut record);
+        }
+        record
+    });
+
+    let extra = serde_json::json!({
+        "partial": outcome.partial,
+        "candidates_truncated": outcome.truncated
+    });
+    let page = cache.page(
+        records.collect(),
+        extra.clone(),
+        &redactor,
+        cursor.offset,
+        payload.limit,
+        limits,
+    );
+    Ok(Json(page.into_json_with("results", extra)))
+}
 
-    let params = HybridParams::new(payload.alpha, payload.beta);
-    let results = db.hybrid_query(
+/// Finds evidence chains from query-matching nodes to tagged nodes.
+pub async fn evidence_chains(
+    State(db): State<DbState>,
+    Json(payload): Json<EvidenceChainsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let chains = db.evidence_chains(
         &payload.query_embedding,
-        payload.start,
+        &payload.target_tag,
         payload.max_hops,
         payload.k,
-        params,
     );
 
-    let response: Vec<_> = results
-        .iter()
-        .map(|r| {
+    Ok(Json(serde_json::json!({
+        "chains": chains,
+        "count": chains.len()
+    })))
+}
+
+/// Evaluates a query as if the given nodes and edges were removed.
+pub async fn what_if_query(
+    State(db): State<DbState>,
+    Json(payload): Json<WhatIfRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let view = db.what_if(&payload.counterfactual);
+
+    let response = match payload.query {
+        WhatIfQuery::Reachability {
+            start,
+            max_hops,
+            direction,
+        } => serde_json::json!({
+            "reachable": view.bfs_hops(start, max_hops, direction),
+            "lost": view.lost_reachability(start, max_hops, direction)
+        }),
+        WhatIfQuery::ShortestPath { from, to } => {
+            let path = |p: Option<(Vec<u64>, f32)>| match p {
+                Some((path, cost)) => serde_json::json!({"path": path, "cost": cost}),
+                None => serde_json::Value::Null,
+            };
             serde_json::json!({
-                "id": r.id,
-                "score": r.score,
-                "vector_distance": r.vector_distance,
-                "graph_distance": r.graph_distance,
-                "path": r.path
+                "result": path(view.shortest_path_weighted(from, to)),
+                "baseline": path(db.shortest_path_weighted(from, to))
             })
-        })
-        .collect();
+        }
+        WhatIfQuery::Hybrid {
+            start,
+            query_embedding,
+            max_hops,
+            k,
+            alpha,
+            beta,
+            direction,
+        } => {
+            let params = HybridParams::new(alpha, beta).with_direction(direction);
+            serde_json::json!({
+                "results": view.hybrid_query(&query_embedding, start, max_hops, k, params)
+            })
+        }
+    };
 
-    Ok(Json(serde_json::json!({
-        "results": response
-    })))
+    Ok(Json(response))
 }
 
 /// Records a decision.
@@ -267,9 +1382,29 @@ pub async fn record_decision(
     if let Some(notes) = payload.notes {
         record = record.with_notes(notes);
     }
+    if let Some(node_id) = payload.node_id {
+        record = record.as_node(node_id);
+    }
+    if let Some(session_id) = payload.session_id {
+        record = record.with_session(session_id);
+    }
+    if let Some(parent_id) = payload.parent_decision_id {
+        record = record.with_parent(parent_id);
+    }
+    if !payload.embedding.is_empty() {
+        record = record.with_embedding(payload.embedding);
+    }
+    if !payload.tags.is_empty() {
+        record = record.with_tags(payload.tags);
+    }
+    if !payload.steps.is_empty() {
+        record = record.with_steps(payload.steps);
+    }
+    record.tokens = payload.tokens;
+    record.latency_ms = payload.latency_ms;
+    record.cost = payload.cost;
 
-    db.record_decision(record.clone())
-        .map_err(|e| AppError::internal(e.to_string()))?;
+    db.record_decision(record.clone()).map_err(AppError::from)?;
 
     Ok((
         StatusCode::CREATED,
@@ -281,84 +1416,318 @@ pub async fn record_decision(
                 "root_node": record.root_node,
                 "path": record.path,
                 "score": record.score,
-                "created_at": record.created_at
+                "created_at": record.created_at,
+                "node_id": record.node_id,
+                "session_id": record.session_id,
+                "parent_decision_id": record.parent_decision_id
             }
         })),
     ))
 }
 
-/// Lists decisions for an agent.
+/// Lists decisions, optionally of one agent, one page at a time.
 pub async fn list_decisions(
     State(db): State<DbState>,
+    redactor: Redactor,
+    limits: ResultLimits,
     Query(query): Query<ListDecisionsQuery>,
 ) -> Result<impl IntoResponse, AppError> {
+    let spec = parse_sort(query.sort.as_deref())?;
+    let offset = parse_cursor(query.cursor.as_deref())?;
     let db = db.lock().await;
 
-    let decisions = db.list_decisions_for_agent(query.agent_id);
+    // One extra decision tells whether another page follows
+    let size = page_size(query.limit, limits).saturating_add(1);
+    let decisions = db
+        .list_decisions_page(query.agent_id, query.tag.as_deref(), &spec, offset, size)
+        .map_err(AppError::from)?;
+    let records = decisions.iter().map(|d| decision_json(&db, &redactor, d));
 
-    let response: Vec<_> = decisions
-        .iter()
-        .map(|d| {
-            serde_json::json!({
-                "id": d.id,
-                "agent_id": d.agent_id,
-                "root_node": d.root_node,
-                "path": d.path,
-                "score": d.score,
-                "created_at": d.created_at,
-                "notes": d.notes
-            })
-        })
+    Ok(Json(
+        page_at(records, offset, query.limit, limits).into_json("decisions"),
+    ))
+}
+
+/// Lists the decisions of one agent run in the order they were recorded.
+pub async fn list_session_decisions(
+    State(db): State<DbState>,
+    redactor: Redactor,
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+
+    let response: Vec<_> = db
+        .list_decisions_for_session(&session_id)
+        .into_iter()
+        .map(|d| decision_json(&db, &redactor, d))
         .collect();
 
     Ok(Json(serde_json::json!({
+        "session_id": session_id,
         "decisions": response
     })))
 }
 
+/// Finds the past decisions whose embeddings are nearest a query.
+pub async fn search_decisions(
+    State(db): State<DbState>,
+    redactor: Redactor,
+    Json(payload): Json<SearchDecisionsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+
+    let results: Vec<_> = db
+        .search_decisions(&payload.query_embedding, payload.k)
+        .into_iter()
+        .map(|(d, distance)| {
+            let mut record = decision_json(&db, &redactor, d);
+            record["distance"] = serde_json::json!(distance);
+            record
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "results": results
+    })))
+}
+
+/// Checks a decision's recorded path against the graph.
+pub async fn validate_decision(
+    State(db): State<DbState>,
+    Path(id): Path<u64>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let report = db.validate_decision(id).ok_or_else(|| {
+        AppError::new(StatusCode::NOT_FOUND, format!("Decision {} not found", id))
+    })?;
+
+    Ok(Json(serde_json::json!({
+        "valid": report.is_valid(),
+        "report": report
+    })))
+}
+
+/// Gets a decision's chain of parents, root first, and its direct children.
+pub async fn decision_chain(
+    State(db): State<DbState>,
+    redactor: Redactor,
+    Path(id): Path<u64>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    if db.get_decision(id).is_none() {
+        return Err(AppError::new(
+            StatusCode::NOT_FOUND,
+            format!("Decision {} not found", id),
+        ));
+    }
+
+    let chain: Vec<_> = db
+        .decision_chain(id)
+        .into_iter()
+        .map(|d| decision_json(&db, &redactor, d))
+        .collect();
+    let children: Vec<_> = db.child_decisions(id).iter().map(|d| d.id).collect();
+
+    Ok(Json(serde_json::json!({
+        "chain": chain,
+        "children": children
+    })))
+}
+
+/// Redacts a decision's notes (and optionally its path) for a privacy
+/// request, keeping the record in the audit trail.
+pub async fn redact_decision(
+    State(db): State<DbState>,
+    redactor: Redactor,
+    Path(id): Path<u64>,
+    Query(query): Query<RedactDecisionQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut db = db.lock().await;
+    if !db.redact_decision(id, query.path).map_err(AppError::from)? {
+        return Err(AppError::new(
+            StatusCode::NOT_FOUND,
+            format!("Decision {} not found", id),
+        ));
+    }
+
+    let decision = db.get_decision(id).expect("redacted decision exists");
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "decision": decision_json(&db, &redactor, decision)
+    })))
+}
+
+/// Renders a decision for a response, redacted by its root node's tags.
+fn decision_json(db: &BarqGraphDb, redactor: &Redactor, d: &DecisionRecord) -> serde_json::Value {
+    let mut record = serde_json::json!({
+        "id": d.id,
+        "agent_id": d.agent_id,
+        "root_node": d.root_node,
+        "path": d.path,
+        "score": d.score,
+        "created_at": d.created_at,
+        "notes": d.notes,
+        "node_id": d.node_id,
+        "session_id": d.session_id,
+        "parent_decision_id": d.parent_decision_id,
+        "tags": d.tags,
+        "steps": d.steps,
+        "tokens": d.tokens,
+        "latency_ms": d.latency_ms,
+        "cost": d.cost
+    });
+    // Decisions inherit the tags of the node they start from
+    let tags = db.get_node(d.root_node).map_or(&[][..], |n| &n.rule_tags);
+    redactor.apply(tags, &mut record);
+    record
+}
+
 /// Gets a single node by ID.
 pub async fn get_node(
     State(db): State<DbState>,
+    redactor: Redactor,
     Path(id): Path<u64>,
+    Query(query): Query<GetNodeQuery>,
 ) -> Result<impl IntoResponse, AppError> {
+    let mut include_embedding = false;
+    for field in query.include.iter().flat_map(|s| s.split(',')) {
+        match field.trim() {
+            "embedding" => include_embedding = true,
+            "" => {}
+            other => {
+                return Err(AppError::bad_request(format!(
+                    "Unknown include field: {:?}",
+                    other
+                )))
+            }
+        }
+    }
     let db = db.lock().await;
 
     let node = db
         .get_node(id)
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, format!("Node {} not found", id)))?;
 
-    Ok(Json(serde_json::json!({
+    let mut record = serde_json::json!({
         "id": node.id,
         "label": node.label,
-        "embedding": node.embedding,
         "agent_id": node.agent_id,
         "rule_tags": node.rule_tags,
         "edges": node.edges,
+        "incoming_edges": db.incoming_edges(id),
         "timestamp": node.timestamp
-    })))
+    });
+    // Embeddings can be large, so they are only sent on request
+    if include_embedding {
+        record["embedding"] = serde_json::json!(node.embedding);
+    }
+    redactor.apply(&node.rule_tags, &mut record);
+
+    Ok(Json(record))
 }
 
-/// Lists all nodes.
-pub async fn list_nodes(State(db): State<DbState>) -> Result<impl IntoResponse, AppError> {
+/// Lists all nodes, one page at a time.
+pub async fn list_nodes(
+    State(db): State<DbState>,
+    redactor: Redactor,
+    limits: ResultLimits,
+    Query(query): Query<ListNodesQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let spec = parse_sort(query.sort.as_deref())?;
+    let offset = parse_cursor(query.cursor.as_deref())?;
     let db = db.lock().await;
 
+    // One extra node tells whether another page follows
+    let size = page_size(query.limit, limits).saturating_add(1);
+    let nodes = db
+        .list_nodes_page(&spec, offset, size)
+        .map_err(AppError::from)?;
+    let records = nodes.iter().map(|n| {
+        let mut record = serde_json::json!({
+            "id": n.id,
+            "label": n.label,
+            "has_embedding": !n.embedding.is_empty(),
+            "agent_id": n.agent_id,
+            "timestamp": n.timestamp
+        });
+        redactor.apply(&n.rule_tags, &mut record);
+        record
+    });
+
+    Ok(Json(
+        page_at(records, offset, query.limit, limits).into_json("nodes"),
+    ))
+}
+
+/// Runs a BFS traversal from a node, one page at a time.
+///
+/// The traversal is lazy, so nodes past the requested page are never
+/// visited.
+pub async fn bfs(
+    State(db): State<DbState>,
+    limits: ResultLimits,
+    Path(id): Path<u64>,
+    Query(query): Query<BfsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let offset = parse_cursor(query.cursor.as_deref())?;
+    let db = db.lock().await;
+
+    let mut traversal = db
+        .traverse(id)
+        .max_hops(query.max_hops)
+        .direction(query.direction);
+    if query.since.is_some() || query.until.is_some() {
+        traversal =
+            traversal.time_window(query.since.unwrap_or(0), query.until.unwrap_or(u64::MAX));
+    }
+    let records = traversal
+        .with_depths()
+        .map(|(id, depth)| serde_json::json!({"id": id, "depth": depth}));
+
+    Ok(Json(
+        paginate(records, offset, query.limit, limits).into_json("nodes"),
+    ))
+}
+
+/// Returns the top-k most central nodes.
+pub async fn central_nodes(
+    State(db): State<DbState>,
+    limits: ResultLimits,
+    Query(query): Query<CentralityQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
     let nodes: Vec<_> = db
-        .list_nodes()
-        .iter()
-        .map(|n| {
-            serde_json::json!({
-                "id": n.id,
-                "label": n.label,
-                "has_embedding": !n.embedding.is_empty(),
-                "agent_id": n.agent_id,
-                "timestamp": n.timestamp
-            })
-        })
+        .top_central(
+            query.measure,
+            query.k.min(limits.max_results),
+            query.samples,
+        )
+        .into_iter()
+        .map(|(id, score)| serde_json::json!({"id": id, "score": score}))
         .collect();
 
     Ok(Json(serde_json::json!({
+        "measure": query.measure,
+        "nodes": nodes,
+    })))
+}
+
+/// Returns the top-k nodes by degree from maintained counters.
+pub async fn hubs(
+    State(db): State<DbState>,
+    limits: ResultLimits,
+    Query(query): Query<HubsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let nodes: Vec<_> = db
+        .top_degree_nodes(query.k.min(limits.max_results), query.direction)
+        .into_iter()
+        .map(|(id, degree)| serde_json::json!({"id": id, "degree": degree}))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "direction": query.direction,
         "nodes": nodes,
-        "count": nodes.len()
     })))
 }
 
@@ -370,6 +1739,354 @@ pub async fn get_stats(State(db): State<DbState>) -> Result<impl IntoResponse, A
         "node_count": db.node_count(),
         "edge_count": db.edge_count(),
         "vector_count": db.vector_count(),
-        "decision_count": db.decision_count()
+        "decision_count": db.decision_count(),
+        "memory": db.memory_usage(),
+        "disk": db.disk_status().map_err(AppError::from)?,
+        "tier": db.tier_stats()
+    })))
+}
+
+/// Gets structural statistics of the graph.
+pub async fn get_graph_stats(State(db): State<DbState>) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    Ok(Json(db.graph_stats()))
+}
+
+/// Gets activity statistics of one agent.
+pub async fn get_agent_stats(
+    State(db): State<DbState>,
+    Path(agent_id): Path<u64>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    Ok(Json(db.agent_stats(agent_id)))
+}
+
+/// Stores a memory for an agent as a linked node.
+pub async fn remember(
+    State(db): State<DbState>,
+    Path(agent_id): Path<u64>,
+    Json(payload): Json<RememberRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut db = db.lock().await;
+    let id = db
+        .remember(agent_id, payload.text, payload.embedding, &payload.links)
+        .map_err(AppError::from)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "status": "ok",
+            "id": id
+        })),
+    ))
+}
+
+/// Recalls an agent's memories most relevant to a query.
+pub async fn recall(
+    State(db): State<DbState>,
+    redactor: Redactor,
+    Path(agent_id): Path<u64>,
+    Json(payload): Json<RecallRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let memories: Vec<_> = db
+        .recall(agent_id, &payload.query_embedding, payload.k)
+        .into_iter()
+        .map(|memory| {
+            let mut record = serde_json::to_value(&memory).expect("memories serialize");
+            let tags = db.get_node(memory.id).map_or(&[][..], |n| &n.rule_tags);
+            redactor.apply(tags, &mut record);
+            record
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({ "memories": memories })))
+}
+
+/// Totals decision tokens, cost and latency per agent and day.
+pub async fn daily_usage(
+    State(db): State<DbState>,
+    Query(query): Query<UsageQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let range = query.since.unwrap_or(0)..=query.until.unwrap_or(u64::MAX);
+    let usage: Vec<_> = db
+        .daily_usage(range)
+        .into_iter()
+        .filter(|u| query.agent_id.is_none_or(|id| u.agent_id == id))
+        .collect();
+
+    Ok(Json(serde_json::json!({ "usage": usage })))
+}
+
+/// Compares two agents' decisions from the same root node.
+pub async fn compare_agents(
+    State(db): State<DbState>,
+    Query(query): Query<CompareAgentsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    Ok(Json(db.compare_agents(
+        query.agent_a,
+        query.agent_b,
+        query.root,
+    )))
+}
+
+/// Lists saved query templates.
+pub async fn list_templates(State(db): State<DbState>) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let templates = db.list_templates();
+
+    Ok(Json(serde_json::json!({
+        "templates": templates,
+        "count": templates.len()
     })))
 }
+
+/// Saves a query template, bumping its version if the name exists.
+pub async fn save_template(
+    State(db): State<DbState>,
+    Json(payload): Json<QueryTemplate>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut db = db.lock().await;
+    let name = payload.name.clone();
+    let version = db.save_template(payload).map_err(AppError::from)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "status": "ok",
+            "name": name,
+            "version": version
+        })),
+    ))
+}
+
+/// Gets a saved query template by name.
+pub async fn get_template(
+    State(db): State<DbState>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+
+    let template = db.get_template(&name).ok_or_else(|| {
+        AppError::new(
+            StatusCode::NOT_FOUND,
+            format!("Template {} not found", name),
+        )
+    })?;
+
+    Ok(Json(serde_json::json!(template)))
+}
+
+/// Deletes a saved query template.
+pub async fn delete_template(
+    State(db): State<DbState>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut db = db.lock().await;
+
+    if !db.delete_template(&name).map_err(AppError::from)? {
+        return Err(AppError::new(
+            StatusCode::NOT_FOUND,
+            format!("Template {} not found", name),
+        ));
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "name": name
+    })))
+}
+
+/// Runs a saved query template with per-request overrides.
+pub async fn run_template(
+    State(db): State<DbState>,
+    Path(name): Path<String>,
+    Json(params): Json<TemplateParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let db = db.lock().await;
+    let output = db.run_template(&name, &params).map_err(AppError::from)?;
+
+    Ok(Json(serde_json::json!(output)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate_caps_results_and_bytes() {
+        let records = || (0..10).map(|i| serde_json::json!({ "id": i }));
+        let limits = ResultLimits {
+            max_results: 4,
+            max_bytes: 1024,
+        };
+
+        let first = paginate(records(), 0, None, limits);
+        assert_eq!(first.items.len(), 4);
+        assert!(first.truncated);
+        assert_eq!(first.next_cursor.as_deref(), Some("4"));
+
+        let offset = parse_cursor(first.next_cursor.as_deref()).unwrap();
+        let last = paginate(records(), offset, Some(100), limits).into_json("nodes");
+        assert_eq!(last["nodes"][0]["id"], 4);
+        assert_eq!(last["count"], 4);
+        let tail = paginate(records(), 8, None, limits);
+        assert!(!tail.truncated && tail.next_cursor.is_none());
+
+        // Byte cap cuts the page short but always returns one record
+        let tiny = ResultLimits {
+            max_results: 10,
+            max_bytes: 1,
+        };
+        let page = paginate(records(), 0, None, tiny);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.next_cursor.as_deref(), Some("1"));
+        assert!(parse_cursor(Some("abc")).is_err());
+    }
+
+    #[test]
+    fn test_result_cache_serves_later_pages() {
+        let records: Vec<_> = (0..10).map(|i| serde_json::json!({ "id": i })).collect();
+        let limits = ResultLimits::default();
+        let cache = ResultCache::default();
+
+        let first = cache.page(
+            records,
+            serde_json::json!({ "partial": true }),
+            &Redactor::default(),
+            0,
+            Some(4),
+            limits,
+        );
+        assert_eq!(first.items.len(), 4);
+        let cursor = QueryCursor::parse(first.next_cursor.as_deref()).unwrap();
+        assert!(cursor.token.is_some());
+        assert_eq!(cursor.offset, 4);
+
+        let (second, extra) = cache
+            .cached_page(cursor, &Redactor::default(), Some(4), limits)
+            .unwrap();
+        assert_eq!(extra["partial"], true);
+        assert_eq!(second.items[0]["id"], 4);
+        let cursor = QueryCursor::parse(second.next_cursor.as_deref()).unwrap();
+        let (last, _) = cache
+            .cached_page(cursor, &Redactor::default(), Some(4), limits)
+            .unwrap();
+        assert_eq!(last.items.len(), 2);
+        assert!(last.next_cursor.is_none());
+
+        // Plain offsets and unknown tokens fall back to re-running the query
+        let plain = QueryCursor::parse(Some("4")).unwrap();
+        assert_eq!(plain.token, None);
+        assert!(cache
+            .cached_page(plain, &Redactor::default(), None, limits)
+            .is_none());
+        let unknown = QueryCursor::parse(Some("ff:4")).unwrap();
+        assert!(cache
+            .cached_page(unknown, &Redactor::default(), None, limits)
+            .is_none());
+        assert!(QueryCursor::parse(Some("zz:4")).is_err());
+
+        // A disabled cache keeps plain offset cursors
+        let disabled = ResultCache::disabled();
+        let records: Vec<_> = (0..10).map(|i| serde_json::json!({ "id": i })).collect();
+        let page = disabled.page(
+            records,
+            serde_json::Value::Null,
+            &Redactor::default(),
+            0,
+            Some(4),
+            limits,
+        );
+        assert_eq!(page.next_cursor.as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn test_result_cache_evicts_oldest_and_expired() {
+        let records = || vec![serde_json::json!(1), serde_json::json!(2)];
+        let cache = ResultCache::new(2, Duration::from_secs(60));
+        let a = cache
+            .insert(records(), serde_json::Value::Null, false)
+            .unwrap();
+        let b = cache
+            .insert(records(), serde_json::Value::Null, false)
+            .unwrap();
+        let c = cache
+            .insert(records(), serde_json::Value::Null, false)
+            .unwrap();
+        assert!(cache.get(a, false).is_none());
+        assert!(cache.get(b, false).is_some() && cache.get(c, false).is_some());
+        assert!(cache.get(b, true).is_none());
+
+        let expired = ResultCache::new(2, Duration::ZERO);
+        let token = expired
+            .insert(records(), serde_json::Value::Null, false)
+            .unwrap();
+        assert!(expired.get(token, false).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cached_pages_follow_caller_redaction() {
+        let mut db = BarqGraphDb::open(crate::storage::DbOptions::in_memory()).unwrap();
+        for id in 1..=4 {
+            let mut node = Node::new(id, format!("secret {}", id));
+            node.rule_tags = vec!["pii".to_string()];
+            node.embedding = vec![id as f32, 0.0];
+            db.append_node(node).unwrap();
+        }
+        let db: DbState = Arc::new(Mutex::new(db));
+        let policy: RedactionPolicy = serde_json::from_value(serde_json::json!({
+            "privileged_keys": ["auditor"],
+            "rules": [{"tag": "pii", "fields": ["label"]}]
+        }))
+        .unwrap();
+        let auditor = Redactor::default();
+        let agent = Redactor(Some(Arc::new(policy)));
+        let cache = ResultCache::default();
+
+        let knn = |redactor: Redactor, cursor: Option<String>| {
+            let request = serde_json::from_value(serde_json::json!({
+                "query_embedding": [0.0, 0.0],
+                "k": 4,
+                "limit": 2,
+                "include_nodes": true,
+                "cursor": cursor,
+            }))
+            .unwrap();
+            let (db, cache) = (db.clone(), cache.clone());
+            async move {
+                let response = knn_query(
+                    State(db),
+                    redactor,
+                    ResultLimits::default(),
+                    cache,
+                    Json(request),
+                )
+                .await
+                .unwrap()
+                .into_response();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let first = knn(auditor.clone(), None).await;
+        assert_eq!(first["results"][0]["label"], "secret 1");
+        let cursor = first["next_cursor"].as_str().unwrap().to_string();
+        assert!(cursor.contains(':'));
+
+        // The privileged ranking is cached, but not served to a masked caller
+        let masked = knn(agent.clone(), Some(cursor.clone())).await;
+        assert_eq!(masked["results"][0]["id"], 3);
+        assert_eq!(masked["results"][0]["label"], "[REDACTED]");
+        let unmasked = knn(auditor, Some(cursor)).await;
+        assert_eq!(unmasked["results"][0]["label"], "secret 3");
+
+        let first = knn(agent, None).await;
+        assert_eq!(first["results"][0]["label"], "[REDACTED]");
+    }
+}
@@ -5,15 +5,20 @@
 //! initializing databases, adding nodes, and querying data.
 
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use serde_json::json;
 
 use barq_graphdb::agent::DecisionRecord;
+use barq_graphdb::export::export_ann_benchmark;
 use barq_graphdb::hybrid::HybridParams;
-use barq_graphdb::storage::{BarqGraphDb, DbOptions};
-use barq_graphdb::Node;
+use barq_graphdb::sort::SortSpec;
+use barq_graphdb::storage::{verify_audit_export, BarqGraphDb, Connectivity, DbOptions, IndexType};
+use barq_graphdb::template::{QueryTemplate, TemplateParams, TemplateQuery};
+use barq_graphdb::vector::DistanceMetric;
+use barq_graphdb::{Edge, Node};
 
 /// Barq-GraphDB command-line interface.
 ///
@@ -58,6 +63,10 @@ enum Commands {
         /// Path to the database directory.
         #[arg(long)]
         path: PathBuf,
+
+        /// Sort specification, e.g. "degree:desc,label".
+        #[arg(long, default_value = "")]
+        sort: SortSpec,
     },
 
     /// Add a directed edge between two nodes.
@@ -77,6 +86,33 @@ enum Commands {
         /// Edge type/label.
         #[arg(long, name = "type")]
         edge_type: String,
+
+        /// Optional traversal cost for weighted shortest paths.
+        #[arg(long)]
+        weight: Option<f32>,
+
+        /// Optional edge properties as a JSON object, e.g. '{"since":2021}'.
+        #[arg(long)]
+        properties: Option<String>,
+    },
+
+    /// Remove a directed edge between two nodes.
+    RemoveEdge {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Source node ID.
+        #[arg(long)]
+        from: u64,
+
+        /// Target node ID.
+        #[arg(long)]
+        to: u64,
+
+        /// Edge type/label.
+        #[arg(long, name = "type")]
+        edge_type: String,
     },
 
     /// List neighbors of a node.
@@ -105,6 +141,32 @@ enum Commands {
         hops: usize,
     },
 
+    /// Perform a depth-first traversal from a starting node.
+    Dfs {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Starting node ID.
+        #[arg(long)]
+        start: u64,
+
+        /// Maximum traversal depth.
+        #[arg(long)]
+        depth: usize,
+    },
+
+    /// Report connected components and the islands outside the largest one.
+    Components {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Group by strong (directed) instead of weak connectivity.
+        #[arg(long)]
+        strong: bool,
+    },
+
     /// Set embedding for a node.
     SetEmbedding {
         /// Path to the database directory.
@@ -164,6 +226,10 @@ enum Commands {
         /// Weight for graph distance (0.0 to 1.0).
         #[arg(long, default_value = "0.5")]
         beta: f32,
+
+        /// Optional latency budget in milliseconds (results may be partial).
+        #[arg(long)]
+        budget_ms: Option<u64>,
     },
 
     /// Record an agent decision.
@@ -202,6 +268,126 @@ enum Commands {
         /// Agent ID to filter by.
         #[arg(long)]
         agent_id: u64,
+
+        /// Sort specification, e.g. "score:desc,timestamp".
+        #[arg(long, default_value = "")]
+        sort: SortSpec,
+    },
+
+    /// Save a named query template (bumps the version if it exists).
+    SaveTemplate {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Template name.
+        #[arg(long)]
+        name: String,
+
+        /// Query as JSON, e.g. '{"type":"knn","k":10}'.
+        #[arg(long)]
+        query: String,
+
+        /// Optional description of the template.
+        #[arg(long)]
+        description: Option<String>,
+    },
+
+    /// List saved query templates.
+    ListTemplates {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+    },
+
+    /// Delete a saved query template.
+    DeleteTemplate {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Template name.
+        #[arg(long)]
+        name: String,
+    },
+
+    /// Run a saved query template.
+    RunTemplate {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Template name.
+        #[arg(long)]
+        name: String,
+
+        /// Inputs and overrides as JSON, e.g. '{"vector":[0.1,0.2],"k":5}'.
+        #[arg(long, default_value = "{}")]
+        params: String,
+    },
+
+    /// Replay the WAL into a fresh database with a new index or metric.
+    Rebuild {
+        /// Path to the existing database directory.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Path for the rebuilt database directory.
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Vector index type: linear, hnsw or ivf[:nlist[:nprobe]].
+        #[arg(long, default_value = "hnsw")]
+        index: IndexType,
+
+        /// Distance metric: l2, cosine or dot.
+        #[arg(long, default_value = "l2")]
+        metric: DistanceMetric,
+    },
+
+    /// Verify WAL checksums and referential integrity.
+    Verify {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+    },
+
+    /// Export embeddings and exact ground truth in ANN-benchmarks format.
+    ExportAnn {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Output directory for the .fvecs/.ivecs files.
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Hold out one query vector per this many embeddings.
+        #[arg(long, default_value = "100")]
+        query_stride: usize,
+
+        /// Number of ground-truth neighbors per query.
+        #[arg(long, default_value = "100")]
+        k: usize,
+    },
+
+    /// Export decisions and agent-made nodes and edges as checksummed JSONL.
+    AuditExport {
+        /// Path to the database directory.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Output file for the JSONL export.
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Earliest Unix timestamp to export (inclusive).
+        #[arg(long, default_value = "0")]
+        since: u64,
+
+        /// Latest Unix timestamp to export (inclusive); no limit if omitted.
+        #[arg(long)]
+        until: Option<u64>,
     },
 }
 
@@ -212,15 +398,25 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::Init { path } => init_database(path),
         Commands::AddNode { path, id, label } => add_node(path, id, label),
-        Commands::ListNodes { path } => list_nodes(path),
+        Commands::ListNodes { path, sort } => list_nodes(path, sort),
         Commands::AddEdge {
             path,
             from,
             to,
             edge_type,
-        } => add_edge(path, from, to, edge_type),
+            weight,
+            properties,
+        } => add_edge(path, from, to, edge_type, weight, properties),
+        Commands::RemoveEdge {
+            path,
+            from,
+            to,
+            edge_type,
+        } => remove_edge(path, from, to, edge_type),
         Commands::Neighbors { path, id } => neighbors(path, id),
         Commands::Bfs { path, start, hops } => bfs(path, start, hops),
+        Commands::Dfs { path, start, depth } => dfs(path, start, depth),
+        Commands::Components { path, strong } => components(path, strong),
         Commands::SetEmbedding { path, id, vec } => set_embedding(path, id, vec),
         Commands::Knn { path, vec, k } => knn(path, vec, k),
         Commands::Hybrid {
@@ -231,7 +427,8 @@ fn main() -> Result<()> {
             vec,
             alpha,
             beta,
-        } => hybrid(path, start, hops, k, vec, alpha, beta),
+            budget_ms,
+        } => hybrid(path, start, hops, k, vec, alpha, beta, budget_ms),
         Commands::RecordDecision {
             path,
             agent_id,
@@ -240,7 +437,39 @@ fn main() -> Result<()> {
             score,
             notes,
         } => record_decision(path, agent_id, root, decision_path, score, notes),
-        Commands::ListDecisions { path, agent_id } => list_decisions(path, agent_id),
+        Commands::ListDecisions {
+            path,
+            agent_id,
+            sort,
+        } => list_decisions(path, agent_id, sort),
+        Commands::SaveTemplate {
+            path,
+            name,
+            query,
+            description,
+        } => save_template(path, name, query, description),
+        Commands::ListTemplates { path } => list_templates(path),
+        Commands::DeleteTemplate { path, name } => delete_template(path, name),
+        Commands::RunTemplate { path, name, params } => run_template(path, name, params),
+        Commands::Rebuild {
+            path,
+            out,
+            index,
+            metric,
+        } => rebuild(path, out, index, metric),
+        Commands::Verify { path } => verify(path),
+        Commands::ExportAnn {
+            path,
+            out,
+            query_stride,
+            k,
+        } => export_ann(path, out, query_stride, k),
+        Commands::AuditExport {
+            path,
+            out,
+            since,
+            until,
+        } => audit_export(path, out, since, until),
     }
 }
 
@@ -289,13 +518,13 @@ fn add_node(path: PathBuf, id: u64, label: String) -> Result<()> {
 /// Lists all nodes in the database.
 ///
 /// Outputs a JSON array containing basic information about each node.
-fn list_nodes(path: PathBuf) -> Result<()> {
+fn list_nodes(path: PathBuf, sort: SortSpec) -> Result<()> {
     let opts = DbOptions::new(path.clone());
     let db = BarqGraphDb::open(opts)
         .with_context(|| format!("Failed to open database at {:?}", path))?;
 
     let nodes: Vec<_> = db
-        .list_nodes()
+        .list_nodes_sorted(&sort)?
         .iter()
         .map(|node| {
             json!({
@@ -312,16 +541,53 @@ fn list_nodes(path: PathBuf) -> Result<()> {
 }
 
 /// Adds a directed edge between two nodes.
-fn add_edge(path: PathBuf, from: u64, to: u64, edge_type: String) -> Result<()> {
+fn add_edge(
+    path: PathBuf,
+    from: u64,
+    to: u64,
+    edge_type: String,
+    weight: Option<f32>,
+    properties: Option<String>,
+) -> Result<()> {
     let opts = DbOptions::new(path.clone());
     let mut db = BarqGraphDb::open(opts)
         .with_context(|| format!("Failed to open database at {:?}", path))?;
 
-    db.add_edge(from, to, &edge_type)
+    let mut edge = Edge::new(from, to, edge_type.clone());
+    edge.weight = weight;
+    if let Some(props) = properties {
+        edge.properties = serde_json::from_str(&props)
+            .with_context(|| format!("Failed to parse edge properties: {}", props))?;
+    }
+    db.add_edge_data(edge)
         .with_context(|| format!("Failed to add edge from {} to {}", from, to))?;
 
     let output = json!({
         "status": "ok",
+        "edge": {
+            "from": from,
+            "to": to,
+            "type": edge_type,
+            "weight": weight
+        }
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+/// Removes a directed edge between two nodes.
+fn remove_edge(path: PathBuf, from: u64, to: u64, edge_type: String) -> Result<()> {
+    let opts = DbOptions::new(path.clone());
+    let mut db = BarqGraphDb::open(opts)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+
+    let removed = db
+        .remove_edge(from, to, &edge_type)
+        .with_context(|| format!("Failed to remove edge from {} to {}", from, to))?;
+
+    let output = json!({
+        "status": if removed { "ok" } else { "not_found" },
         "edge": {
             "from": from,
             "to": to,
@@ -361,6 +627,47 @@ fn bfs(path: PathBuf, start: u64, hops: usize) -> Result<()> {
     Ok(())
 }
 
+/// Performs DFS traversal from a node.
+fn dfs(path: PathBuf, start: u64, depth: usize) -> Result<()> {
+    let opts = DbOptions::new(path.clone());
+    let db = BarqGraphDb::open(opts)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+
+    let result = db.dfs_hops(start, depth);
+
+    let output = json!({ "dfs": result });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+/// Prints connected component sizes and the islands outside the largest one.
+fn components(path: PathBuf, strong: bool) -> Result<()> {
+    let opts = DbOptions::new(path.clone());
+    let db = BarqGraphDb::open(opts)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+
+    let connectivity = if strong {
+        Connectivity::Strong
+    } else {
+        Connectivity::Weak
+    };
+    let components = db.connected_components(connectivity);
+    let islands: Vec<_> = (1..components.count())
+        .map(|label| components.members(label))
+        .collect();
+
+    let output = json!({
+        "connectivity": connectivity,
+        "count": components.count(),
+        "sizes": components.sizes(),
+        "islands": islands
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
 /// Sets embedding for a node.
 fn set_embedding(path: PathBuf, id: u64, vec_str: String) -> Result<()> {
     let opts = DbOptions::new(path.clone());
@@ -407,6 +714,7 @@ fn knn(path: PathBuf, vec_str: String, k: usize) -> Result<()> {
 }
 
 /// Performs hybrid query combining vector similarity and graph distance.
+#[allow(clippy::too_many_arguments)]
 fn hybrid(
     path: PathBuf,
     start: u64,
@@ -415,6 +723,7 @@ fn hybrid(
     vec_str: String,
     alpha: f32,
     beta: f32,
+    budget_ms: Option<u64>,
 ) -> Result<()> {
     let opts = DbOptions::new(path.clone());
     let db = BarqGraphDb::open(opts)
@@ -424,9 +733,23 @@ fn hybrid(
         .with_context(|| format!("Failed to parse query vector: {}", vec_str))?;
 
     let params = HybridParams::new(alpha, beta);
-    let results = db.hybrid_query(&query, start, hops, k, params);
+    let (results, partial) = match budget_ms {
+        Some(ms) => {
+            let outcome = db.hybrid_query_with_budget(
+                &query,
+                start,
+                hops,
+                k,
+                params,
+                Duration::from_millis(ms),
+            );
+            (outcome.results, outcome.partial)
+        }
+        None => (db.hybrid_query(&query, start, hops, k, params), false),
+    };
 
     let output = json!({
+        "partial": partial,
         "results": results.iter().map(|r| {
             json!({
                 "id": r.id,
@@ -487,12 +810,12 @@ fn record_decision(
 }
 
 /// Lists decisions for an agent.
-fn list_decisions(path: PathBuf, agent_id: u64) -> Result<()> {
+fn list_decisions(path: PathBuf, agent_id: u64, sort: SortSpec) -> Result<()> {
     let opts = DbOptions::new(path.clone());
     let db = BarqGraphDb::open(opts)
         .with_context(|| format!("Failed to open database at {:?}", path))?;
 
-    let decisions = db.list_decisions_for_agent(agent_id);
+    let decisions = db.list_decisions_sorted(agent_id, &sort)?;
 
     let output = json!({
         "decisions": decisions.iter().map(|d| {
@@ -511,3 +834,161 @@ fn list_decisions(path: PathBuf, agent_id: u64) -> Result<()> {
 
     Ok(())
 }
+
+/// Saves a named query template.
+fn save_template(
+    path: PathBuf,
+    name: String,
+    query_str: String,
+    description: Option<String>,
+) -> Result<()> {
+    let opts = DbOptions::new(path.clone());
+    let mut db = BarqGraphDb::open(opts)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+
+    let query: TemplateQuery = serde_json::from_str(&query_str)
+        .with_context(|| format!("Failed to parse template query: {}", query_str))?;
+    let mut template = QueryTemplate::new(name.clone(), query);
+    template.description = description;
+
+    let version = db
+        .save_template(template)
+        .with_context(|| format!("Failed to save template {}", name))?;
+
+    let output = json!({
+        "status": "ok",
+        "template": {
+            "name": name,
+            "version": version
+        }
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+/// Lists saved query templates.
+fn list_templates(path: PathBuf) -> Result<()> {
+    let opts = DbOptions::new(path.clone());
+    let db = BarqGraphDb::open(opts)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+
+    let output = json!({ "templates": db.list_templates() });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+/// Deletes a saved query template.
+fn delete_template(path: PathBuf, name: String) -> Result<()> {
+    let opts = DbOptions::new(path.clone());
+    let mut db = BarqGraphDb::open(opts)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+
+    let deleted = db
+        .delete_template(&name)
+        .with_context(|| format!("Failed to delete template {}", name))?;
+
+    let output = json!({
+        "status": "ok",
+        "deleted": deleted
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+/// Runs a saved query template.
+fn run_template(path: PathBuf, name: String, params_str: String) -> Result<()> {
+    let opts = DbOptions::new(path.clone());
+    let db = BarqGraphDb::open(opts)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+
+    let params: TemplateParams = serde_json::from_str(&params_str)
+        .with_context(|| format!("Failed to parse template params: {}", params_str))?;
+    let output = db
+        .run_template(&name, &params)
+        .with_context(|| format!("Failed to run template {}", name))?;
+
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+/// Replays a database's WAL into a new directory with different settings.
+fn rebuild(path: PathBuf, out: PathBuf, index: IndexType, metric: DistanceMetric) -> Result<()> {
+    let mut opts = DbOptions::new(out.clone());
+    opts.index_type = index;
+    opts.metric = metric;
+
+    let (_db, report) = BarqGraphDb::rebuild(&DbOptions::new(path.clone()), opts)
+        .with_context(|| format!("Failed to rebuild {:?} into {:?}", path, out))?;
+
+    let output = json!({
+        "status": "ok",
+        "rebuild": {
+            "source": path,
+            "destination": out,
+            "index": format!("{:?}", index),
+            "metric": metric.to_string(),
+            "report": report
+        }
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+/// Verifies a database and exits non-zero if problems are found.
+fn verify(path: PathBuf) -> Result<()> {
+    let report = BarqGraphDb::verify_path(&DbOptions::new(path.clone()))
+        .with_context(|| format!("Failed to verify database at {:?}", path))?;
+
+    let output = json!({
+        "status": if report.is_ok() { "ok" } else { "issues" },
+        "verify": report
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    if !report.is_ok() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Exports embeddings and ground truth for ANN-benchmarks tooling.
+fn export_ann(path: PathBuf, out: PathBuf, query_stride: usize, k: usize) -> Result<()> {
+    let opts = DbOptions::new(path.clone());
+    let db = BarqGraphDb::open(opts)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+
+    let summary = export_ann_benchmark(&db, &out, query_stride, k)
+        .with_context(|| format!("Failed to export to {:?}", out))?;
+
+    let output = json!({
+        "status": "ok",
+        "export": summary
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+/// Writes a checksummed audit export of a time window to a file.
+fn audit_export(path: PathBuf, out: PathBuf, since: u64, until: Option<u64>) -> Result<()> {
+    let opts = DbOptions::new(path.clone());
+    let db = BarqGraphDb::open(opts)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+
+    let export = db.export_audit(since..=until.unwrap_or(u64::MAX))?;
+    std::fs::write(&out, &export).with_context(|| format!("Failed to write {:?}", out))?;
+    let trailer = verify_audit_export(&export)?;
+
+    let output = json!({
+        "status": "ok",
+        "out": out,
+        "export": trailer
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
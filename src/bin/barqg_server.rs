@@ -5,10 +5,11 @@
 
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
 use clap::Parser;
 use tokio::net::TcpListener;
@@ -16,8 +17,14 @@ use tokio::sync::Mutex;
 use tonic::transport::Server;
 
 use barq_graphdb::api;
+use barq_graphdb::disk::{DiskAlert, DiskQuota};
+use barq_graphdb::graph::NeighborOrder;
 use barq_graphdb::grpc;
+use barq_graphdb::metrics::{BackgroundTask, MetricsSink, Operation};
+use barq_graphdb::redaction::RedactionPolicy;
 use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+use barq_graphdb::tier::TierPolicy;
+use barq_graphdb::warmup::WarmupOptions;
 
 /// Barq-GraphDB HTTP Server.
 #[derive(Parser)]
@@ -40,6 +47,116 @@ struct Args {
     /// Port to listen on (gRPC).
     #[arg(long, default_value = "50051")]
     grpc_port: u16,
+
+    /// Seconds between background checkpoints (disabled if omitted).
+    #[arg(long)]
+    checkpoint_interval_secs: Option<u64>,
+
+    /// Prime caches and run canary queries before reporting ready.
+    #[arg(long)]
+    warmup: bool,
+
+    /// JSON file with redaction rules for non-privileged API keys.
+    #[arg(long)]
+    redaction_policy: Option<PathBuf>,
+
+    /// Alert when the database directory and WAL exceed this many bytes.
+    #[arg(long)]
+    max_db_bytes: Option<u64>,
+
+    /// Alert when free space on the database filesystem drops below this many bytes.
+    #[arg(long)]
+    min_free_bytes: Option<u64>,
+
+    /// Reject writes while a disk limit is exceeded.
+    #[arg(long)]
+    read_only_on_disk_breach: bool,
+
+    /// Plain-HTTP URL that receives disk alerts as JSON POSTs.
+    #[arg(long)]
+    disk_alert_webhook: Option<String>,
+
+    /// Maintain per-tag centroid vectors for query routing.
+    #[arg(long)]
+    tag_centroids: bool,
+
+    /// Neighbor visit order for traversals: insertion, node_id or weight.
+    #[arg(long, default_value = "insertion")]
+    neighbor_order: NeighborOrder,
+
+    /// Move nodes untouched for this many days to compressed cold storage.
+    #[arg(long)]
+    cold_after_days: Option<u64>,
+
+    /// Maximum number of items returned by one list or query response.
+    #[arg(long, default_value = "10000")]
+    max_results: usize,
+
+    /// Approximate maximum size of one list or query response, in bytes.
+    #[arg(long, default_value = "16777216")]
+    max_response_bytes: usize,
+}
+
+/// Logs disk alerts and background failures to stderr, optionally
+/// forwarding alerts to an HTTP webhook.
+///
+/// Supports plain `http://host[:port]/path` URLs; each alert is posted
+/// from its own thread so the database lock is never held on I/O.
+struct AlertLogSink {
+    webhook: Option<String>,
+}
+
+impl MetricsSink for AlertLogSink {
+    fn record(&self, _op: Operation, _elapsed: Duration) {}
+
+    fn disk_alert(&self, alert: &DiskAlert) {
+        let body = serde_json::to_string(alert).unwrap_or_default();
+        eprintln!("Disk alert: {}", body);
+        let Some(url) = self.webhook.clone() else {
+            return;
+        };
+        std::thread::spawn(move || {
+            if let Err(e) = post_json(&url, &body) {
+                eprintln!("Failed to deliver disk alert to {}: {}", url, e);
+            }
+        });
+    }
+
+    fn background_error(&self, task: BackgroundTask, error: &anyhow::Error) {
+        eprintln!("Background {:?} failed: {:#}", task, error);
+    }
+}
+
+/// Sends a minimal HTTP/1.1 JSON POST.
+fn post_json(url: &str, body: &str) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind, Read, Write};
+
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "only http:// URLs are supported"))?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let addr = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+
+    let mut stream = std::net::TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    write!(
+        stream,
+        "POST /{} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    )?;
+    let mut status = [0u8; 12];
+    stream.read_exact(&mut status)?;
+    match &status[9..10] {
+        b"2" => Ok(()),
+        _ => Err(Error::other(String::from_utf8_lossy(&status).into_owned())),
+    }
 }
 
 #[tokio::main]
@@ -47,26 +164,80 @@ async fn main() {
     let args = Args::parse();
 
     // Open database
-    let opts = DbOptions::new(args.path.clone());
-    let db = match BarqGraphDb::open(opts) {
+    let mut opts = DbOptions::new(args.path.clone());
+    if let Some(secs) = args.checkpoint_interval_secs {
+        opts = opts.with_checkpoint_interval(Duration::from_secs(secs));
+    }
+    opts = opts
+        .with_tag_centroids(args.tag_centroids)
+        .with_neighbor_order(args.neighbor_order);
+    if let Some(days) = args.cold_after_days {
+        opts = opts.with_tier_policy(TierPolicy::days(days));
+    }
+    if args.max_db_bytes.is_some() || args.min_free_bytes.is_some() {
+        let mut quota = DiskQuota::new().with_read_only_on_breach(args.read_only_on_disk_breach);
+        quota.max_db_bytes = args.max_db_bytes;
+        quota.min_free_bytes = args.min_free_bytes;
+        opts = opts.with_disk_quota(quota);
+    }
+    let mut db = match BarqGraphDb::open(opts) {
         Ok(db) => db,
         Err(e) => {
             eprintln!("Failed to open database: {}", e);
             std::process::exit(1);
         }
     };
+    for warning in db.open_warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let redaction = Arc::new(match &args.redaction_policy {
+        Some(path) => match RedactionPolicy::load(path) {
+            Ok(policy) => policy,
+            Err(e) => {
+                eprintln!("Failed to load redaction policy: {:#}", e);
+                std::process::exit(1);
+            }
+        },
+        None => RedactionPolicy::default(),
+    });
+
+    db.set_metrics_sink(Some(Arc::new(AlertLogSink {
+        webhook: args.disk_alert_webhook.clone(),
+    })));
 
     let state = Arc::new(Mutex::new(db));
+    BarqGraphDb::spawn_checkpoint_thread(&state);
+    BarqGraphDb::spawn_disk_monitor(&state);
+    BarqGraphDb::spawn_tiering_thread(&state);
+
+    // Warm up in the background; /ready reports 503 until it finishes
+    let readiness = api::Readiness::new();
+    if args.warmup {
+        let warm_state = state.clone();
+        let warm_ready = readiness.clone();
+        tokio::task::spawn_blocking(move || {
+            let report = warm_state.blocking_lock().warmup(&WarmupOptions::default());
+            println!(
+                "Warmup complete in {}ms ({} nodes, {} canary queries)",
+                report.elapsed_ms, report.nodes_touched, report.canary_queries
+            );
+            warm_ready.mark_warmed();
+        });
+    } else {
+        readiness.mark_warmed();
+    }
 
     // Spawn gRPC server
     let grpc_addr = format!("{}:{}", args.host, args.grpc_port)
         .parse()
         .expect("Invalid gRPC address");
     let grpc_state = state.clone();
+    let grpc_redaction = redaction.clone();
 
     println!("Barq-GraphDB gRPC server starting on grpc://{}", grpc_addr);
     tokio::spawn(async move {
-        let service = grpc::MyBarqService::new(grpc_state);
+        let service = grpc::MyBarqService::new(grpc_state).with_redaction(grpc_redaction);
         Server::builder()
             .add_service(grpc::barq_rpc::barq_service_server::BarqServiceServer::new(
                 service,
@@ -80,21 +251,63 @@ async fn main() {
     let app = Router::new()
         // Health and stats
         .route("/health", get(api::health_check))
+        .route("/ready", get(api::readiness))
         .route("/stats", get(api::get_stats))
+        .route("/stats/graph", get(api::get_graph_stats))
+        .route("/agents/compare", get(api::compare_agents))
+        .route("/agents/:id/stats", get(api::get_agent_stats))
+        .route("/agents/:id/memories", post(api::remember))
+        .route("/agents/:id/recall", post(api::recall))
+        .route("/centrality", get(api::central_nodes))
+        .route("/hubs", get(api::hubs))
         // Node operations
         .route("/nodes", get(api::list_nodes))
         .route("/nodes/:id", get(api::get_node))
+        .route("/nodes/:id/bfs", get(api::bfs))
         .route("/nodes", post(api::create_node))
         // Edge operations
-        .route("/edges", post(api::create_edge))
+        .route(
+            "/edges",
+            get(api::edges_between)
+                .post(api::create_edge)
+                .delete(api::remove_edge),
+        )
         // Vector operations
         .route("/embeddings", post(api::set_embedding))
         // Query operations
+        .route("/query/knn", post(api::knn_query))
+        .route("/query/route", post(api::route_query))
+        .route("/centroids", get(api::list_centroids))
         .route("/query/hybrid", post(api::hybrid_query))
+        .route("/query/evidence", post(api::evidence_chains))
+        .route("/query/what-if", post(api::what_if_query))
         // Decision operations
         .route("/decisions", get(api::list_decisions))
         .route("/decisions", post(api::record_decision))
+        .route("/decisions/search", post(api::search_decisions))
+        .route("/decisions/usage", get(api::daily_usage))
+        .route("/decisions/:id/chain", get(api::decision_chain))
+        .route("/decisions/:id/validate", get(api::validate_decision))
+        .route("/decisions/:id/redact", post(api::redact_decision))
+        .route("/sessions/:id/decisions", get(api::list_session_decisions))
+        // Saved query templates
+        .route(
+            "/templates",
+            get(api::list_templates).post(api::save_template),
+        )
+        .route(
+            "/templates/:name",
+            get(api::get_template).delete(api::delete_template),
+        )
+        .route("/templates/:name/run", post(api::run_template))
         // Add state
+        .layer(Extension(readiness))
+        .layer(Extension(redaction))
+        .layer(Extension(api::ResultLimits {
+            max_results: args.max_results,
+            max_bytes: args.max_response_bytes,
+        }))
+        .layer(Extension(api::ResultCache::default()))
         .with_state(state);
 
     let addr = format!("{}:{}", args.host, args.port);
@@ -0,0 +1,177 @@
+//! Per-tag centroid vectors for coarse query routing.
+//!
+//! When `DbOptions::tag_centroids` is enabled, the database keeps a
+//! running centroid of the embeddings of all nodes carrying each rule
+//! tag. Centroids are updated incrementally on every write and rebuilt
+//! from the nodes on open, so they cost nothing in the WAL. They live
+//! beside the node map rather than in it, so node counts, kNN results and
+//! traversals never return a summary in place of real data. Routing a
+//! query against a handful of centroids ("which topic cluster does this
+//! belong to?") is far cheaper than a full kNN search, and its answer can
+//! narrow the fine-grained search that follows.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::vector::l2_distance;
+use crate::Node;
+
+/// Summary of the nodes carrying one tag.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TagCentroid {
+    /// Rule tag the centroid summarizes.
+    pub tag: String,
+    /// Mean embedding of the tagged nodes.
+    pub centroid: Vec<f32>,
+    /// Number of tagged nodes with an embedding.
+    pub count: usize,
+}
+
+/// Running sum of the embeddings for one tag.
+#[derive(Debug, Clone, Default)]
+struct Accumulator {
+    sum: Vec<f32>,
+    count: usize,
+}
+
+/// Incrementally maintained centroids keyed by rule tag.
+///
+/// Embeddings whose dimension differs from a tag's existing members are
+/// skipped, so one malformed vector can't corrupt a centroid.
+#[derive(Debug, Clone, Default)]
+pub struct CentroidIndex {
+    tags: HashMap<String, Accumulator>,
+}
+
+impl CentroidIndex {
+    /// Builds centroids from scratch over a set of nodes.
+    pub fn build<'a>(nodes: impl IntoIterator<Item = &'a Node>) -> Self {
+        let mut index = Self::default();
+        for node in nodes {
+            index.add(node);
+        }
+        index
+    }
+
+    /// Adds a node's embedding to the centroids of its tags.
+    pub fn add(&mut self, node: &Node) {
+        self.update(node, 1.0);
+    }
+
+    /// Removes a node's embedding from the centroids of its tags.
+    ///
+    /// Must be called with the node exactly as it was added.
+    pub fn remove(&mut self, node: &Node) {
+        self.update(node, -1.0);
+    }
+
+    fn update(&mut self, node: &Node, sign: f32) {
+        if node.embedding.is_empty() {
+            return;
+        }
+        for tag in &node.rule_tags {
+            let acc = self.tags.entry(tag.clone()).or_default();
+            if acc.count == 0 {
+                acc.sum = vec![0.0; node.embedding.len()];
+            } else if acc.sum.len() != node.embedding.len() {
+                continue;
+            }
+            if sign > 0.0 {
+                acc.count += 1;
+            } else {
+                acc.count = acc.count.saturating_sub(1);
+            }
+            if acc.count == 0 {
+                self.tags.remove(tag);
+                continue;
+            }
+            for (s, x) in acc.sum.iter_mut().zip(&node.embedding) {
+                *s += sign * x;
+            }
+        }
+    }
+
+    /// Returns the centroid of a tag, if any tagged node has an embedding.
+    pub fn get(&self, tag: &str) -> Option<TagCentroid> {
+        self.tags.get(tag).map(|acc| summarize(tag, acc))
+    }
+
+    /// Returns all centroids, ordered by tag.
+    pub fn all(&self) -> Vec<TagCentroid> {
+        let mut centroids: Vec<_> = self
+            .tags
+            .iter()
+            .map(|(tag, acc)| summarize(tag, acc))
+            .collect();
+        centroids.sort_by(|a, b| a.tag.cmp(&b.tag));
+        centroids
+    }
+
+    /// Returns the `n` tags whose centroids are closest to `query`.
+    ///
+    /// # Returns
+    ///
+    /// `(tag, L2 distance)` pairs sorted by distance ascending. Centroids
+    /// of a different dimension than the query are ignored.
+    pub fn nearest(&self, query: &[f32], n: usize) -> Vec<(String, f32)> {
+        let mut ranked: Vec<(String, f32)> = self
+            .tags
+            .iter()
+            .filter(|(_, acc)| acc.sum.len() == query.len())
+            .map(|(tag, acc)| {
+                let centroid = summarize(tag, acc).centroid;
+                (tag.clone(), l2_distance(query, &centroid))
+            })
+            .collect();
+        ranked.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(n);
+        ranked
+    }
+}
+
+fn summarize(tag: &str, acc: &Accumulator) -> TagCentroid {
+    let scale = 1.0 / acc.count as f32;
+    TagCentroid {
+        tag: tag.to_string(),
+        centroid: acc.sum.iter().map(|s| s * scale).collect(),
+        count: acc.count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: u64, embedding: Vec<f32>, tags: &[&str]) -> Node {
+        let mut node = Node::new(id, format!("n{}", id));
+        node.embedding = embedding;
+        node.rule_tags = tags.iter().map(|t| t.to_string()).collect();
+        node
+    }
+
+    #[test]
+    fn test_incremental_centroids_and_routing() {
+        let a = node(1, vec![0.0, 0.0], &["billing"]);
+        let b = node(2, vec![2.0, 0.0], &["billing", "urgent"]);
+        let c = node(3, vec![0.0, 10.0], &["auth"]);
+        let mut index = CentroidIndex::build([&a, &b, &c]);
+
+        let billing = index.get("billing").unwrap();
+        assert_eq!(billing.centroid, vec![1.0, 0.0]);
+        assert_eq!(billing.count, 2);
+        assert_eq!(index.nearest(&[1.0, 1.0], 1)[0].0, "billing");
+
+        index.remove(&b);
+        assert_eq!(index.get("billing").unwrap().centroid, vec![0.0, 0.0]);
+        assert!(index.get("urgent").is_none());
+        assert_eq!(
+            index
+                .all()
+                .iter()
+                .map(|c| c.tag.as_str())
+                .collect::<Vec<_>>(),
+            vec!["auth", "billing"]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Counterfactual ("what-if") graph queries.
+//!
+//! A `Counterfactual` describes a hypothetical removal of nodes and
+//! edges. Queries run through `BarqGraphDb::what_if` see the graph as if
+//! those were gone, without touching the WAL or in-memory state, so an
+//! agent can ask "if service X is down, what breaks?" against live data.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::graph::Direction;
+use crate::hybrid::{HybridParams, HybridResult};
+use crate::storage::BarqGraphDb;
+use crate::NodeId;
+
+/// Nodes and edges hypothetically removed from the graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counterfactual {
+    /// Removed nodes; all edges touching them are ignored too.
+    #[serde(default)]
+    pub removed_nodes: HashSet<NodeId>,
+    /// Removed `(from, to)` pairs; parallel edges are removed together.
+    #[serde(default)]
+    pub removed_edges: HashSet<(NodeId, NodeId)>,
+}
+
+impl Counterfactual {
+    /// Creates a counterfactual that removes nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes a node and every edge touching it.
+    pub fn without_node(mut self, id: NodeId) -> Self {
+        self.removed_nodes.insert(id);
+        self
+    }
+
+    /// Removes all edges from `from` to `to`.
+    pub fn without_edge(mut self, from: NodeId, to: NodeId) -> Self {
+        self.removed_edges.insert((from, to));
+        self
+    }
+
+    /// Returns true if the node is removed.
+    pub fn removes_node(&self, id: NodeId) -> bool {
+        self.removed_nodes.contains(&id)
+    }
+
+    /// Returns true if edges from `from` to `to` are removed.
+    pub fn removes_edge(&self, from: NodeId, to: NodeId) -> bool {
+        self.removed_edges.contains(&(from, to))
+    }
+}
+
+/// Read-only view of a database under a counterfactual.
+///
+/// Created by `BarqGraphDb::what_if`.
+pub struct WhatIf<'a> {
+    db: &'a BarqGraphDb,
+    counterfactual: &'a Counterfactual,
+}
+
+impl BarqGraphDb {
+    /// Returns a view that queries the graph as if the counterfactual's
+    /// nodes and edges were removed.
+    ///
+    /// The database is not modified; the view borrows it immutably.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::counterfactual::Counterfactual;
+    /// use barq_graphdb::graph::Direction;
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// // Which services can no longer reach the database if the gateway (7) is down?
+    /// let down = Counterfactual::new().without_node(7);
+    /// let broken = db.what_if(&down).lost_reachability(42, 5, Direction::Incoming);
+    /// ```
+    pub fn what_if<'a>(&'a self, counterfactual: &'a Counterfactual) -> WhatIf<'a> {
+        WhatIf {
+            db: self,
+            counterfactual,
+        }
+    }
+}
+
+impl WhatIf<'_> {
+    /// Performs BFS traversal from `start` without the removed elements.
+    ///
+    /// Returns an empty vector if `start` itself is removed.
+    pub fn bfs_hops(&self, start: NodeId, max_hops: usize, direction: Direction) -> Vec<NodeId> {
+        self.db
+            .bfs_masked(start, max_hops, direction, self.counterfactual)
+    }
+
+    /// Returns the nodes reachable from `start` today that would become
+    /// unreachable, in BFS discovery order.
+    ///
+    /// Removed nodes are included, since they are lost as well. With
+    /// `Direction::Incoming` this lists the nodes that could no longer
+    /// reach `start`, i.e. its dependents that would break.
+    pub fn lost_reachability(
+        &self,
+        start: NodeId,
+        max_hops: usize,
+        direction: Direction,
+    ) -> Vec<NodeId> {
+        let remaining: HashSet<NodeId> = self
+            .bfs_hops(start, max_hops, direction)
+            .into_iter()
+            .collect();
+        self.db
+            .bfs_hops_directed(start, max_hops, direction)
+            .into_iter()
+            .filter(|id| !remaining.contains(id))
+            .collect()
+    }
+
+    /// Finds the cheapest directed path avoiding the removed elements.
+    ///
+    /// See `BarqGraphDb::shortest_path_weighted`.
+    pub fn shortest_path_weighted(&self, from: NodeId, to: NodeId) -> Option<(Vec<NodeId>, f32)> {
+        if self.counterfactual.removes_node(to) {
+            return None;
+        }
+        self.db.dijkstra(from, to, self.counterfactual)
+    }
+
+    /// Runs a hybrid query that neither traverses nor returns removed
+    /// elements.
+    ///
+    /// See `BarqGraphDb::hybrid_query`.
+    pub fn hybrid_query(
+        &self,
+        query_embedding: &[f32],
+        start: NodeId,
+        max_hops: usize,
+        k: usize,
+        params: HybridParams,
+    ) -> Vec<HybridResult> {
+        self.db
+            .run_hybrid_query(
+                query_embedding,
+                &[start],
+                max_hops,
+                k,
+                params,
+                None,
+                self.counterfactual,
+            )
+            .results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::Node;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_what_if_queries_leave_db_untouched() {
+        let dir = tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        for id in 1..=5 {
+            let mut node = Node::new(id, format!("svc{}", id));
+            node.embedding = vec![id as f32 / 10.0, 0.0];
+            db.append_node(node).unwrap();
+        }
+        // 1 -> 2 -> 4 (cheap), 1 -> 3 -> 4 (expensive), 4 -> 5
+        db.add_weighted_edge(1, 2, "CALLS", 1.0).unwrap();
+        db.add_weighted_edge(2, 4, "CALLS", 1.0).unwrap();
+        db.add_weighted_edge(1, 3, "CALLS", 5.0).unwrap();
+        db.add_weighted_edge(3, 4, "CALLS", 5.0).unwrap();
+        db.add_edge(4, 5, "CALLS").unwrap();
+
+        let down = Counterfactual::new().without_node(2);
+        let view = db.what_if(&down);
+        assert_eq!(
+            view.shortest_path_weighted(1, 5),
+            Some((vec![1, 3, 4, 5], 11.0))
+        );
+        assert_eq!(view.lost_reachability(1, 2, Direction::Outgoing), vec![2]);
+        assert!(view.bfs_hops(2, 3, Direction::Outgoing).is_empty());
+        let hybrid = view.hybrid_query(&[0.2, 0.0], 1, 3, 5, HybridParams::default());
+        assert!(hybrid.iter().all(|r| r.id != 2 && !r.path.contains(&2)));
+
+        let cut = Counterfactual::new().without_edge(4, 5);
+        assert_eq!(
+            db.what_if(&cut)
+                .lost_reachability(5, 3, Direction::Incoming),
+            vec![4, 2, 3, 1]
+        );
+        assert_eq!(db.what_if(&cut).shortest_path_weighted(1, 5), None);
+
+        // The real graph is unchanged
+        assert_eq!(
+            db.shortest_path_weighted(1, 5),
+            Some((vec![1, 2, 4, 5], 3.0))
+        );
+        assert_eq!(db.edge_count(), 5);
+    }
+}
@@ -0,0 +1,225 @@
+//! Conflict-free replicated metadata for offline merges.
+//!
+//! Agents that work disconnected write to their own copy of a database
+//! and sync later with `BarqGraphDb::merge_from`. With
+//! `DbOptions::with_crdt_replica` set, every node write also records a
+//! `NodeClock`: a last-writer-wins stamp per scalar field (label,
+//! embedding, agent ID) and observed-remove sets for rule tags and
+//! outgoing edges. Stamps are Lamport counters tie-broken by replica ID,
+//! so merging is commutative, associative and idempotent: replicas that
+//! have seen the same writes converge no matter the merge order.
+//!
+//! Clocks hold only stamps and set members; field values stay in the
+//! nodes themselves.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::NodeId;
+
+/// A Lamport timestamp tie-broken by the writing replica.
+///
+/// The default stamp (counter 0) means "never written" and loses to
+/// every real write.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Stamp {
+    /// Logical time of the write.
+    pub counter: u64,
+    /// Replica that performed the write.
+    pub replica: u64,
+}
+
+impl Stamp {
+    /// Creates a stamp.
+    pub fn new(counter: u64, replica: u64) -> Self {
+        Self { counter, replica }
+    }
+}
+
+/// Observed-remove set: concurrent add and remove resolve to "added".
+///
+/// Every add is tagged with a unique stamp; a remove tombstones only the
+/// tags it has observed, so an add made elsewhere at the same time
+/// survives the merge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrSet<T: Ord> {
+    adds: BTreeSet<(T, Stamp)>,
+    removed: BTreeSet<(T, Stamp)>,
+}
+
+impl<T: Ord> Default for OrSet<T> {
+    fn default() -> Self {
+        Self {
+            adds: BTreeSet::new(),
+            removed: BTreeSet::new(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> OrSet<T> {
+    /// Returns true if the element is present.
+    pub fn contains(&self, value: &T) -> bool {
+        self.adds
+            .iter()
+            .any(|entry| &entry.0 == value && !self.removed.contains(entry))
+    }
+
+    /// Returns the present elements in order, without duplicates.
+    pub fn elements(&self) -> Vec<T> {
+        let mut elements: Vec<T> = self
+            .adds
+            .iter()
+            .filter(|entry| !self.removed.contains(*entry))
+            .map(|(v, _)| v.clone())
+            .collect();
+        elements.dedup();
+        elements
+    }
+
+    /// Adds an element under a fresh stamp.
+    pub fn add(&mut self, value: T, stamp: Stamp) {
+        self.adds.insert((value, stamp));
+    }
+
+    /// Removes every observed instance of an element.
+    pub fn remove(&mut self, value: &T) {
+        let observed: Vec<(T, Stamp)> = self
+            .adds
+            .iter()
+            .filter(|(v, _)| v == value)
+            .cloned()
+            .collect();
+        self.removed.extend(observed);
+    }
+
+    /// Merges another replica's set into this one.
+    pub fn merge(&mut self, other: &Self) {
+        self.adds.extend(other.adds.iter().cloned());
+        self.removed.extend(other.removed.iter().cloned());
+    }
+
+    fn max_counter(&self) -> u64 {
+        self.adds
+            .iter()
+            .map(|(_, s)| s.counter)
+            .chain(self.removed.iter().map(|(_, s)| s.counter))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Key of an outgoing edge: target node and edge type.
+pub type EdgeKey = (NodeId, String);
+
+/// Replication metadata of one node.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeClock {
+    /// Stamp of the last label write.
+    pub label: Stamp,
+    /// Stamp of the last embedding write.
+    pub embedding: Stamp,
+    /// Stamp of the last agent ID write.
+    pub agent_id: Stamp,
+    /// Rule tags of the node.
+    pub tags: OrSet<String>,
+    /// Outgoing edges of the node.
+    pub edges: OrSet<EdgeKey>,
+    /// Stamp of the write that created the node; the default stamp for
+    /// clocks recorded before creations were stamped.
+    #[serde(default)]
+    pub created: Stamp,
+}
+
+impl NodeClock {
+    /// Merges another replica's clock into this one.
+    pub fn merge(&mut self, other: &Self) {
+        self.label = self.label.max(other.label);
+        self.embedding = self.embedding.max(other.embedding);
+        self.agent_id = self.agent_id.max(other.agent_id);
+        self.tags.merge(&other.tags);
+        self.edges.merge(&other.edges);
+        self.created = self.created.max(other.created);
+    }
+
+    /// Returns true if two clocks belong to nodes created independently
+    /// under the same ID, which must not be merged.
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        let unknown = Stamp::default();
+        self.created != unknown && other.created != unknown && self.created != other.created
+    }
+
+    /// Returns the highest Lamport counter the clock has seen.
+    pub fn max_counter(&self) -> u64 {
+        [
+            self.label.counter,
+            self.embedding.counter,
+            self.agent_id.counter,
+            self.created.counter,
+            self.tags.max_counter(),
+            self.edges.max_counter(),
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+    }
+}
+
+/// Summary of a `BarqGraphDb::merge_from` call.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MergeReport {
+    /// Nodes copied from the other replica.
+    pub nodes_added: usize,
+    /// Existing nodes whose fields or tags changed.
+    pub nodes_updated: usize,
+    /// Edges added by the merge.
+    pub edges_added: usize,
+    /// Edges removed by the merge.
+    pub edges_removed: usize,
+    /// Nodes created independently under the same ID on both replicas,
+    /// left unmerged.
+    pub conflicts: Vec<NodeId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_or_set_add_wins_and_merge_commutes() {
+        let mut a = OrSet::default();
+        a.add("urgent".to_string(), Stamp::new(1, 1));
+        let mut b = a.clone();
+
+        // Replica 1 removes the tag while replica 2 re-adds it concurrently
+        a.remove(&"urgent".to_string());
+        b.add("urgent".to_string(), Stamp::new(2, 2));
+        b.add("billing".to_string(), Stamp::new(3, 2));
+
+        let mut ab = a.clone();
+        ab.merge(&b);
+        let mut ba = b.clone();
+        ba.merge(&a);
+        assert_eq!(ab, ba);
+        assert_eq!(ab.elements(), vec!["billing", "urgent"]);
+
+        // A remove that observed both adds wins
+        ab.remove(&"urgent".to_string());
+        ab.merge(&b);
+        assert!(!ab.contains(&"urgent".to_string()));
+
+        let mut clock = NodeClock {
+            label: Stamp::new(4, 1),
+            ..Default::default()
+        };
+        clock.merge(&NodeClock {
+            label: Stamp::new(4, 2),
+            tags: b,
+            ..Default::default()
+        });
+        assert_eq!(clock.label, Stamp::new(4, 2));
+        assert_eq!(clock.max_counter(), 4);
+    }
+}
@@ -0,0 +1,198 @@
+//! Disk-space guardrails.
+//!
+//! A `DiskQuota` sets soft limits on the size of the database directory
+//! and on the free space left on its filesystem. When a limit is crossed
+//! the database raises a `DiskAlert` (forwarded to the metrics sink) and can switch itself to read-only mode, so writes are rejected
+//! cleanly instead of failing halfway through a WAL append.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Default interval of the background disk monitor.
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Soft limits on disk usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskQuota {
+    /// Maximum bytes used by the database directory and WAL.
+    pub max_db_bytes: Option<u64>,
+    /// Minimum free bytes to keep on the database filesystem.
+    pub min_free_bytes: Option<u64>,
+    /// Whether to reject writes while a limit is exceeded.
+    pub read_only_on_breach: bool,
+    /// Interval of the background monitor started by `spawn_disk_monitor`.
+    pub check_interval: Duration,
+}
+
+impl Default for DiskQuota {
+    fn default() -> Self {
+        Self {
+            max_db_bytes: None,
+            min_free_bytes: None,
+            read_only_on_breach: false,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+        }
+    }
+}
+
+impl DiskQuota {
+    /// Creates a quota with no limits; add them with the `with_*` methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the size of the database directory and WAL.
+    pub fn with_max_db_bytes(mut self, bytes: u64) -> Self {
+        self.max_db_bytes = Some(bytes);
+        self
+    }
+
+    /// Requires this much free space on the database filesystem.
+    pub fn with_min_free_bytes(mut self, bytes: u64) -> Self {
+        self.min_free_bytes = Some(bytes);
+        self
+    }
+
+    /// Switches the database to read-only mode while a limit is exceeded.
+    pub fn with_read_only_on_breach(mut self, enabled: bool) -> Self {
+        self.read_only_on_breach = enabled;
+        self
+    }
+
+    /// Sets how often the background monitor checks disk usage.
+    pub fn with_check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = interval;
+        self
+    }
+
+    /// Returns the limits `status` violates.
+    pub fn breaches(&self, status: &DiskStatus) -> Vec<DiskAlert> {
+        let mut alerts = Vec::new();
+        if let Some(limit) = self.max_db_bytes.filter(|&l| status.db_bytes > l) {
+            alerts.push(DiskAlert::QuotaExceeded {
+                db_bytes: status.db_bytes,
+                limit,
+            });
+        }
+        if let (Some(min_free), Some(free_bytes)) = (self.min_free_bytes, status.free_bytes) {
+            if free_bytes < min_free {
+                alerts.push(DiskAlert::LowDiskSpace {
+                    free_bytes,
+                    min_free,
+                });
+            }
+        }
+        alerts
+    }
+}
+
+/// Measured disk usage of a database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DiskStatus {
+    /// Bytes used by the database directory and WAL.
+    pub db_bytes: u64,
+    /// Free bytes on the database filesystem, if the platform reports it.
+    pub free_bytes: Option<u64>,
+    /// Whether writes are currently rejected.
+    pub read_only: bool,
+}
+
+/// A disk limit was crossed or usage returned below all limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DiskAlert {
+    /// The database grew beyond `DiskQuota::max_db_bytes`.
+    QuotaExceeded { db_bytes: u64, limit: u64 },
+    /// Free space dropped below `DiskQuota::min_free_bytes`.
+    LowDiskSpace { free_bytes: u64, min_free: u64 },
+    /// All limits are satisfied again after a breach.
+    Recovered { db_bytes: u64 },
+}
+
+/// Returns the total size of the files under `path`.
+pub fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        total += if meta.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            meta.len()
+        };
+    }
+    Ok(total)
+}
+
+/// Returns the bytes available to unprivileged users on `path`'s filesystem.
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is a valid NUL-terminated string and `stat` is only
+    // read after `statvfs` reports success.
+    let stat = unsafe {
+        if libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Returns the bytes available on `path`'s filesystem (unsupported here).
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaches_and_dir_size() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), [0u8; 100]).unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("b"), [0u8; 50]).unwrap();
+        assert_eq!(dir_size(dir.path()).unwrap(), 150);
+        #[cfg(unix)]
+        assert!(free_space(dir.path()).is_some());
+
+        let quota = DiskQuota::new()
+            .with_max_db_bytes(120)
+            .with_min_free_bytes(1_000);
+        let status = DiskStatus {
+            db_bytes: 150,
+            free_bytes: Some(500),
+            read_only: false,
+        };
+        assert_eq!(
+            quota.breaches(&status),
+            vec![
+                DiskAlert::QuotaExceeded {
+                    db_bytes: 150,
+                    limit: 120
+                },
+                DiskAlert::LowDiskSpace {
+                    free_bytes: 500,
+                    min_free: 1_000
+                },
+            ]
+        );
+        let healthy = DiskStatus {
+            db_bytes: 100,
+            free_bytes: None,
+            read_only: false,
+        };
+        assert!(quota.breaches(&healthy).is_empty());
+    }
+}
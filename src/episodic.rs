@@ -0,0 +1,209 @@
+//! Episodic memory for agents.
+//!
+//! A memory is a node holding a piece of text an agent wants to keep,
+//! with its embedding, tagged `memory` and linked to the nodes it is
+//! about (the session, the decisions or documents it came from).
+//! `remember` stores one and `recall` finds an agent's memories again by
+//! hybrid retrieval, so agent integrations don't have to wire node
+//! creation, linking and querying together by hand.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::error::BarqError;
+use crate::graph::Direction;
+use crate::hybrid::{HybridFilter, HybridQuery};
+use crate::storage::BarqGraphDb;
+use crate::{Node, NodeId};
+
+/// Rule tag carried by memory nodes.
+pub const MEMORY_TAG: &str = "memory";
+
+/// Edge type from a memory to a node it is about.
+pub const MEMORY_OF: &str = "MEMORY_OF";
+
+/// Vector matches explored from per recalled memory.
+const SEEDS_PER_RESULT: usize = 4;
+
+/// Hops explored from the vector matches during recall.
+const RECALL_HOPS: usize = 2;
+
+/// A memory returned by `BarqGraphDb::recall`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Recollection {
+    /// Node holding the memory.
+    pub id: NodeId,
+    /// Remembered text.
+    pub text: String,
+    /// Unix timestamp the memory was stored at.
+    pub timestamp: u64,
+    /// Hybrid score of the memory (higher is more relevant).
+    pub score: f32,
+    /// Nodes the memory is about.
+    pub links: Vec<NodeId>,
+}
+
+impl BarqGraphDb {
+    /// Stores a memory for an agent.
+    ///
+    /// The memory becomes a new node labelled with the text, carrying
+    /// the agent and embedding, tagged `memory`, with a `MEMORY_OF`
+    /// edge to each linked node.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent_id` - Agent the memory belongs to
+    /// * `text` - Content to remember
+    /// * `embedding` - Embedding of the text, used by `recall`
+    /// * `links` - Nodes the memory is about, e.g. the session node or
+    ///   decision nodes it came from
+    ///
+    /// # Returns
+    ///
+    /// The ID of the new memory node.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a linked node doesn't exist, no node ID is
+    /// left, or the writes fail.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let session = 100;
+    /// let id = db
+    ///     .remember(42, "User prefers metric units", vec![0.1, 0.2, 0.3], &[session])
+    ///     .unwrap();
+    /// ```
+    pub fn remember(
+        &mut self,
+        agent_id: u64,
+        text: impl Into<String>,
+        embedding: Vec<f32>,
+        links: &[NodeId],
+    ) -> Result<NodeId> {
+        if let Some(missing) = links.iter().find(|&&id| self.get_node(id).is_none()) {
+            return Err(BarqError::InvalidOperation(format!(
+                "Cannot link memory to missing node {}",
+                missing
+            ))
+            .into());
+        }
+
+        let id = self.allocate_node_id()?;
+        let mut node = Node::new(id, text.into());
+        node.agent_id = Some(agent_id);
+        node.rule_tags = vec![MEMORY_TAG.to_string()];
+        node.embedding = embedding;
+        self.append_node(node)?;
+
+        let edges: Vec<_> = links.iter().map(|&to| (id, to, MEMORY_OF)).collect();
+        self.add_edges(&edges)?;
+        Ok(id)
+    }
+
+    /// Recalls an agent's memories most relevant to a query.
+    ///
+    /// Runs a hybrid query from the nodes nearest the query, following
+    /// edges both ways, so a memory ranks higher both when its text is
+    /// similar and when it is linked to nodes that are. Only the agent's
+    /// own memories are returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent_id` - Agent whose memories to search
+    /// * `query_embedding` - Embedding of what to recall
+    /// * `k` - Maximum number of memories to return
+    ///
+    /// # Returns
+    ///
+    /// Memories by score, most relevant first.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// for memory in db.recall(42, &[0.1, 0.2, 0.3], 5) {
+    ///     println!("{:.2} {}", memory.score, memory.text);
+    /// }
+    /// ```
+    pub fn recall(&self, agent_id: u64, query_embedding: &[f32], k: usize) -> Vec<Recollection> {
+        let filter = HybridFilter {
+            rule_tags_all: vec![MEMORY_TAG.to_string()],
+            agent_id: Some(agent_id),
+            ..Default::default()
+        };
+        HybridQuery::new(query_embedding)
+            .seeds(k.saturating_mul(SEEDS_PER_RESULT))
+            .max_hops(RECALL_HOPS)
+            .k(k)
+            .direction(Direction::Both)
+            .filter(filter)
+            .run(self)
+            .results
+            .into_iter()
+            .filter_map(|result| {
+                let node = self.get_node(result.id)?;
+                Some(Recollection {
+                    id: node.id,
+                    text: node.label.clone(),
+                    timestamp: node.timestamp,
+                    score: result.score,
+                    links: node
+                        .edges
+                        .iter()
+                        .filter(|e| e.edge_type == MEMORY_OF)
+                        .map(|e| e.to)
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{DbOptions, IndexType};
+
+    #[test]
+    fn test_remember_and_recall() {
+        let mut opts = DbOptions::in_memory();
+        opts.index_type = IndexType::Linear;
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        db.append_node(Node::new(1, "session".to_string())).unwrap();
+
+        let units = db
+            .remember(7, "Prefers metric units", vec![1.0, 0.0], &[1])
+            .unwrap();
+        let tz = db
+            .remember(7, "Lives in UTC+2", vec![0.0, 1.0], &[1])
+            .unwrap();
+        // Another agent's memory right next to the query
+        db.remember(8, "Prefers imperial units", vec![1.0, 0.0], &[])
+            .unwrap();
+
+        let node = db.get_node(units).unwrap();
+        assert_eq!(node.agent_id, Some(7));
+        assert_eq!(node.rule_tags, vec![MEMORY_TAG]);
+        assert_eq!(node.edges[0].edge_type, MEMORY_OF);
+
+        let recalled = db.recall(7, &[0.9, 0.1], 5);
+        let ids: Vec<_> = recalled.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![units, tz]);
+        assert_eq!(recalled[0].text, "Prefers metric units");
+        assert_eq!(recalled[0].links, vec![1]);
+        assert_eq!(db.recall(7, &[0.9, 0.1], 1).len(), 1);
+        assert!(db.recall(9, &[0.9, 0.1], 5).is_empty());
+
+        assert!(db.remember(7, "Orphan", vec![0.5, 0.5], &[99]).is_err());
+        assert!(db.get_node(tz + 2).is_none());
+    }
+}
@@ -24,6 +24,10 @@ pub enum BarqError {
     #[error("Node already exists: {0}")]
     NodeAlreadyExists(u64),
 
+    /// Requested query template was not found.
+    #[error("Query template not found: {0}")]
+    TemplateNotFound(String),
+
     /// Error occurred during WAL (Write-Ahead Log) operations.
     #[error("WAL error: {0}")]
     WalError(String),
@@ -35,7 +39,104 @@ pub enum BarqError {
     /// Database is in an invalid state.
     #[error("Database corrupt: {0}")]
     DatabaseCorrupt(String),
+
+    /// Writes are rejected because a disk quota was exceeded.
+    #[error("Database is read-only: {0}")]
+    ReadOnly(String),
+
+    /// A write would exceed the configured memory budget.
+    #[error("Memory budget exceeded: {required} bytes required, limit is {limit}")]
+    MemoryBudgetExceeded { required: usize, limit: usize },
+}
+
+impl BarqError {
+    /// Returns a stable, machine-readable code for this error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BarqError::Io(_) => "io_error",
+            BarqError::Serialization(_) => "serialization_error",
+            BarqError::NodeNotFound(_) => "node_not_found",
+            BarqError::NodeAlreadyExists(_) => "node_already_exists",
+            BarqError::TemplateNotFound(_) => "template_not_found",
+            BarqError::WalError(_) => "wal_error",
+            BarqError::InvalidOperation(_) => "invalid_operation",
+            BarqError::DatabaseCorrupt(_) => "database_corrupt",
+            BarqError::MemoryBudgetExceeded { .. } => "memory_budget_exceeded",
+            BarqError::ReadOnly(_) => "read_only",
+        }
+    }
+
+    /// Returns true if the same request may succeed when retried later.
+    ///
+    /// Transient I/O and WAL failures and resource exhaustion are
+    /// retryable with backoff; invalid requests and corruption are not.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            BarqError::Io(_)
+                | BarqError::WalError(_)
+                | BarqError::MemoryBudgetExceeded { .. }
+                | BarqError::ReadOnly(_)
+        )
+    }
+}
+
+/// Machine-readable classification of an error for API clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorClass {
+    /// Stable error code, e.g. `node_not_found`.
+    pub code: &'static str,
+    /// Whether clients should retry the request.
+    pub retryable: bool,
+}
+
+/// Classifies an error by the first `BarqError` or I/O error in its chain.
+///
+/// Errors that carry neither are reported as non-retryable `internal`.
+pub fn classify(err: &anyhow::Error) -> ErrorClass {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<BarqError>() {
+            return ErrorClass {
+                code: e.code(),
+                retryable: e.is_retryable(),
+            };
+        }
+        if cause.downcast_ref::<std::io::Error>().is_some() {
+            return ErrorClass {
+                code: "io_error",
+                retryable: true,
+            };
+        }
+    }
+    ErrorClass {
+        code: "internal",
+        retryable: false,
+    }
 }
 
 /// Result type alias for Barq operations.
 pub type BarqResult<T> = Result<T, BarqError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_classify_through_context() {
+        let err: anyhow::Error = BarqError::NodeNotFound(7).into();
+        let err = err.context("while linking");
+        assert_eq!(
+            classify(&err),
+            ErrorClass {
+                code: "node_not_found",
+                retryable: false
+            }
+        );
+
+        let io: anyhow::Result<()> =
+            Err(std::io::Error::other("disk busy")).context("Failed to write WAL");
+        assert!(classify(&io.unwrap_err()).retryable);
+        assert_eq!(classify(&anyhow::anyhow!("boom")).code, "internal");
+    }
+}
@@ -0,0 +1,241 @@
+//! Dataset export for external ANN evaluation tools.
+//!
+//! This module writes a database's embeddings in the TEXMEX
+//! `.fvecs`/`.ivecs` layout used by ANN-benchmarks and big-ann tooling,
+//! together with exact ground truth so Barq's HNSW settings can be
+//! compared against other engines on real data.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::storage::BarqGraphDb;
+use crate::vector::DistanceMetric;
+use crate::NodeId;
+
+/// Summary of an ANN-benchmark export.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnExportSummary {
+    /// Number of base (train) vectors written.
+    pub base_count: usize,
+    /// Number of query (test) vectors written.
+    pub query_count: usize,
+    /// Vector dimension.
+    pub dimension: usize,
+    /// Number of ground-truth neighbors per query.
+    pub k: usize,
+    /// Metric the ground truth was computed with (the database's).
+    pub metric: DistanceMetric,
+}
+
+/// Exports embeddings and exact ground truth in ANN-benchmarks layout.
+///
+/// Every `query_stride`-th embedded node (ordered by ID) is held out as
+/// a query; the rest form the base set. Files written to `out_dir`:
+///
+/// - `base.fvecs` - base vectors
+/// - `query.fvecs` - held-out query vectors
+/// - `groundtruth.ivecs` - row indices into `base.fvecs` of the exact `k` nearest neighbors
+/// - `distances.fvecs` - distances matching `groundtruth.ivecs`
+/// - `ids.json` - NodeIds of the base and query rows
+/// - `dataset.json` - this summary, including the distance metric
+///
+/// Ground truth uses the database's distance metric, so it matches what
+/// its own index returns.
+///
+/// # Arguments
+///
+/// * `db` - Database to export from
+/// * `out_dir` - Output directory (created if missing)
+/// * `query_stride` - Hold out one query per this many vectors (minimum 2)
+/// * `k` - Number of ground-truth neighbors per query
+///
+/// # Errors
+///
+/// Returns an error if the database has no embeddings, embeddings have
+/// mixed dimensions, or the files cannot be written.
+pub fn export_ann_benchmark(
+    db: &BarqGraphDb,
+    out_dir: &Path,
+    query_stride: usize,
+    k: usize,
+) -> Result<AnnExportSummary> {
+    let mut embedded: Vec<(NodeId, &[f32])> = db
+        .list_nodes()
+        .into_iter()
+        .filter(|n| !n.embedding.is_empty())
+        .map(|n| (n.id, n.embedding.as_slice()))
+        .collect();
+    embedded.sort_by_key(|(id, _)| *id);
+
+    let dimension = match embedded.first() {
+        Some((_, v)) => v.len(),
+        None => anyhow::bail!("Database has no embeddings to export"),
+    };
+    if let Some((id, v)) = embedded.iter().find(|(_, v)| v.len() != dimension) {
+        anyhow::bail!(
+            "Node {} has dimension {}, expected {}",
+            id,
+            v.len(),
+            dimension
+        );
+    }
+
+    let stride = query_stride.max(2);
+    let (mut base, mut queries) = (Vec::new(), Vec::new());
+    for (i, entry) in embedded.into_iter().enumerate() {
+        if i % stride == stride - 1 {
+            queries.push(entry);
+        } else {
+            base.push(entry);
+        }
+    }
+
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create export directory: {:?}", out_dir))?;
+
+    write_fvecs(&out_dir.join("base.fvecs"), base.iter().map(|(_, v)| *v))?;
+    write_fvecs(
+        &out_dir.join("query.fvecs"),
+        queries.iter().map(|(_, v)| *v),
+    )?;
+
+    // Exact ground truth by brute force over the base set
+    let metric = db.metric();
+    let k = k.min(base.len());
+    let mut neighbors = Vec::with_capacity(queries.len());
+    let mut distances = Vec::with_capacity(queries.len());
+    for (_, query) in &queries {
+        let mut scored: Vec<(usize, f32)> = base
+            .iter()
+            .enumerate()
+            .map(|(row, (_, v))| (row, metric.distance(query, v)))
+            .collect();
+        scored.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(k);
+        neighbors.push(
+            scored
+                .iter()
+                .map(|(row, _)| *row as i32)
+                .collect::<Vec<_>>(),
+        );
+        distances.push(scored.iter().map(|(_, d)| *d).collect::<Vec<_>>());
+    }
+    write_ivecs(&out_dir.join("groundtruth.ivecs"), &neighbors)?;
+    write_fvecs(
+        &out_dir.join("distances.fvecs"),
+        distances.iter().map(|d| d.as_slice()),
+    )?;
+
+    let ids = serde_json::json!({
+        "base": base.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+        "query": queries.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+    });
+    fs::write(out_dir.join("ids.json"), serde_json::to_vec(&ids)?)
+        .with_context(|| "Failed to write ids.json")?;
+
+    let summary = AnnExportSummary {
+        base_count: base.len(),
+        query_count: queries.len(),
+        dimension,
+        k,
+        metric,
+    };
+    fs::write(out_dir.join("dataset.json"), serde_json::to_vec(&summary)?)
+        .with_context(|| "Failed to write dataset.json")?;
+
+    Ok(summary)
+}
+
+/// Writes vectors in `.fvecs` format (little-endian dim + f32 values per row).
+fn write_fvecs<'a>(path: &Path, rows: impl Iterator<Item = &'a [f32]>) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("Failed to create {:?}", path))?;
+    let mut writer = BufWriter::new(file);
+    for row in rows {
+        writer.write_all(&(row.len() as i32).to_le_bytes())?;
+        for x in row {
+            writer.write_all(&x.to_le_bytes())?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes integer rows in `.ivecs` format (little-endian dim + i32 values per row).
+fn write_ivecs(path: &Path, rows: &[Vec<i32>]) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("Failed to create {:?}", path))?;
+    let mut writer = BufWriter::new(file);
+    for row in rows {
+        writer.write_all(&(row.len() as i32).to_le_bytes())?;
+        for x in row {
+            writer.write_all(&x.to_le_bytes())?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::Node;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_ann_benchmark() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for i in 1..=10u64 {
+            let mut node = Node::new(i, format!("n{}", i));
+            node.embedding = vec![i as f32, 0.0];
+            db.append_node(node).unwrap();
+        }
+
+        let dir = TempDir::new().unwrap();
+        let summary = export_ann_benchmark(&db, dir.path(), 5, 2).unwrap();
+        assert_eq!(summary.query_count, 2);
+        assert_eq!(summary.base_count, 8);
+        assert_eq!(summary.dimension, 2);
+
+        // Each base row is 4 bytes of header + 2 floats
+        let base = fs::read(dir.path().join("base.fvecs")).unwrap();
+        assert_eq!(base.len(), 8 * (4 + 2 * 4));
+
+        // Query node 5 is nearest to base rows holding nodes 4 and 6
+        let gt = fs::read(dir.path().join("groundtruth.ivecs")).unwrap();
+        let first: Vec<i32> = gt[4..12]
+            .chunks(4)
+            .map(|c| i32::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+        let mut rows = first.clone();
+        rows.sort();
+        assert_eq!(rows, vec![3, 4]);
+
+        let dataset: serde_json::Value =
+            serde_json::from_slice(&fs::read(dir.path().join("dataset.json")).unwrap()).unwrap();
+        assert_eq!(dataset["metric"], "l2");
+    }
+
+    #[test]
+    fn test_export_ground_truth_uses_db_metric() {
+        let mut opts = DbOptions::in_memory();
+        opts.metric = DistanceMetric::Cosine;
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        // Node 1 points the query's way but is far; node 2 is near at an angle
+        for (id, embedding) in [(1, [10.0, 0.5]), (2, [1.0, 1.0]), (3, [1.0, 0.05])] {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = embedding.to_vec();
+            db.append_node(node).unwrap();
+        }
+
+        let dir = TempDir::new().unwrap();
+        let summary = export_ann_benchmark(&db, dir.path(), 3, 1).unwrap();
+        assert_eq!(summary.metric, DistanceMetric::Cosine);
+
+        let gt = fs::read(dir.path().join("groundtruth.ivecs")).unwrap();
+        assert_eq!(i32::from_le_bytes(gt[4..8].try_into().unwrap()), 0);
+    }
+}
@@ -1,20 +1,92 @@
 //! Graph index for efficient traversal operations.
 //!
 //! This module provides a graph index structure using adjacency lists
-//! for fast neighbor lookups and BFS traversal.
+//! for fast neighbor lookups and BFS traversal, plus centrality and
+//! clustering metrics computed over any adjacency map.
 
 use std::collections::{HashMap, HashSet, VecDeque};
 
+use serde::{Deserialize, Serialize};
+
 use crate::NodeId;
 
+/// Edge direction followed by a traversal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Follow edges from source to target.
+    #[default]
+    Outgoing,
+    /// Follow edges backwards, from target to source.
+    Incoming,
+    /// Follow edges in either direction.
+    Both,
+}
+
+impl Direction {
+    /// Returns true if outgoing edges are followed.
+    pub fn follows_outgoing(self) -> bool {
+        matches!(self, Direction::Outgoing | Direction::Both)
+    }
+
+    /// Returns true if incoming edges are followed.
+    pub fn follows_incoming(self) -> bool {
+        matches!(self, Direction::Incoming | Direction::Both)
+    }
+}
+
+/// Order in which a traversal expands a node's neighbors.
+///
+/// Adjacency lists keep edges in insertion order, which can differ
+/// between runs, replays and merged databases. Sorting at traversal time
+/// makes BFS and hybrid output reproducible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NeighborOrder {
+    /// Edge insertion order (fastest, not reproducible across histories).
+    #[default]
+    Insertion,
+    /// Ascending node ID.
+    NodeId,
+    /// Ascending edge weight (unweighted edges cost 1.0), ties by node ID.
+    Weight,
+}
+
+impl std::str::FromStr for NeighborOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "insertion" => Ok(NeighborOrder::Insertion),
+            "node_id" | "id" => Ok(NeighborOrder::NodeId),
+            "weight" => Ok(NeighborOrder::Weight),
+            other => Err(format!("Unknown neighbor order: {}", other)),
+        }
+    }
+}
+
 /// In-memory graph index backed by adjacency lists.
 ///
-/// Provides O(1) neighbor lookups and efficient BFS traversal
-/// for exploring connected nodes.
-#[derive(Debug, Default)]
+/// Provides O(1) neighbor lookups in both directions and efficient BFS
+/// traversal for exploring connected nodes. Each edge keeps its type,
+/// and weighted edges record their weight per `(from, to)` pair. This is
+/// the structure `BarqGraphDb` keeps its edges in, so traversals built on
+/// it see the same graph as the database.
+#[derive(Debug, Default, Clone)]
 pub struct GraphIndex {
     /// Adjacency list mapping each node to its outgoing neighbors.
     adjacency: HashMap<NodeId, Vec<NodeId>>,
+    /// Reverse adjacency list mapping each node to the sources of its
+    /// incoming edges.
+    reverse: HashMap<NodeId, Vec<NodeId>>,
+    /// Interned type of each `adjacency` entry, in the same order.
+    types: HashMap<NodeId, Vec<u32>>,
+    /// Edge type names, indexed by interned ID.
+    type_names: Vec<String>,
+    /// Interned ID of each edge type name.
+    type_ids: HashMap<String, u32>,
+    /// Explicit weights of weighted edges; absent pairs are unweighted.
+    weights: HashMap<NodeId, HashMap<NodeId, f32>>,
 }
 
 impl GraphIndex {
@@ -24,12 +96,47 @@ impl GraphIndex {
     ///
     /// A new `GraphIndex` with no edges.
     pub fn new() -> Self {
-        Self {
-            adjacency: HashMap::new(),
+        Self::default()
+    }
+
+    /// Rebuilds an index from its serialized parts.
+    ///
+    /// `types` lists the edge type of each adjacency entry in order;
+    /// missing or mismatched lists leave those edges untyped.
+    ///
+    /// # Arguments
+    ///
+    /// * `adjacency` - Outgoing adjacency list
+    /// * `weights` - Explicit edge weights keyed by source then target
+    /// * `types` - Edge types parallel to `adjacency`
+    pub fn from_parts(
+        adjacency: HashMap<NodeId, Vec<NodeId>>,
+        weights: HashMap<NodeId, HashMap<NodeId, f32>>,
+        mut types: HashMap<NodeId, Vec<String>>,
+    ) -> Self {
+        let mut index = Self {
+            weights,
+            ..Self::default()
+        };
+        for (&from, targets) in &adjacency {
+            let names = types
+                .remove(&from)
+                .filter(|names| names.len() == targets.len())
+                .unwrap_or_else(|| vec![String::new(); targets.len()]);
+            let interned = names.iter().map(|name| index.intern(name)).collect();
+            index.types.insert(from, interned);
+            for &to in targets {
+                index.reverse.entry(to).or_default().push(from);
+            }
         }
+        for sources in index.reverse.values_mut() {
+            sources.sort_unstable();
+        }
+        index.adjacency = adjacency;
+        index
     }
 
-    /// Adds a directed edge from one node to another.
+    /// Adds a directed, untyped edge from one node to another.
     ///
     /// If the source node doesn't exist in the index, it will be created.
     /// Duplicate edges are allowed.
@@ -39,9 +146,101 @@ impl GraphIndex {
     /// * `from` - Source node ID
     /// * `to` - Target node ID
     pub fn add_edge(&mut self, from: NodeId, to: NodeId) {
+        self.add_typed_edge(from, to, "", None);
+    }
+
+    /// Adds a directed edge with a type and optional weight.
+    ///
+    /// Parallel edges between the same pair keep the smallest weight.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Source node ID
+    /// * `to` - Target node ID
+    /// * `edge_type` - Type/label of the edge
+    /// * `weight` - Explicit traversal cost, if any
+    pub fn add_typed_edge(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        edge_type: &str,
+        weight: Option<f32>,
+    ) {
+        let type_id = self.intern(edge_type);
         self.adjacency.entry(from).or_default().push(to);
+        self.types.entry(from).or_default().push(type_id);
         // Ensure target node exists in the adjacency map (may have no outgoing edges)
         self.adjacency.entry(to).or_default();
+        self.reverse.entry(to).or_default().push(from);
+        if let Some(weight) = weight {
+            let entry = self
+                .weights
+                .entry(from)
+                .or_default()
+                .entry(to)
+                .or_insert(weight);
+            *entry = entry.min(weight);
+        }
+    }
+
+    /// Removes the oldest edge from `from` to `to` with the given type.
+    ///
+    /// Untyped edges match any type. The pair's weight is dropped once no
+    /// edge links it anymore.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a matching edge existed and was removed.
+    pub fn remove_edge(&mut self, from: NodeId, to: NodeId, edge_type: &str) -> bool {
+        let Some(pos) = self.position(from, to, edge_type) else {
+            return false;
+        };
+        if let Some(targets) = self.adjacency.get_mut(&from) {
+            targets.remove(pos);
+        }
+        if let Some(types) = self.types.get_mut(&from) {
+            types.remove(pos);
+        }
+        if let Some(sources) = self.reverse.get_mut(&to) {
+            if let Some(pos) = sources.iter().position(|&s| s == from) {
+                sources.remove(pos);
+            }
+            if sources.is_empty() {
+                self.reverse.remove(&to);
+            }
+        }
+        if !self.neighbors(from).is_some_and(|t| t.contains(&to)) {
+            self.set_weight(from, to, None);
+        }
+        true
+    }
+
+    /// Checks whether an edge from `from` to `to` with the given type exists.
+    ///
+    /// Untyped edges match any type.
+    pub fn has_edge(&self, from: NodeId, to: NodeId, edge_type: &str) -> bool {
+        self.position(from, to, edge_type).is_some()
+    }
+
+    /// Replaces the explicit weight of a `(from, to)` pair; `None` clears it.
+    pub fn set_weight(&mut self, from: NodeId, to: NodeId, weight: Option<f32>) {
+        let pair_weights = self.weights.entry(from).or_default();
+        match weight {
+            Some(weight) => {
+                pair_weights.insert(to, weight);
+            }
+            None => {
+                pair_weights.remove(&to);
+            }
+        }
+        if pair_weights.is_empty() {
+            self.weights.remove(&from);
+        }
+    }
+
+    /// Returns the explicit weight of the edge from `from` to `to`, if any.
+    pub fn weight(&self, from: NodeId, to: NodeId) -> Option<f32> {
+        self.weights.get(&from)?.get(&to).copied()
     }
 
     /// Returns the neighbors (outgoing edges) of a node.
@@ -58,6 +257,41 @@ impl GraphIndex {
         self.adjacency.get(&id).map(|v| v.as_slice())
     }
 
+    /// Returns the sources of all edges pointing at a node.
+    ///
+    /// # Returns
+    ///
+    /// A slice of source node IDs (one entry per edge), or `None` if no
+    /// edge points at the node.
+    pub fn incoming(&self, id: NodeId) -> Option<&[NodeId]> {
+        self.reverse.get(&id).map(|v| v.as_slice())
+    }
+
+    /// Iterates the outgoing edges of a node as `(target, edge_type)` pairs.
+    ///
+    /// Untyped edges report an empty type.
+    pub fn typed_neighbors(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &str)> + '_ {
+        let targets = self.adjacency.get(&id).into_iter().flatten();
+        let types = self.types.get(&id).into_iter().flatten();
+        targets
+            .zip(types)
+            .map(|(&to, &t)| (to, self.type_names[t as usize].as_str()))
+    }
+
+    /// Iterates the targets of a node's outgoing edges of one type.
+    ///
+    /// Untyped edges match any type. Parallel edges yield their target
+    /// once per edge.
+    pub fn neighbors_of_type<'a>(
+        &'a self,
+        id: NodeId,
+        edge_type: &'a str,
+    ) -> impl Iterator<Item = NodeId> + 'a {
+        self.typed_neighbors(id)
+            .filter(move |(_, t)| *t == edge_type || t.is_empty())
+            .map(|(to, _)| to)
+    }
+
     /// Performs BFS traversal from a start node up to a maximum depth.
     ///
     /// Returns all nodes reachable within `max_hops` edges from the start.
@@ -107,25 +341,516 @@ impl GraphIndex {
         result
     }
 
+    /// Checks whether `to` can be reached from `from` within `max_hops` edges.
+    ///
+    /// Searches from both ends at once, always expanding the smaller
+    /// frontier, and stops as soon as the two searches meet. A node
+    /// always reaches itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Source node ID
+    /// * `to` - Target node ID
+    /// * `max_hops` - Maximum path length in edges
+    pub fn is_reachable(&self, from: NodeId, to: NodeId, max_hops: usize) -> bool {
+        if from == to {
+            return true;
+        }
+        let mut forward = (HashSet::from([from]), vec![from]);
+        let mut backward = (HashSet::from([to]), vec![to]);
+        for _ in 0..max_hops {
+            let forward_step = forward.1.len() <= backward.1.len();
+            let ((seen, frontier), (other, _)) = if forward_step {
+                (&mut forward, &backward)
+            } else {
+                (&mut backward, &forward)
+            };
+            let mut next = Vec::new();
+            for &node in frontier.iter() {
+                let neighbors = if forward_step {
+                    self.neighbors(node)
+                } else {
+                    self.incoming(node)
+                };
+                for &neighbor in neighbors.unwrap_or_default() {
+                    if other.contains(&neighbor) {
+                        return true;
+                    }
+                    if seen.insert(neighbor) {
+                        next.push(neighbor);
+                    }
+                }
+            }
+            if next.is_empty() {
+                return false;
+            }
+            *frontier = next;
+        }
+        false
+    }
+
+    /// Returns every node reachable from `start` within `max_hops` edges
+    /// in the given direction, excluding `start` itself.
+    ///
+    /// `Outgoing` yields descendants, `Incoming` ancestors and `Both`
+    /// the union of undirected neighborhoods.
+    pub fn reachable_set(
+        &self,
+        start: NodeId,
+        max_hops: usize,
+        direction: Direction,
+    ) -> HashSet<NodeId> {
+        let mut seen = HashSet::from([start]);
+        let mut frontier = vec![start];
+        for _ in 0..max_hops {
+            let mut next = Vec::new();
+            for &node in &frontier {
+                let outgoing = self
+                    .neighbors(node)
+                    .filter(|_| direction.follows_outgoing());
+                let incoming = self.incoming(node).filter(|_| direction.follows_incoming());
+                for &neighbor in outgoing.into_iter().chain(incoming).flatten() {
+                    if seen.insert(neighbor) {
+                        next.push(neighbor);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        seen.remove(&start);
+        seen
+    }
+
     /// Returns the number of nodes in the graph index.
     pub fn node_count(&self) -> usize {
         self.adjacency.len()
     }
 
+    /// Returns the number of nodes with at least one incoming edge.
+    pub fn target_count(&self) -> usize {
+        self.reverse.len()
+    }
+
     /// Returns the total number of edges in the graph.
     pub fn edge_count(&self) -> usize {
         self.adjacency.values().map(|v| v.len()).sum()
     }
 
+    /// Returns the number of `(from, to)` pairs with an explicit weight.
+    pub fn weight_count(&self) -> usize {
+        self.weights.values().map(|w| w.len()).sum()
+    }
+
     /// Checks if a node exists in the graph index.
     pub fn contains_node(&self, id: NodeId) -> bool {
         self.adjacency.contains_key(&id)
     }
 
+    /// Returns the outgoing adjacency list, for algorithms that take one.
+    pub fn adjacency(&self) -> &HashMap<NodeId, Vec<NodeId>> {
+        &self.adjacency
+    }
+
+    /// Returns the explicit edge weights keyed by source then target.
+    pub fn weights(&self) -> &HashMap<NodeId, HashMap<NodeId, f32>> {
+        &self.weights
+    }
+
+    /// Returns the edge types of every source, parallel to `adjacency`.
+    pub fn edge_types(&self) -> HashMap<NodeId, Vec<&str>> {
+        self.types
+            .iter()
+            .map(|(&from, types)| {
+                let names = types
+                    .iter()
+                    .map(|&t| self.type_names[t as usize].as_str())
+                    .collect();
+                (from, names)
+            })
+            .collect()
+    }
+
     /// Clears all nodes and edges from the graph index.
     pub fn clear(&mut self) {
-        self.adjacency.clear();
+        *self = Self::default();
     }
+
+    /// Finds the oldest `from -> to` entry of a type, or an untyped one.
+    fn position(&self, from: NodeId, to: NodeId, edge_type: &str) -> Option<usize> {
+        let type_id = self.type_ids.get(edge_type).copied();
+        let untyped = self.type_ids.get("").copied();
+        let targets = self.adjacency.get(&from)?;
+        let types = self.types.get(&from)?;
+        targets
+            .iter()
+            .zip(types)
+            .position(|(&t, &ty)| t == to && (Some(ty) == type_id || Some(ty) == untyped))
+    }
+
+    /// Returns the interned ID of an edge type, registering it if new.
+    fn intern(&mut self, edge_type: &str) -> u32 {
+        if let Some(&id) = self.type_ids.get(edge_type) {
+            return id;
+        }
+        let id = self.type_names.len() as u32;
+        self.type_names.push(edge_type.to_string());
+        self.type_ids.insert(edge_type.to_string(), id);
+        id
+    }
+}
+
+/// Centrality measure ranking how important a node is to the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Centrality {
+    /// Share of other nodes linked to the node in either direction.
+    #[default]
+    Degree,
+    /// Share of shortest paths between other nodes passing through the node.
+    Betweenness,
+    /// Closeness of the node to the nodes it can reach.
+    Closeness,
+}
+
+impl std::str::FromStr for Centrality {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "degree" => Ok(Centrality::Degree),
+            "betweenness" => Ok(Centrality::Betweenness),
+            "closeness" => Ok(Centrality::Closeness),
+            other => Err(format!("Unknown centrality measure: {}", other)),
+        }
+    }
+}
+
+/// Adjacency map re-indexed to dense vertex numbers, parallel edges and
+/// self-loops dropped.
+struct Dense {
+    ids: Vec<NodeId>,
+    successors: Vec<Vec<usize>>,
+}
+
+impl Dense {
+    fn new(adjacency: &HashMap<NodeId, Vec<NodeId>>) -> Self {
+        let mut ids: Vec<NodeId> = adjacency
+            .iter()
+            .flat_map(|(&from, targets)| std::iter::once(from).chain(targets.iter().copied()))
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let index: HashMap<NodeId, usize> =
+            ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let successors = ids
+            .iter()
+            .map(|id| {
+                let mut targets: Vec<usize> = adjacency
+                    .get(id)
+                    .into_iter()
+                    .flatten()
+                    .filter(|t| *t != id)
+                    .map(|t| index[t])
+                    .collect();
+                targets.sort_unstable();
+                targets.dedup();
+                targets
+            })
+            .collect();
+        Self { ids, successors }
+    }
+
+    fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Sorted neighbors of each vertex, ignoring edge direction.
+    fn undirected(&self) -> Vec<Vec<usize>> {
+        let mut neighbors = self.successors.clone();
+        for (v, targets) in self.successors.iter().enumerate() {
+            for &w in targets {
+                neighbors[w].push(v);
+            }
+        }
+        for list in &mut neighbors {
+            list.sort_unstable();
+            list.dedup();
+        }
+        neighbors
+    }
+
+    fn predecessors(&self) -> Vec<Vec<usize>> {
+        let mut predecessors = vec![Vec::new(); self.len()];
+        for (v, targets) in self.successors.iter().enumerate() {
+            for &w in targets {
+                predecessors[w].push(v);
+            }
+        }
+        predecessors
+    }
+
+    /// Picks `samples` evenly spaced source vertices, or all of them.
+    fn sources(&self, samples: Option<usize>) -> Vec<usize> {
+        let n = self.len();
+        match samples {
+            Some(k) if k > 0 && k < n => (0..k).map(|i| i * n / k).collect(),
+            _ => (0..n).collect(),
+        }
+    }
+
+    fn into_scores(self, scores: Vec<f64>) -> HashMap<NodeId, f64> {
+        self.ids.into_iter().zip(scores).collect()
+    }
+}
+
+/// Hop distances from `source` along `edges`, `usize::MAX` if unreachable.
+fn distances(edges: &[Vec<usize>], source: usize) -> Vec<usize> {
+    let mut dist = vec![usize::MAX; edges.len()];
+    dist[source] = 0;
+    let mut queue = VecDeque::from([source]);
+    while let Some(v) = queue.pop_front() {
+        for &w in &edges[v] {
+            if dist[w] == usize::MAX {
+                dist[w] = dist[v] + 1;
+                queue.push_back(w);
+            }
+        }
+    }
+    dist
+}
+
+/// Computes a centrality score for every node of an adjacency map.
+///
+/// Scores are normalized to 0.0..=1.0. Betweenness and closeness run a
+/// BFS per source node; pass `samples` to use that many evenly spaced
+/// sources instead, trading accuracy for time on large graphs. Degree
+/// centrality is always exact.
+///
+/// # Arguments
+///
+/// * `adjacency` - Outgoing neighbors of each node
+/// * `measure` - Centrality measure to compute
+/// * `samples` - Optional number of BFS sources for approximation
+///
+/// # Returns
+///
+/// A map from node ID to score, covering every node and edge target.
+pub fn centrality(
+    adjacency: &HashMap<NodeId, Vec<NodeId>>,
+    measure: Centrality,
+    samples: Option<usize>,
+) -> HashMap<NodeId, f64> {
+    let dense = Dense::new(adjacency);
+    let scores = match measure {
+        Centrality::Degree => degree_scores(&dense),
+        Centrality::Betweenness => betweenness_scores(&dense, samples),
+        Centrality::Closeness => closeness_scores(&dense, samples),
+    };
+    dense.into_scores(scores)
+}
+
+/// Returns the `k` highest-scoring nodes, ties broken by ascending ID.
+pub fn top_k(scores: &HashMap<NodeId, f64>, k: usize) -> Vec<(NodeId, f64)> {
+    let mut ranked: Vec<(NodeId, f64)> = scores.iter().map(|(&id, &s)| (id, s)).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked.truncate(k);
+    ranked
+}
+
+fn degree_scores(dense: &Dense) -> Vec<f64> {
+    let n = dense.len();
+    let mut degree = vec![0usize; n];
+    for (v, targets) in dense.successors.iter().enumerate() {
+        degree[v] += targets.len();
+        for &w in targets {
+            degree[w] += 1;
+        }
+    }
+    let scale = if n > 1 { 1.0 / (n - 1) as f64 } else { 0.0 };
+    degree.into_iter().map(|d| d as f64 * scale).collect()
+}
+
+/// Brandes' algorithm over unweighted directed edges.
+fn betweenness_scores(dense: &Dense, samples: Option<usize>) -> Vec<f64> {
+    let n = dense.len();
+    let sources = dense.sources(samples);
+    let mut scores = vec![0.0; n];
+    for &source in &sources {
+        let mut order = Vec::with_capacity(n);
+        let mut parents: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut paths = vec![0.0f64; n];
+        let mut dist = vec![usize::MAX; n];
+        paths[source] = 1.0;
+        dist[source] = 0;
+        let mut queue = VecDeque::from([source]);
+        while let Some(v) = queue.pop_front() {
+            order.push(v);
+            for &w in &dense.successors[v] {
+                if dist[w] == usize::MAX {
+                    dist[w] = dist[v] + 1;
+                    queue.push_back(w);
+                }
+                if dist[w] == dist[v] + 1 {
+                    paths[w] += paths[v];
+                    parents[w].push(v);
+                }
+            }
+        }
+
+        let mut dependency = vec![0.0; n];
+        for &w in order.iter().rev() {
+            for &v in &parents[w] {
+                dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+            }
+            if w != source {
+                scores[w] += dependency[w];
+            }
+        }
+    }
+
+    if n < 3 {
+        return vec![0.0; n];
+    }
+    // Extrapolate sampled sources, then normalize by ordered pairs
+    let scale = n as f64 / sources.len() as f64 / ((n - 1) * (n - 2)) as f64;
+    scores.into_iter().map(|s| s * scale).collect()
+}
+
+/// Wasserman-Faust closeness, which stays meaningful on disconnected graphs.
+fn closeness_scores(dense: &Dense, samples: Option<usize>) -> Vec<f64> {
+    let n = dense.len();
+    let sources = dense.sources(samples);
+    let exact = sources.len() == n;
+    let predecessors = dense.predecessors();
+    let mut reached = vec![0usize; n];
+    let mut total = vec![0usize; n];
+    if exact {
+        for v in 0..n {
+            for d in distances(&dense.successors, v) {
+                if d != 0 && d != usize::MAX {
+                    reached[v] += 1;
+                    total[v] += d;
+                }
+            }
+        }
+    } else {
+        // Distances from every node to each sampled target via reverse BFS
+        for &target in &sources {
+            for (v, d) in distances(&predecessors, target).into_iter().enumerate() {
+                if d != 0 && d != usize::MAX {
+                    reached[v] += 1;
+                    total[v] += d;
+                }
+            }
+        }
+    }
+
+    let others = if exact {
+        n.saturating_sub(1)
+    } else {
+        sources.len()
+    };
+    (0..n)
+        .map(|v| {
+            if total[v] == 0 {
+                0.0
+            } else {
+                let r = reached[v] as f64;
+                (r / others as f64) * (r / total[v] as f64)
+            }
+        })
+        .collect()
+}
+
+/// Triangle counts of a graph, ignoring edge direction.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Triangles {
+    /// Distinct triangles in the graph.
+    pub total: usize,
+    /// Triangles each node belongs to.
+    pub per_node: HashMap<NodeId, usize>,
+    /// Share of connected triples that close into a triangle (global
+    /// clustering coefficient, 0.0 to 1.0).
+    pub transitivity: f64,
+}
+
+/// Counts triangles, treating edges as undirected.
+///
+/// Edge direction, parallel edges and self-loops are ignored, so
+/// `a -> b`, `b -> c`, `c -> a` and `a -> b`, `a -> c`, `b -> c` both form
+/// one triangle.
+pub fn triangles(adjacency: &HashMap<NodeId, Vec<NodeId>>) -> Triangles {
+    let dense = Dense::new(adjacency);
+    let neighbors = dense.undirected();
+    let mut counts = vec![0usize; dense.len()];
+    let mut total = 0;
+    // Visit each triangle once as u < v < w
+    for (u, list) in neighbors.iter().enumerate() {
+        for &v in list.iter().filter(|&&v| v > u) {
+            let (mut i, mut j) = (0, 0);
+            let (a, b) = (&neighbors[u], &neighbors[v]);
+            while i < a.len() && j < b.len() {
+                match a[i].cmp(&b[j]) {
+                    std::cmp::Ordering::Less => i += 1,
+                    std::cmp::Ordering::Greater => j += 1,
+                    std::cmp::Ordering::Equal => {
+                        let w = a[i];
+                        if w > v {
+                            total += 1;
+                            counts[u] += 1;
+                            counts[v] += 1;
+                            counts[w] += 1;
+                        }
+                        i += 1;
+                        j += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let triples: usize = neighbors
+        .iter()
+        .map(|list| list.len() * list.len().saturating_sub(1) / 2)
+        .sum();
+    Triangles {
+        total,
+        per_node: dense.ids.iter().copied().zip(counts).collect(),
+        transitivity: if triples == 0 {
+            0.0
+        } else {
+            3.0 * total as f64 / triples as f64
+        },
+    }
+}
+
+/// Computes the local clustering coefficient of every node.
+///
+/// The coefficient is the share of a node's neighbor pairs that are
+/// linked themselves, treating edges as undirected: 1.0 for a node whose
+/// neighbors form a clique, 0.0 for the center of a star or a node with
+/// fewer than two neighbors.
+pub fn clustering_coefficients(adjacency: &HashMap<NodeId, Vec<NodeId>>) -> HashMap<NodeId, f64> {
+    let dense = Dense::new(adjacency);
+    let degrees: Vec<usize> = dense.undirected().iter().map(Vec::len).collect();
+    let per_node = triangles(adjacency).per_node;
+    let scores = dense
+        .ids
+        .iter()
+        .zip(degrees)
+        .map(|(id, d)| {
+            let pairs = d * d.saturating_sub(1) / 2;
+            if pairs == 0 {
+                0.0
+            } else {
+                per_node[id] as f64 / pairs as f64
+            }
+        })
+        .collect();
+    dense.into_scores(scores)
 }
 
 #[cfg(test)]
@@ -252,4 +977,89 @@ mod tests {
         let result = graph2.bfs_hops(1, 5);
         assert_eq!(result, vec![1]);
     }
+
+    #[test]
+    fn test_typed_weighted_edges() {
+        let mut graph = GraphIndex::new();
+        graph.add_typed_edge(1, 2, "CALLS", Some(3.0));
+        graph.add_typed_edge(1, 2, "USES", Some(1.5));
+        graph.add_typed_edge(3, 2, "CALLS", None);
+
+        assert_eq!(graph.incoming(2), Some(&[1, 1, 3][..]));
+        assert_eq!(graph.weight(1, 2), Some(1.5));
+        assert_eq!(
+            graph.typed_neighbors(1).collect::<Vec<_>>(),
+            vec![(2, "CALLS"), (2, "USES")]
+        );
+
+        assert!(!graph.remove_edge(3, 2, "USES"));
+        assert!(graph.remove_edge(1, 2, "USES"));
+        assert_eq!(graph.incoming(2), Some(&[1, 3][..]));
+        // The pair stays weighted while an edge still links it
+        assert_eq!(graph.weight(1, 2), Some(1.5));
+        assert!(graph.remove_edge(1, 2, "CALLS"));
+        assert_eq!(graph.weight(1, 2), None);
+
+        // Rebuilt indexes without type lists treat edges as untyped
+        let rebuilt =
+            GraphIndex::from_parts(graph.adjacency().clone(), HashMap::new(), HashMap::new());
+        assert!(rebuilt.has_edge(3, 2, "ANY"));
+        assert_eq!(rebuilt.incoming(2), Some(&[3][..]));
+    }
+
+    #[test]
+    fn test_centrality_star_and_chain() {
+        // Hub 1 relays everything between 2, 3 and 4
+        let mut graph = GraphIndex::new();
+        for leaf in [2, 3, 4] {
+            graph.add_edge(leaf, 1);
+            graph.add_edge(1, leaf);
+        }
+        graph.add_edge(4, 5);
+
+        let degree = centrality(&graph.adjacency, Centrality::Degree, None);
+        assert_eq!(top_k(&degree, 1)[0].0, 1);
+        assert!((degree[&5] - 0.25).abs() < 1e-9);
+
+        let betweenness = centrality(&graph.adjacency, Centrality::Betweenness, None);
+        let ranked = top_k(&betweenness, 2);
+        assert_eq!((ranked[0].0, ranked[1].0), (1, 4));
+        assert_eq!(betweenness[&2], 0.0);
+
+        // Sampling every node matches the exact result
+        let sampled = centrality(&graph.adjacency, Centrality::Betweenness, Some(5));
+        assert_eq!(sampled, betweenness);
+        let approx = centrality(&graph.adjacency, Centrality::Betweenness, Some(2));
+        assert_eq!(approx.len(), 5);
+
+        let closeness = centrality(&graph.adjacency, Centrality::Closeness, None);
+        assert_eq!(top_k(&closeness, 1)[0].0, 1);
+        assert_eq!(closeness[&5], 0.0);
+        let approx = centrality(&graph.adjacency, Centrality::Closeness, Some(3));
+        assert!(approx[&1] > 0.0);
+    }
+
+    #[test]
+    fn test_triangles_and_clustering() {
+        // Directed cycle 1 -> 2 -> 3 -> 1 plus a tail 3 -> 4
+        let mut graph = GraphIndex::new();
+        graph.add_edge(1, 2);
+        graph.add_edge(2, 3);
+        graph.add_edge(3, 1);
+        graph.add_edge(1, 2);
+        graph.add_edge(3, 4);
+
+        let counts = triangles(&graph.adjacency);
+        assert_eq!(counts.total, 1);
+        assert_eq!(counts.per_node[&3], 1);
+        assert_eq!(counts.per_node[&4], 0);
+        // Triples: one at 1, one at 2, three at 3
+        assert!((counts.transitivity - 3.0 / 5.0).abs() < 1e-9);
+
+        let clustering = clustering_coefficients(&graph.adjacency);
+        assert_eq!(clustering[&1], 1.0);
+        assert!((clustering[&3] - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(clustering[&4], 0.0);
+        assert!(triangles(&HashMap::new()).per_node.is_empty());
+    }
 }
@@ -1,3 +1,7 @@
+use crate::api::API_KEY_HEADER;
+use crate::error::classify;
+use crate::redaction::RedactionPolicy;
+use crate::sort::SortSpec;
 use crate::storage::BarqGraphDb;
 use crate::{Node, NodeId};
 use std::sync::Arc;
@@ -10,17 +14,134 @@ pub mod barq_rpc {
 
 use barq_rpc::barq_service_server::BarqService;
 use barq_rpc::{
-    EdgeProto, EmbeddingProto, Empty, HealthCheckResponse, HybridQueryRequest, HybridQueryResponse,
-    HybridResultProto, NodeIdProto, NodeProto, Result as RpcResult,
+    DecisionProto, EdgeProto, EmbeddingProto, Empty, HealthCheckResponse, HybridQueryRequest,
+    HybridQueryResponse, HybridResultProto, ListDecisionsRequest, ListDecisionsResponse,
+    NodeIdProto, NodeProto, ReasoningStepProto, Result as RpcResult,
 };
 
+/// Builds a failed `Result` carrying the error's code and retryability.
+fn error_result(err: &anyhow::Error) -> RpcResult {
+    let class = classify(err);
+    RpcResult {
+        success: false,
+        error: err.to_string(),
+        error_code: class.code.into(),
+        retryable: class.retryable,
+    }
+}
+
+/// Masks the node fields a redaction policy covers.
+fn redact_node(policy: &RedactionPolicy, tags: &[String], node: &mut NodeProto) {
+    if policy.masks(tags, "label") {
+        node.label = policy.mask().to_string();
+    }
+    if policy.masks(tags, "embedding") {
+        node.embedding.clear();
+    }
+    if policy.masks(tags, "edges") {
+        node.edges.clear();
+    }
+}
+
+/// Masks the hybrid result fields a redaction policy covers.
+fn redact_hybrid_result(policy: &RedactionPolicy, tags: &[String], result: &mut HybridResultProto) {
+    if policy.masks(tags, "label") {
+        result.label = policy.mask().to_string();
+    }
+    if policy.masks(tags, "rule_tags") {
+        result.rule_tags.clear();
+    }
+    if policy.masks(tags, "timestamp") {
+        result.timestamp = 0;
+    }
+    if policy.masks(tags, "agent_id") {
+        result.agent_id = None;
+    }
+    if policy.masks(tags, "path") {
+        result.path.clear();
+    }
+}
+
+/// Masks the decision fields a redaction policy covers.
+///
+/// Fields without an unset value in the proto (IDs, scores, times) are
+/// zeroed, as the HTTP API turns them into `null`.
+fn redact_decision(policy: &RedactionPolicy, tags: &[String], d: &mut DecisionProto) {
+    let masked = |field| policy.masks(tags, field);
+    let mask = || policy.mask().to_string();
+    if masked("agent_id") {
+        d.agent_id = 0;
+    }
+    if masked("created_at") {
+        d.created_at = 0;
+    }
+    if masked("root_node") {
+        d.root_node = 0;
+    }
+    if masked("path") {
+        d.path.clear();
+    }
+    if masked("score") {
+        d.score = 0.0;
+    }
+    if masked("notes") {
+        d.notes = d.notes.as_ref().map(|_| mask());
+    }
+    if masked("node_id") {
+        d.node_id = None;
+    }
+    if masked("session_id") {
+        d.session_id = d.session_id.as_ref().map(|_| mask());
+    }
+    if masked("parent_decision_id") {
+        d.parent_decision_id = None;
+    }
+    if masked("tags") {
+        d.tags.clear();
+    }
+    if masked("steps") {
+        d.steps.clear();
+    }
+    if masked("tokens") {
+        d.tokens = None;
+    }
+    if masked("latency_ms") {
+        d.latency_ms = None;
+    }
+    if masked("cost") {
+        d.cost = None;
+    }
+}
+
 pub struct MyBarqService {
     db: Arc<Mutex<BarqGraphDb>>,
+    redaction: Arc<RedactionPolicy>,
 }
 
 impl MyBarqService {
     pub fn new(db: Arc<Mutex<BarqGraphDb>>) -> Self {
-        Self { db }
+        Self {
+            db,
+            redaction: Arc::default(),
+        }
+    }
+
+    /// Masks node and decision reads with a redaction policy, as the
+    /// HTTP API does. Callers whose `x-api-key` metadata carries a
+    /// privileged key see unredacted data.
+    pub fn with_redaction(mut self, policy: Arc<RedactionPolicy>) -> Self {
+        self.redaction = policy;
+        self
+    }
+
+    /// Returns the policy to apply to a request, or `None` for a
+    /// privileged caller.
+    fn redaction_for<T>(&self, request: &Request<T>) -> Option<Arc<RedactionPolicy>> {
+        let api_key = request
+            .metadata()
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok());
+        (!self.redaction.is_privileged(api_key)).then(|| self.redaction.clone())
     }
 }
 
@@ -48,16 +169,14 @@ impl BarqService for MyBarqService {
         match db.append_node(node) {
             Ok(_) => Ok(Response::new(RpcResult {
                 success: true,
-                error: "".into(),
-            })),
-            Err(e) => Ok(Response::new(RpcResult {
-                success: false,
-                error: e.to_string(),
+                ..Default::default()
             })),
+            Err(e) => Ok(Response::new(error_result(&e))),
         }
     }
 
     async fn get_node(&self, request: Request<NodeIdProto>) -> Result<Response<NodeProto>, Status> {
+        let redaction = self.redaction_for(&request);
         let req = request.into_inner();
         let db = self.db.lock().await;
 
@@ -72,12 +191,16 @@ impl BarqService for MyBarqService {
                 })
                 .collect();
 
-            Ok(Response::new(NodeProto {
+            let mut proto = NodeProto {
                 id: node.id,
                 label: node.label.clone(),
                 embedding: node.embedding.clone(),
                 edges,
-            }))
+            };
+            if let Some(policy) = &redaction {
+                redact_node(policy, &node.rule_tags, &mut proto);
+            }
+            Ok(Response::new(proto))
         } else {
             Err(Status::not_found("Node not found"))
         }
@@ -93,12 +216,9 @@ impl BarqService for MyBarqService {
         match db.add_edge(req.from, req.to, &req.r#type) {
             Ok(_) => Ok(Response::new(RpcResult {
                 success: true,
-                error: "".into(),
-            })),
-            Err(e) => Ok(Response::new(RpcResult {
-                success: false,
-                error: e.to_string(),
+                ..Default::default()
             })),
+            Err(e) => Ok(Response::new(error_result(&e))),
         }
     }
 
@@ -112,12 +232,9 @@ impl BarqService for MyBarqService {
         match db.set_embedding(req.id, req.vec) {
             Ok(_) => Ok(Response::new(RpcResult {
                 success: true,
-                error: "".into(),
-            })),
-            Err(e) => Ok(Response::new(RpcResult {
-                success: false,
-                error: e.to_string(),
+                ..Default::default()
             })),
+            Err(e) => Ok(Response::new(error_result(&e))),
         }
     }
 
@@ -125,11 +242,20 @@ impl BarqService for MyBarqService {
         &self,
         request: Request<HybridQueryRequest>,
     ) -> Result<Response<HybridQueryResponse>, Status> {
+        let redaction = self.redaction_for(&request);
         let req = request.into_inner();
         let db = self.db.lock().await;
 
-        let params = crate::hybrid::HybridParams::new(req.alpha, req.beta);
-        let results = db.hybrid_query(
+        let mut params = crate::hybrid::HybridParams::new(req.alpha, req.beta);
+        // Zero budgets and caps mean "unlimited"
+        if req.budget_ms > 0 {
+            params = params.with_timeout(std::time::Duration::from_millis(req.budget_ms));
+        }
+        if req.max_candidates > 0 {
+            params = params.with_max_candidates(req.max_candidates as usize);
+        }
+        params = params.with_include_nodes(req.include_nodes);
+        let outcome = db.hybrid_query_outcome(
             &req.query_embedding,
             req.start_node as NodeId,
             req.max_hops as usize,
@@ -137,17 +263,188 @@ impl BarqService for MyBarqService {
             params,
         );
 
-        let proto_results = results
+        let proto_results = outcome
+            .results
             .into_iter()
-            .map(|r| HybridResultProto {
-                id: r.id,
-                score: r.score,
-                path: r.path,
+            .map(|r| {
+                let has_node = r.node.is_some();
+                let node = r.node.unwrap_or_default();
+                let mut result = HybridResultProto {
+                    id: r.id,
+                    score: r.score,
+                    path: r.path,
+                    label: node.label,
+                    rule_tags: node.rule_tags.clone(),
+                    timestamp: node.timestamp,
+                    agent_id: node.agent_id,
+                };
+                if let Some(policy) = redaction.as_ref().filter(|_| has_node) {
+                    redact_hybrid_result(policy, &node.rule_tags, &mut result);
+                }
+                result
             })
             .collect();
 
         Ok(Response::new(HybridQueryResponse {
             results: proto_results,
+            partial: outcome.partial,
+            truncated: outcome.truncated,
         }))
     }
+
+    async fn list_decisions(
+        &self,
+        request: Request<ListDecisionsRequest>,
+    ) -> Result<Response<ListDecisionsResponse>, Status> {
+        let redaction = self.redaction_for(&request);
+        let req = request.into_inner();
+        let spec: SortSpec = req.sort.parse().map_err(Status::invalid_argument)?;
+        let offset = req.offset as usize;
+        let limit = match req.limit {
+            0 => usize::MAX,
+            limit => limit as usize,
+        };
+        let db = self.db.lock().await;
+
+        // One extra decision tells whether another page follows
+        let mut decisions = db
+            .list_decisions_page(
+                req.agent_id,
+                req.tag.as_deref(),
+                &spec,
+                offset,
+                limit.saturating_add(1),
+            )
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let more = decisions.len() > limit;
+        decisions.truncate(limit);
+        let next_offset = more.then(|| (offset + decisions.len()) as u32);
+
+        let decisions = decisions
+            .into_iter()
+            .map(|d| {
+                let mut proto = DecisionProto {
+                    id: d.id,
+                    agent_id: d.agent_id,
+                    created_at: d.created_at,
+                    root_node: d.root_node,
+                    path: d.path.clone(),
+                    score: d.score,
+                    notes: d.notes.clone(),
+                    node_id: d.node_id,
+                    session_id: d.session_id.clone(),
+                    parent_decision_id: d.parent_decision_id,
+                    tags: d.tags.clone(),
+                    steps: d
+                        .steps
+                        .iter()
+                        .map(|s| ReasoningStepProto {
+                            node_id: s.node_id,
+                            action: s.action.clone(),
+                            rationale: s.rationale.clone(),
+                            score: s.score,
+                        })
+                        .collect(),
+                    tokens: d.tokens,
+                    latency_ms: d.latency_ms,
+                    cost: d.cost,
+                };
+                // Decisions inherit the tags of the node they start from
+                if let Some(policy) = &redaction {
+                    let tags = db.get_node(d.root_node).map_or(&[][..], |n| &n.rule_tags);
+                    redact_decision(policy, tags, &mut proto);
+                }
+                proto
+            })
+            .collect();
+
+        Ok(Response::new(ListDecisionsResponse {
+            decisions,
+            next_offset,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::DecisionRecord;
+    use crate::storage::{DbOptions, IndexType};
+
+    fn service() -> MyBarqService {
+        let mut opts = DbOptions::in_memory();
+        opts.index_type = IndexType::Linear;
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        let mut node = Node::new(1, "Jane Doe".to_string());
+        node.rule_tags = vec!["pii".to_string()];
+        node.embedding = vec![1.0, 0.0];
+        db.append_node(node).unwrap();
+        let record =
+            DecisionRecord::new(1, 7, 1, vec![1], 0.5).with_notes("called Jane".to_string());
+        db.record_decision(record).unwrap();
+
+        let policy: RedactionPolicy = serde_json::from_value(serde_json::json!({
+            "privileged_keys": ["auditor"],
+            "rules": [
+                {"tag": "pii", "fields": ["label", "embedding"]},
+                {"fields": ["notes"]}
+            ]
+        }))
+        .unwrap();
+        MyBarqService::new(Arc::new(Mutex::new(db))).with_redaction(Arc::new(policy))
+    }
+
+    fn with_key<T>(message: T, api_key: Option<&str>) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(key) = api_key {
+            request
+                .metadata_mut()
+                .insert(API_KEY_HEADER, key.parse().unwrap());
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn test_reads_are_redacted_without_privileged_key() {
+        let service = service();
+        for (api_key, redacted) in [
+            (None, true),
+            (Some("agent"), true),
+            (Some("auditor"), false),
+        ] {
+            let node = service
+                .get_node(with_key(NodeIdProto { id: 1 }, api_key))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(node.label == "[REDACTED]", redacted);
+            assert_eq!(node.embedding.is_empty(), redacted);
+
+            let query = HybridQueryRequest {
+                query_embedding: vec![1.0, 0.0],
+                start_node: 1,
+                k: 1,
+                alpha: 0.5,
+                beta: 0.5,
+                include_nodes: true,
+                ..Default::default()
+            };
+            let results = service
+                .hybrid_query(with_key(query, api_key))
+                .await
+                .unwrap()
+                .into_inner()
+                .results;
+            assert_eq!(results[0].label == "[REDACTED]", redacted);
+
+            let decisions = service
+                .list_decisions(with_key(ListDecisionsRequest::default(), api_key))
+                .await
+                .unwrap()
+                .into_inner()
+                .decisions;
+            let notes = decisions[0].notes.as_deref().unwrap();
+            assert_eq!(notes == "[REDACTED]", redacted);
+        }
+    }
 }
@@ -1,17 +1,90 @@
 //! Hybrid query combining vector similarity and graph distance.
 //!
 //! This module provides hybrid scoring that combines vector embedding
-//! similarity with graph traversal distance for ranking results.
+//! similarity with graph traversal distance for ranking results. The
+//! weighted formula is the default; a custom `HybridScorer` set with
+//! `HybridParams::with_scorer` replaces it. `HybridQuery` bundles a query
+//! and all of its options into one builder.
 
-use crate::NodeId;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::iter;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::graph::Direction;
+use crate::storage::BarqGraphDb;
+use crate::vector::{cosine_distance, l2_distance};
+use crate::{Node, NodeId};
 
 /// Parameters for hybrid scoring.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct HybridParams {
     /// Weight for vector similarity component (0.0 to 1.0).
     pub alpha: f32,
     /// Weight for graph distance component (0.0 to 1.0).
     pub beta: f32,
+    /// Edge direction followed when exploring from the start node.
+    pub direction: Direction,
+    /// Conditions a node must meet to be scored and returned.
+    pub filter: HybridFilter,
+    /// How vector distances are turned into similarities.
+    pub normalization: VectorNormalization,
+    /// Custom scoring function; `None` uses `WeightedScorer` with
+    /// `alpha` and `beta`.
+    pub scorer: Option<Arc<dyn HybridScorer>>,
+    /// Measures graph proximity by accumulated edge weight instead of
+    /// hop count.
+    pub weighted_edges: bool,
+    /// Attaches a `HybridExplanation` to every result.
+    pub explain: bool,
+    /// Maximum number of nodes the traversal may discover.
+    pub max_candidates: Option<usize>,
+    /// Maximum time to spend on traversal and scoring.
+    pub timeout: Option<Duration>,
+    /// Attaches each result's `NodePayload`.
+    pub include_nodes: bool,
+    /// Expands only this many of the best-scoring nodes per hop instead
+    /// of the whole BFS frontier.
+    pub beam_width: Option<usize>,
+    /// Further query vectors scored alongside the query embedding.
+    pub query_vectors: Vec<WeightedVector>,
+    /// How similarities to several query vectors are combined.
+    pub aggregation: VectorAggregation,
+    /// How graph proximity is measured.
+    pub graph_proximity: GraphProximity,
+    /// Attaches the types of the edges along each result's path.
+    pub include_edge_types: bool,
+    /// Lowest score a result may have.
+    pub min_score: Option<f32>,
+}
+
+impl fmt::Debug for HybridParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HybridParams")
+            .field("alpha", &self.alpha)
+            .field("beta", &self.beta)
+            .field("direction", &self.direction)
+            .field("filter", &self.filter)
+            .field("normalization", &self.normalization)
+            .field("scorer", &self.scorer.as_ref().map(|_| "custom"))
+            .field("weighted_edges", &self.weighted_edges)
+            .field("explain", &self.explain)
+            .field("max_candidates", &self.max_candidates)
+            .field("timeout", &self.timeout)
+            .field("include_nodes", &self.include_nodes)
+            .field("beam_width", &self.beam_width)
+            .field("query_vectors", &self.query_vectors.len())
+            .field("aggregation", &self.aggregation)
+            .field("graph_proximity", &self.graph_proximity)
+            .field("include_edge_types", &self.include_edge_types)
+            .field("min_score", &self.min_score)
+            .finish()
+    }
 }
 
 impl Default for HybridParams {
@@ -19,6 +92,21 @@ impl Default for HybridParams {
         Self {
             alpha: 0.5,
             beta: 0.5,
+            direction: Direction::Outgoing,
+            filter: HybridFilter::default(),
+            normalization: VectorNormalization::default(),
+            scorer: None,
+            weighted_edges: false,
+            explain: false,
+            max_candidates: None,
+            timeout: None,
+            include_nodes: false,
+            beam_width: None,
+            query_vectors: Vec::new(),
+            aggregation: VectorAggregation::default(),
+            graph_proximity: GraphProximity::default(),
+            include_edge_types: false,
+            min_score: None,
         }
     }
 }
@@ -31,23 +119,508 @@ impl HybridParams {
     /// * `alpha` - Weight for vector similarity (higher = more emphasis on similarity)
     /// * `beta` - Weight for graph distance (higher = more emphasis on graph proximity)
     pub fn new(alpha: f32, beta: f32) -> Self {
-        Self { alpha, beta }
+        Self {
+            alpha,
+            beta,
+            direction: Direction::Outgoing,
+            filter: HybridFilter::default(),
+            normalization: VectorNormalization::default(),
+            scorer: None,
+            weighted_edges: false,
+            explain: false,
+            max_candidates: None,
+            timeout: None,
+            include_nodes: false,
+            beam_width: None,
+            query_vectors: Vec::new(),
+            aggregation: VectorAggregation::default(),
+            graph_proximity: GraphProximity::default(),
+            include_edge_types: false,
+            min_score: None,
+        }
+    }
+
+    /// Sets the edge direction followed by the graph traversal.
+    ///
+    /// `Direction::Both` treats the graph as undirected, so nodes that
+    /// point at the start node are also candidates.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Restricts results to nodes matching `filter`.
+    ///
+    /// Filtered-out nodes are still traversed, so they don't cut off the
+    /// nodes behind them, but they are never scored or counted towards
+    /// `k`.
+    pub fn with_filter(mut self, filter: HybridFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets how vector distances are turned into similarities.
+    pub fn with_normalization(mut self, normalization: VectorNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Ranks candidates with `scorer` instead of the weighted formula.
+    ///
+    /// `alpha` and `beta` are then unused unless the scorer reads them.
+    pub fn with_scorer(mut self, scorer: Arc<dyn HybridScorer>) -> Self {
+        self.scorer = Some(scorer);
+        self
+    }
+
+    /// Scores graph proximity by accumulated edge weight.
+    ///
+    /// Each result's `graph_cost` becomes the Dijkstra distance from the
+    /// start among the nodes within `max_hops`, with unweighted edges
+    /// costing 1.0, so strong (cheap) relationships pull results closer
+    /// than weak ones. Paths follow the cheapest route.
+    pub fn with_weighted_edges(mut self, weighted: bool) -> Self {
+        self.weighted_edges = weighted;
+        self
+    }
+
+    /// Reports how each result's score came about in
+    /// `HybridResult::explanation`.
+    pub fn with_explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    /// Stops the traversal once it has discovered `max` nodes.
+    ///
+    /// Guards against a start node on a dense graph expanding into the
+    /// whole database. Nodes are discovered in BFS order, so the ones
+    /// closest to the start are kept; `HybridOutcome::truncated` reports
+    /// when the cap was hit.
+    pub fn with_max_candidates(mut self, max: usize) -> Self {
+        self.max_candidates = Some(max);
+        self
+    }
+
+    /// Stops traversal and scoring once `timeout` has elapsed.
+    ///
+    /// The best results found so far are returned and
+    /// `HybridOutcome::partial` is set.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Attaches the label, rule tags, timestamp and agent of each
+    /// result's node in `HybridResult::node`, saving a lookup per result.
+    pub fn with_include_nodes(mut self, include: bool) -> Self {
+        self.include_nodes = include;
+        self
+    }
+
+    /// Explores by beam search: at each hop, only the `width` nodes of
+    /// the newly reached layer with the best hybrid score are expanded.
+    ///
+    /// Every reached node is still a candidate, but the traversal no
+    /// longer grows with the full frontier, which keeps deep (5-6 hop)
+    /// queries on large graphs tractable. Graph distances and paths are
+    /// those of the beam, so they may be longer than the BFS ones.
+    pub fn with_beam_width(mut self, width: usize) -> Self {
+        self.beam_width = Some(width);
+        self
+    }
+
+    /// Scores candidates against `vectors` as well as the query
+    /// embedding, which counts with weight 1.0.
+    ///
+    /// Each vector's distances are normalized on their own, then the
+    /// similarities are combined by `aggregation` into the result's
+    /// vector similarity, e.g. to match both a question and a summary of
+    /// the conversation so far. Vectors whose dimension differs from the
+    /// query embedding are ignored.
+    pub fn with_query_vectors(
+        mut self,
+        vectors: Vec<WeightedVector>,
+        aggregation: VectorAggregation,
+    ) -> Self {
+        self.query_vectors = vectors;
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Sets how graph proximity is measured.
+    ///
+    /// `GraphProximity::PersonalizedPageRank` rewards nodes reached along
+    /// many paths, not just a short one; it can be combined with
+    /// `with_weighted_edges`, which then makes cheap edges likelier steps.
+    pub fn with_graph_proximity(mut self, proximity: GraphProximity) -> Self {
+        self.graph_proximity = proximity;
+        self
+    }
+
+    /// Lists the types of the edges along each result's path in
+    /// `HybridResult::edge_types`, so the path reads as a chain such as
+    /// `user -OWNS-> doc -CITES-> paper`.
+    pub fn with_edge_types(mut self, include: bool) -> Self {
+        self.include_edge_types = include;
+        self
+    }
+
+    /// Drops results scoring below `min_score`, so a query returns only
+    /// results above a quality bar and possibly fewer than `k`.
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
+    /// Whether `score` meets `min_score`.
+    pub(crate) fn accepts_score(&self, score: f32) -> bool {
+        self.min_score.is_none_or(|min| score >= min)
+    }
+
+    /// Distances from `embedding` to each of the `query_vectors`.
+    pub(crate) fn query_distances(&self, embedding: &[f32]) -> Vec<f32> {
+        self.query_vectors
+            .iter()
+            .map(|v| self.normalization.distance(&v.embedding, embedding))
+            .collect()
+    }
+}
+
+/// What a `HybridScorer` knows about one candidate.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreInput<'a> {
+    /// Raw distance from the query vector.
+    pub vector_distance: f32,
+    /// `vector_distance` mapped to `[0, 1]` by the query's
+    /// `VectorNormalization`.
+    pub vector_similarity: f32,
+    /// Number of hops from the start node.
+    pub graph_distance: usize,
+    /// Graph distance used for proximity: the hop count, or the total
+    /// edge weight with `HybridParams::with_weighted_edges`.
+    pub graph_cost: f32,
+    /// The candidate node, for scoring by tags, agent or age; `None` when
+    /// scoring a bare distance pair.
+    pub node: Option<&'a Node>,
+}
+
+/// Scoring function for hybrid query candidates.
+///
+/// # Example
+///
+/// ```rust
+/// use barq_graphdb::hybrid::{HybridParams, HybridScorer, ScoreInput};
+/// use std::sync::Arc;
+///
+/// /// Prefers similar nodes, boosting those tagged "verified".
+/// struct Verified;
+///
+/// impl HybridScorer for Verified {
+///     fn score(&self, input: &ScoreInput<'_>) -> f32 {
+///         let verified = input
+///             .node
+///             .is_some_and(|n| n.rule_tags.iter().any(|t| t == "verified"));
+///         input.vector_similarity + if verified { 0.5 } else { 0.0 }
+///     }
+/// }
+///
+/// let params = HybridParams::default().with_scorer(Arc::new(Verified));
+/// ```
+pub trait HybridScorer: Send + Sync {
+    /// Scores a candidate; higher scores rank first.
+    fn score(&self, input: &ScoreInput<'_>) -> f32;
+}
+
+/// The default scorer:
+/// `alpha * vector_similarity + beta * (1 / (1 + graph_cost))`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightedScorer {
+    /// Weight for vector similarity.
+    pub alpha: f32,
+    /// Weight for graph proximity.
+    pub beta: f32,
+}
+
+impl HybridScorer for WeightedScorer {
+    fn score(&self, input: &ScoreInput<'_>) -> f32 {
+        // Convert graph distance to similarity (decreases with distance)
+        let graph_sim = 1.0 / (1.0 + input.graph_cost);
+
+        self.alpha * input.vector_similarity + self.beta * graph_sim
+    }
+}
+
+/// Mapping from vector distance to a similarity in `[0, 1]`.
+///
+/// The default clamps L2 distance at 1.0, which suits unit-scale
+/// vectors but gives every candidate zero similarity on typical model
+/// embeddings, whose distances are well above 1; the other modes keep
+/// `alpha` meaningful there.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum VectorNormalization {
+    /// `1 - min(l2, 1)`.
+    #[default]
+    Clamp,
+    /// L2 distance rescaled over the candidate set, so the closest
+    /// candidate scores 1 and the farthest 0.
+    MinMax,
+    /// `2 / (1 + e^(l2 / scale))`: 1 at distance 0, about 0.54 at
+    /// `scale` and approaching 0 beyond a few times `scale`.
+    Sigmoid {
+        /// Distance at which similarity has dropped to about half.
+        scale: f32,
+    },
+    /// `1 - cosine_distance / 2`, which ignores vector magnitude.
+    /// Reported vector distances are cosine distances in this mode.
+    Cosine,
+}
+
+impl VectorNormalization {
+    /// Computes the vector distance this mode normalizes.
+    pub fn distance(&self, query: &[f32], embedding: &[f32]) -> f32 {
+        match self {
+            VectorNormalization::Cosine => cosine_distance(query, embedding),
+            _ => l2_distance(query, embedding),
+        }
+    }
+
+    /// Converts a distance to a similarity in `[0, 1]`.
+    ///
+    /// `range` is the lowest and highest distance among the candidates,
+    /// used only by `MinMax`.
+    pub fn similarity(&self, distance: f32, range: (f32, f32)) -> f32 {
+        match *self {
+            VectorNormalization::Clamp => 1.0 - distance.min(1.0),
+            VectorNormalization::MinMax => {
+                let (min, max) = range;
+                if max > min {
+                    (1.0 - (distance - min) / (max - min)).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                }
+            }
+            VectorNormalization::Sigmoid { scale } => {
+                if scale > 0.0 {
+                    2.0 / (1.0 + (distance.max(0.0) / scale).exp())
+                } else {
+                    f32::from(distance <= 0.0)
+                }
+            }
+            VectorNormalization::Cosine => (1.0 - distance / 2.0).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Measure of how close a candidate is to the start node(s) in the graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum GraphProximity {
+    /// Hop count (or edge weight with weighted edges) of the shortest
+    /// path: `graph_similarity = 1 / (1 + graph_cost)`.
+    #[default]
+    Hops,
+    /// Personalized PageRank from the start nodes over the explored
+    /// subgraph: a random walk that follows an edge with probability
+    /// `damping` and jumps back to a start otherwise. Nodes connected
+    /// through many paths rank higher than ones behind a single long
+    /// chain. Each result's `graph_cost` becomes `1 / p - 1`, where `p`
+    /// is its rank relative to the top-ranked node, so its graph
+    /// similarity is `p`.
+    PersonalizedPageRank {
+        /// Probability of following an edge rather than restarting.
+        #[serde(default = "default_damping")]
+        damping: f32,
+        /// Maximum number of power iterations.
+        #[serde(default = "default_iterations")]
+        iterations: usize,
+    },
+}
+
+fn default_damping() -> f32 {
+    0.85
+}
+
+fn default_iterations() -> usize {
+    30
+}
+
+impl GraphProximity {
+    /// Personalized PageRank with the usual damping of 0.85.
+    pub fn personalized_pagerank() -> Self {
+        GraphProximity::PersonalizedPageRank {
+            damping: default_damping(),
+            iterations: default_iterations(),
+        }
+    }
+}
+
+/// A further query vector and its weight in the aggregate similarity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightedVector {
+    /// Query vector.
+    pub embedding: Vec<f32>,
+    /// Relative weight; the main query embedding has weight 1.0.
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+impl WeightedVector {
+    /// Creates a query vector with the given weight.
+    pub fn new(embedding: Vec<f32>, weight: f32) -> Self {
+        Self { embedding, weight }
+    }
+}
+
+/// How similarities to several query vectors become one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorAggregation {
+    /// The best similarity to any vector, ignoring weights: a node only
+    /// has to match one of them.
+    #[default]
+    Max,
+    /// Weighted mean of the similarities: a node has to match all of
+    /// them, the heavier ones more.
+    WeightedMean,
+}
+
+impl VectorAggregation {
+    /// Combines `(similarity, weight)` pairs.
+    pub fn combine(&self, similarities: impl Iterator<Item = (f32, f32)>) -> f32 {
+        match self {
+            VectorAggregation::Max => similarities.map(|(s, _)| s).fold(0.0, f32::max),
+            VectorAggregation::WeightedMean => {
+                let (sum, weights) = similarities.fold((0.0, 0.0), |(sum, weights), (s, w)| {
+                    (sum + s * w, weights + w)
+                });
+                if weights > 0.0 {
+                    sum / weights
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Node conditions for hybrid results; empty conditions match every node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HybridFilter {
+    /// Node must carry at least one of these rule tags.
+    pub rule_tags_any: Vec<String>,
+    /// Node must carry all of these rule tags.
+    pub rule_tags_all: Vec<String>,
+    /// Node must have been created by this agent.
+    pub agent_id: Option<u64>,
+    /// Earliest node timestamp, inclusive.
+    pub min_timestamp: Option<u64>,
+    /// Latest node timestamp, inclusive.
+    pub max_timestamp: Option<u64>,
+}
+
+impl HybridFilter {
+    /// Returns `true` if `node` meets every condition.
+    pub fn matches(&self, node: &Node) -> bool {
+        let has = |tag: &String| node.rule_tags.contains(tag);
+        (self.rule_tags_any.is_empty() || self.rule_tags_any.iter().any(has))
+            && self.rule_tags_all.iter().all(has)
+            && self
+                .agent_id
+                .is_none_or(|agent| node.agent_id == Some(agent))
+            && self.min_timestamp.is_none_or(|min| node.timestamp >= min)
+            && self.max_timestamp.is_none_or(|max| node.timestamp <= max)
     }
 }
 
 /// Result of a hybrid query including both vector and graph metrics.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct HybridResult {
     /// Node ID of this result.
     pub id: NodeId,
     /// Combined hybrid score (higher is better).
     pub score: f32,
-    /// L2 distance from query vector.
+    /// Distance from the query vector: L2, or cosine distance under
+    /// `VectorNormalization::Cosine`.
     pub vector_distance: f32,
     /// Number of hops from start node.
     pub graph_distance: usize,
-    /// BFS path from start node to this node.
+    /// Distance used for graph proximity: the hop count, or the total
+    /// edge weight along `path` with weighted edges.
+    pub graph_cost: f32,
+    /// Path from start node to this node.
+    ///
+    /// A shortest path by hop count from the closest start, as found by
+    /// the BFS; with weighted edges, the cheapest path instead, and with
+    /// beam search, the shortest along the edges the beam followed.
     pub path: Vec<NodeId>,
+    /// Types of the edges along `path`: `edge_types[i]` joins `path[i]`
+    /// and `path[i + 1]`, in whichever direction the query followed it.
+    /// Empty unless the query asked for edge types; an edge whose type
+    /// is unknown (its source has no node record) is `""`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub edge_types: Vec<String>,
+    /// Score breakdown, present when the query asked to explain.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<HybridExplanation>,
+    /// Data of the result's node, present when the query asked for it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<NodePayload>,
+    /// Distances to each of `HybridParams::query_vectors`, in order;
+    /// `vector_distance` stays the distance to the query embedding.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub query_distances: Vec<f32>,
+}
+
+/// The descriptive fields of a result's node, without its embedding
+/// and edges.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NodePayload {
+    /// Label of the node.
+    pub label: String,
+    /// Rule tags of the node.
+    pub rule_tags: Vec<String>,
+    /// Timestamp of the node.
+    pub timestamp: u64,
+    /// Agent that created the node, if any.
+    pub agent_id: Option<u64>,
+}
+
+impl From<&Node> for NodePayload {
+    fn from(node: &Node) -> Self {
+        Self {
+            label: node.label.clone(),
+            rule_tags: node.rule_tags.clone(),
+            timestamp: node.timestamp,
+            agent_id: node.agent_id,
+        }
+    }
+}
+
+/// How a hybrid result was reached and scored.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HybridExplanation {
+    /// Start node (or kNN seed) the result was reached from; `None` when
+    /// no traversal took place.
+    pub seed: Option<NodeId>,
+    /// Node whose expansion reached the result, i.e. the frontier it
+    /// came from; `None` for a start node itself.
+    pub via: Option<NodeId>,
+    /// Normalized vector similarity in `[0, 1]`.
+    pub vector_similarity: f32,
+    /// Graph similarity, `1 / (1 + graph_cost)`.
+    pub graph_similarity: f32,
+    /// `alpha * vector_similarity`; `None` under a custom scorer, whose
+    /// score need not be a weighted sum.
+    pub vector_contribution: Option<f32>,
+    /// `beta * graph_similarity`; `None` under a custom scorer.
+    pub graph_contribution: Option<f32>,
 }
 
 impl HybridResult {
@@ -64,7 +637,227 @@ impl HybridResult {
             score,
             vector_distance,
             graph_distance,
+            graph_cost: graph_distance as f32,
             path,
+            explanation: None,
+            node: None,
+            query_distances: Vec::new(),
+            edge_types: Vec::new(),
+        }
+    }
+}
+
+/// Outcome of a hybrid query that may have been cut short.
+#[derive(Debug, Clone, Default)]
+pub struct HybridOutcome {
+    /// Best results found, sorted by score descending.
+    pub results: Vec<HybridResult>,
+    /// True if the query ran out of time and results may be incomplete.
+    pub partial: bool,
+    /// True if the traversal stopped at `HybridParams::max_candidates`
+    /// nodes, leaving part of the neighborhood unexplored.
+    pub truncated: bool,
+}
+
+/// A hybrid query and all of its options, built step by step.
+///
+/// Unset options keep their defaults: no start node (explore from the
+/// query's 5 nearest vector matches), 3 hops, 10 results and
+/// `HybridParams::default()`. New options are added as builder methods,
+/// so existing call sites keep compiling.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+/// use barq_graphdb::hybrid::HybridQuery;
+/// use std::path::PathBuf;
+///
+/// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+/// let outcome = HybridQuery::new(vec![0.1, 0.2])
+///     .start(1)
+///     .max_hops(3)
+///     .k(10)
+///     .alpha(0.7)
+///     .beta(0.3)
+///     .explain(true)
+///     .run(&db);
+/// for result in outcome.results {
+///     println!("{} scored {}", result.id, result.score);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HybridQuery {
+    embedding: Vec<f32>,
+    start: Option<NodeId>,
+    seeds: usize,
+    max_hops: usize,
+    k: usize,
+    params: HybridParams,
+}
+
+impl HybridQuery {
+    /// Creates a query for nodes near `embedding`.
+    pub fn new(embedding: impl Into<Vec<f32>>) -> Self {
+        Self {
+            embedding: embedding.into(),
+            start: None,
+            seeds: 5,
+            max_hops: 3,
+            k: 10,
+            params: HybridParams::default(),
+        }
+    }
+
+    /// Explores from `start`; without one, the graph is explored from
+    /// the query's nearest vector matches (see `seeds`).
+    pub fn start(mut self, start: NodeId) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Sets how many vector matches to explore from when no `start` is
+    /// given.
+    pub fn seeds(mut self, seeds: usize) -> Self {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Sets the maximum BFS depth.
+    pub fn max_hops(mut self, max_hops: usize) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    /// Sets the number of results to return.
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Sets the weight of vector similarity.
+    pub fn alpha(mut self, alpha: f32) -> Self {
+        self.params.alpha = alpha;
+        self
+    }
+
+    /// Sets the weight of graph proximity.
+    pub fn beta(mut self, beta: f32) -> Self {
+        self.params.beta = beta;
+        self
+    }
+
+    /// See `HybridParams::with_direction`.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.params = self.params.with_direction(direction);
+        self
+    }
+
+    /// See `HybridParams::with_filter`.
+    pub fn filter(mut self, filter: HybridFilter) -> Self {
+        self.params = self.params.with_filter(filter);
+        self
+    }
+
+    /// See `HybridParams::with_normalization`.
+    pub fn normalization(mut self, normalization: VectorNormalization) -> Self {
+        self.params = self.params.with_normalization(normalization);
+        self
+    }
+
+    /// See `HybridParams::with_scorer`.
+    pub fn scorer(mut self, scorer: Arc<dyn HybridScorer>) -> Self {
+        self.params = self.params.with_scorer(scorer);
+        self
+    }
+
+    /// See `HybridParams::with_weighted_edges`.
+    pub fn weighted_edges(mut self, weighted: bool) -> Self {
+        self.params = self.params.with_weighted_edges(weighted);
+        self
+    }
+
+    /// See `HybridParams::with_explain`.
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.params = self.params.with_explain(explain);
+        self
+    }
+
+    /// See `HybridParams::with_max_candidates`.
+    pub fn max_candidates(mut self, max: usize) -> Self {
+        self.params = self.params.with_max_candidates(max);
+        self
+    }
+
+    /// See `HybridParams::with_timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.params = self.params.with_timeout(timeout);
+        self
+    }
+
+    /// See `HybridParams::with_include_nodes`.
+    pub fn include_nodes(mut self, include: bool) -> Self {
+        self.params = self.params.with_include_nodes(include);
+        self
+    }
+
+    /// See `HybridParams::with_beam_width`.
+    pub fn beam_width(mut self, width: usize) -> Self {
+        self.params = self.params.with_beam_width(width);
+        self
+    }
+
+    /// See `HybridParams::with_graph_proximity`.
+    pub fn graph_proximity(mut self, proximity: GraphProximity) -> Self {
+        self.params = self.params.with_graph_proximity(proximity);
+        self
+    }
+
+    /// See `HybridParams::with_edge_types`.
+    pub fn edge_types(mut self, include: bool) -> Self {
+        self.params = self.params.with_edge_types(include);
+        self
+    }
+
+    /// See `HybridParams::with_min_score`.
+    pub fn min_score(mut self, min_score: f32) -> Self {
+        self.params = self.params.with_min_score(min_score);
+        self
+    }
+
+    /// See `HybridParams::with_query_vectors`.
+    pub fn query_vectors(
+        mut self,
+        vectors: Vec<WeightedVector>,
+        aggregation: VectorAggregation,
+    ) -> Self {
+        self.params = self.params.with_query_vectors(vectors, aggregation);
+        self
+    }
+
+    /// Replaces all scoring options at once.
+    pub fn params(mut self, params: HybridParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Runs the query against `db`.
+    ///
+    /// Equivalent to `BarqGraphDb::hybrid_query_outcome`, or to
+    /// `BarqGraphDb::hybrid_query_seedless_outcome` without a start.
+    pub fn run(&self, db: &BarqGraphDb) -> HybridOutcome {
+        let params = self.params.clone();
+        match self.start {
+            Some(start) => {
+                db.hybrid_query_outcome(&self.embedding, start, self.max_hops, self.k, params)
+            }
+            None => db.hybrid_query_seedless_outcome(
+                &self.embedding,
+                self.seeds,
+                self.max_hops,
+                self.k,
+                params,
+            ),
         }
     }
 }
@@ -72,16 +865,20 @@ impl HybridResult {
 /// Computes the hybrid score combining vector similarity and graph distance.
 ///
 /// The score is computed as:
-/// `score = alpha * (1 - normalized_vector_distance) + beta * (1 / (1 + graph_distance))`
+/// `score = alpha * vector_similarity + beta * (1 / (1 + graph_distance))`
 ///
-/// This means:
+/// where `vector_similarity` comes from `params.normalization`, unless
+/// `params.scorer` replaces the formula. This means:
 /// - Higher alpha = more weight on vector similarity
 /// - Higher beta = more weight on graph proximity
 /// - Closer vectors and shorter graph paths result in higher scores
 ///
+/// `MinMax` needs the whole candidate set and treats a lone distance as
+/// the best one; use `score_results` for it.
+///
 /// # Arguments
 ///
-/// * `vec_dist` - L2 distance from query vector (lower is better)
+/// * `vec_dist` - Vector distance from the query (lower is better)
 /// * `graph_dist` - Number of hops from start node (lower is better)
 /// * `params` - Hybrid scoring parameters
 ///
@@ -89,20 +886,186 @@ impl HybridResult {
 ///
 /// A score where higher values indicate better matches.
 pub fn compute_hybrid_score(vec_dist: f32, graph_dist: usize, params: &HybridParams) -> f32 {
-    // Normalize vector distance to similarity (0-1 range, clamped)
-    // Using min(1.0, vec_dist) to cap at 1.0 for normalization
-    let vec_sim = 1.0 - vec_dist.min(1.0);
+    let input = ScoreInput {
+        vector_distance: vec_dist,
+        vector_similarity: params
+            .normalization
+            .similarity(vec_dist, (vec_dist, vec_dist)),
+        graph_distance: graph_dist,
+        graph_cost: graph_dist as f32,
+        node: None,
+    };
+    with_scorer(params, |scorer| scorer.score(&input))
+}
+
+/// Candidates below which hybrid scoring stays on the calling thread.
+pub(crate) const PARALLEL_SCORE_MIN: usize = 1024;
+
+/// Sets the score of every result from its distances and node.
+///
+/// Unlike `compute_hybrid_score`, this normalizes `MinMax` over the
+/// given results and passes each result's node, looked up by `node`, to
+/// the scorer. With `query_vectors`, each vector's distances are
+/// normalized separately and the similarities combined by
+/// `params.aggregation`. At least `PARALLEL_SCORE_MIN` results are scored
+/// in parallel.
+pub fn score_results<'a>(
+    results: &mut [HybridResult],
+    params: &HybridParams,
+    node: impl Fn(NodeId) -> Option<&'a Node> + Sync,
+) {
+    let mut ranges = vec![(f32::INFINITY, f32::NEG_INFINITY); 1 + params.query_vectors.len()];
+    for result in results.iter() {
+        for ((min, max), distance) in ranges.iter_mut().zip(distances(result)) {
+            (*min, *max) = (min.min(distance), max.max(distance));
+        }
+    }
+    let weights = || iter::once(1.0).chain(params.query_vectors.iter().map(|v| v.weight));
+    with_scorer(params, |scorer| {
+        let score = |result: &mut HybridResult| {
+            let similarities = distances(result)
+                .zip(&ranges)
+                .zip(weights())
+                .map(|((d, &range), w)| (params.normalization.similarity(d, range), w));
+            let input = ScoreInput {
+                vector_distance: result.vector_distance,
+                vector_similarity: params.aggregation.combine(similarities),
+                graph_distance: result.graph_distance,
+                graph_cost: result.graph_cost,
+                node: node(result.id),
+            };
+            result.score = scorer.score(&input);
+            if params.explain {
+                result.explanation = Some(explain(result, &input, params));
+            }
+        };
+        if results.len() < PARALLEL_SCORE_MIN {
+            results.iter_mut().for_each(score);
+        } else {
+            results.par_iter_mut().for_each(score);
+        }
+    })
+}
+
+/// A result's distance to the query embedding, then to each further
+/// query vector.
+fn distances(result: &HybridResult) -> impl Iterator<Item = f32> + '_ {
+    iter::once(result.vector_distance).chain(result.query_distances.iter().copied())
+}
+
+/// A result's rank key, ordered so the top of a `BinaryHeap` is the
+/// worst one kept: lower scores first, then later positions.
+#[derive(Debug, Clone, Copy)]
+struct Ranked {
+    score: f32,
+    position: usize,
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .score
+            .total_cmp(&self.score)
+            .then_with(|| self.position.cmp(&other.position))
+    }
+}
+
+/// Returns the `k` highest-scoring results, sorted by score descending.
+///
+/// Ties keep their input order. Only a bounded heap of the best `k` is
+/// kept instead of sorting every candidate.
+pub fn top_k(results: Vec<HybridResult>, k: usize) -> Vec<HybridResult> {
+    let mut heap = BinaryHeap::with_capacity(k.min(results.len()) + 1);
+    for (position, result) in results.iter().enumerate() {
+        let ranked = Ranked {
+            score: result.score,
+            position,
+        };
+        if heap.len() < k {
+            heap.push(ranked);
+        } else if heap.peek().is_some_and(|worst| ranked < *worst) {
+            heap.pop();
+            heap.push(ranked);
+        }
+    }
+    let mut slots: Vec<_> = results.into_iter().map(Some).collect();
+    heap.into_sorted_vec()
+        .into_iter()
+        .filter_map(|r| slots[r.position].take())
+        .collect()
+}
 
-    // Convert graph distance to similarity (decreases with distance)
-    let graph_sim = 1.0 / (1.0 + graph_dist as f32);
+fn explain(
+    result: &HybridResult,
+    input: &ScoreInput<'_>,
+    params: &HybridParams,
+) -> HybridExplanation {
+    let graph_similarity = 1.0 / (1.0 + input.graph_cost);
+    let weighted = params.scorer.is_none();
+    HybridExplanation {
+        seed: result.path.first().copied(),
+        via: result.path.iter().rev().nth(1).copied(),
+        vector_similarity: input.vector_similarity,
+        graph_similarity,
+        vector_contribution: weighted.then_some(params.alpha * input.vector_similarity),
+        graph_contribution: weighted.then_some(params.beta * graph_similarity),
+    }
+}
 
-    params.alpha * vec_sim + params.beta * graph_sim
+/// Runs `f` with the params' scorer, or the weighted default.
+fn with_scorer<T>(params: &HybridParams, f: impl FnOnce(&dyn HybridScorer) -> T) -> T {
+    match &params.scorer {
+        Some(scorer) => f(scorer.as_ref()),
+        None => f(&WeightedScorer {
+            alpha: params.alpha,
+            beta: params.beta,
+        }),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_vector_aggregation() {
+        let pairs = || [(0.2, 1.0), (0.8, 3.0)].into_iter();
+        assert!((VectorAggregation::Max.combine(pairs()) - 0.8).abs() < 1e-6);
+        assert!((VectorAggregation::WeightedMean.combine(pairs()) - 0.65).abs() < 1e-6);
+        assert_eq!(VectorAggregation::WeightedMean.combine(iter::empty()), 0.0);
+    }
+
+    #[test]
+    fn test_top_k_keeps_best_in_order() {
+        let scores = [0.3, 0.9, 0.5, 0.9, 0.1];
+        let results: Vec<_> = scores
+            .iter()
+            .enumerate()
+            .map(|(id, &score)| HybridResult::new(id as NodeId, score, 0.0, 0, Vec::new()))
+            .collect();
+
+        let best = top_k(results.clone(), 3);
+        let ids: Vec<NodeId> = best.iter().map(|r| r.id).collect();
+        // Ties keep input order
+        assert_eq!(ids, vec![1, 3, 2]);
+        assert_eq!(top_k(results.clone(), 10).len(), 5);
+        assert!(top_k(results, 0).is_empty());
+    }
+
     #[test]
     fn test_default_params() {
         let params = HybridParams::default();
@@ -167,6 +1130,93 @@ mod tests {
         assert!((score - 0.0).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_filter_matches_tags_agent_and_time() {
+        let mut node = Node::new(1, "n".to_string());
+        node.rule_tags = vec!["fact".to_string(), "verified".to_string()];
+        node.agent_id = Some(7);
+        node.timestamp = 100;
+
+        assert!(HybridFilter::default().matches(&node));
+        let filter = HybridFilter {
+            rule_tags_any: vec!["fact".to_string(), "rumor".to_string()],
+            rule_tags_all: vec!["fact".to_string(), "verified".to_string()],
+            agent_id: Some(7),
+            min_timestamp: Some(100),
+            max_timestamp: Some(100),
+        };
+        assert!(filter.matches(&node));
+        for other in [
+            HybridFilter {
+                rule_tags_any: vec!["rumor".to_string()],
+                ..filter.clone()
+            },
+            HybridFilter {
+                rule_tags_all: vec!["fact".to_string(), "stale".to_string()],
+                ..filter.clone()
+            },
+            HybridFilter {
+                agent_id: Some(8),
+                ..filter.clone()
+            },
+            HybridFilter {
+                min_timestamp: Some(101),
+                ..filter.clone()
+            },
+        ] {
+            assert!(!other.matches(&node));
+        }
+    }
+
+    #[test]
+    fn test_normalization_keeps_large_distances_apart() {
+        let mut results: Vec<HybridResult> = [12.0, 20.0, 28.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| HybridResult::new(i as NodeId, 0.0, d, 1, Vec::new()))
+            .collect();
+        let scores =
+            |results: &[HybridResult]| -> Vec<f32> { results.iter().map(|r| r.score).collect() };
+
+        // Clamping gives every real-world distance the same similarity
+        score_results(&mut results, &HybridParams::new(1.0, 0.0), |_| None);
+        assert_eq!(scores(&results), vec![0.0, 0.0, 0.0]);
+
+        let min_max = HybridParams::new(1.0, 0.0).with_normalization(VectorNormalization::MinMax);
+        score_results(&mut results, &min_max, |_| None);
+        assert_eq!(scores(&results), vec![1.0, 0.5, 0.0]);
+
+        let sigmoid = HybridParams::new(1.0, 0.0)
+            .with_normalization(VectorNormalization::Sigmoid { scale: 20.0 });
+        score_results(&mut results, &sigmoid, |_| None);
+        let s = scores(&results);
+        assert!(s[0] > s[1] && s[1] > s[2] && s[2] > 0.0);
+        assert!((s[1] - 2.0 / (1.0 + 1f32.exp())).abs() < 1e-6);
+
+        let cosine = VectorNormalization::Cosine;
+        let d = cosine.distance(&[1.0, 0.0], &[10.0, 10.0]);
+        assert!((cosine.similarity(d, (0.0, 0.0)) - (0.5 + 0.5 / 2f32.sqrt())).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_explanation_adds_up_to_score() {
+        let mut results = vec![
+            HybridResult::new(3, 0.0, 0.2, 2, vec![1, 2, 3]),
+            HybridResult::new(1, 0.0, 0.6, 0, vec![1]),
+        ];
+        let params = HybridParams::new(0.7, 0.3).with_explain(true);
+        score_results(&mut results, &params, |_| None);
+
+        let far = results[0].explanation.clone().unwrap();
+        assert_eq!((far.seed, far.via), (Some(1), Some(2)));
+        assert!((far.vector_similarity - 0.8).abs() < 1e-6);
+        assert!((far.graph_similarity - 1.0 / 3.0).abs() < 1e-6);
+        let sum = far.vector_contribution.unwrap() + far.graph_contribution.unwrap();
+        assert!((sum - results[0].score).abs() < 1e-6);
+        let start = results[1].explanation.clone().unwrap();
+        assert_eq!((start.seed, start.via), (Some(1), None));
+    }
+
     #[test]
     fn test_hybrid_result_creation() {
         let result = HybridResult::new(42, 0.85, 0.15, 2, vec![1, 5, 42]);
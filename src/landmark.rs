@@ -0,0 +1,152 @@
+//! Landmark-based approximate graph distances.
+//!
+//! This module precomputes hop distances between every node and a small
+//! set of landmark nodes. The distance between any two nodes can then be
+//! estimated in O(landmarks) time through the triangle inequality, which
+//! lets hybrid scoring skip live BFS on very large graphs.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::NodeId;
+
+/// Marker for "not reachable" in landmark distance tables.
+const UNREACHABLE: u32 = u32::MAX;
+
+/// Precomputed hop distances to and from a set of landmark nodes.
+#[derive(Debug, Clone, Default)]
+pub struct LandmarkIndex {
+    /// Selected landmark node IDs.
+    landmarks: Vec<NodeId>,
+    /// Per node, hop distance from each landmark to the node.
+    from_landmark: HashMap<NodeId, Vec<u32>>,
+    /// Per node, hop distance from the node to each landmark.
+    to_landmark: HashMap<NodeId, Vec<u32>>,
+}
+
+impl LandmarkIndex {
+    /// Builds a landmark index over an adjacency list.
+    ///
+    /// The `count` nodes with the highest total degree are used as
+    /// landmarks, since hubs lie on many shortest paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `adjacency` - Outgoing adjacency list of the graph
+    /// * `count` - Number of landmarks to select
+    pub fn build(adjacency: &HashMap<NodeId, Vec<NodeId>>, count: usize) -> Self {
+        let mut reverse: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        let mut degree: HashMap<NodeId, usize> = HashMap::new();
+        for (&from, targets) in adjacency {
+            *degree.entry(from).or_default() += targets.len();
+            for &to in targets {
+                reverse.entry(to).or_default().push(from);
+                *degree.entry(to).or_default() += 1;
+            }
+        }
+
+        let mut ranked: Vec<(NodeId, usize)> = degree.into_iter().collect();
+        // Highest degree first; ties broken by ID for determinism
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let landmarks: Vec<NodeId> = ranked.into_iter().take(count).map(|(id, _)| id).collect();
+
+        let mut index = Self {
+            landmarks: landmarks.clone(),
+            ..Default::default()
+        };
+        for (slot, &landmark) in landmarks.iter().enumerate() {
+            for (node, dist) in bfs_all(adjacency, landmark) {
+                index
+                    .from_landmark
+                    .entry(node)
+                    .or_insert_with(|| vec![UNREACHABLE; landmarks.len()])[slot] = dist;
+            }
+            for (node, dist) in bfs_all(&reverse, landmark) {
+                index
+                    .to_landmark
+                    .entry(node)
+                    .or_insert_with(|| vec![UNREACHABLE; landmarks.len()])[slot] = dist;
+            }
+        }
+        index
+    }
+
+    /// Returns the selected landmark node IDs.
+    pub fn landmarks(&self) -> &[NodeId] {
+        &self.landmarks
+    }
+
+    /// Estimates the hop distance from `from` to `to`.
+    ///
+    /// The estimate is the upper bound `min_l d(from, l) + d(l, to)` over
+    /// all landmarks `l`, which is exact whenever a shortest path passes
+    /// through a landmark.
+    ///
+    /// # Returns
+    ///
+    /// `Some(hops)` if a route via some landmark exists, otherwise `None`.
+    pub fn estimate_distance(&self, from: NodeId, to: NodeId) -> Option<usize> {
+        if from == to {
+            return Some(0);
+        }
+        let to_l = self.to_landmark.get(&from)?;
+        let from_l = self.from_landmark.get(&to)?;
+        to_l.iter()
+            .zip(from_l.iter())
+            .filter(|(a, b)| **a != UNREACHABLE && **b != UNREACHABLE)
+            .map(|(a, b)| (*a + *b) as usize)
+            .min()
+    }
+}
+
+/// Unbounded BFS returning the hop distance of every reachable node.
+fn bfs_all(adjacency: &HashMap<NodeId, Vec<NodeId>>, start: NodeId) -> HashMap<NodeId, u32> {
+    let mut dist = HashMap::new();
+    let mut queue = VecDeque::new();
+    dist.insert(start, 0u32);
+    queue.push_back(start);
+
+    while let Some(current) = queue.pop_front() {
+        let d = dist[&current];
+        if let Some(neighbors) = adjacency.get(&current) {
+            for &neighbor in neighbors {
+                if let std::collections::hash_map::Entry::Vacant(e) = dist.entry(neighbor) {
+                    e.insert(d + 1);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+    dist
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn star_with_tail() -> HashMap<NodeId, Vec<NodeId>> {
+        // 1 -> 0, 2 -> 0, 0 -> 3, 3 -> 4
+        let mut adj: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        adj.insert(1, vec![0]);
+        adj.insert(2, vec![0]);
+        adj.insert(0, vec![3]);
+        adj.insert(3, vec![4]);
+        adj.insert(4, vec![]);
+        adj
+    }
+
+    #[test]
+    fn test_hub_selected_as_landmark() {
+        let index = LandmarkIndex::build(&star_with_tail(), 1);
+        assert_eq!(index.landmarks(), &[0]);
+    }
+
+    #[test]
+    fn test_estimate_through_landmark_is_exact() {
+        let index = LandmarkIndex::build(&star_with_tail(), 1);
+        assert_eq!(index.estimate_distance(1, 4), Some(3));
+        assert_eq!(index.estimate_distance(2, 3), Some(2));
+        assert_eq!(index.estimate_distance(4, 4), Some(0));
+        // No route from the tail back to the sources
+        assert_eq!(index.estimate_distance(4, 1), None);
+    }
+}
@@ -25,12 +25,33 @@ pub mod api;
 pub mod batch_indexer;
 pub mod batch_queue;
 pub mod bench_utils;
+pub mod centroid;
+pub mod counterfactual;
+pub mod crdt;
+pub mod disk;
+pub mod episodic;
 pub mod error;
+pub mod evidence;
+pub mod export;
 pub mod graph;
 pub mod grpc;
 pub mod hybrid;
+pub mod landmark;
+pub mod memory;
+pub mod metrics;
+pub mod pattern;
+pub mod projection;
+pub mod redaction;
+pub mod sort;
 pub mod storage;
+pub mod template;
+pub mod tier;
+pub mod traverse;
 pub mod vector;
+pub mod vector_expr;
+pub mod warmup;
+
+use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
@@ -46,6 +67,57 @@ pub struct Edge {
     pub to: NodeId,
     /// Type/label of the edge (e.g., "CALLS", "DEPENDS_ON").
     pub edge_type: String,
+    /// Optional traversal cost; unweighted edges cost 1.0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f32>,
+    /// Arbitrary metadata attached to the edge.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub properties: HashMap<String, serde_json::Value>,
+    /// Unix timestamp when this edge was created (0 if recorded before
+    /// edges carried timestamps).
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+impl Edge {
+    /// Creates an unweighted edge without properties, timestamped now.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Source node ID
+    /// * `to` - Target node ID
+    /// * `edge_type` - Type/label of the edge
+    pub fn new(from: NodeId, to: NodeId, edge_type: impl Into<String>) -> Self {
+        Self {
+            from,
+            to,
+            edge_type: edge_type.into(),
+            weight: None,
+            properties: HashMap::new(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+
+    /// Sets the creation timestamp of the edge.
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Sets the traversal cost of the edge.
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+
+    /// Adds a metadata property to the edge.
+    pub fn with_property(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.properties.insert(key.into(), value);
+        self
+    }
 }
 
 /// Represents a node in the graph with optional vector embedding.
@@ -0,0 +1,118 @@
+//! Approximate memory accounting.
+//!
+//! The database tracks an estimate of the bytes held by its node map,
+//! adjacency list and vector index. With a `MemoryBudget` configured,
+//! writes that would push the estimate over the limit are rejected
+//! instead of letting the host run out of memory.
+//!
+//! Estimates count payload sizes plus fixed per-entry overheads; they
+//! are meant for capacity limits, not exact allocator statistics.
+
+use std::mem::size_of;
+
+use serde::Serialize;
+
+use crate::vector::Quantization;
+use crate::{Edge, Node, NodeId};
+
+/// Estimated per-entry overhead of a hash map slot (hash, control byte, padding).
+const HASH_SLOT_OVERHEAD: usize = 16;
+
+/// Bytes charged for one remembered removed-edge pair.
+pub(crate) const REMOVED_EDGE_BYTES: usize = 2 * size_of::<NodeId>() + HASH_SLOT_OVERHEAD;
+
+/// Estimated per-vector overhead of the vector index (graph links, ID mapping).
+const VECTOR_INDEX_OVERHEAD: usize = 128;
+
+/// Bytes charged for one adjacency list entry.
+pub(crate) const ADJACENCY_ENTRY_BYTES: usize = size_of::<NodeId>();
+
+/// Bytes charged for one adjacency list key (with its empty `Vec`).
+pub(crate) const ADJACENCY_KEY_BYTES: usize =
+    size_of::<NodeId>() + size_of::<Vec<NodeId>>() + HASH_SLOT_OVERHEAD;
+
+/// Bytes charged for one explicit edge weight.
+pub(crate) const EDGE_WEIGHT_BYTES: usize =
+    size_of::<NodeId>() + size_of::<f32>() + HASH_SLOT_OVERHEAD;
+
+/// Upper limit on the estimated memory a database may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// Maximum estimated bytes across nodes, adjacency and vectors.
+    pub max_bytes: usize,
+}
+
+impl MemoryBudget {
+    /// Creates a budget with the given byte limit.
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+/// Estimated bytes used by each in-memory structure.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MemoryUsage {
+    /// Node map, including labels, tags, edges and embeddings.
+    pub nodes: usize,
+    /// Adjacency list, including the pairs of removed edges kept for
+    /// decision path explanations.
+    pub adjacency: usize,
+    /// Vector index.
+    pub vectors: usize,
+}
+
+impl MemoryUsage {
+    /// Returns the total estimated bytes.
+    pub fn total(&self) -> usize {
+        self.nodes + self.adjacency + self.vectors
+    }
+}
+
+/// Estimated bytes of an edge stored inside a node.
+pub(crate) fn edge_bytes(edge: &Edge) -> usize {
+    size_of::<Edge>()
+        + edge.edge_type.len()
+        + edge
+            .properties
+            .iter()
+            .map(|(k, v)| size_of::<String>() + k.len() + v.to_string().len() + HASH_SLOT_OVERHEAD)
+            .sum::<usize>()
+}
+
+/// Estimated bytes of a node in the node map.
+pub(crate) fn node_bytes(node: &Node) -> usize {
+    size_of::<NodeId>()
+        + size_of::<Node>()
+        + HASH_SLOT_OVERHEAD
+        + node.label.len()
+        + node.embedding.len() * size_of::<f32>()
+        + node.edges.iter().map(edge_bytes).sum::<usize>()
+        + node
+            .rule_tags
+            .iter()
+            .map(|t| size_of::<String>() + t.len())
+            .sum::<usize>()
+}
+
+/// Estimated bytes of one vector in the vector index.
+pub(crate) fn vector_bytes(dimension: usize, quantization: Quantization) -> usize {
+    quantization.stored_bytes(dimension) + VECTOR_INDEX_OVERHEAD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_bytes_grow_with_payload() {
+        let small = Node::new(1, "a".to_string());
+        let mut large = Node::new(1, "a much longer label".to_string());
+        large.embedding = vec![0.0; 64];
+        large.rule_tags = vec!["tag".to_string()];
+        assert!(node_bytes(&large) >= node_bytes(&small) + 64 * 4 + 18);
+        assert_eq!(
+            vector_bytes(4, Quantization::None),
+            16 + VECTOR_INDEX_OVERHEAD
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Operation latency telemetry for embedded users.
+//!
+//! This module provides a lightweight metrics sink trait that
+//! `BarqGraphDb` calls after each instrumented operation, plus a
+//! built-in histogram sink for users who don't have their own
+//! telemetry pipeline. The same sink receives disk alerts and the
+//! failures of background threads, which the library never prints.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::disk::DiskAlert;
+
+/// Database operations that report latency to a metrics sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// `BarqGraphDb::append_node`.
+    AppendNode,
+    /// `BarqGraphDb::add_edge`.
+    AddEdge,
+    /// `BarqGraphDb::remove_edge`.
+    RemoveEdge,
+    /// `BarqGraphDb::knn_search`.
+    Knn,
+    /// `BarqGraphDb::hybrid_query`.
+    Hybrid,
+    /// Reading a node back from the cold tier (see `crate::tier`).
+    Hydrate,
+}
+
+/// Maintenance work run on a background thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundTask {
+    /// The disk monitor started by `BarqGraphDb::spawn_disk_monitor`.
+    DiskCheck,
+    /// The checkpoint thread started by `BarqGraphDb::spawn_checkpoint_thread`.
+    Checkpoint,
+    /// The tiering thread started by `BarqGraphDb::spawn_tiering_thread`.
+    Tiering,
+}
+
+/// Receiver for per-operation latency measurements.
+///
+/// Implementations must be cheap: `record` is called synchronously
+/// on the hot path of every instrumented operation.
+pub trait MetricsSink: Send + Sync {
+    /// Records the latency of a single completed operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operation that completed
+    /// * `elapsed` - Wall-clock time spent in the operation
+    fn record(&self, op: Operation, elapsed: Duration);
+
+    /// Receives a disk-usage alert raised by the database's `DiskQuota`.
+    ///
+    /// The default implementation ignores alerts.
+    fn disk_alert(&self, _alert: &DiskAlert) {}
+
+    /// Receives the error of a failed background run.
+    ///
+    /// The thread keeps going and tries again at its next interval. The
+    /// default implementation ignores failures.
+    fn background_error(&self, _task: BackgroundTask, _error: &anyhow::Error) {}
+}
+
+/// Upper bounds (in microseconds) of the histogram buckets.
+///
+/// Measurements above the last bound fall into an overflow bucket.
+pub const LATENCY_BUCKETS_US: [u64; 12] = [
+    10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 50_000, 100_000,
+];
+
+/// Snapshot of the latency distribution for a single operation.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistogramSnapshot {
+    /// Number of recorded measurements.
+    pub count: u64,
+    /// Sum of all measurements in microseconds.
+    pub sum_us: u64,
+    /// Largest recorded measurement in microseconds.
+    pub max_us: u64,
+    /// Per-bucket counts aligned with `LATENCY_BUCKETS_US`, plus one overflow bucket.
+    pub buckets: Vec<u64>,
+}
+
+impl HistogramSnapshot {
+    /// Returns the mean latency in microseconds, or 0 if empty.
+    pub fn mean_us(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum_us as f64 / self.count as f64
+        }
+    }
+
+    /// Returns an upper bound for the given quantile (0.0 to 1.0).
+    ///
+    /// The result is the bucket boundary the quantile falls in, or
+    /// `max_us` if it falls in the overflow bucket.
+    pub fn quantile_us(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let target = ((self.count as f64) * q.clamp(0.0, 1.0)).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, &c) in self.buckets.iter().enumerate() {
+            seen += c;
+            if seen >= target {
+                return LATENCY_BUCKETS_US.get(i).copied().unwrap_or(self.max_us);
+            }
+        }
+        self.max_us
+    }
+}
+
+/// In-process histogram sink with fixed latency buckets.
+///
+/// Attach it with `BarqGraphDb::set_metrics_sink` and read the
+/// distributions back with `snapshot`.
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    histograms: Mutex<HashMap<Operation, HistogramSnapshot>>,
+}
+
+impl LatencyHistogram {
+    /// Creates an empty histogram sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current distribution for an operation.
+    pub fn snapshot(&self, op: Operation) -> HistogramSnapshot {
+        self.histograms
+            .lock()
+            .unwrap()
+            .get(&op)
+            .cloned()
+            .unwrap_or_else(|| HistogramSnapshot {
+                buckets: vec![0; LATENCY_BUCKETS_US.len() + 1],
+                ..Default::default()
+            })
+    }
+
+    /// Clears all recorded measurements.
+    pub fn reset(&self) {
+        self.histograms.lock().unwrap().clear();
+    }
+}
+
+impl MetricsSink for LatencyHistogram {
+    fn record(&self, op: Operation, elapsed: Duration) {
+        let us = elapsed.as_micros() as u64;
+        let bucket = LATENCY_BUCKETS_US
+            .iter()
+            .position(|&bound| us <= bound)
+            .unwrap_or(LATENCY_BUCKETS_US.len());
+
+        let mut histograms = self.histograms.lock().unwrap();
+        let h = histograms.entry(op).or_insert_with(|| HistogramSnapshot {
+            buckets: vec![0; LATENCY_BUCKETS_US.len() + 1],
+            ..Default::default()
+        });
+        h.count += 1;
+        h.sum_us += us;
+        h.max_us = h.max_us.max(us);
+        h.buckets[bucket] += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_records_buckets() {
+        let hist = LatencyHistogram::new();
+        hist.record(Operation::Knn, Duration::from_micros(5));
+        hist.record(Operation::Knn, Duration::from_micros(80));
+        hist.record(Operation::Knn, Duration::from_millis(500));
+
+        let snap = hist.snapshot(Operation::Knn);
+        assert_eq!(snap.count, 3);
+        assert_eq!(snap.buckets[0], 1);
+        assert_eq!(snap.buckets[3], 1);
+        assert_eq!(*snap.buckets.last().unwrap(), 1);
+        assert_eq!(snap.max_us, 500_000);
+    }
+
+    #[test]
+    fn test_histogram_quantiles() {
+        let hist = LatencyHistogram::new();
+        for _ in 0..9 {
+            hist.record(Operation::AddEdge, Duration::from_micros(20));
+        }
+        hist.record(Operation::AddEdge, Duration::from_micros(900));
+
+        let snap = hist.snapshot(Operation::AddEdge);
+        assert_eq!(snap.quantile_us(0.5), 25);
+        assert_eq!(snap.quantile_us(1.0), 1_000);
+        assert_eq!(hist.snapshot(Operation::Hybrid).count, 0);
+    }
+}
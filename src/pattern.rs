@@ -0,0 +1,317 @@
+//! Structural pattern matching.
+//!
+//! A `Pattern` is a list of `(a)-[:TYPE]->(b)` triples over named
+//! variables, built programmatically. `BarqGraphDb::match_pattern` finds
+//! every assignment of node IDs to the variables under which all triples
+//! exist as edges, so questions like "which services call something that
+//! depends on the auth module" don't need hand-written nested loops.
+//!
+//! Matching backtracks over the triples, always extending from an
+//! already bound variable when one exists, so a bound or selective
+//! starting point keeps the search local to its neighborhood.
+
+use std::collections::BTreeMap;
+
+use crate::graph::GraphIndex;
+use crate::storage::BarqGraphDb;
+use crate::NodeId;
+
+/// Node IDs assigned to a pattern's variables, keyed by variable name.
+pub type Bindings = BTreeMap<String, NodeId>;
+
+/// One `(from)-[:edge_type]->(to)` triple, with variables as indices.
+#[derive(Debug, Clone)]
+struct Step {
+    from: usize,
+    to: usize,
+    edge_type: Option<String>,
+}
+
+/// A graph pattern of edge triples between named variables.
+///
+/// Variables are created on first use. Several variables may bind the
+/// same node, and parallel edges never produce duplicate matches.
+///
+/// # Example
+///
+/// ```rust
+/// use barq_graphdb::pattern::Pattern;
+///
+/// // (a)-[:CALLS]->(b)-[:DEPENDS_ON]->(c), with c fixed to node 42
+/// let pattern = Pattern::new()
+///     .edge("a", "CALLS", "b")
+///     .edge("b", "DEPENDS_ON", "c")
+///     .bind("c", 42);
+/// assert_eq!(pattern.variables(), ["a", "b", "c"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Pattern {
+    variables: Vec<String>,
+    steps: Vec<Step>,
+    fixed: Vec<(usize, NodeId)>,
+}
+
+impl Pattern {
+    /// Creates an empty pattern.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires an edge of type `edge_type` from `from` to `to`.
+    pub fn edge(self, from: &str, edge_type: &str, to: &str) -> Self {
+        self.step(from, Some(edge_type), to)
+    }
+
+    /// Requires an edge of any type from `from` to `to`.
+    pub fn any_edge(self, from: &str, to: &str) -> Self {
+        self.step(from, None, to)
+    }
+
+    /// Fixes a variable to a specific node.
+    pub fn bind(mut self, variable: &str, id: NodeId) -> Self {
+        let var = self.variable(variable);
+        self.fixed.push((var, id));
+        self
+    }
+
+    /// Returns the variable names in order of first use.
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    fn step(mut self, from: &str, edge_type: Option<&str>, to: &str) -> Self {
+        let from = self.variable(from);
+        let to = self.variable(to);
+        self.steps.push(Step {
+            from,
+            to,
+            edge_type: edge_type.map(str::to_string),
+        });
+        self
+    }
+
+    fn variable(&mut self, name: &str) -> usize {
+        match self.variables.iter().position(|v| v == name) {
+            Some(index) => index,
+            None => {
+                self.variables.push(name.to_string());
+                self.variables.len() - 1
+            }
+        }
+    }
+}
+
+/// Backtracking search state for one `match_pattern` call.
+struct Matcher<'a> {
+    graph: &'a GraphIndex,
+    pattern: &'a Pattern,
+    bound: Vec<Option<NodeId>>,
+    done: Vec<bool>,
+    limit: usize,
+    matches: Vec<Bindings>,
+}
+
+impl Matcher<'_> {
+    fn search(&mut self) {
+        if self.matches.len() >= self.limit {
+            return;
+        }
+        // Extend from the step with the most bound endpoints
+        let next = (0..self.pattern.steps.len())
+            .filter(|&i| !self.done[i])
+            .max_by_key(|&i| {
+                let step = &self.pattern.steps[i];
+                (
+                    self.bound[step.from].is_some() as u8 + self.bound[step.to].is_some() as u8,
+                    std::cmp::Reverse(i),
+                )
+            });
+        let Some(index) = next else {
+            self.record();
+            return;
+        };
+
+        let step = &self.pattern.steps[index];
+        let (from, to) = (step.from, step.to);
+        match (self.bound[from], self.bound[to]) {
+            (Some(a), Some(b)) => {
+                if self.targets(a, index).contains(&b) {
+                    self.complete(index);
+                }
+            }
+            (Some(a), None) => {
+                for b in self.targets(a, index) {
+                    self.assign(to, b, |m| m.complete(index));
+                }
+            }
+            (None, Some(b)) => {
+                let mut sources = self.graph.incoming(b).unwrap_or_default().to_vec();
+                sources.sort_unstable();
+                sources.dedup();
+                for a in sources {
+                    if self.targets(a, index).contains(&b) {
+                        self.assign(from, a, |m| m.complete(index));
+                    }
+                }
+            }
+            (None, None) => {
+                let mut sources: Vec<NodeId> = self
+                    .graph
+                    .adjacency()
+                    .iter()
+                    .filter(|(_, targets)| !targets.is_empty())
+                    .map(|(&id, _)| id)
+                    .collect();
+                sources.sort_unstable();
+                for a in sources {
+                    self.assign(from, a, Self::search);
+                }
+            }
+        }
+    }
+
+    /// Distinct targets of `from` along edges matching a step's type.
+    fn targets(&self, from: NodeId, index: usize) -> Vec<NodeId> {
+        let mut targets: Vec<NodeId> = match &self.pattern.steps[index].edge_type {
+            Some(edge_type) => self.graph.neighbors_of_type(from, edge_type).collect(),
+            None => self.graph.neighbors(from).unwrap_or_default().to_vec(),
+        };
+        targets.sort_unstable();
+        targets.dedup();
+        targets
+    }
+
+    fn assign(&mut self, var: usize, id: NodeId, then: impl FnOnce(&mut Self)) {
+        self.bound[var] = Some(id);
+        then(self);
+        self.bound[var] = None;
+    }
+
+    fn complete(&mut self, index: usize) {
+        self.done[index] = true;
+        self.search();
+        self.done[index] = false;
+    }
+
+    fn record(&mut self) {
+        let bindings = self
+            .pattern
+            .variables
+            .iter()
+            .zip(&self.bound)
+            .filter_map(|(name, id)| Some((name.clone(), (*id)?)))
+            .collect();
+        self.matches.push(bindings);
+    }
+}
+
+impl BarqGraphDb {
+    /// Finds up to `limit` variable bindings satisfying every triple of a pattern.
+    ///
+    /// Matches are ordered by the node IDs bound along the search, so
+    /// repeated calls return the same results. Nodes referenced only by
+    /// edges (without a stored record) can be bound too.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Edge triples to match
+    /// * `limit` - Maximum number of matches to return
+    ///
+    /// # Returns
+    ///
+    /// One `Bindings` map per match. A pattern without triples matches
+    /// nothing.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::pattern::Pattern;
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let pattern = Pattern::new()
+    ///     .edge("svc", "CALLS", "lib")
+    ///     .edge("lib", "DEPENDS_ON", "auth")
+    ///     .bind("auth", 7);
+    /// for m in db.match_pattern(&pattern, 100) {
+    ///     println!("service {} reaches auth through {}", m["svc"], m["lib"]);
+    /// }
+    /// ```
+    pub fn match_pattern(&self, pattern: &Pattern, limit: usize) -> Vec<Bindings> {
+        if pattern.steps.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+        let mut bound = vec![None; pattern.variables.len()];
+        for &(var, id) in &pattern.fixed {
+            match bound[var] {
+                Some(existing) if existing != id => return Vec::new(),
+                _ => bound[var] = Some(id),
+            }
+        }
+
+        let mut matcher = Matcher {
+            graph: self.graph(),
+            pattern,
+            bound,
+            done: vec![false; pattern.steps.len()],
+            limit,
+            matches: Vec::new(),
+        };
+        matcher.search();
+        matcher.matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_match_call_dependency_chains() {
+        let dir = tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        db.add_edges(&[
+            (1, 10, "CALLS"),
+            (1, 10, "CALLS"),
+            (2, 10, "CALLS"),
+            (2, 11, "CALLS"),
+            (3, 11, "IMPORTS"),
+            (10, 20, "DEPENDS_ON"),
+            (11, 21, "DEPENDS_ON"),
+        ])
+        .unwrap();
+
+        let chain = Pattern::new()
+            .edge("a", "CALLS", "b")
+            .edge("b", "DEPENDS_ON", "c");
+        let found: Vec<(NodeId, NodeId, NodeId)> = db
+            .match_pattern(&chain, 10)
+            .iter()
+            .map(|m| (m["a"], m["b"], m["c"]))
+            .collect();
+        // The parallel 1 -> 10 edge doesn't duplicate the match
+        assert_eq!(found, vec![(1, 10, 20), (2, 10, 20), (2, 11, 21)]);
+
+        let into_21 = chain.clone().bind("c", 21);
+        assert_eq!(db.match_pattern(&into_21, 10).len(), 1);
+        assert_eq!(db.match_pattern(&chain, 2).len(), 2);
+
+        // Two callers sharing a callee, regardless of edge type
+        let shared = Pattern::new()
+            .edge("x", "CALLS", "lib")
+            .any_edge("y", "lib")
+            .bind("x", 2)
+            .bind("y", 3);
+        let m = db.match_pattern(&shared, 10);
+        assert_eq!(m.len(), 1);
+        assert_eq!(m[0]["lib"], 11);
+
+        assert!(db.match_pattern(&Pattern::new(), 10).is_empty());
+        assert!(db
+            .match_pattern(&Pattern::new().edge("a", "CALLS", "a"), 10)
+            .is_empty());
+    }
+}
@@ -0,0 +1,253 @@
+//! Event-sourced projections over the WAL.
+//!
+//! The WAL is the database's source of truth. This module lets callers
+//! fold it into their own read models (per-tag counters, external search
+//! indexes, ...) and resume from a checkpoint, so downstream systems stay
+//! consistent with Barq without re-reading the whole log.
+//!
+//! A database checkpoint (`BarqGraphDb::checkpoint`) replaces the WAL, so
+//! projection checkpoints remember which log they were taken on and are
+//! rejected once it is gone; the projection must then be rebuilt.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::error::BarqError;
+use crate::storage::{wal, BarqGraphDb, WalRecord};
+
+/// A user-defined read model built from WAL records.
+pub trait Projection {
+    /// Applies a single WAL record to the projection.
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - Zero-based position of the record in the WAL
+    /// * `record` - The record to apply
+    ///
+    /// # Errors
+    ///
+    /// Returning an error stops the fold; the checkpoint is not advanced
+    /// past the failing record.
+    fn apply(&mut self, sequence: u64, record: &WalRecord) -> Result<()>;
+}
+
+/// Position in the WAL up to which a projection has been applied.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Byte offset of the next unread record.
+    pub offset: u64,
+    /// Sequence number of the next unread record.
+    pub sequence: u64,
+    /// Snapshot ID of the checkpoint marker the WAL started with when
+    /// this position was taken (0 for a WAL never replaced).
+    #[serde(default)]
+    pub wal_id: u64,
+}
+
+impl Checkpoint {
+    /// Loads a checkpoint from a JSON file, or the start of the WAL if missing.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+        serde_json::from_slice(&data).with_context(|| "Failed to parse checkpoint")
+    }
+
+    /// Saves the checkpoint as a JSON file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec(self)?)
+            .with_context(|| format!("Failed to write checkpoint {:?}", path))
+    }
+}
+
+impl BarqGraphDb {
+    /// Folds WAL records into a projection, starting at a checkpoint.
+    ///
+    /// Records before `from` are skipped without being parsed. The
+    /// returned checkpoint points just past the last applied record and
+    /// can be passed to a later call to continue incrementally, as long
+    /// as the database is not checkpointed in between.
+    ///
+    /// # Arguments
+    ///
+    /// * `projection` - Read model receiving the records
+    /// * `from` - Checkpoint to resume from (`Checkpoint::default()` for a full replay)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for in-memory databases, unreadable WAL records,
+    /// or if the projection rejects a record. Resuming from a checkpoint
+    /// taken before the WAL was replaced by a database checkpoint fails
+    /// with `BarqError::InvalidOperation`; rebuild the projection from
+    /// `Checkpoint::default()` then.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::projection::{Checkpoint, Projection};
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions, WalRecord};
+    /// use std::path::PathBuf;
+    ///
+    /// #[derive(Default)]
+    /// struct NodeCounter(u64);
+    ///
+    /// impl Projection for NodeCounter {
+    ///     fn apply(&mut self, _seq: u64, record: &WalRecord) -> anyhow::Result<()> {
+    ///         if let WalRecord::Node { .. } = record {
+    ///             self.0 += 1;
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let mut counter = NodeCounter::default();
+    /// let checkpoint = db.fold_wal(&mut counter, Checkpoint::default()).unwrap();
+    /// ```
+    pub fn fold_wal<P: Projection + ?Sized>(
+        &self,
+        projection: &mut P,
+        from: Checkpoint,
+    ) -> Result<Checkpoint> {
+        let wal_path = self
+            .wal_path()
+            .ok_or_else(|| anyhow::anyhow!("In-memory databases have no WAL to project"))?;
+
+        let wal_id = self.wal_generation()?;
+        let resuming = from.offset > 0 || from.sequence > 0;
+        if resuming && from.wal_id != wal_id {
+            return Err(BarqError::InvalidOperation(format!(
+                "Projection checkpoint was taken on WAL {} but the WAL now continues from \
+                 snapshot {}; rebuild the projection from the start",
+                from.wal_id, wal_id
+            ))
+            .into());
+        }
+
+        let mut file = File::open(&wal_path)
+            .with_context(|| format!("Failed to open WAL for reading: {:?}", wal_path))?;
+        file.seek(SeekFrom::Start(from.offset))
+            .with_context(|| format!("Failed to seek WAL to offset {}", from.offset))?;
+
+        let mut reader = BufReader::new(file);
+        let mut checkpoint = Checkpoint { wal_id, ..from };
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .with_context(|| format!("Failed to read WAL record {}", checkpoint.sequence))?;
+            // Stop at EOF or at a partially written trailing record
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+
+            if !line.trim().is_empty() {
+                let (record, _) = wal::decode_line(&line).with_context(|| {
+                    format!("Failed to parse WAL record {}", checkpoint.sequence)
+                })?;
+                projection.apply(checkpoint.sequence, &record)?;
+                checkpoint.sequence += 1;
+            }
+            checkpoint.offset += read as u64;
+        }
+
+        Ok(checkpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::Node;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[derive(Default)]
+    struct TagCounter {
+        counts: HashMap<String, usize>,
+    }
+
+    impl Projection for TagCounter {
+        fn apply(&mut self, _sequence: u64, record: &WalRecord) -> Result<()> {
+            if let WalRecord::Node { data } = record {
+                for tag in &data.rule_tags {
+                    *self.counts.entry(tag.clone()).or_default() += 1;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn tagged(id: u64, tag: &str) -> Node {
+        let mut node = Node::new(id, format!("n{}", id));
+        node.rule_tags = vec![tag.to_string()];
+        node
+    }
+
+    #[test]
+    fn test_incremental_fold_with_checkpoint() {
+        let dir = TempDir::new().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        db.append_node(tagged(1, "risk")).unwrap();
+        db.append_node(tagged(2, "risk")).unwrap();
+
+        let mut projection = TagCounter::default();
+        let checkpoint = db.fold_wal(&mut projection, Checkpoint::default()).unwrap();
+        assert_eq!(checkpoint.sequence, 2);
+        assert_eq!(projection.counts["risk"], 2);
+
+        // Persist and resume: only new records are applied
+        let cp_path = dir.path().join("tags.checkpoint");
+        checkpoint.save(&cp_path).unwrap();
+        db.append_node(tagged(3, "policy")).unwrap();
+
+        let resumed = Checkpoint::load(&cp_path).unwrap();
+        let checkpoint = db.fold_wal(&mut projection, resumed).unwrap();
+        assert_eq!(checkpoint.sequence, 3);
+        assert_eq!(projection.counts["risk"], 2);
+        assert_eq!(projection.counts["policy"], 1);
+    }
+
+    #[test]
+    fn test_fold_rejects_checkpoint_from_replaced_wal() {
+        let dir = TempDir::new().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        db.append_node(tagged(1, "risk")).unwrap();
+
+        let mut projection = TagCounter::default();
+        let before = db.fold_wal(&mut projection, Checkpoint::default()).unwrap();
+        assert_eq!(before.wal_id, 0);
+
+        let report = db.checkpoint().unwrap();
+        db.append_node(tagged(2, "risk")).unwrap();
+        let err = db.fold_wal(&mut projection, before).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BarqError>(),
+            Some(BarqError::InvalidOperation(_))
+        ));
+        assert_eq!(projection.counts["risk"], 1);
+
+        // A rebuild starts over on the new WAL and can resume on it
+        let mut rebuilt = TagCounter::default();
+        let after = db.fold_wal(&mut rebuilt, Checkpoint::default()).unwrap();
+        assert_eq!(after.wal_id, report.snapshot_id);
+        db.append_node(tagged(3, "risk")).unwrap();
+        db.fold_wal(&mut rebuilt, after).unwrap();
+        assert_eq!(rebuilt.counts["risk"], 2);
+    }
+
+    #[test]
+    fn test_fold_in_memory_fails() {
+        let db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        let mut projection = TagCounter::default();
+        assert!(db.fold_wal(&mut projection, Checkpoint::default()).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Read-time redaction policies.
+//!
+//! A `RedactionPolicy` masks sensitive fields in HTTP and gRPC responses
+//! for callers that don't present a privileged API key. Redaction happens
+//! only when serializing responses; the WAL and in-memory state keep
+//! the original values so authorized audits see full records.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Replacement for masked string fields when no mask is configured.
+pub const DEFAULT_MASK: &str = "[REDACTED]";
+
+/// Masks a set of response fields, optionally only for tagged nodes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionRule {
+    /// Rule tag that selects which records are masked; `None` masks all.
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Response field keys to mask, e.g. `label` or `notes`.
+    pub fields: Vec<String>,
+}
+
+impl RedactionRule {
+    /// Returns true if the rule applies to a record with these tags.
+    fn matches(&self, tags: &[String]) -> bool {
+        self.tag.as_ref().is_none_or(|tag| tags.contains(tag))
+    }
+}
+
+/// Redaction rules and the API keys exempt from them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionPolicy {
+    /// Rules applied to non-privileged callers.
+    #[serde(default)]
+    pub rules: Vec<RedactionRule>,
+    /// API keys that see unredacted data.
+    #[serde(default)]
+    pub privileged_keys: HashSet<String>,
+    /// Replacement for masked string fields (defaults to `[REDACTED]`).
+    #[serde(default)]
+    pub mask: Option<String>,
+}
+
+impl RedactionPolicy {
+    /// Loads a policy from a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::redaction::RedactionPolicy;
+    /// use std::path::Path;
+    ///
+    /// let policy = RedactionPolicy::load(Path::new("./redaction.json")).unwrap();
+    /// ```
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open redaction policy: {:?}", path))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to parse redaction policy: {:?}", path))
+    }
+
+    /// Returns true if the API key is exempt from redaction.
+    pub fn is_privileged(&self, api_key: Option<&str>) -> bool {
+        api_key.is_some_and(|key| self.privileged_keys.contains(key))
+    }
+
+    /// Returns the replacement for masked string fields.
+    pub fn mask(&self) -> &str {
+        self.mask.as_deref().unwrap_or(DEFAULT_MASK)
+    }
+
+    /// Returns true if a field of a record with these tags is masked.
+    ///
+    /// # Arguments
+    ///
+    /// * `tags` - Rule tags of the record (or of the node it refers to)
+    /// * `field` - Response field key, e.g. `label`
+    pub fn masks(&self, tags: &[String], field: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|r| r.matches(tags) && r.fields.iter().any(|f| f == field))
+    }
+
+    /// Masks fields of a serialized record in place.
+    ///
+    /// String fields are replaced with the mask; other values (arrays,
+    /// numbers, objects) become `null`. Keys absent from `record` are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `tags` - Rule tags of the record (or of the node it refers to)
+    /// * `record` - JSON object about to be returned to the caller
+    pub fn apply(&self, tags: &[String], record: &mut Value) {
+        let Some(object) = record.as_object_mut() else {
+            return;
+        };
+        let mask = self.mask();
+        for rule in self.rules.iter().filter(|r| r.matches(tags)) {
+            for field in &rule.fields {
+                if let Some(value) = object.get_mut(field) {
+                    *value = match value {
+                        Value::String(_) => Value::String(mask.to_string()),
+                        _ => Value::Null,
+                    };
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rules_mask_by_tag_and_key() {
+        let policy: RedactionPolicy = serde_json::from_value(json!({
+            "privileged_keys": ["auditor"],
+            "rules": [
+                {"tag": "pii", "fields": ["label", "embedding"]},
+                {"fields": ["notes"]}
+            ]
+        }))
+        .unwrap();
+        assert!(policy.is_privileged(Some("auditor")));
+        assert!(!policy.is_privileged(Some("agent")));
+        assert!(!policy.is_privileged(None));
+
+        let mut tagged = json!({"id": 1, "label": "Jane Doe", "embedding": [0.1], "notes": "x"});
+        policy.apply(&["pii".to_string()], &mut tagged);
+        assert_eq!(
+            tagged,
+            json!({"id": 1, "label": DEFAULT_MASK, "embedding": null, "notes": DEFAULT_MASK})
+        );
+
+        let mut plain = json!({"id": 2, "label": "Public"});
+        policy.apply(&[], &mut plain);
+        assert_eq!(plain, json!({"id": 2, "label": "Public"}));
+
+        assert!(policy.masks(&["pii".to_string()], "label"));
+        assert!(!policy.masks(&[], "label"));
+        assert!(policy.masks(&[], "notes"));
+    }
+}
@@ -0,0 +1,349 @@
+//! Server-side ordering for list operations.
+//!
+//! Sort specifications are written as comma-separated `field[:order]`
+//! keys, e.g. `timestamp:desc,label`. Earlier keys take precedence and
+//! ties are always broken by ID so results are deterministic.
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use anyhow::Result;
+
+use crate::agent::DecisionRecord;
+use crate::error::BarqError;
+use crate::storage::BarqGraphDb;
+use crate::Node;
+
+/// Field a list can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    /// Node or decision ID.
+    Id,
+    /// Node timestamp or decision `created_at`.
+    Timestamp,
+    /// Node label (nodes only).
+    Label,
+    /// Decision score (decisions only).
+    Score,
+    /// Number of outgoing edges (nodes only).
+    Degree,
+}
+
+/// Direction of a sort key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// Smallest first.
+    #[default]
+    Asc,
+    /// Largest first.
+    Desc,
+}
+
+/// A single field and direction in a sort specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    /// Field to compare.
+    pub field: SortField,
+    /// Direction of the comparison.
+    pub order: SortOrder,
+}
+
+/// An ordered list of sort keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortSpec(pub Vec<SortKey>);
+
+impl FromStr for SortField {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "id" => Ok(SortField::Id),
+            "timestamp" | "created_at" => Ok(SortField::Timestamp),
+            "label" => Ok(SortField::Label),
+            "score" => Ok(SortField::Score),
+            "degree" => Ok(SortField::Degree),
+            other => Err(format!("Unknown sort field: {}", other)),
+        }
+    }
+}
+
+impl FromStr for SortSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut keys = Vec::new();
+        for part in s.split(',').filter(|p| !p.trim().is_empty()) {
+            let (field, order) = match part.split_once(':') {
+                Some((field, order)) => (field, order.trim().to_ascii_lowercase()),
+                None => (part, "asc".to_string()),
+            };
+            let order = match order.as_str() {
+                "asc" => SortOrder::Asc,
+                "desc" => SortOrder::Desc,
+                other => return Err(format!("Unknown sort order: {}", other)),
+            };
+            keys.push(SortKey {
+                field: field.parse()?,
+                order,
+            });
+        }
+        Ok(SortSpec(keys))
+    }
+}
+
+impl SortKey {
+    /// Applies this key's direction to an ascending comparison.
+    fn apply(&self, ordering: Ordering) -> Ordering {
+        match self.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    }
+}
+
+/// Rejects sort fields that don't exist on the listed entity.
+fn check_fields(spec: &SortSpec, unsupported: &[SortField], entity: &str) -> Result<()> {
+    if let Some(key) = spec.0.iter().find(|k| unsupported.contains(&k.field)) {
+        return Err(BarqError::InvalidOperation(format!(
+            "Cannot sort {} by {:?}",
+            entity, key.field
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+impl BarqGraphDb {
+    /// Lists all nodes ordered by a sort specification.
+    ///
+    /// Supports `id`, `timestamp`, `label` and `degree`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification uses `score`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::sort::SortSpec;
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let spec: SortSpec = "degree:desc,label".parse().unwrap();
+    /// let hubs_first = db.list_nodes_sorted(&spec).unwrap();
+    /// ```
+    pub fn list_nodes_sorted(&self, spec: &SortSpec) -> Result<Vec<&Node>> {
+        check_fields(spec, &[SortField::Score], "nodes")?;
+        let degree = |n: &Node| self.neighbors(n.id).map_or(0, |v| v.len());
+
+        let mut nodes = self.list_nodes();
+        nodes.sort_by(|a, b| {
+            spec.0
+                .iter()
+                .map(|key| {
+                    key.apply(match key.field {
+                        SortField::Id => a.id.cmp(&b.id),
+                        SortField::Timestamp => a.timestamp.cmp(&b.timestamp),
+                        SortField::Label => a.label.cmp(&b.label),
+                        SortField::Degree => degree(a).cmp(&degree(b)),
+                        SortField::Score => Ordering::Equal,
+                    })
+                })
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| a.id.cmp(&b.id))
+        });
+        Ok(nodes)
+    }
+
+    /// Lists one page of nodes ordered by a sort specification.
+    ///
+    /// A single `label` or `degree` key is read off the label index and
+    /// the degree counters, so a page costs O(offset + limit) instead of
+    /// a sort of every node (`degree` also gathers the nodes without
+    /// outgoing edges once the page reaches them). Nodes have no index
+    /// by ID or timestamp, so those and multi-key specifications sort
+    /// every node as `list_nodes_sorted` does.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - Order of the listing; empty orders by ID
+    /// * `offset` - Number of nodes to skip
+    /// * `limit` - Maximum number of nodes to return
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification uses `score`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::sort::SortSpec;
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let hubs: SortSpec = "degree:desc".parse().unwrap();
+    /// let top_ten = db.list_nodes_page(&hubs, 0, 10).unwrap();
+    /// ```
+    pub fn list_nodes_page(
+        &self,
+        spec: &SortSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<&Node>> {
+        check_fields(spec, &[SortField::Score], "nodes")?;
+        if let [key] = spec.0.as_slice() {
+            let descending = key.order == SortOrder::Desc;
+            let indexed = match key.field {
+                SortField::Label => Some(self.nodes_by_label(descending)),
+                SortField::Degree => Some(self.nodes_by_out_degree(descending)),
+                _ => None,
+            };
+            if let Some(nodes) = indexed {
+                return Ok(nodes.skip(offset).take(limit).collect());
+            }
+        }
+
+        let nodes = self.list_nodes_sorted(spec)?;
+        Ok(nodes.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// Lists an agent's decisions ordered by a sort specification.
+    ///
+    /// Supports `id`, `timestamp` and `score`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification uses `label` or `degree`.
+    pub fn list_decisions_sorted(
+        &self,
+        agent_id: u64,
+        spec: &SortSpec,
+    ) -> Result<Vec<&DecisionRecord>> {
+        let mut decisions = self.list_decisions_for_agent(agent_id);
+        sort_decisions(&mut decisions, spec)?;
+        Ok(decisions)
+    }
+}
+
+/// Orders decisions by a sort specification, ties by ID.
+///
+/// # Errors
+///
+/// Returns an error if the specification uses `label` or `degree`.
+pub(crate) fn sort_decisions(decisions: &mut [&DecisionRecord], spec: &SortSpec) -> Result<()> {
+    check_fields(spec, &[SortField::Label, SortField::Degree], "decisions")?;
+    decisions.sort_by(|a, b| {
+        spec.0
+            .iter()
+            .map(|key| {
+                key.apply(match key.field {
+                    SortField::Id => a.id.cmp(&b.id),
+                    SortField::Timestamp => a.created_at.cmp(&b.created_at),
+                    SortField::Score => a.score.total_cmp(&b.score),
+                    SortField::Label | SortField::Degree => Ordering::Equal,
+                })
+            })
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| a.id.cmp(&b.id))
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+
+    #[test]
+    fn test_parse_sort_spec() {
+        let spec: SortSpec = "timestamp:desc, label".parse().unwrap();
+        assert_eq!(
+            spec.0,
+            vec![
+                SortKey {
+                    field: SortField::Timestamp,
+                    order: SortOrder::Desc
+                },
+                SortKey {
+                    field: SortField::Label,
+                    order: SortOrder::Asc
+                },
+            ]
+        );
+        assert!("weight".parse::<SortSpec>().is_err());
+        assert!("id:sideways".parse::<SortSpec>().is_err());
+    }
+
+    #[test]
+    fn test_multi_field_node_and_decision_sort() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        db.append_node(Node::with_timestamp(1, "b".to_string(), 10))
+            .unwrap();
+        db.append_node(Node::with_timestamp(2, "a".to_string(), 10))
+            .unwrap();
+        db.append_node(Node::with_timestamp(3, "c".to_string(), 20))
+            .unwrap();
+        db.add_edge(3, 1, "X").unwrap();
+
+        let spec: SortSpec = "timestamp:desc,label".parse().unwrap();
+        let ids: Vec<_> = db
+            .list_nodes_sorted(&spec)
+            .unwrap()
+            .iter()
+            .map(|n| n.id)
+            .collect();
+        assert_eq!(ids, vec![3, 2, 1]);
+
+        let spec: SortSpec = "degree:desc".parse().unwrap();
+        assert_eq!(db.list_nodes_sorted(&spec).unwrap()[0].id, 3);
+        assert!(db.list_nodes_sorted(&"score".parse().unwrap()).is_err());
+        assert!(db.list_nodes_page(&"score".parse().unwrap(), 0, 1).is_err());
+
+        for (id, score) in [(1, 0.2), (2, 0.9), (3, 0.5)] {
+            db.record_decision(DecisionRecord::new(id, 7, 1, vec![1], score))
+                .unwrap();
+        }
+        let spec: SortSpec = "score:desc".parse().unwrap();
+        let ids: Vec<_> = db
+            .list_decisions_sorted(7, &spec)
+            .unwrap()
+            .iter()
+            .map(|d| d.id)
+            .collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_indexed_node_pages_match_full_sort() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for (id, label) in [(1, "b"), (2, "a"), (3, "c"), (4, "a"), (5, "d"), (6, "b")] {
+            db.append_node(Node::new(id, label.to_string())).unwrap();
+        }
+        for (from, to) in [(3, 1), (3, 2), (5, 1), (5, 2), (1, 2), (6, 1), (6, 1)] {
+            db.add_edge(from, to, "X").unwrap();
+        }
+        db.append_node(Node::new(2, "e".to_string())).unwrap();
+
+        for spec in ["label", "label:desc", "degree", "degree:desc", "timestamp"] {
+            let spec: SortSpec = spec.parse().unwrap();
+            let full: Vec<_> = db
+                .list_nodes_sorted(&spec)
+                .unwrap()
+                .iter()
+                .map(|n| n.id)
+                .collect();
+            for offset in 0..=full.len() {
+                let page: Vec<_> = db
+                    .list_nodes_page(&spec, offset, 2)
+                    .unwrap()
+                    .iter()
+                    .map(|n| n.id)
+                    .collect();
+                let expected: Vec<_> = full.iter().copied().skip(offset).take(2).collect();
+                assert_eq!(page, expected, "{:?} at offset {}", spec, offset);
+            }
+        }
+    }
+}
@@ -0,0 +1,560 @@
+//! Per-agent activity statistics.
+//!
+//! Summarizes what one agent did: how many decisions it recorded and how
+//! confident they were, how many nodes it created, which nodes its
+//! decision paths lean on, and how its activity is spread over time.
+//! Two agents' decisions from the same root node can also be compared,
+//! to A/B-test agent policies against the same graph, and the resources
+//! decisions consumed can be totalled per agent and day.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeBounds;
+
+use serde::Serialize;
+
+use super::BarqGraphDb;
+use crate::NodeId;
+
+/// Length of an activity bucket in seconds (one day).
+pub const ACTIVITY_BUCKET_SECS: u64 = 86_400;
+
+/// Number of nodes listed in `AgentStats::most_traversed`.
+const MOST_TRAVERSED: usize = 10;
+
+/// Distribution of decision scores.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ScoreStats {
+    /// Lowest score.
+    pub min: f32,
+    /// Median score.
+    pub p50: f32,
+    /// 90th percentile score.
+    pub p90: f32,
+    /// 99th percentile score.
+    pub p99: f32,
+    /// Highest score.
+    pub max: f32,
+    /// Mean score.
+    pub mean: f64,
+}
+
+impl ScoreStats {
+    /// Summarizes a list of scores using nearest-rank percentiles.
+    fn from_scores(mut scores: Vec<f32>) -> Self {
+        if scores.is_empty() {
+            return Self::default();
+        }
+        scores.sort_unstable_by(f32::total_cmp);
+        let n = scores.len();
+        let percentile = |p: usize| scores[(p * n).div_ceil(100).max(1) - 1];
+        Self {
+            min: scores[0],
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: scores[n - 1],
+            mean: scores.iter().map(|&s| f64::from(s)).sum::<f64>() / n as f64,
+        }
+    }
+}
+
+/// A node and how often an agent's decision paths passed through it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct NodeVisits {
+    /// The visited node.
+    pub id: NodeId,
+    /// Occurrences across all of the agent's decision paths.
+    pub visits: usize,
+}
+
+/// An agent's activity within one bucket of time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ActivityBucket {
+    /// Unix timestamp the bucket starts at, a multiple of
+    /// `ACTIVITY_BUCKET_SECS`.
+    pub start: u64,
+    /// Decisions recorded in the bucket.
+    pub decisions: usize,
+    /// Nodes created in the bucket.
+    pub nodes_created: usize,
+}
+
+/// Summary returned by `BarqGraphDb::agent_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AgentStats {
+    /// The summarized agent.
+    pub agent_id: u64,
+    /// Decisions the agent recorded.
+    pub decisions: usize,
+    /// Distribution of the agent's decision scores.
+    pub scores: ScoreStats,
+    /// Nodes whose `agent_id` is the agent.
+    pub nodes_created: usize,
+    /// Nodes the agent's decision paths visit most, most visited first
+    /// (ties by ID).
+    pub most_traversed: Vec<NodeVisits>,
+    /// Daily activity, oldest first; days without any are left out.
+    pub activity: Vec<ActivityBucket>,
+}
+
+/// Resources one agent's decisions consumed within one day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DailyUsage {
+    /// The agent.
+    pub agent_id: u64,
+    /// Unix timestamp the day starts at, a multiple of
+    /// `ACTIVITY_BUCKET_SECS`.
+    pub start: u64,
+    /// Decisions created that day.
+    pub decisions: usize,
+    /// Total tokens of the decisions that recorded them.
+    pub tokens: u64,
+    /// Total cost of the decisions that recorded it.
+    pub cost: f64,
+    /// Mean latency of the decisions that recorded it, if any did.
+    pub mean_latency_ms: Option<f64>,
+}
+
+/// A path one agent took from the compared root.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathOutcome {
+    /// Nodes of the path.
+    pub path: Vec<NodeId>,
+    /// Decisions that took this path.
+    pub decisions: usize,
+    /// Mean score of those decisions.
+    pub mean_score: f64,
+    /// Length of the longest prefix shared with a path of the other
+    /// agent; the paths diverge right after it.
+    pub common_prefix: usize,
+}
+
+/// A path both compared agents took.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SharedPath {
+    /// Nodes of the path.
+    pub path: Vec<NodeId>,
+    /// Mean score of the first agent's decisions on this path.
+    pub score_a: f64,
+    /// Mean score of the second agent's decisions on this path.
+    pub score_b: f64,
+    /// `score_b - score_a`.
+    pub score_diff: f64,
+}
+
+/// One side of an `AgentComparison`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ComparedAgent {
+    /// The agent.
+    pub agent_id: u64,
+    /// The agent's decisions from the compared root.
+    pub decisions: usize,
+    /// Distribution of their scores.
+    pub scores: ScoreStats,
+}
+
+/// Result of `BarqGraphDb::compare_agents`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AgentComparison {
+    /// Root node the compared decisions start from.
+    pub root: NodeId,
+    /// The first agent.
+    pub agent_a: ComparedAgent,
+    /// The second agent.
+    pub agent_b: ComparedAgent,
+    /// Difference of the mean scores (second minus first), if both
+    /// agents decided from the root.
+    pub score_diff: Option<f64>,
+    /// Paths both agents took, ordered by path.
+    pub shared: Vec<SharedPath>,
+    /// Paths only the first agent took, most taken first (ties by path).
+    pub only_a: Vec<PathOutcome>,
+    /// Paths only the second agent took, most taken first (ties by path).
+    pub only_b: Vec<PathOutcome>,
+}
+
+/// Decision count and score sum of each distinct path.
+type PathTally<'a> = BTreeMap<&'a [NodeId], (usize, f64)>;
+
+impl BarqGraphDb {
+    /// Compares two agents' decisions starting from the same root node.
+    ///
+    /// Decisions are grouped by path. Paths both agents took are paired
+    /// with their mean scores; paths only one agent took are listed with
+    /// how far they follow the other agent's paths before diverging.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent_a` - ID of the first agent (e.g. the baseline policy)
+    /// * `agent_b` - ID of the second agent (e.g. the candidate policy)
+    /// * `root` - Root node of the compared decisions
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let comparison = db.compare_agents(1, 2, 100);
+    /// if let Some(diff) = comparison.score_diff {
+    ///     println!("candidate scores {:+.3} on average", diff);
+    /// }
+    /// for outcome in &comparison.only_b {
+    ///     println!("only the candidate took {:?}", outcome.path);
+    /// }
+    /// ```
+    pub fn compare_agents(&self, agent_a: u64, agent_b: u64, root: NodeId) -> AgentComparison {
+        let (side_a, tally_a) = self.compared_agent(agent_a, root);
+        let (side_b, tally_b) = self.compared_agent(agent_b, root);
+        let mean = |&(count, sum): &(usize, f64)| sum / count as f64;
+
+        let shared = tally_a
+            .iter()
+            .filter_map(|(path, a)| {
+                let b = tally_b.get(path)?;
+                Some(SharedPath {
+                    path: path.to_vec(),
+                    score_a: mean(a),
+                    score_b: mean(b),
+                    score_diff: mean(b) - mean(a),
+                })
+            })
+            .collect();
+        let only = |tally: &PathTally, other: &PathTally| {
+            let mut outcomes: Vec<PathOutcome> = tally
+                .iter()
+                .filter(|(path, _)| !other.contains_key(*path))
+                .map(|(path, outcome)| PathOutcome {
+                    path: path.to_vec(),
+                    decisions: outcome.0,
+                    mean_score: mean(outcome),
+                    common_prefix: other
+                        .keys()
+                        .map(|o| {
+                            path.iter()
+                                .zip(o.iter())
+                                .take_while(|(x, y)| x == y)
+                                .count()
+                        })
+                        .max()
+                        .unwrap_or(0),
+                })
+                .collect();
+            outcomes.sort_by(|x, y| y.decisions.cmp(&x.decisions).then(x.path.cmp(&y.path)));
+            outcomes
+        };
+
+        let score_diff = (side_a.decisions > 0 && side_b.decisions > 0)
+            .then_some(side_b.scores.mean - side_a.scores.mean);
+        AgentComparison {
+            root,
+            only_a: only(&tally_a, &tally_b),
+            only_b: only(&tally_b, &tally_a),
+            agent_a: side_a,
+            agent_b: side_b,
+            score_diff,
+            shared,
+        }
+    }
+
+    /// Totals the tokens, cost and latency of decisions per agent and
+    /// UTC day, so spend can be set against graph activity.
+    ///
+    /// Decisions count toward the day they were created. Decisions
+    /// without a recorded value still count as decisions but add
+    /// nothing to that total.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Range of Unix timestamps of the decisions to total
+    ///
+    /// # Returns
+    ///
+    /// One entry per agent and day with decisions, ordered by agent,
+    /// then day.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// for day in db.daily_usage(1_735_603_200..) {
+    ///     println!("agent {} on {}: {:.2}", day.agent_id, day.start, day.cost);
+    /// }
+    /// ```
+    pub fn daily_usage(&self, range: impl RangeBounds<u64>) -> Vec<DailyUsage> {
+        let mut days: BTreeMap<(u64, u64), (DailyUsage, u64, usize)> = BTreeMap::new();
+        for decision in self.decisions.created_in(range) {
+            let start = decision.created_at - decision.created_at % ACTIVITY_BUCKET_SECS;
+            let (usage, latency, timed) =
+                days.entry((decision.agent_id, start)).or_insert_with(|| {
+                    let usage = DailyUsage {
+                        agent_id: decision.agent_id,
+                        start,
+                        ..Default::default()
+                    };
+                    (usage, 0, 0)
+                });
+            usage.decisions += 1;
+            usage.tokens += decision.tokens.unwrap_or(0);
+            usage.cost += decision.cost.unwrap_or(0.0);
+            if let Some(latency_ms) = decision.latency_ms {
+                *latency += latency_ms;
+                *timed += 1;
+            }
+        }
+        days.into_values()
+            .map(|(mut usage, latency, timed)| {
+                usage.mean_latency_ms = (timed > 0).then(|| latency as f64 / timed as f64);
+                usage
+            })
+            .collect()
+    }
+
+    /// Summarizes one agent's decisions from `root`, tallied by path.
+    fn compared_agent(&self, agent_id: u64, root: NodeId) -> (ComparedAgent, PathTally<'_>) {
+        let decisions: Vec<_> = self
+            .decisions
+            .for_agent(agent_id)
+            .filter(|d| d.root_node == root)
+            .collect();
+        let mut tally = PathTally::new();
+        for decision in &decisions {
+            let entry = tally.entry(decision.path.as_slice()).or_default();
+            entry.0 += 1;
+            entry.1 += f64::from(decision.score);
+        }
+        let side = ComparedAgent {
+            agent_id,
+            decisions: decisions.len(),
+            scores: ScoreStats::from_scores(decisions.iter().map(|d| d.score).collect()),
+        };
+        (side, tally)
+    }
+
+    /// Summarizes one agent's activity.
+    ///
+    /// Decisions count toward the day they were created, nodes toward
+    /// their timestamp. An agent without any activity gets zeroed stats.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent_id` - ID of the agent to summarize
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let stats = db.agent_stats(42);
+    /// println!(
+    ///     "{} decisions, median score {}, {} nodes created",
+    ///     stats.decisions, stats.scores.p50, stats.nodes_created
+    /// );
+    /// ```
+    pub fn agent_stats(&self, agent_id: u64) -> AgentStats {
+        let decisions = self.list_decisions_for_agent(agent_id);
+        let mut activity: BTreeMap<u64, ActivityBucket> = BTreeMap::new();
+
+        let mut visits: HashMap<NodeId, usize> = HashMap::new();
+        for decision in &decisions {
+            bucket(&mut activity, decision.created_at).decisions += 1;
+            for &id in &decision.path {
+                *visits.entry(id).or_default() += 1;
+            }
+        }
+        let mut nodes_created = 0;
+        for node in self.nodes.values().filter(|n| n.agent_id == Some(agent_id)) {
+            bucket(&mut activity, node.timestamp).nodes_created += 1;
+            nodes_created += 1;
+        }
+
+        let mut most_traversed: Vec<NodeVisits> = visits
+            .into_iter()
+            .map(|(id, visits)| NodeVisits { id, visits })
+            .collect();
+        most_traversed.sort_unstable_by(|a, b| b.visits.cmp(&a.visits).then(a.id.cmp(&b.id)));
+        most_traversed.truncate(MOST_TRAVERSED);
+
+        AgentStats {
+            agent_id,
+            decisions: decisions.len(),
+            scores: ScoreStats::from_scores(decisions.iter().map(|d| d.score).collect()),
+            nodes_created,
+            most_traversed,
+            activity: activity.into_values().collect(),
+        }
+    }
+}
+
+/// Returns the bucket holding `timestamp`, adding it if needed.
+fn bucket(activity: &mut BTreeMap<u64, ActivityBucket>, timestamp: u64) -> &mut ActivityBucket {
+    let start = timestamp - timestamp % ACTIVITY_BUCKET_SECS;
+    activity.entry(start).or_insert(ActivityBucket {
+        start,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::DecisionRecord;
+    use crate::storage::DbOptions;
+    use crate::Node;
+
+    #[test]
+    fn test_agent_stats() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        let day = ACTIVITY_BUCKET_SECS;
+        for (id, agent_id, timestamp) in [(1, Some(7), 10), (2, Some(7), day + 5), (3, None, 10)] {
+            let mut node = Node::with_timestamp(id, format!("n{}", id), timestamp);
+            node.agent_id = agent_id;
+            db.append_node(node).unwrap();
+        }
+        let decisions = [
+            (1, 7, 20, vec![1, 2, 3], 0.2),
+            (2, 7, 30, vec![2, 3], 0.4),
+            (3, 7, 3 * day, vec![3], 0.9),
+            (4, 8, 40, vec![1], 1.0),
+        ];
+        for (id, agent, created_at, path, score) in decisions {
+            let record = DecisionRecord::with_timestamp(id, agent, created_at, 1, path, score);
+            db.record_decision(record).unwrap();
+        }
+
+        let stats = db.agent_stats(7);
+        assert_eq!((stats.decisions, stats.nodes_created), (3, 2));
+        assert_eq!(
+            (stats.scores.min, stats.scores.p50, stats.scores.max),
+            (0.2, 0.4, 0.9)
+        );
+        assert!((stats.scores.mean - 0.5).abs() < 1e-6);
+        assert_eq!(
+            stats.most_traversed,
+            vec![
+                NodeVisits { id: 3, visits: 3 },
+                NodeVisits { id: 2, visits: 2 },
+                NodeVisits { id: 1, visits: 1 },
+            ]
+        );
+        let activity: Vec<_> = stats
+            .activity
+            .iter()
+            .map(|b| (b.start, b.decisions, b.nodes_created))
+            .collect();
+        assert_eq!(activity, vec![(0, 2, 1), (day, 0, 1), (3 * day, 1, 0)]);
+
+        let idle = db.agent_stats(9);
+        assert_eq!(
+            idle,
+            AgentStats {
+                agent_id: 9,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_daily_usage() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        let day = ACTIVITY_BUCKET_SECS;
+        let decisions = [
+            (1, 8, 10, Some(100), Some(0.5), Some(200)),
+            (2, 7, 20, Some(50), Some(0.25), None),
+            (3, 7, 30, None, Some(1.0), Some(300)),
+            (4, 7, 40, Some(10), None, Some(100)),
+            (5, 7, day + 1, None, None, None),
+        ];
+        for (id, agent, created_at, tokens, cost, latency_ms) in decisions {
+            let mut record = DecisionRecord::with_timestamp(id, agent, created_at, 1, vec![1], 0.5);
+            record.tokens = tokens;
+            record.cost = cost;
+            record.latency_ms = latency_ms;
+            db.record_decision(record).unwrap();
+        }
+
+        let usage = db.daily_usage(..);
+        assert_eq!(
+            usage,
+            vec![
+                DailyUsage {
+                    agent_id: 7,
+                    start: 0,
+                    decisions: 3,
+                    tokens: 60,
+                    cost: 1.25,
+                    mean_latency_ms: Some(200.0),
+                },
+                DailyUsage {
+                    agent_id: 7,
+                    start: day,
+                    decisions: 1,
+                    ..Default::default()
+                },
+                DailyUsage {
+                    agent_id: 8,
+                    start: 0,
+                    decisions: 1,
+                    tokens: 100,
+                    cost: 0.5,
+                    mean_latency_ms: Some(200.0),
+                },
+            ]
+        );
+        assert_eq!(db.daily_usage(15..=day + 1).len(), 2);
+        assert!(db.daily_usage(2 * day..).is_empty());
+    }
+
+    #[test]
+    fn test_compare_agents() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        let decisions = [
+            (1, 7, 1, vec![1, 2, 3], 0.4),
+            (2, 7, 1, vec![1, 2, 3], 0.6),
+            (3, 7, 1, vec![1, 4], 0.2),
+            (4, 8, 1, vec![1, 2, 3], 0.9),
+            (5, 8, 1, vec![1, 2, 5], 0.7),
+            (6, 8, 1, vec![1, 2, 5], 0.8),
+            // Other roots are left out
+            (7, 8, 9, vec![9], 0.0),
+        ];
+        for (id, agent, root, path, score) in decisions {
+            db.record_decision(DecisionRecord::new(id, agent, root, path, score))
+                .unwrap();
+        }
+
+        let comparison = db.compare_agents(7, 8, 1);
+        assert_eq!(
+            (comparison.agent_a.decisions, comparison.agent_b.decisions),
+            (3, 3)
+        );
+        assert!((comparison.score_diff.unwrap() - 0.4).abs() < 1e-6);
+
+        assert_eq!(comparison.shared.len(), 1);
+        let shared = &comparison.shared[0];
+        assert_eq!(shared.path, vec![1, 2, 3]);
+        assert!((shared.score_a - 0.5).abs() < 1e-6);
+        assert!((shared.score_diff - 0.4).abs() < 1e-6);
+
+        let only = |outcomes: &[PathOutcome]| {
+            outcomes
+                .iter()
+                .map(|o| (o.path.clone(), o.decisions, o.common_prefix))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(only(&comparison.only_a), vec![(vec![1, 4], 1, 1)]);
+        assert_eq!(only(&comparison.only_b), vec![(vec![1, 2, 5], 2, 2)]);
+        assert!((comparison.only_b[0].mean_score - 0.75).abs() < 1e-6);
+
+        let one_sided = db.compare_agents(7, 9, 1);
+        assert_eq!(one_sided.score_diff, None);
+        assert!(one_sided.shared.is_empty() && one_sided.only_b.is_empty());
+        assert_eq!(one_sided.only_a.len(), 2);
+    }
+}
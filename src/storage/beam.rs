@@ -0,0 +1,122 @@
+//! Beam-search traversal for hybrid queries.
+//!
+//! Instead of expanding the whole BFS frontier, each hop scores the
+//! newly reached layer and expands only the best `beam_width` nodes, so
+//! the work per hop stays bounded however dense the graph is.
+
+use std::time::Instant;
+
+use super::{BarqGraphDb, BfsTree};
+use crate::counterfactual::Counterfactual;
+use crate::hybrid::{score_results, top_k, HybridParams, HybridResult};
+use crate::NodeId;
+
+impl BarqGraphDb {
+    /// Runs the beam search used by hybrid queries with a beam width.
+    ///
+    /// Records every node reached, like `bfs_tree`, but only the
+    /// `params.beam_width` best-scoring nodes of each layer are expanded.
+    /// Layers are scored against `query_embedding` as a hybrid query
+    /// would; nodes that can't be scored (no or mismatched embedding)
+    /// only fill beam slots the scored ones leave free. Deadlines and
+    /// `params.max_candidates` stop the search as in `bfs_tree`.
+    pub(super) fn beam_tree(
+        &self,
+        starts: &[NodeId],
+        max_hops: usize,
+        query_embedding: &[f32],
+        params: &HybridParams,
+        mask: &Counterfactual,
+        deadline: Option<Instant>,
+    ) -> BfsTree {
+        let width = params.beam_width.unwrap_or(usize::MAX);
+        let full = |found: usize| params.max_candidates.is_some_and(|max| found >= max);
+
+        let mut tree = BfsTree::default();
+        let mut frontier = Vec::new();
+        for &start in starts {
+            if (!self.nodes.contains_key(&start) && !self.graph.contains_node(start))
+                || mask.removes_node(start)
+                || tree.parents.contains_key(&start)
+            {
+                continue;
+            }
+            if full(tree.order.len()) {
+                tree.truncated = true;
+                break;
+            }
+            tree.parents.insert(start, None);
+            tree.order.push((start, 0));
+            frontier.push(start);
+        }
+
+        for depth in 0..max_hops {
+            let mut layer = Vec::new();
+            'expand: for &current in &frontier {
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    tree.partial = true;
+                    return tree;
+                }
+                for neighbor in self.ordered_neighbors(current, params.direction, mask) {
+                    if let std::collections::hash_map::Entry::Vacant(slot) =
+                        tree.parents.entry(neighbor)
+                    {
+                        if full(tree.order.len()) {
+                            tree.truncated = true;
+                            break 'expand;
+                        }
+                        slot.insert(Some(current));
+                        tree.order.push((neighbor, depth + 1));
+                        layer.push(neighbor);
+                    }
+                }
+            }
+            if tree.truncated || layer.is_empty() {
+                break;
+            }
+            frontier = self.best_of_layer(&layer, depth + 1, query_embedding, params, width);
+        }
+
+        tree
+    }
+
+    /// Returns the `width` nodes of a layer with the best hybrid score,
+    /// followed by unscorable nodes while slots remain.
+    fn best_of_layer(
+        &self,
+        layer: &[NodeId],
+        depth: usize,
+        query_embedding: &[f32],
+        params: &HybridParams,
+        width: usize,
+    ) -> Vec<NodeId> {
+        if layer.len() <= width {
+            return layer.to_vec();
+        }
+        let (mut scored, mut unscored) = (Vec::new(), Vec::new());
+        for &id in layer {
+            match self.get_node(id) {
+                Some(node)
+                    if !node.embedding.is_empty()
+                        && node.embedding.len() == query_embedding.len() =>
+                {
+                    let distance = params
+                        .normalization
+                        .distance(query_embedding, &node.embedding);
+                    let mut result = HybridResult::new(id, 0.0, distance, depth, Vec::new());
+                    result.query_distances = params.query_distances(&node.embedding);
+                    scored.push(result);
+                }
+                _ => unscored.push(id),
+            }
+        }
+        score_results(&mut scored, params, |id| self.get_node(id));
+
+        top_k(scored, width)
+            .into_iter()
+            .map(|r| r.id)
+            .chain(unscored)
+            .take(width)
+            .collect()
+    }
+}
@@ -0,0 +1,60 @@
+//! Centrality and clustering over the stored graph (see `crate::graph`).
+
+use std::collections::HashMap;
+
+use super::BarqGraphDb;
+use crate::graph::{self, Centrality, Triangles};
+use crate::NodeId;
+
+impl BarqGraphDb {
+    /// Scores every node of the graph with a centrality measure.
+    ///
+    /// Nodes without edges score zero. Pass `samples` to approximate
+    /// betweenness and closeness from that many BFS sources on large
+    /// graphs.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::graph::Centrality;
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let scores = db.centrality(Centrality::Betweenness, Some(256));
+    /// ```
+    pub fn centrality(&self, measure: Centrality, samples: Option<usize>) -> HashMap<NodeId, f64> {
+        let mut scores = graph::centrality(self.graph.adjacency(), measure, samples);
+        for &id in self.nodes.keys() {
+            scores.entry(id).or_insert(0.0);
+        }
+        scores
+    }
+
+    /// Returns the `k` most central nodes with their scores, highest first.
+    pub fn top_central(
+        &self,
+        measure: Centrality,
+        k: usize,
+        samples: Option<usize>,
+    ) -> Vec<(NodeId, f64)> {
+        graph::top_k(&self.centrality(measure, samples), k)
+    }
+
+    /// Counts triangles in the graph, ignoring edge direction.
+    pub fn triangles(&self) -> Triangles {
+        graph::triangles(self.graph.adjacency())
+    }
+
+    /// Computes the local clustering coefficient of every node.
+    ///
+    /// Values near 1.0 mark tightly knit regions where a node's
+    /// neighbors are linked to each other; nodes without edges score 0.0.
+    pub fn clustering_coefficients(&self) -> HashMap<NodeId, f64> {
+        let mut scores = graph::clustering_coefficients(self.graph.adjacency());
+        for &id in self.nodes.keys() {
+            scores.entry(id).or_insert(0.0);
+        }
+        scores
+    }
+}
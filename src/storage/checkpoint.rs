@@ -0,0 +1,563 @@
+//! Snapshot checkpoints.
+//!
+//! A checkpoint writes the full in-memory state to `snapshot.json` and
+//! replaces the WAL with a fresh one, so recovery time and disk usage
+//! stay bounded. The fresh WAL starts with a `WalRecord::Checkpoint`
+//! marker naming the snapshot it continues from and is renamed over the
+//! old log, so the WAL on disk is always either marked or the complete
+//! old log. In the latter case (a crash or failure between installing
+//! the snapshot and swapping the log) the snapshot's recorded WAL length
+//! tells recovery which prefix is already covered. An HNSW
+//! vector index is saved alongside and reconciled with the recovered
+//! embeddings on open, so it doesn't have to be rebuilt from scratch.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::{
+    AdjacencyMap, BarqGraphDb, ClockMap, ModelMap, PairSet, RecoveredState, TemplateMap, VectorMap,
+    WalRecord, WeightMap,
+};
+use crate::agent::DecisionRecord;
+use crate::error::BarqError;
+use crate::graph::GraphIndex;
+use crate::metrics::BackgroundTask;
+use crate::{Node, NodeId};
+
+/// On-disk snapshot written by `BarqGraphDb::checkpoint`.
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    id: u64,
+    wal_bytes: u64,
+    nodes: Vec<Cow<'a, Node>>,
+    adjacency: &'a AdjacencyMap,
+    edge_weights: &'a WeightMap,
+    edge_types: HashMap<NodeId, Vec<&'a str>>,
+    orphan_embeddings: &'a VectorMap,
+    decisions: &'a [DecisionRecord],
+    templates: &'a TemplateMap,
+    clocks: &'a ClockMap,
+    embedding_models: &'a ModelMap,
+    removed_edges: &'a PairSet,
+}
+
+/// Owned form of `SnapshotRef` used when loading.
+#[derive(Deserialize)]
+struct Snapshot {
+    id: u64,
+    wal_bytes: u64,
+    nodes: Vec<Node>,
+    adjacency: AdjacencyMap,
+    #[serde(default)]
+    edge_weights: WeightMap,
+    /// Edge types parallel to `adjacency`; older snapshots leave edges untyped.
+    #[serde(default)]
+    edge_types: HashMap<NodeId, Vec<String>>,
+    orphan_embeddings: VectorMap,
+    decisions: Vec<DecisionRecord>,
+    #[serde(default)]
+    templates: TemplateMap,
+    #[serde(default)]
+    clocks: ClockMap,
+    #[serde(default)]
+    embedding_models: ModelMap,
+    #[serde(default)]
+    removed_edges: PairSet,
+}
+
+/// Summary of a completed checkpoint.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckpointReport {
+    /// Identifier of the written snapshot.
+    pub snapshot_id: u64,
+    /// Nodes captured in the snapshot.
+    pub nodes: usize,
+    /// Decisions captured in the snapshot.
+    pub decisions: usize,
+    /// WAL bytes folded into the snapshot and truncated.
+    pub wal_bytes: u64,
+}
+
+/// Restores state from a snapshot file, if present.
+///
+/// # Returns
+///
+/// The restored state and the number of leading WAL bytes that are
+/// already covered by the snapshot and must not be replayed again.
+pub(super) fn load_snapshot(
+    snapshot_path: &Path,
+    wal_path: &Path,
+) -> Result<(RecoveredState, u64)> {
+    if !snapshot_path.exists() {
+        return Ok((Default::default(), 0));
+    }
+    let file = File::open(snapshot_path)
+        .with_context(|| format!("Failed to open snapshot: {:?}", snapshot_path))?;
+    let snapshot: Snapshot = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Failed to parse snapshot: {:?}", snapshot_path))?;
+
+    // A WAL that was not truncated yet still holds the snapshotted prefix
+    let skip_bytes = match fs::metadata(wal_path) {
+        Ok(meta) if meta.len() >= snapshot.wal_bytes => {
+            if first_checkpoint_id(wal_path)? == Some(snapshot.id) {
+                0
+            } else {
+                snapshot.wal_bytes
+            }
+        }
+        _ => 0,
+    };
+
+    let mut vectors = snapshot.orphan_embeddings;
+    for node in &snapshot.nodes {
+        if !node.embedding.is_empty() {
+            vectors.insert(node.id, node.embedding.clone());
+        }
+    }
+    let nodes = snapshot.nodes.into_iter().map(|n| (n.id, n)).collect();
+
+    Ok((
+        RecoveredState {
+            nodes,
+            graph: GraphIndex::from_parts(
+                snapshot.adjacency,
+                snapshot.edge_weights,
+                snapshot.edge_types,
+            ),
+            vectors,
+            decisions: snapshot.decisions,
+            templates: snapshot.templates,
+            clocks: snapshot.clocks,
+            models: snapshot.embedding_models,
+            removed_edges: snapshot.removed_edges,
+        },
+        skip_bytes,
+    ))
+}
+
+/// Returns the snapshot ID of a leading checkpoint marker in the WAL.
+fn first_checkpoint_id(wal_path: &Path) -> Result<Option<u64>> {
+    let file = File::open(wal_path)
+        .with_context(|| format!("Failed to open WAL for reading: {:?}", wal_path))?;
+    let mut line = String::new();
+    BufReader::new(file)
+        .read_line(&mut line)
+        .with_context(|| "Failed to read first WAL record")?;
+    Ok(match super::wal::decode_line(&line) {
+        Ok((WalRecord::Checkpoint { id }, _)) => Some(id),
+        _ => None,
+    })
+}
+
+impl BarqGraphDb {
+    /// Returns the snapshot ID the current WAL continues from, or 0 for
+    /// a WAL that was never replaced by a checkpoint.
+    pub(crate) fn wal_generation(&self) -> Result<u64> {
+        match self.wal_path() {
+            Some(wal_path) if wal_path.exists() => Ok(first_checkpoint_id(&wal_path)?.unwrap_or(0)),
+            _ => Ok(0),
+        }
+    }
+
+    /// Writes a snapshot of the current state and starts a fresh WAL.
+    ///
+    /// Subsequent opens load the snapshot and replay only the records
+    /// written after it. Projection checkpoints (see
+    /// `crate::projection`) taken on the old log are rejected by
+    /// `fold_wal` afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for in-memory databases or if the snapshot or
+    /// fresh WAL cannot be written. The old WAL stays in use then, so
+    /// no acknowledged write is lost.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let report = db.checkpoint().unwrap();
+    /// println!("Folded {} WAL bytes", report.wal_bytes);
+    /// ```
+    pub fn checkpoint(&mut self) -> Result<CheckpointReport> {
+        let Some(wal) = self.wal.as_mut() else {
+            return Err(BarqError::InvalidOperation(
+                "In-memory databases cannot be checkpointed".to_string(),
+            )
+            .into());
+        };
+        wal.flush().with_context(|| "Failed to flush WAL")?;
+        let wal_bytes = wal.metadata().with_context(|| "Failed to stat WAL")?.len();
+
+        // Save the vector index so the next open can skip rebuilding it;
+        // embeddings still queued for async indexing go in first
+        self.drain_index_queue();
+        self.vector_index
+            .persist(&self.options.hnsw_base())
+            .with_context(|| "Failed to save vector index")?;
+
+        let snapshot_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        // Cold nodes are written in full, without hydrating them in memory
+        let nodes = self
+            .nodes
+            .values()
+            .map(|node| self.load_full(node))
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| "Failed to read cold nodes for snapshot")?;
+        let snapshot = SnapshotRef {
+            id: snapshot_id,
+            wal_bytes,
+            nodes,
+            adjacency: self.graph.adjacency(),
+            edge_weights: self.graph.weights(),
+            edge_types: self.graph.edge_types(),
+            orphan_embeddings: &self.orphan_embeddings,
+            decisions: self.decisions.records(),
+            templates: &self.templates,
+            clocks: &self.clocks,
+            embedding_models: self.models.by_node(),
+            removed_edges: &self.removed_edges,
+        };
+
+        // Write to a temporary file and rename so a crash never leaves a torn snapshot
+        let snapshot_path = self.options.snapshot_file();
+        let tmp_path = snapshot_path.with_extension("json.tmp");
+        {
+            let file = File::create(&tmp_path)
+                .with_context(|| format!("Failed to create snapshot: {:?}", tmp_path))?;
+            let mut writer = std::io::BufWriter::new(file);
+            serde_json::to_writer(&mut writer, &snapshot)
+                .with_context(|| "Failed to serialize snapshot")?;
+            let file = writer
+                .into_inner()
+                .map_err(|e| e.into_error())
+                .with_context(|| "Failed to write snapshot")?;
+            file.sync_all().with_context(|| "Failed to sync snapshot")?;
+        }
+        fs::rename(&tmp_path, &snapshot_path)
+            .with_context(|| format!("Failed to install snapshot: {:?}", snapshot_path))?;
+
+        let report = CheckpointReport {
+            snapshot_id,
+            nodes: snapshot.nodes.len(),
+            decisions: self.decisions.len(),
+            wal_bytes,
+        };
+
+        // Swap in a log holding only the marker; the old log stays in
+        // place, complete, until the rename succeeds
+        let wal_path = self.options.wal_file();
+        let fresh_path = fresh_wal_path(&wal_path);
+        let fresh = write_fresh_wal(&fresh_path, snapshot_id)?;
+        fs::rename(&fresh_path, &wal_path)
+            .with_context(|| format!("Failed to install fresh WAL: {:?}", wal_path))?;
+        self.wal = Some(fresh);
+
+        Ok(report)
+    }
+
+    /// Starts the background maintenance thread for a shared database.
+    ///
+    /// Every `DbOptions::checkpoint_interval` the thread takes a
+    /// checkpoint if the WAL grew since the last one, reporting failures
+    /// to the metrics sink's `background_error`. It exits when the
+    /// interval is unset or once the database has been dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use tokio::sync::Mutex;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"))
+    ///     .with_checkpoint_interval(Duration::from_secs(300));
+    /// let db = Arc::new(Mutex::new(BarqGraphDb::open(opts).unwrap()));
+    /// BarqGraphDb::spawn_checkpoint_thread(&db);
+    /// ```
+    pub fn spawn_checkpoint_thread(db: &Arc<Mutex<BarqGraphDb>>) -> JoinHandle<()> {
+        let weak = Arc::downgrade(db);
+        std::thread::spawn(move || run_checkpoint_loop(weak))
+    }
+}
+
+/// Location the fresh WAL is written to before replacing the old one.
+fn fresh_wal_path(wal_path: &Path) -> PathBuf {
+    let mut path = wal_path.as_os_str().to_owned();
+    path.push(".tmp");
+    PathBuf::from(path)
+}
+
+/// Creates a WAL holding only the marker of a snapshot.
+///
+/// # Returns
+///
+/// The file opened for appending, synced to disk.
+fn write_fresh_wal(path: &Path, snapshot_id: u64) -> Result<File> {
+    let mut line = super::wal::encode_line(&WalRecord::Checkpoint { id: snapshot_id })
+        .with_context(|| "Failed to serialize checkpoint to JSON")?;
+    line.push('\n');
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)
+        .with_context(|| format!("Failed to create fresh WAL: {:?}", path))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| "Failed to write checkpoint to fresh WAL")?;
+    file.sync_all()
+        .with_context(|| "Failed to sync fresh WAL")?;
+    drop(file);
+
+    fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open fresh WAL: {:?}", path))
+}
+
+/// Body of the background checkpoint thread.
+fn run_checkpoint_loop(db: Weak<Mutex<BarqGraphDb>>) {
+    let Some(interval) = db
+        .upgrade()
+        .and_then(|db| db.blocking_lock().options.checkpoint_interval)
+    else {
+        return;
+    };
+
+    // WAL length right after the previous checkpoint (just the marker)
+    let mut checkpointed_len = None;
+    loop {
+        std::thread::sleep(interval);
+        let Some(db) = db.upgrade() else {
+            break;
+        };
+        let mut db = db.blocking_lock();
+        let Some(wal_path) = db.wal_path() else {
+            break;
+        };
+
+        let wal_len = fs::metadata(&wal_path).map(|m| m.len()).unwrap_or(0);
+        if wal_len == 0 || Some(wal_len) == checkpointed_len {
+            continue;
+        }
+        match db.checkpoint() {
+            Ok(_) => checkpointed_len = fs::metadata(&wal_path).map(|m| m.len()).ok(),
+            Err(e) => db.report_background_error(BackgroundTask::Checkpoint, &e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn populate(db: &mut BarqGraphDb) {
+        let mut node = Node::new(1, "a".to_string());
+        node.embedding = vec![1.0, 0.0];
+        db.append_node(node).unwrap();
+        db.append_node(Node::new(2, "b".to_string())).unwrap();
+        db.add_edge(1, 2, "NEXT").unwrap();
+        db.set_embedding(9, vec![0.0, 1.0]).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_truncates_wal_and_recovers() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            populate(&mut db);
+            let before = fs::metadata(opts.wal_file()).unwrap().len();
+            let report = db.checkpoint().unwrap();
+            assert_eq!(report.nodes, 2);
+            assert_eq!(report.wal_bytes, before);
+            assert!(fs::metadata(opts.wal_file()).unwrap().len() < before);
+
+            // Writes after the checkpoint land in the fresh WAL
+            db.add_edge(2, 1, "BACK").unwrap();
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.node_count(), 2);
+        assert_eq!(db.edge_count(), 2);
+        assert_eq!(db.vector_count(), 2);
+        assert_eq!(db.knn_search(&[0.0, 1.0], 1)[0].0, 9);
+    }
+
+    #[test]
+    fn test_recovery_before_wal_truncation() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+        populate(&mut db);
+        let wal_before = fs::read(opts.wal_file()).unwrap();
+        db.checkpoint().unwrap();
+        drop(db);
+
+        // Simulate a crash between installing the snapshot and swapping
+        // the WAL, with the fresh log left half-written
+        let fresh = fs::read(opts.wal_file()).unwrap();
+        fs::write(fresh_wal_path(&opts.wal_file()), &fresh[..fresh.len() / 2]).unwrap();
+        fs::write(opts.wal_file(), &wal_before).unwrap();
+        let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+        assert_eq!(db.node_count(), 2);
+        assert_eq!(db.edge_count(), 1);
+
+        // The unmarked log keeps growing past the snapshotted prefix
+        db.append_node(Node::new(3, "c".to_string())).unwrap();
+        drop(db);
+        let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+        assert_eq!(db.node_count(), 3);
+        assert_eq!(db.edge_count(), 1);
+
+        // The next checkpoint replaces the stale fresh log
+        db.checkpoint().unwrap();
+        db.add_edge(3, 1, "BACK").unwrap();
+        drop(db);
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.node_count(), 3);
+        assert_eq!(db.edge_count(), 2);
+    }
+
+    #[test]
+    fn test_failed_wal_swap_keeps_writes() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+        populate(&mut db);
+
+        // A directory in the way makes creating the fresh WAL fail after
+        // the snapshot is installed
+        fs::create_dir(fresh_wal_path(&opts.wal_file())).unwrap();
+        assert!(db.checkpoint().is_err());
+        assert!(opts.snapshot_file().exists());
+
+        // The database keeps appending to the old, complete log
+        db.append_node(Node::new(3, "c".to_string())).unwrap();
+        db.add_edge(3, 1, "BACK").unwrap();
+        drop(db);
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.node_count(), 3);
+        assert_eq!(db.edge_count(), 2);
+        assert_eq!(db.vector_count(), 2);
+    }
+
+    #[test]
+    fn test_background_checkpoint_thread() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf())
+            .with_checkpoint_interval(Duration::from_millis(20));
+        let db = Arc::new(Mutex::new(BarqGraphDb::open(opts.clone()).unwrap()));
+        populate(&mut db.blocking_lock());
+
+        let handle = BarqGraphDb::spawn_checkpoint_thread(&db);
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(opts.snapshot_file().exists());
+
+        drop(db);
+        handle.join().unwrap();
+        assert_eq!(BarqGraphDb::open(opts).unwrap().node_count(), 2);
+    }
+
+    #[test]
+    fn test_background_checkpoint_failures_reach_sink() {
+        #[derive(Default)]
+        struct FailureLog(std::sync::Mutex<Vec<BackgroundTask>>);
+
+        impl crate::metrics::MetricsSink for FailureLog {
+            fn record(&self, _op: crate::metrics::Operation, _elapsed: Duration) {}
+
+            fn background_error(&self, task: BackgroundTask, _error: &anyhow::Error) {
+                self.0.lock().unwrap().push(task);
+            }
+        }
+
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf())
+            .with_checkpoint_interval(Duration::from_millis(10));
+        let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+        populate(&mut db);
+        let log = Arc::new(FailureLog::default());
+        db.set_metrics_sink(Some(log.clone()));
+        fs::create_dir(fresh_wal_path(&opts.wal_file())).unwrap();
+
+        let db = Arc::new(Mutex::new(db));
+        let handle = BarqGraphDb::spawn_checkpoint_thread(&db);
+        while log.0.lock().unwrap().is_empty() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(log.0.lock().unwrap()[0], BackgroundTask::Checkpoint);
+        drop(db);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_saved_hnsw_index_is_reconciled_on_open() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for i in 0..20u64 {
+                db.set_embedding(i, vec![i as f32, 1.0]).unwrap();
+            }
+            db.checkpoint().unwrap();
+            // Changes after the checkpoint only reach the WAL
+            db.set_embedding(5, vec![100.0, 1.0]).unwrap();
+            db.set_embedding(20, vec![-50.0, 1.0]).unwrap();
+        }
+        assert!(opts.hnsw_base().with_extension("ids.json").exists());
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.knn_search(&[100.0, 1.0], 1)[0].0, 5);
+        assert_eq!(db.knn_search(&[-50.0, 1.0], 1)[0].0, 20);
+        assert_eq!(db.knn_search(&[6.2, 1.0], 1)[0].0, 6);
+        assert!(db.open_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_unreadable_saved_hnsw_index_is_rebuilt_with_warning() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for i in 0..20u64 {
+                db.set_embedding(i, vec![i as f32, 1.0]).unwrap();
+            }
+            db.checkpoint().unwrap();
+        }
+        fs::write(opts.hnsw_base().with_extension("ids.json"), b"not json").unwrap();
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.open_warnings().len(), 1);
+        assert!(db.open_warnings()[0].contains("HNSW"));
+        assert_eq!(db.knn_search(&[6.2, 1.0], 1)[0].0, 6);
+    }
+
+    #[test]
+    fn test_checkpoint_in_memory_fails() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        assert!(db.checkpoint().is_err());
+    }
+}
@@ -0,0 +1,263 @@
+//! Connected component labelling.
+//!
+//! Weak components ignore edge direction; strong components require a
+//! directed path both ways and are found with an iterative Tarjan search,
+//! so deep chains can't overflow the stack. Labels are ordered by size,
+//! largest first, so label 0 is the main body of the graph and the tail
+//! lists the orphaned islands.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use super::BarqGraphDb;
+use crate::NodeId;
+
+/// Kind of connectivity used to group nodes into components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Connectivity {
+    /// Nodes linked by edges in either direction.
+    #[default]
+    Weak,
+    /// Nodes that can each reach the other along edge direction.
+    Strong,
+}
+
+/// Component label of every node.
+///
+/// Labels run from 0 to `count() - 1`, ordered by component size
+/// descending, then by smallest member ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Components {
+    labels: HashMap<NodeId, usize>,
+    sizes: Vec<usize>,
+}
+
+impl Components {
+    /// Returns the label of a node, or `None` if it isn't in the graph.
+    pub fn label(&self, id: NodeId) -> Option<usize> {
+        self.labels.get(&id).copied()
+    }
+
+    /// Returns the labels of all nodes.
+    pub fn labels(&self) -> &HashMap<NodeId, usize> {
+        &self.labels
+    }
+
+    /// Returns the number of components.
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// Returns the number of nodes in each component, indexed by label.
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    /// Returns the members of a component, sorted by ID.
+    pub fn members(&self, label: usize) -> Vec<NodeId> {
+        let mut members: Vec<NodeId> = self
+            .labels
+            .iter()
+            .filter(|(_, &l)| l == label)
+            .map(|(&id, _)| id)
+            .collect();
+        members.sort_unstable();
+        members
+    }
+
+    /// Builds labels from unordered groups of node IDs.
+    fn from_groups(mut groups: Vec<Vec<NodeId>>) -> Self {
+        for group in &mut groups {
+            group.sort_unstable();
+        }
+        groups.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].cmp(&b[0])));
+        let mut labels = HashMap::new();
+        for (label, group) in groups.iter().enumerate() {
+            labels.extend(group.iter().map(|&id| (id, label)));
+        }
+        Self {
+            labels,
+            sizes: groups.iter().map(Vec::len).collect(),
+        }
+    }
+}
+
+impl BarqGraphDb {
+    /// Labels each node with the component it belongs to.
+    ///
+    /// Covers every stored node and every edge endpoint; a node without
+    /// edges forms a component of its own.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, Connectivity, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let components = db.connected_components(Connectivity::Weak);
+    /// // Everything outside the largest component is an island
+    /// for label in 1..components.count() {
+    ///     println!("island: {:?}", components.members(label));
+    /// }
+    /// ```
+    pub fn connected_components(&self, connectivity: Connectivity) -> Components {
+        let mut ids: Vec<NodeId> = self
+            .nodes
+            .keys()
+            .chain(self.graph.adjacency().keys())
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let index: HashMap<NodeId, usize> =
+            ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let successors: Vec<Vec<usize>> = ids
+            .iter()
+            .map(|id| {
+                self.graph.neighbors(*id).map_or_else(Vec::new, |targets| {
+                    targets.iter().map(|t| index[t]).collect()
+                })
+            })
+            .collect();
+
+        let groups = match connectivity {
+            Connectivity::Weak => weak_groups(&successors),
+            Connectivity::Strong => strong_groups(&successors),
+        };
+        Components::from_groups(
+            groups
+                .into_iter()
+                .map(|group| group.into_iter().map(|i| ids[i]).collect())
+                .collect(),
+        )
+    }
+}
+
+/// Groups vertices reachable from each other ignoring direction.
+fn weak_groups(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut undirected = vec![Vec::new(); successors.len()];
+    for (v, targets) in successors.iter().enumerate() {
+        for &w in targets {
+            undirected[v].push(w);
+            undirected[w].push(v);
+        }
+    }
+
+    let mut seen = vec![false; successors.len()];
+    let mut groups = Vec::new();
+    for root in 0..successors.len() {
+        if seen[root] {
+            continue;
+        }
+        seen[root] = true;
+        let mut group = Vec::new();
+        let mut queue = VecDeque::from([root]);
+        while let Some(v) = queue.pop_front() {
+            group.push(v);
+            for &w in &undirected[v] {
+                if !seen[w] {
+                    seen[w] = true;
+                    queue.push_back(w);
+                }
+            }
+        }
+        groups.push(group);
+    }
+    groups
+}
+
+/// Groups vertices into strongly connected components (Tarjan).
+fn strong_groups(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+    let n = successors.len();
+    let mut order = vec![UNVISITED; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut next_order = 0;
+    let mut groups = Vec::new();
+
+    for root in 0..n {
+        if order[root] != UNVISITED {
+            continue;
+        }
+        // Explicit call stack of (vertex, next successor to visit)
+        let mut calls = vec![(root, 0)];
+        order[root] = next_order;
+        low[root] = next_order;
+        next_order += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&(v, i)) = calls.last() {
+            if let Some(&w) = successors[v].get(i) {
+                calls.last_mut().unwrap().1 += 1;
+                if order[w] == UNVISITED {
+                    order[w] = next_order;
+                    low[w] = next_order;
+                    next_order += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(order[w]);
+                }
+                continue;
+            }
+
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+            if low[v] == order[v] {
+                let mut group = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    group.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                groups.push(group);
+            }
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::Node;
+
+    #[test]
+    fn test_weak_and_strong_components() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        for id in 1..=7 {
+            db.append_node(Node::new(id, format!("n{}", id))).unwrap();
+        }
+        // Cycle 1 -> 2 -> 3 -> 1 feeding 4; 5 -> 6 apart; 7 isolated
+        db.add_edge(1, 2, "X").unwrap();
+        db.add_edge(2, 3, "X").unwrap();
+        db.add_edge(3, 1, "X").unwrap();
+        db.add_edge(3, 4, "X").unwrap();
+        db.add_edge(5, 6, "X").unwrap();
+
+        let weak = db.connected_components(Connectivity::Weak);
+        assert_eq!(weak.sizes(), &[4, 2, 1]);
+        assert_eq!(weak.members(0), vec![1, 2, 3, 4]);
+        assert_eq!(weak.label(6), Some(1));
+        assert_eq!(weak.label(7), Some(2));
+
+        let strong = db.connected_components(Connectivity::Strong);
+        assert_eq!(strong.count(), 5);
+        assert_eq!(strong.members(0), vec![1, 2, 3]);
+        assert_ne!(strong.label(5), strong.label(6));
+        assert_eq!(strong.label(99), None);
+    }
+}
@@ -0,0 +1,862 @@
+//! Decision records with lookup indices.
+//!
+//! Records are kept in the order they were recorded, with indices by
+//! decision ID, agent, session, tag, parent decision and creation time, so
+//! audit queries don't scan every decision. Decision embeddings live in
+//! a vector index of their own, apart from node embeddings.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeBounds;
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{BarqGraphDb, WalRecord};
+use crate::agent::DecisionRecord;
+use crate::sort::{sort_decisions, SortField, SortOrder, SortSpec};
+use crate::vector::VectorIndex;
+use crate::NodeId;
+
+/// How two consecutive nodes of a decision path are linked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepLink {
+    /// An edge leads from the first node to the second.
+    Edge,
+    /// Only an edge from the second node back to the first exists.
+    ReverseEdge,
+    /// An edge between them existed but has been removed.
+    RemovedEdge,
+    /// No edge between them ever existed.
+    Missing,
+}
+
+/// One consecutive pair of a decision path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PathStep {
+    /// Node the step leaves.
+    pub from: NodeId,
+    /// Node the step reaches.
+    pub to: NodeId,
+    /// How the two are linked.
+    pub link: StepLink,
+}
+
+/// Result of `BarqGraphDb::validate_decision`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DecisionValidation {
+    /// The checked decision.
+    pub decision_id: u64,
+    /// Root and path nodes that don't exist, in path order.
+    pub missing_nodes: Vec<NodeId>,
+    /// Every consecutive pair of the path, in order.
+    pub steps: Vec<PathStep>,
+}
+
+impl DecisionValidation {
+    /// Returns true if every node exists and every step is (or was)
+    /// connected by an edge in either direction.
+    pub fn is_valid(&self) -> bool {
+        self.missing_nodes.is_empty() && self.steps.iter().all(|s| s.link != StepLink::Missing)
+    }
+}
+
+/// Decision records in recording order, with their indices.
+pub(super) struct DecisionLog {
+    records: Vec<DecisionRecord>,
+    /// Positions of each ID's records, ascending; lookups by ID see the
+    /// first.
+    by_id: HashMap<u64, Vec<usize>>,
+    /// Positions of each agent's records, ascending.
+    by_agent: HashMap<u64, Vec<usize>>,
+    /// Positions of each session's records, ascending.
+    by_session: HashMap<String, Vec<usize>>,
+    /// Positions of the records carrying each tag, ascending.
+    by_tag: HashMap<String, Vec<usize>>,
+    /// Positions of each decision's children, ascending.
+    by_parent: HashMap<u64, Vec<usize>>,
+    /// Positions of the records created at each timestamp, ascending.
+    by_time: BTreeMap<u64, Vec<usize>>,
+    /// Embeddings of the records that have one, keyed by decision ID.
+    vectors: Arc<dyn VectorIndex>,
+}
+
+impl DecisionLog {
+    /// Indexes every given record, keeping their order, with embeddings
+    /// going into the empty index `vectors`.
+    pub(super) fn build(
+        records: impl IntoIterator<Item = DecisionRecord>,
+        vectors: Arc<dyn VectorIndex>,
+    ) -> Self {
+        let mut log = Self {
+            records: Vec::new(),
+            by_id: HashMap::new(),
+            by_agent: HashMap::new(),
+            by_session: HashMap::new(),
+            by_tag: HashMap::new(),
+            by_parent: HashMap::new(),
+            by_time: BTreeMap::new(),
+            vectors,
+        };
+        for record in records {
+            log.insert(record);
+        }
+        log
+    }
+
+    pub(super) fn insert(&mut self, record: DecisionRecord) {
+        let pos = self.records.len();
+        let same_id = self.by_id.entry(record.id).or_default();
+        // Lookups by ID see the first record with it, so only that one is searchable
+        if same_id.is_empty() && !record.embedding.is_empty() {
+            self.vectors.insert(record.id, &record.embedding);
+        }
+        same_id.push(pos);
+        self.by_agent.entry(record.agent_id).or_default().push(pos);
+        if let Some(session) = &record.session_id {
+            self.by_session
+                .entry(session.clone())
+                .or_default()
+                .push(pos);
+        }
+        for (i, tag) in record.tags.iter().enumerate() {
+            // A tag repeated on one record indexes it once
+            if !record.tags[..i].contains(tag) {
+                self.by_tag.entry(tag.clone()).or_default().push(pos);
+            }
+        }
+        if let Some(parent) = record.parent_decision_id {
+            self.by_parent.entry(parent).or_default().push(pos);
+        }
+        self.by_time.entry(record.created_at).or_default().push(pos);
+        self.records.push(record);
+    }
+
+    /// All records in recording order.
+    pub(super) fn records(&self) -> &[DecisionRecord] {
+        &self.records
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub(super) fn get(&self, id: u64) -> Option<&DecisionRecord> {
+        self.by_id
+            .get(&id)
+            .map(|positions| &self.records[positions[0]])
+    }
+
+    /// An agent's records in recording order.
+    pub(super) fn for_agent(&self, agent_id: u64) -> impl Iterator<Item = &DecisionRecord> {
+        self.by_agent
+            .get(&agent_id)
+            .into_iter()
+            .flatten()
+            .map(|&pos| &self.records[pos])
+    }
+
+    /// A session's records in recording order.
+    pub(super) fn for_session(&self, session_id: &str) -> impl Iterator<Item = &DecisionRecord> {
+        self.by_session
+            .get(session_id)
+            .into_iter()
+            .flatten()
+            .map(|&pos| &self.records[pos])
+    }
+
+    /// Redacts every record with ID `id`, dropping its embedding from
+    /// the index.
+    ///
+    /// # Returns
+    ///
+    /// `true` if any record had this ID.
+    pub(super) fn redact(&mut self, id: u64, include_path: bool) -> bool {
+        let Some(positions) = self.by_id.get(&id) else {
+            return false;
+        };
+        for &pos in positions {
+            self.records[pos].redact(include_path);
+        }
+        self.vectors.remove(id);
+        true
+    }
+
+    /// Records carrying `tag`, in recording order.
+    pub(super) fn for_tag(&self, tag: &str) -> impl Iterator<Item = &DecisionRecord> {
+        self.by_tag
+            .get(tag)
+            .into_iter()
+            .flatten()
+            .map(|&pos| &self.records[pos])
+    }
+
+    /// Records naming `parent_id` as their parent, in recording order.
+    pub(super) fn children(&self, parent_id: u64) -> impl Iterator<Item = &DecisionRecord> {
+        self.by_parent
+            .get(&parent_id)
+            .into_iter()
+            .flatten()
+            .map(|&pos| &self.records[pos])
+    }
+
+    /// Records created within `range`, oldest first.
+    pub(super) fn created_in(
+        &self,
+        range: impl RangeBounds<u64>,
+    ) -> impl Iterator<Item = &DecisionRecord> {
+        self.by_time
+            .range(range)
+            .flat_map(|(_, positions)| positions)
+            .map(|&pos| &self.records[pos])
+    }
+
+    /// Records nearest to `query`, with their distances.
+    pub(super) fn nearest(&self, query: &[f32], k: usize) -> Vec<(&DecisionRecord, f32)> {
+        self.vectors
+            .knn(query, k)
+            .into_iter()
+            .filter_map(|(id, distance)| Some((self.get(id)?, distance)))
+            .collect()
+    }
+
+    /// All records by creation time, records created at the same time
+    /// by ID.
+    pub(super) fn by_creation(
+        &self,
+        descending: bool,
+    ) -> Box<dyn Iterator<Item = &DecisionRecord> + '_> {
+        let group = |positions: &Vec<usize>| {
+            let mut records: Vec<_> = positions.iter().map(|&pos| &self.records[pos]).collect();
+            records.sort_by_key(|d| d.id);
+            records
+        };
+        if descending {
+            Box::new(self.by_time.values().rev().flat_map(group))
+        } else {
+            Box::new(self.by_time.values().flat_map(group))
+        }
+    }
+}
+
+impl BarqGraphDb {
+    /// Lists decisions created within a time range, oldest first.
+    ///
+    /// Decisions with the same timestamp keep the order they were
+    /// recorded in.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Range of Unix timestamps, e.g. `1_700_000_000..` for
+    ///   everything since then
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// for decision in db.list_decisions_between(1_700_000_000..1_700_086_400) {
+    ///     println!("{} by agent {}", decision.id, decision.agent_id);
+    /// }
+    /// ```
+    pub fn list_decisions_between(&self, range: impl RangeBounds<u64>) -> Vec<&DecisionRecord> {
+        self.decisions.created_in(range).collect()
+    }
+
+    /// Lists the decisions of one agent run in the order they were
+    /// recorded, so the run can be replayed step by step.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - Session given by `DecisionRecord::with_session`
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// for (step, decision) in db.list_decisions_for_session("run-42").iter().enumerate() {
+    ///     println!("step {}: {:?}", step, decision.path);
+    /// }
+    /// ```
+    pub fn list_decisions_for_session(&self, session_id: &str) -> Vec<&DecisionRecord> {
+        self.decisions.for_session(session_id).collect()
+    }
+
+    /// Redacts a decision's sensitive content for a privacy request,
+    /// keeping the record itself so the audit trail still shows that the
+    /// decision was made, by whom, when and with what score.
+    ///
+    /// Notes and step rationales are replaced with `[REDACTED]` and the
+    /// embedding is dropped from decision search; with `include_path`,
+    /// the path and steps are cleared too. The redaction is logged as a
+    /// record superseding the original, which later checkpoints drop
+    /// from the WAL. A node materialized by `DecisionRecord::as_node`
+    /// is left as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - ID of the decision to redact
+    /// * `include_path` - Also clear the path and steps
+    ///
+    /// # Returns
+    ///
+    /// `true` if the decision exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the WAL write fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// assert!(db.redact_decision(12, false).unwrap());
+    /// assert_eq!(db.get_decision(12).unwrap().notes.as_deref(), Some("[REDACTED]"));
+    /// ```
+    pub fn redact_decision(&mut self, id: u64, include_path: bool) -> Result<bool> {
+        if self.decisions.get(id).is_none() {
+            return Ok(false);
+        }
+        let record = WalRecord::DecisionRedacted {
+            id,
+            path: include_path,
+        };
+        self.write_wal(&record, "decision redaction", true)?;
+        Ok(self.decisions.redact(id, include_path))
+    }
+
+    /// Lists the decisions carrying a tag in the order they were
+    /// recorded, so audits can filter by category instead of parsing
+    /// notes.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - Tag given by `DecisionRecord::with_tags`
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let tool_calls = db.list_decisions_with_tag("tool_call");
+    /// println!("{} tool calls", tool_calls.len());
+    /// ```
+    pub fn list_decisions_with_tag(&self, tag: &str) -> Vec<&DecisionRecord> {
+        self.decisions.for_tag(tag).collect()
+    }
+
+    /// Finds the k past decisions whose embeddings are nearest a query.
+    ///
+    /// Only decisions recorded with `DecisionRecord::with_embedding` are
+    /// searched. They are indexed apart from node embeddings, so a
+    /// decision never turns up in `knn_search` and a node never turns up
+    /// here.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query vector, e.g. an embedding of the current goal
+    /// * `k` - Number of decisions to return
+    ///
+    /// # Returns
+    ///
+    /// (decision, distance) pairs sorted by distance ascending.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// for (decision, distance) in db.search_decisions(&[0.1, 0.2, 0.3], 3) {
+    ///     println!("{:?} at {}", decision.notes, distance);
+    /// }
+    /// ```
+    pub fn search_decisions(&self, query: &[f32], k: usize) -> Vec<(&DecisionRecord, f32)> {
+        let query = self.prepare_query(query);
+        self.decisions.nearest(&query, k)
+    }
+
+    /// Checks a recorded decision path against the graph.
+    ///
+    /// Reports root and path nodes that don't exist and, for each
+    /// consecutive pair of the path, whether an edge links them now
+    /// (either way) or did before being removed. Nothing checks paths
+    /// when decisions are recorded, so this is how incoherent ones are
+    /// found.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - ID of the decision to check
+    ///
+    /// # Returns
+    ///
+    /// `None` if no decision has this ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// if let Some(report) = db.validate_decision(1) {
+    ///     if !report.is_valid() {
+    ///         println!("missing nodes {:?}, steps {:?}", report.missing_nodes, report.steps);
+    ///     }
+    /// }
+    /// ```
+    pub fn validate_decision(&self, id: u64) -> Option<DecisionValidation> {
+        let decision = self.decisions.get(id)?;
+        let mut report = DecisionValidation {
+            decision_id: id,
+            ..Default::default()
+        };
+
+        for &node in std::iter::once(&decision.root_node).chain(&decision.path) {
+            if !self.nodes.contains_key(&node) && !report.missing_nodes.contains(&node) {
+                report.missing_nodes.push(node);
+            }
+        }
+
+        let linked = |from: NodeId, to: NodeId| {
+            self.graph
+                .neighbors(from)
+                .is_some_and(|targets| targets.contains(&to))
+        };
+        for pair in decision.path.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let link = if linked(from, to) {
+                StepLink::Edge
+            } else if linked(to, from) {
+                StepLink::ReverseEdge
+            } else if self.removed_edges.contains(&(from, to))
+                || self.removed_edges.contains(&(to, from))
+            {
+                StepLink::RemovedEdge
+            } else {
+                StepLink::Missing
+            };
+            report.steps.push(PathStep { from, to, link });
+        }
+        Some(report)
+    }
+
+    /// Walks from a decision up through its parents.
+    ///
+    /// Returns the chain root first, ending with decision `id` itself,
+    /// e.g. `[plan, subtask, action]`. The walk stops at a parent that
+    /// was never recorded or one already visited (a cycle). Unknown
+    /// `id`s give an empty chain.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let steps: Vec<u64> = db.decision_chain(42).iter().map(|d| d.id).collect();
+    /// println!("{:?}", steps);
+    /// ```
+    pub fn decision_chain(&self, id: u64) -> Vec<&DecisionRecord> {
+        let mut chain: Vec<&DecisionRecord> = Vec::new();
+        let mut next = Some(id);
+        while let Some(record) = next.and_then(|id| self.decisions.get(id)) {
+            if chain.iter().any(|d| d.id == record.id) {
+                break;
+            }
+            chain.push(record);
+            next = record.parent_decision_id;
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Lists the decisions recorded directly under a decision, in the
+    /// order they were recorded.
+    ///
+    /// Together with `decision_chain`, this lets a plan be audited as a
+    /// tree from any of its decisions.
+    pub fn child_decisions(&self, id: u64) -> Vec<&DecisionRecord> {
+        self.decisions.children(id).collect()
+    }
+
+    /// Lists one page of decisions ordered by a sort specification.
+    ///
+    /// Supports `id`, `timestamp` and `score`, ties broken by ID, as
+    /// `list_decisions_sorted`. Ordering every decision by timestamp
+    /// alone walks the time index instead of sorting, so paging through
+    /// a large audit log stays cheap.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent_id` - Only list this agent's decisions, if given
+    /// * `tag` - Only list decisions carrying this tag, if given
+    /// * `spec` - Order of the listing; empty orders by ID
+    /// * `offset` - Number of decisions to skip
+    /// * `limit` - Maximum number of decisions to return
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification uses `label` or `degree`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::sort::SortSpec;
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let newest_first: SortSpec = "created_at:desc".parse().unwrap();
+    /// let second_page = db.list_decisions_page(None, None, &newest_first, 50, 50).unwrap();
+    /// ```
+    pub fn list_decisions_page(
+        &self,
+        agent_id: Option<u64>,
+        tag: Option<&str>,
+        spec: &SortSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<&DecisionRecord>> {
+        if let (None, None, [key]) = (agent_id, tag, spec.0.as_slice()) {
+            if key.field == SortField::Timestamp {
+                let descending = key.order == SortOrder::Desc;
+                return Ok(self
+                    .decisions
+                    .by_creation(descending)
+                    .skip(offset)
+                    .take(limit)
+                    .collect());
+            }
+        }
+
+        let mut decisions = match (agent_id, tag) {
+            (agent_id, Some(tag)) => self
+                .decisions
+                .for_tag(tag)
+                .filter(|d| agent_id.is_none_or(|id| d.agent_id == id))
+                .collect(),
+            (Some(agent_id), None) => self.list_decisions_for_agent(agent_id),
+            (None, None) => self.list_all_decisions(),
+        };
+        sort_decisions(&mut decisions, spec)?;
+        Ok(decisions.into_iter().skip(offset).take(limit).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::DecisionRecord;
+    use crate::storage::{BarqGraphDb, DbOptions};
+
+    #[test]
+    fn test_indexed_decision_lookups() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        let decisions = [
+            (1, 7, 300),
+            (2, 8, 100),
+            (3, 7, 200),
+            (4, 7, 100),
+            (1, 9, 50),
+        ];
+        for (id, agent, created_at) in decisions {
+            db.record_decision(DecisionRecord::with_timestamp(
+                id,
+                agent,
+                created_at,
+                1,
+                vec![1],
+                0.5,
+            ))
+            .unwrap();
+        }
+
+        let ids = |records: Vec<&DecisionRecord>| records.iter().map(|d| d.id).collect::<Vec<_>>();
+        // The first record with a duplicated ID wins, as before
+        assert_eq!(db.get_decision(1).unwrap().agent_id, 7);
+        assert!(db.get_decision(5).is_none());
+        assert_eq!(ids(db.list_decisions_for_agent(7)), vec![1, 3, 4]);
+        assert!(db.list_decisions_for_agent(10).is_empty());
+        assert_eq!(ids(db.list_decisions_between(100..=200)), vec![2, 4, 3]);
+        assert_eq!(ids(db.list_decisions_between(..)), vec![1, 2, 4, 3, 1]);
+        assert_eq!(ids(db.list_all_decisions()), vec![1, 2, 3, 4, 1]);
+    }
+
+    #[test]
+    fn test_list_decisions_page() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        let decisions = [
+            (5, 7, 20, 0.1),
+            (3, 8, 10, 0.9),
+            (4, 7, 20, 0.5),
+            (1, 7, 30, 0.7),
+        ];
+        for (id, agent, created_at, score) in decisions {
+            let record = DecisionRecord::with_timestamp(id, agent, created_at, 1, vec![1], score);
+            db.record_decision(record).unwrap();
+        }
+
+        let page = |agent_id: Option<u64>, spec: &str, offset: usize, limit: usize| {
+            db.list_decisions_page(agent_id, None, &spec.parse().unwrap(), offset, limit)
+                .unwrap()
+                .iter()
+                .map(|d| d.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(page(None, "", 0, 10), vec![1, 3, 4, 5]);
+        assert_eq!(page(None, "", 1, 2), vec![3, 4]);
+        assert!(page(None, "", 4, 2).is_empty());
+        // Equal timestamps fall back to ID in either direction
+        assert_eq!(page(None, "created_at", 0, 10), vec![3, 4, 5, 1]);
+        assert_eq!(page(None, "created_at:desc", 0, 10), vec![1, 4, 5, 3]);
+        assert_eq!(page(None, "created_at:desc", 1, 2), vec![4, 5]);
+        assert_eq!(page(None, "score:desc", 0, 2), vec![3, 1]);
+        assert_eq!(page(Some(7), "created_at:desc", 0, 10), vec![1, 4, 5]);
+        assert_eq!(page(Some(7), "score", 1, 10), vec![4, 1]);
+        assert!(db
+            .list_decisions_page(None, None, &"label".parse().unwrap(), 0, 10)
+            .is_err());
+    }
+
+    #[test]
+    fn test_list_decisions_with_tag() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            let decisions = [
+                (1, 7, 0.2, vec!["plan"]),
+                (2, 7, 0.9, vec!["tool_call", "retrieval", "tool_call"]),
+                (3, 8, 0.5, vec!["tool_call"]),
+                (4, 7, 0.7, vec![]),
+            ];
+            for (id, agent, score, tags) in decisions {
+                let record = DecisionRecord::new(id, agent, 1, vec![1], score);
+                db.record_decision(record.with_tags(tags)).unwrap();
+            }
+        }
+
+        // Tags are rebuilt from the log on reopen
+        let db = BarqGraphDb::open(opts).unwrap();
+        let ids = |records: Vec<&DecisionRecord>| records.iter().map(|d| d.id).collect::<Vec<_>>();
+        assert_eq!(ids(db.list_decisions_with_tag("tool_call")), vec![2, 3]);
+        assert_eq!(ids(db.list_decisions_with_tag("plan")), vec![1]);
+        assert!(db.list_decisions_with_tag("memory").is_empty());
+        assert!(db.get_decision(4).unwrap().tags.is_empty());
+
+        let page = |agent_id: Option<u64>, tag: &str, spec: &str| {
+            ids(db
+                .list_decisions_page(agent_id, Some(tag), &spec.parse().unwrap(), 0, 10)
+                .unwrap())
+        };
+        assert_eq!(page(None, "tool_call", "score"), vec![3, 2]);
+        assert_eq!(page(Some(7), "tool_call", "created_at:desc"), vec![2]);
+        assert!(page(Some(8), "plan", "").is_empty());
+    }
+
+    #[test]
+    fn test_decision_chain_and_children() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        let decision = |id: u64| DecisionRecord::new(id, 7, 1, vec![1], 0.5);
+        db.record_decision(decision(1)).unwrap();
+        db.record_decision(decision(2).with_parent(1)).unwrap();
+        db.record_decision(decision(3).with_parent(2)).unwrap();
+        db.record_decision(decision(4).with_parent(2)).unwrap();
+        // Parent never recorded, and a two-decision cycle
+        db.record_decision(decision(5).with_parent(99)).unwrap();
+        db.record_decision(decision(6).with_parent(7)).unwrap();
+        db.record_decision(decision(7).with_parent(6)).unwrap();
+
+        let ids = |records: Vec<&DecisionRecord>| records.iter().map(|d| d.id).collect::<Vec<_>>();
+        assert_eq!(ids(db.decision_chain(4)), vec![1, 2, 4]);
+        assert_eq!(ids(db.decision_chain(1)), vec![1]);
+        assert_eq!(ids(db.decision_chain(5)), vec![5]);
+        assert_eq!(ids(db.decision_chain(7)), vec![6, 7]);
+        assert!(db.decision_chain(42).is_empty());
+        assert_eq!(ids(db.child_decisions(2)), vec![3, 4]);
+        assert!(db.child_decisions(3).is_empty());
+    }
+
+    #[test]
+    fn test_search_decisions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            let mut node = crate::Node::new(1, "n1".to_string());
+            node.embedding = vec![1.0, 0.0];
+            db.append_node(node).unwrap();
+
+            let decision = |id: u64| DecisionRecord::new(id, 7, 1, vec![1], 0.5);
+            db.record_decision(decision(1).with_embedding(vec![0.0, 1.0]))
+                .unwrap();
+            db.record_decision(decision(2).with_embedding(vec![1.0, 1.0]))
+                .unwrap();
+            db.record_decision(decision(3)).unwrap();
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        let found: Vec<_> = db
+            .search_decisions(&[0.0, 1.0], 5)
+            .iter()
+            .map(|(d, _)| d.id)
+            .collect();
+        assert_eq!(found, vec![1, 2]);
+        // Node and decision embeddings don't mix
+        assert_eq!(db.knn_search(&[0.0, 1.0], 5).len(), 1);
+    }
+
+    #[test]
+    fn test_redact_decision() {
+        use crate::agent::ReasoningStep;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            let steps = vec![
+                ReasoningStep::new(1, "retrieve", 0.9).with_rationale("Patient history"),
+                ReasoningStep::new(2, "select", 0.7),
+            ];
+            let decision = |id: u64| {
+                DecisionRecord::new(id, 7, 1, vec![1], 0.5)
+                    .with_notes("Diagnosis for Jane Doe".to_string())
+                    .with_embedding(vec![1.0, 0.0])
+            };
+            db.record_decision(decision(1).with_steps(steps)).unwrap();
+            db.record_decision(decision(2)).unwrap();
+
+            assert!(db.redact_decision(1, false).unwrap());
+            assert!(db.redact_decision(2, true).unwrap());
+            assert!(!db.redact_decision(3, true).unwrap());
+        }
+
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        let first = db.get_decision(1).unwrap();
+        assert_eq!(first.notes.as_deref(), Some("[REDACTED]"));
+        assert_eq!(first.path, vec![1, 2]);
+        assert_eq!(first.steps[0].rationale.as_deref(), Some("[REDACTED]"));
+        assert_eq!(first.steps[1].rationale, None);
+        let second = db.get_decision(2).unwrap();
+        assert!(second.path.is_empty() && second.steps.is_empty());
+        // The record survives with its metadata, but no longer matches searches
+        assert_eq!((second.agent_id, second.score), (7, 0.5));
+        assert!(db.search_decisions(&[1.0, 0.0], 5).is_empty());
+
+        // Checkpoints drop the superseded content from disk
+        db.checkpoint().unwrap();
+        let wal = std::fs::read_to_string(db.wal_path().unwrap()).unwrap();
+        assert!(!wal.contains("Jane Doe"));
+    }
+
+    #[test]
+    fn test_redact_decision_covers_duplicate_ids() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for (id, agent) in [(1, 7), (2, 7), (1, 8)] {
+            let record = DecisionRecord::new(id, agent, 1, vec![1], 0.5)
+                .with_notes(format!("Notes of {} by {}", id, agent));
+            db.record_decision(record).unwrap();
+        }
+
+        assert!(db.redact_decision(1, false).unwrap());
+        let notes: Vec<_> = db
+            .list_all_decisions()
+            .iter()
+            .map(|d| (d.id, d.notes.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            notes,
+            vec![
+                (1, "[REDACTED]".to_string()),
+                (2, "Notes of 2 by 7".to_string()),
+                (1, "[REDACTED]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_decision() {
+        use super::StepLink;
+        use crate::Node;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for id in 1..=4 {
+                db.append_node(Node::new(id, format!("n{}", id))).unwrap();
+            }
+            db.add_edge(1, 2, "NEXT").unwrap();
+            db.add_edge(3, 2, "BACK").unwrap();
+            db.add_edge(3, 4, "GONE").unwrap();
+            db.record_decision(DecisionRecord::new(1, 7, 1, vec![1, 2, 3, 4, 9, 1], 0.5))
+                .unwrap();
+            db.record_decision(DecisionRecord::new(2, 7, 1, vec![1, 2], 0.5))
+                .unwrap();
+            db.remove_edge(3, 4, "GONE").unwrap();
+            // Removals folded into a snapshot are still remembered
+            db.checkpoint().unwrap();
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        let report = db.validate_decision(1).unwrap();
+        assert_eq!(report.missing_nodes, vec![9]);
+        let links: Vec<_> = report.steps.iter().map(|s| s.link).collect();
+        assert_eq!(
+            links,
+            vec![
+                StepLink::Edge,
+                StepLink::ReverseEdge,
+                StepLink::RemovedEdge,
+                StepLink::Missing,
+                StepLink::Missing
+            ]
+        );
+        assert_eq!((report.steps[2].from, report.steps[2].to), (3, 4));
+        assert!(!report.is_valid());
+        assert!(db.validate_decision(2).unwrap().is_valid());
+        assert!(db.validate_decision(3).is_none());
+    }
+
+    #[test]
+    fn test_list_decisions_for_session() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            // Two interleaved runs of one agent, with clocks out of order
+            let steps = [(1, "a", 30), (2, "b", 10), (3, "a", 20), (4, "a", 10)];
+            for (id, session, created_at) in steps {
+                let record = DecisionRecord::with_timestamp(id, 7, created_at, 1, vec![1], 0.5);
+                db.record_decision(record.with_session(session)).unwrap();
+            }
+            db.record_decision(DecisionRecord::new(5, 7, 1, vec![1], 0.5))
+                .unwrap();
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        let ids = |session: &str| {
+            db.list_decisions_for_session(session)
+                .iter()
+                .map(|d| d.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("a"), vec![1, 3, 4]);
+        assert_eq!(ids("b"), vec![2]);
+        assert!(ids("c").is_empty());
+        assert_eq!(db.get_decision(5).unwrap().session_id, None);
+    }
+}
@@ -0,0 +1,201 @@
+//! Maintained degree counters for hub lookups.
+//!
+//! Every edge write updates per-node in/out counts and three ordered sets
+//! keyed by degree, so the most connected nodes are read off the front of
+//! a set instead of scanning the adjacency lists.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+
+use super::{AdjacencyMap, BarqGraphDb};
+use crate::graph::Direction;
+use crate::{Node, NodeId};
+
+/// Nodes ordered by degree descending, then by ID.
+type Ranking = BTreeSet<(Reverse<usize>, NodeId)>;
+
+/// In/out degree of every node with at least one edge.
+#[derive(Debug, Default)]
+pub(super) struct DegreeIndex {
+    counts: HashMap<NodeId, (usize, usize)>,
+    outgoing: Ranking,
+    incoming: Ranking,
+    total: Ranking,
+}
+
+impl DegreeIndex {
+    /// Counts the edges of an adjacency map.
+    pub(super) fn build(adjacency: &AdjacencyMap) -> Self {
+        let mut counts: HashMap<NodeId, (usize, usize)> = HashMap::new();
+        for (&from, targets) in adjacency {
+            if !targets.is_empty() {
+                counts.entry(from).or_default().1 += targets.len();
+            }
+            for &to in targets {
+                counts.entry(to).or_default().0 += 1;
+            }
+        }
+        let mut index = Self::default();
+        for (&id, &(incoming, outgoing)) in &counts {
+            index.rank(id, incoming, outgoing);
+        }
+        index.counts = counts;
+        index
+    }
+
+    /// Records a new `from -> to` edge.
+    pub(super) fn add_edge(&mut self, from: NodeId, to: NodeId) {
+        self.update(from, |(_, outgoing)| *outgoing += 1);
+        self.update(to, |(incoming, _)| *incoming += 1);
+    }
+
+    /// Records the removal of a `from -> to` edge.
+    pub(super) fn remove_edge(&mut self, from: NodeId, to: NodeId) {
+        self.update(from, |(_, outgoing)| *outgoing = outgoing.saturating_sub(1));
+        self.update(to, |(incoming, _)| *incoming = incoming.saturating_sub(1));
+    }
+
+    fn update(&mut self, id: NodeId, change: impl FnOnce(&mut (usize, usize))) {
+        let (incoming, outgoing) = self.counts.remove(&id).unwrap_or_default();
+        self.outgoing.remove(&(Reverse(outgoing), id));
+        self.incoming.remove(&(Reverse(incoming), id));
+        self.total.remove(&(Reverse(incoming + outgoing), id));
+
+        let mut degree = (incoming, outgoing);
+        change(&mut degree);
+        if degree != (0, 0) {
+            self.counts.insert(id, degree);
+            self.rank(id, degree.0, degree.1);
+        }
+    }
+
+    fn rank(&mut self, id: NodeId, incoming: usize, outgoing: usize) {
+        if outgoing > 0 {
+            self.outgoing.insert((Reverse(outgoing), id));
+        }
+        if incoming > 0 {
+            self.incoming.insert((Reverse(incoming), id));
+        }
+        self.total.insert((Reverse(incoming + outgoing), id));
+    }
+}
+
+impl BarqGraphDb {
+    /// Returns the `k` nodes with the most edges, highest degree first.
+    ///
+    /// `Outgoing` ranks by out-degree, `Incoming` by in-degree and `Both`
+    /// by their sum; parallel edges count separately and ties are broken
+    /// by ascending ID. Degrees are maintained on every edge write, so
+    /// the lookup costs O(k) regardless of graph size.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::graph::Direction;
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// for (id, degree) in db.top_degree_nodes(10, Direction::Both) {
+    ///     println!("{} has {} edges", id, degree);
+    /// }
+    /// ```
+    pub fn top_degree_nodes(&self, k: usize, direction: Direction) -> Vec<(NodeId, usize)> {
+        let ranking = match direction {
+            Direction::Outgoing => &self.degrees.outgoing,
+            Direction::Incoming => &self.degrees.incoming,
+            Direction::Both => &self.degrees.total,
+        };
+        ranking
+            .iter()
+            .take(k)
+            .map(|&(Reverse(degree), id)| (id, degree))
+            .collect()
+    }
+
+    /// Returns every node ordered by out-degree, ties by ID.
+    ///
+    /// Nodes with outgoing edges are read off the maintained ranking;
+    /// the others are only gathered once the iteration reaches them.
+    pub(crate) fn nodes_by_out_degree(
+        &self,
+        descending: bool,
+    ) -> Box<dyn Iterator<Item = &Node> + '_> {
+        let ranking = &self.degrees.outgoing;
+        let ranked: Box<dyn Iterator<Item = NodeId>> = if descending {
+            Box::new(ranking.iter().map(|&(_, id)| id))
+        } else {
+            // Walk the degrees upwards, each degree's nodes still by ID
+            let mut previous = None;
+            Box::new(
+                ranking
+                    .iter()
+                    .rev()
+                    .filter_map(move |&(Reverse(degree), _)| {
+                        (previous.replace(degree) != Some(degree)).then_some(degree)
+                    })
+                    .flat_map(move |degree| {
+                        ranking
+                            .range((Reverse(degree), NodeId::MIN)..=(Reverse(degree), NodeId::MAX))
+                            .map(|&(_, id)| id)
+                    }),
+            )
+        };
+        let unranked = std::iter::once(()).flat_map(move |_| {
+            let mut ids: Vec<NodeId> = self
+                .nodes
+                .keys()
+                .copied()
+                .filter(|id| self.degrees.counts.get(id).is_none_or(|&(_, out)| out == 0))
+                .collect();
+            ids.sort_unstable();
+            ids
+        });
+        let ids: Box<dyn Iterator<Item = NodeId>> = if descending {
+            Box::new(ranked.chain(unranked))
+        } else {
+            Box::new(unranked.chain(ranked))
+        };
+        Box::new(
+            ids.filter_map(|id| self.nodes.get(&id))
+                .filter_map(|node| self.hydrate(node)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::Node;
+
+    #[test]
+    fn test_top_degree_nodes_track_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            let mut hub = Node::new(1, "hub".to_string());
+            hub.edges = vec![crate::Edge::new(1, 2, "X"), crate::Edge::new(1, 3, "X")];
+            db.append_node(hub).unwrap();
+            db.add_edge(1, 4, "X").unwrap();
+            db.add_edge(2, 3, "X").unwrap();
+            db.add_edge(4, 3, "X").unwrap();
+
+            assert_eq!(
+                db.top_degree_nodes(2, Direction::Outgoing),
+                vec![(1, 3), (2, 1)]
+            );
+            assert_eq!(db.top_degree_nodes(1, Direction::Incoming), vec![(3, 3)]);
+            assert!(db.remove_edge(1, 3, "X").unwrap());
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        // Every node now has two edges, so ties fall back to ID order
+        assert_eq!(
+            db.top_degree_nodes(3, Direction::Both),
+            vec![(1, 2), (2, 2), (3, 2)]
+        );
+        assert_eq!(db.top_degree_nodes(0, Direction::Both), vec![]);
+    }
+}
@@ -0,0 +1,223 @@
+//! Disk quota enforcement.
+//!
+//! Measures the database's disk usage against its `DiskQuota`, raises
+//! alerts on transitions into and out of a breach, and toggles
+//! read-only mode when the quota asks for it.
+
+use std::fs;
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+
+use anyhow::{Context, Result};
+use tokio::sync::Mutex;
+
+use super::BarqGraphDb;
+use crate::disk::{self, DiskAlert, DiskStatus};
+use crate::metrics::BackgroundTask;
+
+impl BarqGraphDb {
+    /// Measures current disk usage without acting on it.
+    ///
+    /// In-memory databases report zero usage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database directory cannot be read.
+    pub fn disk_status(&self) -> Result<DiskStatus> {
+        if self.options.in_memory {
+            return Ok(DiskStatus::default());
+        }
+        let path = &self.options.path;
+        let mut db_bytes = disk::dir_size(path)
+            .with_context(|| format!("Failed to measure database directory: {:?}", path))?;
+        let wal_path = self.options.wal_file();
+        if !wal_path.starts_with(path) {
+            db_bytes += fs::metadata(&wal_path).map_or(0, |m| m.len());
+        }
+        Ok(DiskStatus {
+            db_bytes,
+            free_bytes: disk::free_space(path),
+            read_only: self.read_only,
+        })
+    }
+
+    /// Checks disk usage against the configured quota.
+    ///
+    /// Alerts are raised only when the database enters or leaves a
+    /// breach; they are forwarded to the metrics sink's `disk_alert`. With `read_only_on_breach`, writes are rejected with
+    /// `BarqError::ReadOnly` until usage is back under every limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if disk usage cannot be measured.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::disk::DiskQuota;
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let quota = DiskQuota::new()
+    ///     .with_min_free_bytes(512 * 1024 * 1024)
+    ///     .with_read_only_on_breach(true);
+    /// let opts = DbOptions::new(PathBuf::from("./my_db")).with_disk_quota(quota);
+    /// let mut db = BarqGraphDb::open(opts).unwrap();
+    /// let status = db.check_disk().unwrap();
+    /// println!("{} bytes used, read-only: {}", status.db_bytes, status.read_only);
+    /// ```
+    pub fn check_disk(&mut self) -> Result<DiskStatus> {
+        let Some(quota) = self.options.disk_quota else {
+            return self.disk_status();
+        };
+        let mut status = self.disk_status()?;
+
+        let breaches = quota.breaches(&status);
+        if !breaches.is_empty() {
+            if !self.disk_breached {
+                for alert in &breaches {
+                    self.raise_disk_alert(alert);
+                }
+            }
+            self.disk_breached = true;
+            self.read_only = quota.read_only_on_breach;
+        } else if self.disk_breached {
+            self.disk_breached = false;
+            self.read_only = false;
+            self.raise_disk_alert(&DiskAlert::Recovered {
+                db_bytes: status.db_bytes,
+            });
+        }
+
+        status.read_only = self.read_only;
+        Ok(status)
+    }
+
+    /// Returns true while writes are rejected because of a disk quota.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Spawns a thread that calls `check_disk` at the quota's interval.
+    ///
+    /// The thread exits once the database is dropped, or immediately if
+    /// the database has no disk quota. Failed checks are reported to the
+    /// metrics sink's `background_error`.
+    pub fn spawn_disk_monitor(db: &Arc<Mutex<BarqGraphDb>>) -> JoinHandle<()> {
+        let weak = Arc::downgrade(db);
+        std::thread::spawn(move || run_disk_monitor(weak))
+    }
+
+    fn raise_disk_alert(&self, alert: &DiskAlert) {
+        if let Some(sink) = &self.metrics {
+            sink.disk_alert(alert);
+        }
+    }
+}
+
+/// Body of the background disk monitor thread.
+fn run_disk_monitor(db: Weak<Mutex<BarqGraphDb>>) {
+    let Some(interval) = db
+        .upgrade()
+        .and_then(|db| db.blocking_lock().options.disk_quota)
+        .map(|q| q.check_interval)
+    else {
+        return;
+    };
+
+    loop {
+        std::thread::sleep(interval);
+        let Some(db) = db.upgrade() else {
+            break;
+        };
+        let mut db = db.blocking_lock();
+        if let Err(e) = db.check_disk() {
+            db.report_background_error(BackgroundTask::DiskCheck, &e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as StdMutex;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::disk::DiskQuota;
+    use crate::metrics::{MetricsSink, Operation};
+    use crate::storage::DbOptions;
+    use crate::Node;
+
+    #[derive(Default)]
+    struct AlertLog(StdMutex<Vec<DiskAlert>>, StdMutex<Vec<BackgroundTask>>);
+
+    impl MetricsSink for AlertLog {
+        fn record(&self, _op: Operation, _elapsed: Duration) {}
+
+        fn disk_alert(&self, alert: &DiskAlert) {
+            self.0.lock().unwrap().push(*alert);
+        }
+
+        fn background_error(&self, task: BackgroundTask, _error: &anyhow::Error) {
+            self.1.lock().unwrap().push(task);
+        }
+    }
+
+    #[test]
+    fn test_quota_breach_switches_to_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let quota = DiskQuota::new()
+            .with_max_db_bytes(4096)
+            .with_read_only_on_breach(true);
+        let opts = DbOptions::new(dir.path().to_path_buf()).with_disk_quota(quota);
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        let log = Arc::new(AlertLog::default());
+        db.set_metrics_sink(Some(log.clone()));
+
+        let mut id = 0;
+        while !db.check_disk().unwrap().read_only {
+            id += 1;
+            db.append_node(Node::new(id, "x".repeat(256))).unwrap();
+        }
+        let err = db.append_node(Node::new(id + 1, "late".to_string()));
+        assert_eq!(crate::error::classify(&err.unwrap_err()).code, "read_only");
+        assert!(db.get_node(id + 1).is_none());
+        assert!(matches!(
+            log.0.lock().unwrap().as_slice(),
+            [DiskAlert::QuotaExceeded { limit: 4096, .. }]
+        ));
+
+        // Raising the limit lifts read-only mode
+        db.options.disk_quota = Some(quota.with_max_db_bytes(u64::MAX));
+        assert!(!db.check_disk().unwrap().read_only);
+        db.append_node(Node::new(id + 1, "late".to_string()))
+            .unwrap();
+        assert!(matches!(
+            log.0.lock().unwrap().last(),
+            Some(DiskAlert::Recovered { .. })
+        ));
+    }
+
+    #[test]
+    fn test_monitor_reports_failed_checks_to_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+        let quota = DiskQuota::new()
+            .with_max_db_bytes(u64::MAX)
+            .with_check_interval(Duration::from_millis(10));
+        let mut db =
+            BarqGraphDb::open(DbOptions::new(path.clone()).with_disk_quota(quota)).unwrap();
+        let log = Arc::new(AlertLog::default());
+        db.set_metrics_sink(Some(log.clone()));
+        std::fs::remove_dir_all(&path).unwrap();
+
+        let db = Arc::new(Mutex::new(db));
+        let monitor = BarqGraphDb::spawn_disk_monitor(&db);
+        while log.1.lock().unwrap().is_empty() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(log.1.lock().unwrap()[0], BackgroundTask::DiskCheck);
+        drop(db);
+        monitor.join().unwrap();
+    }
+}
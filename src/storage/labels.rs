@@ -0,0 +1,120 @@
+//! Secondary index from node label to node IDs.
+//!
+//! Labels are kept in a sorted map so exact and prefix lookups resolve
+//! entities by name without scanning every node. The index holds its own
+//! copy of each label, so nodes in the cold tier stay findable.
+
+use std::collections::BTreeMap;
+
+use super::BarqGraphDb;
+use crate::{Node, NodeId};
+
+/// Node IDs grouped by label, each group sorted by ID.
+#[derive(Debug, Default)]
+pub(super) struct LabelIndex {
+    ids: BTreeMap<String, Vec<NodeId>>,
+}
+
+impl LabelIndex {
+    /// Indexes every given node.
+    pub(super) fn build<'a>(nodes: impl IntoIterator<Item = &'a Node>) -> Self {
+        let mut index = Self::default();
+        for node in nodes {
+            index.insert(&node.label, node.id);
+        }
+        index
+    }
+
+    pub(super) fn insert(&mut self, label: &str, id: NodeId) {
+        let ids = self.ids.entry(label.to_string()).or_default();
+        if let Err(pos) = ids.binary_search(&id) {
+            ids.insert(pos, id);
+        }
+    }
+
+    pub(super) fn remove(&mut self, label: &str, id: NodeId) {
+        if let Some(ids) = self.ids.get_mut(label) {
+            if let Ok(pos) = ids.binary_search(&id) {
+                ids.remove(pos);
+            }
+            if ids.is_empty() {
+                self.ids.remove(label);
+            }
+        }
+    }
+}
+
+impl BarqGraphDb {
+    /// Returns the IDs of nodes with exactly this label, sorted by ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// if let Some(&id) = db.find_by_label("Acme Corp").first() {
+    ///     println!("Acme Corp is node {}", id);
+    /// }
+    /// ```
+    pub fn find_by_label(&self, label: &str) -> &[NodeId] {
+        self.labels.ids.get(label).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns up to `limit` IDs of nodes whose label starts with `prefix`.
+    ///
+    /// Results are ordered by label, then by ID.
+    pub fn find_by_label_prefix(&self, prefix: &str, limit: usize) -> Vec<NodeId> {
+        self.labels
+            .ids
+            .range(prefix.to_string()..)
+            .take_while(|(label, _)| label.starts_with(prefix))
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .take(limit)
+            .collect()
+    }
+
+    /// Returns every node ordered by label, nodes sharing a label by ID.
+    pub(crate) fn nodes_by_label(&self, descending: bool) -> Box<dyn Iterator<Item = &Node> + '_> {
+        let ids: Box<dyn Iterator<Item = &Vec<NodeId>>> = if descending {
+            Box::new(self.labels.ids.values().rev())
+        } else {
+            Box::new(self.labels.ids.values())
+        };
+        Box::new(
+            ids.flatten()
+                .filter_map(|id| self.nodes.get(id))
+                .filter_map(|node| self.hydrate(node)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+
+    #[test]
+    fn test_label_lookup_follows_updates_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for (id, label) in [(1, "alice"), (2, "alicia"), (3, "bob"), (4, "alice")] {
+                db.append_node(Node::new(id, label.to_string())).unwrap();
+            }
+            assert_eq!(db.find_by_label("alice"), &[1, 4]);
+            // Relabelling moves the node between entries
+            db.append_node(Node::new(4, "bobby".to_string())).unwrap();
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.find_by_label("alice"), &[1]);
+        assert!(db.find_by_label("carol").is_empty());
+        assert_eq!(db.find_by_label_prefix("ali", 10), vec![1, 2]);
+        assert_eq!(db.find_by_label_prefix("bob", 10), vec![3, 4]);
+        assert_eq!(db.find_by_label_prefix("", 3), vec![1, 2, 3]);
+        assert_eq!(db.find_by_label_prefix("bob", 1), vec![3]);
+    }
+}
@@ -0,0 +1,85 @@
+//! Database manifest.
+//!
+//! `manifest.json` records settings that must stay fixed for the life of
+//! a database. It is written the first time a directory is opened; later
+//! opens adopt the recorded values over `DbOptions`, so embeddings are
+//! never searched under a different metric than the one they were
+//! indexed with. `BarqGraphDb::rebuild` is the way to change them.
+
+use std::fs;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{BarqGraphDb, DbOptions};
+use crate::vector::DistanceMetric;
+
+/// Settings persisted alongside the data files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) struct Manifest {
+    /// Distance metric of the vector index.
+    pub(super) metric: DistanceMetric,
+}
+
+impl Manifest {
+    /// Reads the manifest of `opts.path`, creating it from `opts` if the
+    /// database doesn't have one yet.
+    pub(super) fn load_or_create(opts: &DbOptions) -> Result<Self> {
+        let path = opts.manifest_file();
+        if path.exists() {
+            let data = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read manifest: {:?}", path))?;
+            return serde_json::from_str(&data)
+                .with_context(|| format!("Failed to parse manifest: {:?}", path));
+        }
+
+        let manifest = Self {
+            metric: opts.metric,
+        };
+        // Write to a temporary file and rename so a crash never leaves a torn manifest
+        let tmp_path = path.with_extension("json.tmp");
+        let data =
+            serde_json::to_vec_pretty(&manifest).with_context(|| "Failed to serialize manifest")?;
+        fs::write(&tmp_path, data)
+            .with_context(|| format!("Failed to write manifest: {:?}", tmp_path))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to install manifest: {:?}", path))?;
+        Ok(manifest)
+    }
+}
+
+impl BarqGraphDb {
+    /// Returns the distance metric used by the vector index.
+    ///
+    /// For a reopened database this is the metric recorded in its
+    /// manifest, which may differ from the one passed in `DbOptions`.
+    pub fn metric(&self) -> DistanceMetric {
+        self.options.metric
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::IndexType;
+
+    #[test]
+    fn test_reopen_keeps_recorded_metric() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut opts = DbOptions::new(dir.path().to_path_buf());
+        opts.index_type = IndexType::Linear;
+        {
+            let mut cosine = opts.clone();
+            cosine.metric = DistanceMetric::Cosine;
+            let mut db = BarqGraphDb::open(cosine).unwrap();
+            db.set_embedding(1, vec![10.0, 0.0]).unwrap();
+            db.set_embedding(2, vec![0.5, 0.5]).unwrap();
+        }
+        assert!(opts.manifest_file().exists());
+
+        // Default options ask for L2, but the manifest wins
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.metric(), DistanceMetric::Cosine);
+        assert_eq!(db.knn_search(&[1.0, 0.1], 1)[0].0, 1);
+    }
+}
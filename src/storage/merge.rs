@@ -0,0 +1,423 @@
+//! CRDT stamping and replica merges.
+//!
+//! In CRDT mode the write paths call into this module to stamp the
+//! fields they change and append the node's updated `NodeClock` to the
+//! WAL right after the data record. `merge_from` joins another replica's
+//! clocks with ours and applies the winning values through the regular
+//! write paths, so merged state is logged and replayed like any other
+//! write. A crash between a data record and its clock record leaves that
+//! write with its previous stamp.
+
+use anyhow::Result;
+
+use super::{BarqGraphDb, WalRecord};
+use crate::crdt::{EdgeKey, MergeReport, NodeClock, Stamp};
+use crate::error::BarqError;
+use crate::memory;
+use crate::{Edge, Node, NodeId};
+
+fn edge_key(edge: &Edge) -> EdgeKey {
+    (edge.to, edge.edge_type.clone())
+}
+
+/// Rule tags as a sorted, deduplicated list for comparison.
+fn tag_set(tags: &[String]) -> Vec<String> {
+    let mut tags = tags.to_vec();
+    tags.sort_unstable();
+    tags.dedup();
+    tags
+}
+
+impl BarqGraphDb {
+    /// Merges another replica's nodes, tags and edges into this database.
+    ///
+    /// Both databases must run in CRDT mode (see
+    /// `DbOptions::with_crdt_replica`). Scalar fields take the value of
+    /// the latest write, rule tags and edges keep concurrent additions
+    /// over removals, and node timestamps take the later of the two.
+    /// Merging is deterministic: two replicas that merge from each other
+    /// end up with the same nodes, tags and edges.
+    ///
+    /// Nodes are matched by ID, so replicas must create nodes under
+    /// distinct IDs (see `DbOptions::with_crdt_replica`). A node created
+    /// on both replicas under the same ID is left as it is and its ID is
+    /// reported in `MergeReport::conflicts`.
+    ///
+    /// Only nodes written in CRDT mode on the other replica are merged;
+    /// decisions, templates and edges from nodes that don't exist are not.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidOperation` if either database is not in CRDT mode,
+    /// or an error if a merged write fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut laptop =
+    ///     BarqGraphDb::open(DbOptions::new(PathBuf::from("./laptop_db")).with_crdt_replica(1))
+    ///         .unwrap();
+    /// let phone =
+    ///     BarqGraphDb::open(DbOptions::new(PathBuf::from("./phone_db")).with_crdt_replica(2))
+    ///         .unwrap();
+    /// let report = laptop.merge_from(&phone).unwrap();
+    /// println!("{} nodes added, {} updated", report.nodes_added, report.nodes_updated);
+    /// ```
+    pub fn merge_from(&mut self, other: &BarqGraphDb) -> Result<MergeReport> {
+        if self.options.crdt_replica.is_none() || other.options.crdt_replica.is_none() {
+            return Err(BarqError::InvalidOperation(
+                "merge_from requires CRDT mode on both databases".to_string(),
+            )
+            .into());
+        }
+        // Merged writes carry the other replica's stamps, not fresh ones,
+        // and nodes keep the IDs other writers gave them
+        let replica = self.options.crdt_replica.take();
+        let partition = self.options.id_partition.take();
+        let next_node_id = self.next_node_id;
+        let result = self.merge_clocks(other);
+        self.options.crdt_replica = replica;
+        self.options.id_partition = partition;
+        self.next_node_id = next_node_id;
+        result
+    }
+
+    /// Returns the replication clock of a node, if it was written in CRDT mode.
+    pub fn node_clock(&self, id: NodeId) -> Option<&NodeClock> {
+        self.clocks.get(&id)
+    }
+
+    fn merge_clocks(&mut self, other: &BarqGraphDb) -> Result<MergeReport> {
+        let mut report = MergeReport::default();
+        let mut ids: Vec<NodeId> = other.clocks.keys().copied().collect();
+        ids.sort_unstable();
+
+        for id in ids {
+            let Some(their_node) = other.get_node(id) else {
+                continue;
+            };
+            let theirs = &other.clocks[&id];
+            let mine = self.clocks.get(&id).cloned().unwrap_or_default();
+            if mine.conflicts_with(theirs) {
+                report.conflicts.push(id);
+                continue;
+            }
+            let mut merged = mine.clone();
+            merged.merge(theirs);
+            if merged == mine {
+                continue;
+            }
+
+            match self.get_node(id).cloned() {
+                None => {
+                    let mut node = their_node.clone();
+                    node.rule_tags = merged.tags.elements();
+                    node.edges.retain(|e| merged.edges.contains(&edge_key(e)));
+                    report.nodes_added += 1;
+                    report.edges_added += node.edges.len();
+                    self.append_node(node)?;
+                }
+                Some(current) => {
+                    let mut fields = current.clone();
+                    if theirs.label > mine.label {
+                        fields.label = their_node.label.clone();
+                    }
+                    if theirs.agent_id > mine.agent_id {
+                        fields.agent_id = their_node.agent_id;
+                    }
+                    let tags = merged.tags.elements();
+                    if tag_set(&current.rule_tags) != tags {
+                        fields.rule_tags = tags;
+                    }
+                    fields.timestamp = current.timestamp.max(their_node.timestamp);
+
+                    let mut updated = fields.label != current.label
+                        || fields.agent_id != current.agent_id
+                        || fields.rule_tags != current.rule_tags
+                        || fields.timestamp != current.timestamp;
+                    if updated {
+                        self.update_node_fields(&fields)?;
+                    }
+                    if theirs.embedding > mine.embedding {
+                        if their_node.embedding.is_empty() {
+                            self.remove_embedding(id)?;
+                        } else {
+                            let model = other.embedding_model(id).map(String::from);
+                            self.store_embedding(id, their_node.embedding.clone(), model)?;
+                        }
+                        updated = true;
+                    }
+                    report.nodes_updated += updated as usize;
+
+                    for key in merged.edges.elements() {
+                        if current.edges.iter().any(|e| edge_key(e) == key) {
+                            continue;
+                        }
+                        let edge = their_node
+                            .edges
+                            .iter()
+                            .find(|e| edge_key(e) == key)
+                            .cloned()
+                            .unwrap_or_else(|| Edge::new(id, key.0, key.1.clone()));
+                        self.insert_edge(edge)?;
+                        report.edges_added += 1;
+                    }
+                    for edge in &current.edges {
+                        if !merged.edges.contains(&edge_key(edge))
+                            && self.remove_edge(id, edge.to, &edge.edge_type)?
+                        {
+                            report.edges_removed += 1;
+                        }
+                    }
+                }
+            }
+            self.commit_clock(id, merged)?;
+        }
+        Ok(report)
+    }
+
+    /// Returns a fresh stamp of this replica, or `None` outside CRDT mode.
+    fn next_stamp(&mut self) -> Option<Stamp> {
+        let replica = self.options.crdt_replica?;
+        self.lamport += 1;
+        Some(Stamp::new(self.lamport, replica))
+    }
+
+    /// Stamps the fields a node write changes, before the node is replaced.
+    pub(super) fn stamp_put(&mut self, node: &Node) -> Option<NodeClock> {
+        let stamp = self.next_stamp()?;
+        let old = self.nodes.get(&node.id);
+        let mut clock = self.clocks.get(&node.id).cloned().unwrap_or_default();
+        if old.is_none() && clock.created == Stamp::default() {
+            clock.created = stamp;
+        }
+        if old.is_none_or(|o| o.label != node.label) {
+            clock.label = stamp;
+        }
+        if old.map_or(!node.embedding.is_empty(), |o| {
+            o.embedding != node.embedding
+        }) {
+            clock.embedding = stamp;
+        }
+        if old.is_none_or(|o| o.agent_id != node.agent_id) {
+            clock.agent_id = stamp;
+        }
+        for tag in old.map_or(&[][..], |o| &o.rule_tags) {
+            if !node.rule_tags.contains(tag) {
+                clock.tags.remove(tag);
+            }
+        }
+        for tag in &node.rule_tags {
+            if !clock.tags.contains(tag) {
+                clock.tags.add(tag.clone(), stamp);
+            }
+        }
+        for edge in &node.edges {
+            if !clock.edges.contains(&edge_key(edge)) {
+                clock.edges.add(edge_key(edge), stamp);
+            }
+        }
+        Some(clock)
+    }
+
+    /// Stamps a new embedding of an existing node.
+    pub(super) fn stamp_embedding(&mut self, id: NodeId) -> Option<NodeClock> {
+        if !self.nodes.contains_key(&id) {
+            return None;
+        }
+        let stamp = self.next_stamp()?;
+        let mut clock = self.clocks.get(&id).cloned().unwrap_or_default();
+        clock.embedding = stamp;
+        Some(clock)
+    }
+
+    /// Stamps an edge added to or removed from an existing node.
+    ///
+    /// A removal only takes effect once no parallel edge with the same
+    /// target and type is left.
+    pub(super) fn stamp_edge(&mut self, edge: &Edge, added: bool) -> Option<NodeClock> {
+        let node = self.nodes.get(&edge.from)?;
+        let key = edge_key(edge);
+        if !added && node.edges.iter().any(|e| edge_key(e) == key) {
+            return None;
+        }
+        let stamp = self.next_stamp()?;
+        let mut clock = self.clocks.get(&edge.from).cloned().unwrap_or_default();
+        if added {
+            clock.edges.add(key, stamp);
+        } else {
+            clock.edges.remove(&key);
+        }
+        Some(clock)
+    }
+
+    /// Logs and installs a node's updated clock.
+    pub(super) fn commit_clock(&mut self, id: NodeId, clock: NodeClock) -> Result<()> {
+        let record = WalRecord::Clock { id, clock };
+        self.write_wal(&record, "node clock", false)?;
+        let WalRecord::Clock { clock, .. } = record else {
+            unreachable!()
+        };
+        self.lamport = self.lamport.max(clock.max_counter());
+        self.clocks.insert(id, clock);
+        Ok(())
+    }
+
+    /// Replaces a node's label, agent ID, rule tags and timestamp,
+    /// leaving its edges and embedding untouched.
+    pub(super) fn update_node_fields(&mut self, fields: &Node) -> Result<()> {
+        self.promote(fields.id)?;
+        let record = WalRecord::NodeFields {
+            id: fields.id,
+            label: fields.label.clone(),
+            agent_id: fields.agent_id,
+            rule_tags: fields.rule_tags.clone(),
+            timestamp: fields.timestamp,
+        };
+        self.write_wal(&record, "node fields", false)?;
+
+        let Some(node) = self.nodes.get_mut(&fields.id) else {
+            return Ok(());
+        };
+        if self.options.tag_centroids {
+            self.centroids.remove(node);
+        }
+        let before = memory::node_bytes(node);
+        self.labels.remove(&node.label, node.id);
+        self.labels.insert(&fields.label, node.id);
+        node.label = fields.label.clone();
+        node.agent_id = fields.agent_id;
+        node.rule_tags = fields.rule_tags.clone();
+        node.timestamp = fields.timestamp;
+        self.memory.nodes = self.memory.nodes - before + memory::node_bytes(node);
+        if self.options.tag_centroids {
+            self.centroids.add(node);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::storage::{DbOptions, IdPartition};
+
+    fn edge_set(db: &BarqGraphDb, id: NodeId) -> BTreeSet<EdgeKey> {
+        db.get_node(id)
+            .unwrap()
+            .edges
+            .iter()
+            .map(edge_key)
+            .collect()
+    }
+
+    #[test]
+    fn test_offline_replicas_converge() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = |name: &str, replica| {
+            let opts = DbOptions::new(dir.path().join(name)).with_crdt_replica(replica);
+            BarqGraphDb::open(opts).unwrap()
+        };
+        let mut a = open("a", 1);
+        let mut b = open("b", 2);
+        for id in 1..=3 {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.rule_tags = vec!["memory".to_string()];
+            a.append_node(node).unwrap();
+        }
+        a.add_edge(1, 2, "RELATES").unwrap();
+        b.merge_from(&a).unwrap();
+        assert_eq!(edge_set(&b, 1).len(), 1);
+
+        // Disconnected, both replicas edit node 1
+        let mut edited = a.get_node(1).unwrap().clone();
+        edited.label = "renamed on a".to_string();
+        edited.rule_tags.clear();
+        a.append_node(edited).unwrap();
+        a.remove_edge(1, 2, "RELATES").unwrap();
+
+        let mut edited = b.get_node(1).unwrap().clone();
+        edited.rule_tags.push("urgent".to_string());
+        b.append_node(edited).unwrap();
+        b.add_edge(1, 3, "RELATES").unwrap();
+        b.set_embedding(2, vec![1.0, 0.0]).unwrap();
+        b.append_node(Node::new(4, "only on b".to_string()))
+            .unwrap();
+
+        let report = a.merge_from(&b).unwrap();
+        assert_eq!(report.nodes_added, 1);
+        b.merge_from(&a).unwrap();
+        let again = a.merge_from(&b).unwrap();
+        assert_eq!(again, MergeReport::default());
+
+        for db in [&a, &b] {
+            let node = db.get_node(1).unwrap();
+            assert_eq!(node.label, "renamed on a");
+            // The concurrent add of "urgent" survives a's removal of "memory"
+            assert_eq!(node.rule_tags, vec!["urgent"]);
+            assert_eq!(
+                edge_set(db, 1),
+                BTreeSet::from([(3, "RELATES".to_string())])
+            );
+            assert_eq!(db.get_embedding(2), Some(&[1.0, 0.0][..]));
+            assert!(db.get_node(4).is_some());
+        }
+        assert_eq!(a.node_clock(1), b.node_clock(1));
+
+        // Clocks survive reopen
+        let clock = a.node_clock(1).cloned();
+        drop(a);
+        let a = open("a", 1);
+        assert_eq!(a.node_clock(1).cloned(), clock);
+        assert_eq!(a.get_node(1).unwrap().rule_tags, vec!["urgent"]);
+
+        let mut plain = BarqGraphDb::open(DbOptions::new(dir.path().join("c"))).unwrap();
+        assert!(plain.merge_from(&a).is_err());
+    }
+
+    #[test]
+    fn test_partitioned_replicas_and_id_conflicts() {
+        let open = |replica, writer: Option<u16>| {
+            let mut opts = DbOptions::in_memory().with_crdt_replica(replica);
+            if let Some(writer) = writer {
+                opts = opts.with_id_partition(IdPartition::new(writer));
+            }
+            BarqGraphDb::open(opts).unwrap()
+        };
+
+        // Partitioned replicas never create the same ID
+        let mut a = open(1, Some(2));
+        let mut b = open(2, Some(1));
+        let x = a.allocate_node_id().unwrap();
+        a.append_node(Node::new(x, "from a".to_string())).unwrap();
+        let y = b.allocate_node_id().unwrap();
+        b.append_node(Node::new(y, "from b".to_string())).unwrap();
+
+        let report = b.merge_from(&a).unwrap();
+        assert_eq!(report.nodes_added, 1);
+        assert!(report.conflicts.is_empty());
+        // b can edit a's node, and a's IDs don't move b's allocator
+        let mut edited = b.get_node(x).unwrap().clone();
+        edited.label = "edited on b".to_string();
+        b.append_node(edited).unwrap();
+        assert_eq!(b.allocate_node_id().unwrap(), y + 1);
+        a.merge_from(&b).unwrap();
+        assert_eq!(a.get_node(x).unwrap().label, "edited on b");
+        assert_eq!(a.node_count(), 2);
+
+        // Unpartitioned replicas may reuse an ID for unrelated nodes
+        let mut c = open(3, None);
+        let mut d = open(4, None);
+        c.append_node(Node::new(1, "invoice".to_string())).unwrap();
+        d.append_node(Node::new(1, "customer".to_string())).unwrap();
+        let report = c.merge_from(&d).unwrap();
+        assert_eq!(report.conflicts, vec![1]);
+        assert_eq!(report.nodes_updated, 0);
+        assert_eq!(c.get_node(1).unwrap().label, "invoice");
+    }
+}
@@ -0,0 +1,4972 @@
+//! Storage engine for Barq-GraphDB.
+//!
+//! This module provides the core storage functionality including:
+//! - Append-only Write-Ahead Log (WAL) for durability
+//! - In-memory HashMap for fast node lookups
+//! - Persistence and recovery from disk
+//! - Snapshot checkpoints that bound WAL growth
+
+mod agents;
+mod audit;
+mod beam;
+mod centrality;
+mod checkpoint;
+mod components;
+mod decisions;
+mod degree;
+mod guard;
+mod labels;
+mod manifest;
+mod merge;
+mod models;
+mod paths;
+mod ppr;
+mod searcher;
+mod stats;
+mod tier;
+mod validate;
+mod verify;
+pub(crate) mod wal;
+
+pub use agents::{
+    ActivityBucket, AgentComparison, AgentStats, ComparedAgent, DailyUsage, NodeVisits,
+    PathOutcome, ScoreStats, SharedPath, ACTIVITY_BUCKET_SECS,
+};
+pub use audit::{verify_audit_export, AuditTrailer};
+pub use checkpoint::CheckpointReport;
+pub use components::{Components, Connectivity};
+pub use decisions::{DecisionValidation, PathStep, StepLink};
+pub use searcher::VectorSearcher;
+pub use stats::{DegreeStats, GraphStats};
+pub use validate::BatchValidation;
+pub use verify::{VerifyIssue, VerifyReport};
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::batch_indexer::BatchIndexer;
+use crate::batch_queue::BatchQueue;
+use crate::centroid::{CentroidIndex, TagCentroid};
+use crate::counterfactual::Counterfactual;
+use crate::crdt::NodeClock;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{DecisionRecord, DECIDED_AT, DECISION_TAG, VISITED};
+use crate::disk::DiskQuota;
+use crate::error::BarqError;
+use crate::graph::{Direction, GraphIndex, NeighborOrder};
+use crate::landmark::LandmarkIndex;
+use crate::memory::{self, MemoryBudget, MemoryUsage};
+use crate::metrics::{BackgroundTask, MetricsSink, Operation};
+use crate::template::QueryTemplate;
+use crate::tier::TierPolicy;
+use crate::vector::{
+    self, DistanceMetric, HnswParams, HnswVectorIndex, IndexStats, IvfVectorIndex,
+    LinearVectorIndex, Quantization, QuantizedVectorIndex, VectorIndex,
+};
+use crate::{Edge, Node, NodeId};
+
+/// Type alias for the node storage map.
+type NodeMap = HashMap<NodeId, Node>;
+
+/// Type alias for the adjacency list.
+type AdjacencyMap = HashMap<NodeId, Vec<NodeId>>;
+
+/// Type alias for vector storage during WAL load.
+type VectorMap = HashMap<NodeId, Vec<f32>>;
+
+/// Type alias for explicit edge weights, keyed by source then target.
+type WeightMap = HashMap<NodeId, HashMap<NodeId, f32>>;
+
+/// Type alias for saved query templates keyed by name.
+type TemplateMap = HashMap<String, QueryTemplate>;
+
+/// Type alias for CRDT clocks keyed by node.
+type ClockMap = HashMap<NodeId, NodeClock>;
+
+/// Type alias for embedding model names keyed by node.
+type ModelMap = HashMap<NodeId, String>;
+
+/// Type alias for a set of directed node pairs.
+type PairSet = HashSet<(NodeId, NodeId)>;
+
+/// State reconstructed from a snapshot and WAL replay.
+#[derive(Default)]
+struct RecoveredState {
+    nodes: NodeMap,
+    graph: GraphIndex,
+    vectors: VectorMap,
+    decisions: Vec<DecisionRecord>,
+    templates: TemplateMap,
+    clocks: ClockMap,
+    models: ModelMap,
+    removed_edges: PairSet,
+}
+
+/// Nodes discovered by a BFS, with the depth and parent of each.
+#[derive(Default)]
+pub(crate) struct BfsTree {
+    /// `(node, depth)` pairs in discovery order.
+    pub(crate) order: Vec<(NodeId, usize)>,
+    /// BFS parent of each discovered node (`None` for a start).
+    pub(super) parents: HashMap<NodeId, Option<NodeId>>,
+    /// Whether a deadline stopped the traversal early.
+    pub(crate) partial: bool,
+    /// Whether the node cap stopped the traversal early.
+    pub(crate) truncated: bool,
+}
+
+impl BfsTree {
+    /// Returns the BFS path from the closest start to `id`, inclusive.
+    pub(crate) fn path_to(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path = vec![id];
+        let mut current = id;
+        while let Some(&Some(parent)) = self.parents.get(&current) {
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Removes the first edge matching `(from, to, edge_type)` from recovered
+/// or live state, returning it if the source node held it.
+///
+/// Edges whose source node doesn't exist only live in the graph index.
+/// Returns `None` without changes if no matching edge exists.
+fn unlink_edge(
+    nodes: &mut NodeMap,
+    graph: &mut GraphIndex,
+    from: NodeId,
+    to: NodeId,
+    edge_type: &str,
+) -> Option<Option<Edge>> {
+    let removed = match nodes.get_mut(&from) {
+        Some(node) => {
+            let pos = node
+                .edges
+                .iter()
+                .position(|e| e.to == to && e.edge_type == edge_type)?;
+            Some(node.edges.remove(pos))
+        }
+        None => None,
+    };
+    if !graph.remove_edge(from, to, edge_type) && removed.is_none() {
+        return None;
+    }
+
+    // Recompute the pair's weight from the edges that remain
+    if let Some(node) = nodes.get(&from) {
+        let remaining = node
+            .edges
+            .iter()
+            .filter(|e| e.to == to)
+            .filter_map(|e| e.weight)
+            .reduce(f32::min);
+        graph.set_weight(from, to, remaining);
+    }
+    Some(removed)
+}
+
+/// Rejects weights that would break shortest-path search.
+fn check_weight(weight: f32) -> Result<()> {
+    if !weight.is_finite() || weight < 0.0 {
+        return Err(BarqError::InvalidOperation(format!(
+            "Edge weight must be finite and non-negative, got {}",
+            weight
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Creates an empty vector index of the configured type and metric.
+fn empty_vector_index(opts: &DbOptions) -> Arc<dyn VectorIndex> {
+    match (opts.index_type, opts.quantization) {
+        (IndexType::Linear, Quantization::Int8) => {
+            Arc::new(QuantizedVectorIndex::with_metric(opts.metric))
+        }
+        (IndexType::Linear, Quantization::None) => {
+            Arc::new(LinearVectorIndex::with_metric(opts.metric))
+        }
+        (IndexType::Ivf { nlist, nprobe }, _) => {
+            Arc::new(IvfVectorIndex::new(nlist, nprobe, opts.metric))
+        }
+        (IndexType::Hnsw, _) => Arc::new(HnswVectorIndex::with_params(opts.metric, opts.hnsw)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum IndexType {
+    Linear,
+    Hnsw,
+    /// Inverted file index with `nlist` k-means clusters, scanning the
+    /// `nprobe` closest per query (see `crate::vector::ivf`).
+    Ivf {
+        nlist: usize,
+        nprobe: usize,
+    },
+}
+
+impl std::str::FromStr for IndexType {
+    type Err = String;
+
+    /// Parses `linear`, `hnsw` or `ivf[:nlist[:nprobe]]`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        let mut parts = lower.split(':');
+        let index = match parts.next().unwrap_or_default() {
+            "linear" => IndexType::Linear,
+            "hnsw" => IndexType::Hnsw,
+            "ivf" => {
+                let mut param = |default: usize| match parts.next() {
+                    Some(n) => n
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("Invalid IVF parameter: {}", n)),
+                    None => Ok(default),
+                };
+                IndexType::Ivf {
+                    nlist: param(IvfVectorIndex::DEFAULT_NLIST)?,
+                    nprobe: param(IvfVectorIndex::DEFAULT_NPROBE)?,
+                }
+            }
+            _ => return Err(format!("Unknown index type: {}", s)),
+        };
+        if parts.next().is_some() {
+            return Err(format!("Unknown index type: {}", s));
+        }
+        Ok(index)
+    }
+}
+
+/// Configuration options for opening a database.
+#[derive(Debug, Clone)]
+pub struct DbOptions {
+    /// Path to the database directory.
+    pub path: PathBuf,
+    /// Type of vector index to use.
+    pub index_type: IndexType,
+    /// Distance metric used by the vector index.
+    ///
+    /// Only applies to new databases: an existing one keeps the metric
+    /// recorded in its manifest (see `BarqGraphDb::metric`).
+    pub metric: DistanceMetric,
+    /// Build and search parameters of the HNSW index.
+    pub hnsw: HnswParams,
+    /// How the vector index stores embeddings; `Int8` needs the linear index.
+    pub quantization: Quantization,
+    /// Whether embeddings and query vectors are scaled to unit length.
+    pub normalize_embeddings: bool,
+    /// Whether vectors from different embedding models may share the index.
+    pub allow_mixed_models: bool,
+    /// Whether to flush WAL to disk after every write.
+    pub sync_writes: bool,
+    /// Whether to update vector index asynchronously.
+    pub async_indexing: bool,
+    /// Optional writer partition that node IDs are allocated from.
+    pub id_partition: Option<IdPartition>,
+    /// Whether the database lives purely in memory without a WAL.
+    pub in_memory: bool,
+    /// Optional WAL file location outside the data directory.
+    pub wal_path: Option<PathBuf>,
+    /// Interval of the background checkpoint thread (`None` disables it).
+    pub checkpoint_interval: Option<Duration>,
+    /// Optional limit on estimated in-memory size; writes beyond it are rejected.
+    pub memory_budget: Option<MemoryBudget>,
+    /// Optional soft limits on disk usage (see `crate::disk`).
+    pub disk_quota: Option<DiskQuota>,
+    /// Whether to maintain per-tag centroid vectors (see `crate::centroid`).
+    pub tag_centroids: bool,
+    /// Order in which traversals expand neighbors.
+    pub neighbor_order: NeighborOrder,
+    /// Optional policy moving untouched nodes to cold storage (see `crate::tier`).
+    pub tier_policy: Option<TierPolicy>,
+    /// Replica ID recording CRDT clocks for `merge_from` (`None` disables it).
+    pub crdt_replica: Option<u64>,
+}
+
+impl DbOptions {
+    /// Creates new database options with the specified path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the directory where database files will be stored
+    ///
+    /// # Returns
+    ///
+    /// A new `DbOptions` instance.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            index_type: IndexType::Hnsw,
+            metric: DistanceMetric::L2,
+            hnsw: HnswParams::default(),
+            quantization: Quantization::None,
+            normalize_embeddings: false,
+            allow_mixed_models: false,
+            sync_writes: true,
+            async_indexing: false, // Default to synchronous for consistency
+            id_partition: None,
+            in_memory: false,
+            wal_path: None,
+            checkpoint_interval: None,
+            memory_budget: None,
+            disk_quota: None,
+            tag_centroids: false,
+            neighbor_order: NeighborOrder::Insertion,
+            tier_policy: None,
+            crdt_replica: None,
+        }
+    }
+
+    /// Tunes the HNSW index, e.g. a larger `ef_search` for better recall.
+    ///
+    /// Changing `m`, `ef_construction` or `max_layer` of an existing
+    /// database rebuilds its index on open; `ef_search` applies at once.
+    /// `capacity` is only the starting size, as the index grows itself.
+    pub fn with_hnsw(mut self, params: HnswParams) -> Self {
+        self.hnsw = params;
+        self
+    }
+
+    /// Stores indexed embeddings quantized, e.g. `Int8` for a quarter of
+    /// the vector index memory at a small cost in distance precision.
+    ///
+    /// Only the linear index supports quantization. The WAL and node
+    /// records keep full precision, so it can be changed between opens.
+    pub fn with_quantization(mut self, quantization: Quantization) -> Self {
+        self.quantization = quantization;
+        self
+    }
+
+    /// Scales every embedding and query vector to unit length.
+    ///
+    /// Rankings then depend on direction only and L2, cosine and dot
+    /// product order results the same way. Applied on insert, on WAL
+    /// replay (covering vectors written before it was enabled) and to
+    /// query vectors.
+    pub fn with_normalized_embeddings(mut self, enabled: bool) -> Self {
+        self.normalize_embeddings = enabled;
+        self
+    }
+
+    /// Accepts embeddings from a model other than the one already in
+    /// the index, e.g. while migrating (see `BarqGraphDb::reembed_pending`).
+    pub fn with_mixed_models(mut self, enabled: bool) -> Self {
+        self.allow_mixed_models = enabled;
+        self
+    }
+
+    /// Places the WAL file at a custom location.
+    ///
+    /// Useful for putting the log on a faster filesystem than the
+    /// data directory used for snapshots and exports.
+    pub fn with_wal_path(mut self, wal_path: PathBuf) -> Self {
+        self.wal_path = Some(wal_path);
+        self
+    }
+
+    /// Enables periodic background checkpoints.
+    ///
+    /// The interval is picked up by `BarqGraphDb::spawn_checkpoint_thread`.
+    pub fn with_checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = Some(interval);
+        self
+    }
+
+    /// Rejects writes once estimated memory use would exceed `budget`.
+    pub fn with_memory_budget(mut self, budget: MemoryBudget) -> Self {
+        self.memory_budget = Some(budget);
+        self
+    }
+
+    /// Monitors disk usage against `quota`.
+    ///
+    /// Limits are checked on open, by `BarqGraphDb::check_disk` and by
+    /// the thread started with `BarqGraphDb::spawn_disk_monitor`.
+    pub fn with_disk_quota(mut self, quota: DiskQuota) -> Self {
+        self.disk_quota = Some(quota);
+        self
+    }
+
+    /// Sets the order in which BFS, DFS and hybrid traversals visit
+    /// neighbors; use `NodeId` or `Weight` for reproducible results.
+    pub fn with_neighbor_order(mut self, order: NeighborOrder) -> Self {
+        self.neighbor_order = order;
+        self
+    }
+
+    /// Maintains a centroid vector per rule tag for `route_query`.
+    pub fn with_tag_centroids(mut self, enabled: bool) -> Self {
+        self.tag_centroids = enabled;
+        self
+    }
+
+    /// Moves nodes untouched for the policy's age to cold storage.
+    ///
+    /// Passes run on `BarqGraphDb::run_tiering` and in the thread started
+    /// with `BarqGraphDb::spawn_tiering_thread`.
+    pub fn with_tier_policy(mut self, policy: TierPolicy) -> Self {
+        self.tier_policy = Some(policy);
+        self
+    }
+
+    /// Records CRDT clocks under `replica` so this database can merge
+    /// with other replicas through `BarqGraphDb::merge_from`.
+    ///
+    /// Replica IDs must be unique among the databases that merge with
+    /// each other; they break ties between concurrent writes.
+    ///
+    /// Merges match nodes by ID, so each replica must create nodes under
+    /// IDs no other replica uses: give every replica its own
+    /// `with_id_partition` writer and create nodes with
+    /// `BarqGraphDb::allocate_node_id`. Nodes created on two replicas
+    /// under the same ID are not merged but reported as conflicts.
+    pub fn with_crdt_replica(mut self, replica: u64) -> Self {
+        self.crdt_replica = Some(replica);
+        self
+    }
+
+    /// Returns the snapshot file location.
+    pub fn snapshot_file(&self) -> PathBuf {
+        self.path.join("snapshot.json")
+    }
+
+    /// Returns the effective WAL file location.
+    pub fn wal_file(&self) -> PathBuf {
+        self.wal_path
+            .clone()
+            .unwrap_or_else(|| self.path.join("wal.log"))
+    }
+
+    /// Returns the manifest file location.
+    pub fn manifest_file(&self) -> PathBuf {
+        self.path.join("manifest.json")
+    }
+
+    /// Returns the path prefix of the saved HNSW index files.
+    pub fn hnsw_base(&self) -> PathBuf {
+        self.path.join("hnsw")
+    }
+
+    /// Returns the cold tier file location.
+    pub fn cold_file(&self) -> PathBuf {
+        self.path.join("cold.bin")
+    }
+
+    /// Creates options for an ephemeral in-memory database.
+    ///
+    /// No directory or WAL is created and all data is lost when the
+    /// database is dropped. Useful for tests, benchmarks, and scratch
+    /// memory for agents.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+    /// assert_eq!(db.node_count(), 0);
+    /// ```
+    pub fn in_memory() -> Self {
+        Self {
+            in_memory: true,
+            ..Self::new(PathBuf::new())
+        }
+    }
+
+    /// Restricts the IDs of new nodes to the given writer partition.
+    ///
+    /// Databases written by different writers can then be merged
+    /// without ID collisions. Existing nodes from other partitions,
+    /// e.g. merged from another replica, can still be updated.
+    pub fn with_id_partition(mut self, partition: IdPartition) -> Self {
+        self.id_partition = Some(partition);
+        self
+    }
+}
+
+/// A per-writer slice of the 64-bit node ID space.
+///
+/// The top `WRITER_BITS` bits of an ID hold the writer ID and the
+/// remaining bits hold a writer-local sequence number, so IDs minted
+/// by distinct writers can never collide.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IdPartition {
+    /// Identifier of the writer owning this partition.
+    pub writer_id: u16,
+}
+
+impl IdPartition {
+    /// Number of high bits reserved for the writer ID.
+    pub const WRITER_BITS: u32 = 16;
+
+    /// Number of low bits available for the writer-local sequence.
+    pub const SEQUENCE_BITS: u32 = 64 - Self::WRITER_BITS;
+
+    /// Creates a partition for the given writer.
+    pub fn new(writer_id: u16) -> Self {
+        Self { writer_id }
+    }
+
+    /// Returns the smallest ID in this partition.
+    pub fn first_id(&self) -> NodeId {
+        (self.writer_id as u64) << Self::SEQUENCE_BITS
+    }
+
+    /// Returns the largest ID in this partition.
+    pub fn last_id(&self) -> NodeId {
+        self.first_id() | ((1u64 << Self::SEQUENCE_BITS) - 1)
+    }
+
+    /// Checks whether an ID belongs to this partition.
+    pub fn contains(&self, id: NodeId) -> bool {
+        Self::writer_of(id) == self.writer_id
+    }
+
+    /// Extracts the writer ID encoded in a node ID.
+    pub fn writer_of(id: NodeId) -> u16 {
+        (id >> Self::SEQUENCE_BITS) as u16
+    }
+
+    /// Builds the ID for a writer-local sequence number.
+    pub fn compose(&self, sequence: u64) -> NodeId {
+        self.first_id() | (sequence & ((1u64 << Self::SEQUENCE_BITS) - 1))
+    }
+}
+
+/// WAL record kinds for different operations.
+///
+/// This is the on-disk event format of the database and the input to
+/// user-defined projections (see `crate::projection`). New variants may
+/// be added in future versions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+#[non_exhaustive]
+pub enum WalRecord {
+    /// A node was added or updated.
+    #[serde(rename = "node")]
+    Node { data: Node },
+    /// An edge was added between nodes.
+    #[serde(rename = "edge")]
+    Edge {
+        from: NodeId,
+        to: NodeId,
+        edge_type: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        weight: Option<f32>,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        properties: HashMap<String, serde_json::Value>,
+        #[serde(default)]
+        timestamp: u64,
+    },
+    /// An embedding was set for a node.
+    #[serde(rename = "embedding")]
+    Embedding {
+        id: NodeId,
+        vec: Vec<f32>,
+        /// Model that produced the vector, if recorded.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+    },
+    /// A decision record was added.
+    #[serde(rename = "decision")]
+    Decision { data: DecisionRecord },
+    /// The WAL was truncated after the snapshot with this ID was written.
+    #[serde(rename = "checkpoint")]
+    Checkpoint { id: u64 },
+    /// A query template was saved.
+    #[serde(rename = "template")]
+    Template { data: QueryTemplate },
+    /// A query template was deleted.
+    #[serde(rename = "template_deleted")]
+    TemplateDeleted { name: String },
+    /// Edges added together by `add_edges`.
+    #[serde(rename = "edges")]
+    Edges { edges: Vec<Edge> },
+    /// Tombstone for an edge removed by `remove_edge`.
+    #[serde(rename = "edge_removed")]
+    EdgeRemoved {
+        from: NodeId,
+        to: NodeId,
+        edge_type: String,
+    },
+    /// CRDT clock of a node after a write (see `crate::crdt`).
+    #[serde(rename = "crdt_clock")]
+    Clock { id: NodeId, clock: NodeClock },
+    /// Scalar fields and rule tags of a node were replaced by a merge.
+    #[serde(rename = "node_fields")]
+    NodeFields {
+        id: NodeId,
+        label: String,
+        agent_id: Option<u64>,
+        rule_tags: Vec<String>,
+        timestamp: u64,
+    },
+    /// Tombstone for an embedding removed by `remove_embedding`.
+    #[serde(rename = "embedding_removed")]
+    EmbeddingRemoved { id: NodeId },
+    /// Supersedes the content of a decision redacted by
+    /// `redact_decision`.
+    #[serde(rename = "decision_redacted")]
+    DecisionRedacted { id: u64, path: bool },
+}
+
+/// A kNN result joined with the data of its node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeMatch {
+    /// Node ID of this result.
+    pub id: NodeId,
+    /// Distance from the query vector.
+    pub distance: f32,
+    /// Label of the node.
+    pub label: String,
+    /// Rule tags of the node.
+    pub rule_tags: Vec<String>,
+    /// Timestamp of the node.
+    pub timestamp: u64,
+    /// Agent that created the node, if any.
+    pub agent_id: Option<u64>,
+}
+
+/// Summary of a WAL replay performed by `BarqGraphDb::rebuild`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RebuildReport {
+    /// Total WAL records replayed.
+    pub records: usize,
+    /// Node records replayed.
+    pub nodes: usize,
+    /// Edge records replayed.
+    pub edges: usize,
+    /// Embeddings replayed (standalone records and node payloads).
+    pub embeddings: usize,
+    /// Decision records replayed.
+    pub decisions: usize,
+    /// Embedding dimension observed in the source WAL, if any.
+    pub dimension: Option<usize>,
+}
+
+/// The main database struct providing storage operations.
+///
+/// `BarqGraphDb` manages an append-only WAL for durability and
+/// an in-memory HashMap for fast node lookups.
+pub struct BarqGraphDb {
+    /// Database configuration options.
+    options: DbOptions,
+    /// File handle for the WAL (`None` for in-memory databases).
+    wal: Option<File>,
+    /// In-memory node storage indexed by NodeId.
+    nodes: HashMap<NodeId, Node>,
+    /// Edges in both directions, with their types and explicit weights;
+    /// unweighted pairs cost 1.0.
+    graph: GraphIndex,
+    /// Vector index for similarity search.
+    vector_index: Arc<dyn VectorIndex>,
+    /// Batch queue for async index updates.
+    batch_queue: Option<BatchQueue>,
+    /// Agent decision records, indexed by ID, agent and time.
+    decisions: decisions::DecisionLog,
+    /// Optional sink receiving per-operation latencies.
+    metrics: Option<Arc<dyn MetricsSink>>,
+    /// Next node ID handed out by `allocate_node_id`, `None` once the
+    /// largest ID is taken.
+    next_node_id: Option<NodeId>,
+    /// Embeddings set for IDs that have no node, kept for snapshots.
+    orphan_embeddings: VectorMap,
+    /// Estimated bytes held by nodes, adjacency and vectors.
+    memory: MemoryUsage,
+    /// Saved query templates keyed by name.
+    templates: TemplateMap,
+    /// Pairs that were linked by an edge since removed, so recorded
+    /// decision paths can be checked against the graph they were made on.
+    removed_edges: PairSet,
+    /// Whether a disk quota is currently exceeded.
+    disk_breached: bool,
+    /// Whether writes are rejected because of a disk quota breach.
+    read_only: bool,
+    /// Per-tag centroids, maintained when `options.tag_centroids` is set.
+    centroids: CentroidIndex,
+    /// Payloads of nodes moved to the cold tier.
+    cold: tier::ColdStore,
+    /// Degree counters for `top_degree_nodes`, derived from `graph`.
+    degrees: degree::DegreeIndex,
+    /// Node IDs by label for `find_by_label`.
+    labels: labels::LabelIndex,
+    /// CRDT clocks of nodes written with `options.crdt_replica` set.
+    clocks: ClockMap,
+    /// Embedding models recorded by `set_embedding_with_model`.
+    models: models::ModelIndex,
+    /// Highest Lamport counter seen by this replica.
+    lamport: u64,
+    /// Problems `open` recovered from, such as a discarded saved index.
+    open_warnings: Vec<String>,
+}
+
+impl BarqGraphDb {
+    /// Opens or creates a database at the specified path.
+    ///
+    /// If the database directory doesn't exist, it will be created.
+    /// Existing WAL records will be replayed to restore state.
+    ///
+    /// # Arguments
+    ///
+    /// * `opts` - Database configuration options
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the opened database or an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The HNSW or IVF parameters are out of range, or quantization
+    ///   is requested with an index other than the linear one
+    /// - The directory cannot be created
+    /// - The WAL file cannot be opened
+    /// - Existing WAL records are corrupted
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"));
+    /// let db = BarqGraphDb::open(opts).unwrap();
+    /// ```
+    pub fn open(mut opts: DbOptions) -> Result<Self> {
+        if opts.index_type == IndexType::Hnsw {
+            opts.hnsw
+                .validate()
+                .map_err(|e| BarqError::InvalidOperation(format!("{:#}", e)))?;
+        }
+        if let IndexType::Ivf { nlist, nprobe } = opts.index_type {
+            if nlist == 0 || nprobe == 0 {
+                return Err(BarqError::InvalidOperation(
+                    "IVF nlist and nprobe must be positive".to_string(),
+                )
+                .into());
+            }
+        }
+        if opts.quantization != Quantization::None && opts.index_type != IndexType::Linear {
+            return Err(BarqError::InvalidOperation(
+                "Quantized embeddings require the linear index".to_string(),
+            )
+            .into());
+        }
+
+        // Create directory if it doesn't exist
+        if !opts.in_memory {
+            fs::create_dir_all(&opts.path)
+                .with_context(|| format!("Failed to create database directory: {:?}", opts.path))?;
+            // Settings fixed at creation override the requested ones
+            let manifest = manifest::Manifest::load_or_create(&opts)
+                .with_context(|| "Failed to load manifest")?;
+            opts.metric = manifest.metric;
+        }
+
+        let wal_path = opts.wal_file();
+        if !opts.in_memory {
+            if let Some(parent) = wal_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create WAL directory: {:?}", parent))?;
+            }
+        }
+
+        // The cold tier only caches recovered state, so start without it
+        if !opts.in_memory {
+            match fs::remove_file(opts.cold_file()) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e).with_context(|| "Failed to discard cold tier file");
+                }
+                _ => {}
+            }
+        }
+
+        // Start from the latest snapshot, then replay the WAL on top of it
+        let (state, skip_bytes) = if opts.in_memory {
+            (Default::default(), 0)
+        } else {
+            checkpoint::load_snapshot(&opts.snapshot_file(), &wal_path)
+                .with_context(|| "Failed to load snapshot")?
+        };
+        let RecoveredState {
+            mut nodes,
+            graph,
+            mut vectors,
+            decisions,
+            templates,
+            clocks,
+            models,
+            removed_edges,
+        } = if !opts.in_memory && wal_path.exists() {
+            let (state, valid_len) = Self::load_wal(&wal_path, state, skip_bytes)
+                .with_context(|| "Failed to load WAL")?;
+            // Cut off a record torn by a crash so new appends start on a clean line
+            let wal_len = fs::metadata(&wal_path).map(|m| m.len()).unwrap_or(0);
+            if wal_len > valid_len {
+                OpenOptions::new()
+                    .write(true)
+                    .open(&wal_path)
+                    .and_then(|f| f.set_len(valid_len))
+                    .with_context(|| "Failed to truncate torn WAL record")?;
+            }
+            state
+        } else {
+            state
+        };
+
+        if opts.normalize_embeddings {
+            // Vectors may predate the option
+            for v in vectors.values_mut() {
+                vector::normalize(v);
+            }
+            for node in nodes.values_mut() {
+                vector::normalize(&mut node.embedding);
+            }
+        }
+
+        // Recovered vectors, plus embeddings of nodes without one
+        let embeddings = vectors.iter().map(|(id, v)| (*id, v.as_slice())).chain(
+            nodes
+                .iter()
+                .filter(|(id, n)| !n.embedding.is_empty() && !vectors.contains_key(id))
+                .map(|(id, n)| (*id, n.embedding.as_slice())),
+        );
+
+        // Build vector index based on configuration, reusing a saved HNSW graph
+        let mut open_warnings = Vec::new();
+        let saved_hnsw = match opts.index_type {
+            IndexType::Hnsw if !opts.in_memory => {
+                HnswVectorIndex::load(&opts.hnsw_base(), opts.metric, opts.hnsw).unwrap_or_else(
+                    |e| {
+                        open_warnings.push(format!("Discarded saved HNSW index, rebuilt: {:#}", e));
+                        None
+                    },
+                )
+            }
+            _ => None,
+        };
+        let vector_index: Arc<dyn VectorIndex> = match saved_hnsw {
+            Some(index) => {
+                index.sync(embeddings);
+                Arc::new(index)
+            }
+            None => {
+                let index = empty_vector_index(&opts);
+                index.build_from(&embeddings.collect::<Vec<_>>());
+                index
+            }
+        };
+
+        // Setup async thread if enabled
+        let batch_queue = if opts.async_indexing {
+            let queue = BatchQueue::new(100);
+            BatchIndexer::start_background_thread(
+                queue.clone(),
+                vector_index.clone(),
+                Duration::from_millis(10),
+            );
+            Some(queue)
+        } else {
+            None
+        };
+
+        // Resume ID allocation after the highest ID already in use
+        let next_node_id = Self::initial_node_id(&nodes, opts.id_partition);
+
+        let orphan_embeddings = vectors
+            .into_iter()
+            .filter(|(id, _)| nodes.get(id).is_none_or(|n| n.embedding.is_empty()))
+            .collect();
+
+        // Open WAL file for appending
+        let wal = if opts.in_memory {
+            None
+        } else {
+            Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&wal_path)
+                    .with_context(|| format!("Failed to open WAL file: {:?}", wal_path))?,
+            )
+        };
+
+        let centroids = if opts.tag_centroids {
+            CentroidIndex::build(nodes.values())
+        } else {
+            CentroidIndex::default()
+        };
+        let labels = labels::LabelIndex::build(nodes.values());
+        let decisions = decisions::DecisionLog::build(decisions, empty_vector_index(&opts));
+        let mut db = Self {
+            options: opts,
+            wal,
+            nodes,
+            degrees: degree::DegreeIndex::build(graph.adjacency()),
+            graph,
+            vector_index,
+            batch_queue,
+            decisions,
+            metrics: None,
+            next_node_id,
+            orphan_embeddings,
+            memory: MemoryUsage::default(),
+            templates,
+            removed_edges,
+            disk_breached: false,
+            read_only: false,
+            centroids,
+            cold: Default::default(),
+            labels,
+            lamport: clocks
+                .values()
+                .map(NodeClock::max_counter)
+                .max()
+                .unwrap_or(0),
+            clocks,
+            models: models::ModelIndex::build(models),
+            open_warnings,
+        };
+        db.recompute_memory_usage();
+        db.check_disk()?;
+        Ok(db)
+    }
+
+    /// Returns the problems `open` recovered from instead of failing, such
+    /// as a saved HNSW index that could not be loaded and was rebuilt.
+    pub fn open_warnings(&self) -> &[String] {
+        &self.open_warnings
+    }
+
+    /// Serializes a record and appends it to the WAL.
+    ///
+    /// In-memory databases have no WAL and skip the write entirely.
+    /// The WAL is flushed when `sync_writes` is enabled or `force_flush` is set.
+    fn write_wal(&mut self, record: &WalRecord, what: &str, force_flush: bool) -> Result<()> {
+        // Checkpoints shrink the WAL, so they stay allowed in read-only mode
+        if self.read_only && !matches!(record, WalRecord::Checkpoint { .. }) {
+            return Err(BarqError::ReadOnly(format!(
+                "disk quota exceeded, refusing to write {}",
+                what
+            ))
+            .into());
+        }
+        let sync = self.options.sync_writes || force_flush;
+        let Some(wal) = self.wal.as_mut() else {
+            return Ok(());
+        };
+
+        // Serialize to JSON with a trailing checksum
+        let mut line = wal::encode_line(record)
+            .with_context(|| format!("Failed to serialize {} to JSON", what))?;
+        line.push('\n');
+
+        // Append the whole line, removing any partial bytes if the disk fills up
+        let mut written = 0;
+        while written < line.len() {
+            match wal.write(&line.as_bytes()[written..]) {
+                Ok(0) => {
+                    let err = std::io::Error::from(std::io::ErrorKind::WriteZero);
+                    return Err(Self::rollback_partial_write(wal, written, err))
+                        .with_context(|| format!("Failed to write {} to WAL", what));
+                }
+                Ok(n) => written += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    return Err(Self::rollback_partial_write(wal, written, e))
+                        .with_context(|| format!("Failed to write {} to WAL", what));
+                }
+            }
+        }
+
+        // Flush to ensure durability
+        if sync {
+            wal.flush().with_context(|| "Failed to flush WAL")?;
+        }
+
+        Ok(())
+    }
+
+    /// Truncates the bytes of a partially appended record after a write error.
+    fn rollback_partial_write(wal: &File, written: usize, err: std::io::Error) -> std::io::Error {
+        if written > 0 {
+            if let Ok(meta) = wal.metadata() {
+                let _ = wal.set_len(meta.len().saturating_sub(written as u64));
+            }
+        }
+        err
+    }
+
+    /// Recomputes the memory estimate from scratch.
+    fn recompute_memory_usage(&mut self) {
+        let vector_dims = self
+            .nodes
+            .values()
+            .map(|n| n.embedding.len())
+            .filter(|&d| d > 0)
+            .chain(self.orphan_embeddings.values().map(|v| v.len()))
+            .chain(self.cold.dims());
+        self.memory = MemoryUsage {
+            nodes: self.nodes.values().map(memory::node_bytes).sum(),
+            adjacency: (self.graph.node_count() + self.graph.target_count())
+                * memory::ADJACENCY_KEY_BYTES
+                + 2 * self.edge_count() * memory::ADJACENCY_ENTRY_BYTES
+                + self.graph.weight_count() * memory::EDGE_WEIGHT_BYTES
+                + self.removed_edges.len() * memory::REMOVED_EDGE_BYTES,
+            vectors: vector_dims.map(|d| self.vector_bytes(d)).sum(),
+        };
+    }
+
+    /// Normalizes an incoming embedding if `normalize_embeddings` is set.
+    fn prepare_embedding(&self, embedding: &mut [f32]) {
+        if self.options.normalize_embeddings {
+            vector::normalize(embedding);
+        }
+    }
+
+    /// Returns the query vector as it should be compared with stored
+    /// embeddings: unit-normalized if `normalize_embeddings` is set.
+    pub(crate) fn prepare_query<'a>(&self, query: &'a [f32]) -> Cow<'a, [f32]> {
+        if !self.options.normalize_embeddings {
+            return Cow::Borrowed(query);
+        }
+        let mut normalized = query.to_vec();
+        vector::normalize(&mut normalized);
+        Cow::Owned(normalized)
+    }
+
+    /// Returns the embedding indexed for an ID, whether or not it has a node.
+    fn indexed_embedding(&self, id: NodeId) -> Option<&[f32]> {
+        self.get_embedding(id)
+            .or_else(|| self.orphan_embeddings.get(&id).map(Vec::as_slice))
+    }
+
+    /// Iterates over every embedding held by the vector index.
+    pub(crate) fn indexed_embeddings(&self) -> impl Iterator<Item = (NodeId, &[f32])> + '_ {
+        self.list_nodes()
+            .into_iter()
+            .filter(|n| !n.embedding.is_empty())
+            .map(|n| (n.id, n.embedding.as_slice()))
+            .chain(
+                self.orphan_embeddings
+                    .iter()
+                    .map(|(&id, v)| (id, v.as_slice())),
+            )
+    }
+
+    /// Estimated bytes of one indexed vector under the configured quantization.
+    fn vector_bytes(&self, dimension: usize) -> usize {
+        memory::vector_bytes(dimension, self.options.quantization)
+    }
+
+    /// Returns the current estimated memory usage.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.memory
+    }
+
+    /// Fails if moving to `next` would exceed the memory budget.
+    ///
+    /// Writes that don't grow the estimate are always allowed, so an
+    /// over-budget database can still shrink.
+    fn check_memory_budget(&self, next: &MemoryUsage) -> Result<()> {
+        if let Some(budget) = self.options.memory_budget {
+            if next.total() > budget.max_bytes && next.total() > self.memory.total() {
+                return Err(BarqError::MemoryBudgetExceeded {
+                    required: next.total(),
+                    limit: budget.max_bytes,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the dimension of the vector currently indexed for an ID.
+    fn indexed_dimension(&self, id: NodeId) -> Option<usize> {
+        self.nodes
+            .get(&id)
+            .map(|n| n.embedding.len())
+            .filter(|&d| d > 0)
+            .or_else(|| self.orphan_embeddings.get(&id).map(|v| v.len()))
+    }
+
+    /// Counts the distinct IDs that don't have a reverse adjacency entry yet.
+    fn new_reverse_keys(&self, ids: impl IntoIterator<Item = NodeId>) -> usize {
+        let mut fresh: Vec<NodeId> = ids
+            .into_iter()
+            .filter(|&id| self.graph.incoming(id).is_none())
+            .collect();
+        fresh.sort_unstable();
+        fresh.dedup();
+        fresh.len()
+    }
+
+    /// Counts the distinct edge pairs that don't have a recorded weight yet.
+    fn new_weight_entries(&self, pairs: impl IntoIterator<Item = (NodeId, NodeId)>) -> usize {
+        let mut fresh: Vec<(NodeId, NodeId)> = pairs
+            .into_iter()
+            .filter(|&(from, to)| self.edge_weight(from, to).is_none())
+            .collect();
+        fresh.sort_unstable();
+        fresh.dedup();
+        fresh.len()
+    }
+
+    /// Counts the distinct IDs that don't have an adjacency entry yet.
+    fn new_adjacency_keys(&self, ids: impl IntoIterator<Item = NodeId>) -> usize {
+        let mut fresh: Vec<NodeId> = ids
+            .into_iter()
+            .filter(|&id| !self.graph.contains_node(id))
+            .collect();
+        fresh.sort_unstable();
+        fresh.dedup();
+        fresh.len()
+    }
+
+    /// Computes the first free node ID, honoring the writer partition.
+    ///
+    /// Returns `None` if the largest ID is already taken.
+    fn initial_node_id(nodes: &NodeMap, partition: Option<IdPartition>) -> Option<NodeId> {
+        match partition {
+            Some(p) => nodes
+                .keys()
+                .filter(|id| p.contains(**id))
+                .max()
+                .map_or(Some(p.first_id() + 1), |max| max.checked_add(1)),
+            None => nodes.keys().max().map_or(Some(1), |max| max.checked_add(1)),
+        }
+    }
+
+    /// Allocates a fresh node ID.
+    ///
+    /// IDs are drawn from the configured `IdPartition` when present,
+    /// so independently written databases can be merged without
+    /// collisions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the partition's ID space (or, without a
+    /// partition, the whole ID space) is exhausted.
+    pub fn allocate_node_id(&mut self) -> Result<NodeId> {
+        let partition = self.options.id_partition;
+        let Some(id) = self
+            .next_node_id
+            .filter(|&id| partition.is_none_or(|p| p.contains(id)))
+        else {
+            return Err(BarqError::InvalidOperation(match partition {
+                Some(p) => format!("ID space exhausted for writer {}", p.writer_id),
+                None => "Node ID space exhausted".to_string(),
+            })
+            .into());
+        };
+        self.next_node_id = id.checked_add(1);
+        Ok(id)
+    }
+
+    /// Attaches a metrics sink that receives per-operation latencies.
+    ///
+    /// The sink is called after `append_node`, `add_edge`, `knn_search`
+    /// and `hybrid_query` complete. Pass `None` to detach it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::metrics::{LatencyHistogram, Operation};
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    /// use std::sync::Arc;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let histogram = Arc::new(LatencyHistogram::new());
+    /// db.set_metrics_sink(Some(histogram.clone()));
+    /// db.knn_search(&[0.1, 0.2], 5);
+    /// println!("{:?}", histogram.snapshot(Operation::Knn));
+    /// ```
+    pub fn set_metrics_sink(&mut self, sink: Option<Arc<dyn MetricsSink>>) {
+        self.metrics = sink;
+    }
+
+    /// Reports the elapsed time of an operation to the metrics sink, if any.
+    fn observe(&self, op: Operation, started: Instant) {
+        if let Some(sink) = &self.metrics {
+            sink.record(op, started.elapsed());
+        }
+    }
+
+    /// Reports a failed background run to the metrics sink, if any.
+    pub(super) fn report_background_error(&self, task: BackgroundTask, error: &anyhow::Error) {
+        if let Some(sink) = &self.metrics {
+            sink.background_error(task, error);
+        }
+    }
+
+    /// Loads WAL records from disk and reconstructs the node map.
+    ///
+    /// A trailing record that was only partially written (e.g. by a crash
+    /// mid-append) is ignored; any other unreadable record is an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `wal_path` - Path to the WAL file
+    /// * `initial` - State restored from a snapshot (empty without one)
+    /// * `skip_bytes` - Leading WAL bytes already covered by the snapshot
+    ///
+    /// # Returns
+    ///
+    /// The reconstructed state and the byte length of the intact WAL prefix.
+    fn load_wal(
+        wal_path: &PathBuf,
+        initial: RecoveredState,
+        skip_bytes: u64,
+    ) -> Result<(RecoveredState, u64)> {
+        let mut file = File::open(wal_path)
+            .with_context(|| format!("Failed to open WAL for reading: {:?}", wal_path))?;
+        file.seek(SeekFrom::Start(skip_bytes))
+            .with_context(|| format!("Failed to seek WAL to offset {}", skip_bytes))?;
+
+        let mut reader = BufReader::new(file);
+        let mut state = initial;
+        let mut offset = skip_bytes;
+        let mut line = String::new();
+
+        for line_num in 1.. {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .with_context(|| format!("Failed to read WAL line {}", line_num))?;
+            // Stop at EOF or at a partially written trailing record
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+
+            // Skip empty lines
+            if !line.trim().is_empty() {
+                let (record, _) = wal::decode_line(&line)
+                    .with_context(|| format!("Failed to parse WAL record at line {}", line_num))?;
+                Self::apply_record(&mut state, record);
+            }
+            offset += read as u64;
+        }
+
+        Ok((state, offset))
+    }
+
+    /// Applies a single WAL record to state being recovered.
+    fn apply_record(state: &mut RecoveredState, record: WalRecord) {
+        let RecoveredState {
+            nodes,
+            graph,
+            vectors,
+            decisions,
+            templates,
+            clocks,
+            models,
+            removed_edges,
+        } = state;
+        match record {
+            WalRecord::Node { data: node } => {
+                // Rebuild adjacency from node edges
+                for edge in &node.edges {
+                    graph.add_typed_edge(edge.from, edge.to, &edge.edge_type, edge.weight);
+                }
+                // Store embedding if present
+                if !node.embedding.is_empty() {
+                    if vectors.get(&node.id) != Some(&node.embedding) {
+                        models.remove(&node.id);
+                    }
+                    vectors.insert(node.id, node.embedding.clone());
+                }
+                nodes.insert(node.id, node);
+            }
+            WalRecord::Edge {
+                from,
+                to,
+                edge_type,
+                weight,
+                properties,
+                timestamp,
+            } => {
+                let edge = Edge {
+                    from,
+                    to,
+                    edge_type,
+                    weight,
+                    properties,
+                    timestamp,
+                };
+                Self::recover_edge(nodes, graph, removed_edges, edge);
+            }
+            WalRecord::Edges { edges } => {
+                for edge in edges {
+                    Self::recover_edge(nodes, graph, removed_edges, edge);
+                }
+            }
+            WalRecord::Embedding { id, vec, model } => {
+                match model {
+                    Some(model) => models.insert(id, model),
+                    None => models.remove(&id),
+                };
+                vectors.insert(id, vec.clone());
+                // Update node embedding if node exists
+                if let Some(node) = nodes.get_mut(&id) {
+                    node.embedding = vec;
+                }
+            }
+            WalRecord::Decision { data: decision } => {
+                decisions.push(decision);
+            }
+            WalRecord::Checkpoint { .. } => {}
+            WalRecord::Template { data } => {
+                templates.insert(data.name.clone(), data);
+            }
+            WalRecord::TemplateDeleted { name } => {
+                templates.remove(&name);
+            }
+            WalRecord::EdgeRemoved {
+                from,
+                to,
+                edge_type,
+            } => {
+                if unlink_edge(nodes, graph, from, to, &edge_type).is_some() {
+                    removed_edges.insert((from, to));
+                }
+            }
+            WalRecord::Clock { id, clock } => {
+                clocks.insert(id, clock);
+            }
+            WalRecord::NodeFields {
+                id,
+                label,
+                agent_id,
+                rule_tags,
+                timestamp,
+            } => {
+                if let Some(node) = nodes.get_mut(&id) {
+                    node.label = label;
+                    node.agent_id = agent_id;
+                    node.rule_tags = rule_tags;
+                    node.timestamp = timestamp;
+                }
+            }
+            WalRecord::EmbeddingRemoved { id } => {
+                vectors.remove(&id);
+                models.remove(&id);
+                if let Some(node) = nodes.get_mut(&id) {
+                    node.embedding.clear();
+                }
+            }
+            WalRecord::DecisionRedacted { id, path } => {
+                for decision in decisions.iter_mut().filter(|d| d.id == id) {
+                    decision.redact(path);
+                }
+            }
+        }
+    }
+
+    /// Applies a recovered edge, mirroring `add_edge`, which attaches
+    /// edges to an existing source node and forgets an earlier removal
+    /// of the pair.
+    fn recover_edge(
+        nodes: &mut NodeMap,
+        graph: &mut GraphIndex,
+        removed_edges: &mut PairSet,
+        edge: Edge,
+    ) {
+        removed_edges.remove(&(edge.from, edge.to));
+        graph.add_typed_edge(edge.from, edge.to, &edge.edge_type, edge.weight);
+        if let Some(node) = nodes.get_mut(&edge.from) {
+            node.edges.push(edge);
+        }
+    }
+
+    /// Replays an existing WAL into a fresh database with new options.
+    ///
+    /// Every record of the source WAL is re-applied in order to a new
+    /// database opened with `dest`, so index type and distance metric can
+    /// be changed without hand-written migration code. A source snapshot,
+    /// if any, is restored first and checkpointed into the destination.
+    /// Embedding dimensions are validated along the way. A final record
+    /// torn by a crash is dropped, as when opening the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Options locating the existing database's WAL
+    /// * `dest` - Options for the new database (its WAL must not exist yet)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the destination already contains a WAL, the
+    /// source WAL cannot be parsed, or embeddings have inconsistent dimensions.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions, IndexType};
+    /// use barq_graphdb::vector::DistanceMetric;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut dest = DbOptions::new(PathBuf::from("./my_db_cosine"));
+    /// dest.index_type = IndexType::Hnsw;
+    /// dest.metric = DistanceMetric::Cosine;
+    /// let source = DbOptions::new(PathBuf::from("./my_db"));
+    /// let (db, report) = BarqGraphDb::rebuild(&source, dest).unwrap();
+    /// ```
+    pub fn rebuild(source: &DbOptions, dest: DbOptions) -> Result<(Self, RebuildReport)> {
+        let source_wal = source.wal_file();
+        if !source_wal.exists() {
+            anyhow::bail!("No WAL found at {:?}", source_wal);
+        }
+        if !dest.in_memory && dest.wal_file().exists() {
+            anyhow::bail!("Destination {:?} already contains a WAL", dest.path);
+        }
+
+        let mut file = File::open(&source_wal)
+            .with_context(|| format!("Failed to open WAL for reading: {:?}", source_wal))?;
+        let mut db = Self::open(dest)?;
+        let mut report = RebuildReport::default();
+
+        let had_snapshot = source.snapshot_file().exists();
+        let (
+            RecoveredState {
+                nodes,
+                graph,
+                vectors,
+                decisions,
+                templates,
+                clocks,
+                models,
+                removed_edges,
+            },
+            skip_bytes,
+        ) = checkpoint::load_snapshot(&source.snapshot_file(), &source_wal)
+            .with_context(|| "Failed to load source snapshot")?;
+        for (id, vec) in &vectors {
+            match report.dimension {
+                Some(expected) if expected != vec.len() => anyhow::bail!(
+                    "Dimension mismatch in snapshot for node {}: expected {}, found {}",
+                    id,
+                    expected,
+                    vec.len()
+                ),
+                _ => report.dimension = Some(vec.len()),
+            }
+            report.embeddings += 1;
+        }
+        let batch: Vec<(NodeId, &[f32])> =
+            vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        db.vector_index.build_from(&batch);
+        report.nodes += nodes.len();
+        report.edges += graph.edge_count();
+        report.decisions += decisions.len();
+        db.orphan_embeddings = vectors
+            .into_iter()
+            .filter(|(id, _)| nodes.get(id).is_none_or(|n| n.embedding.is_empty()))
+            .collect();
+        db.next_node_id = Self::initial_node_id(&nodes, db.options.id_partition);
+        if db.options.tag_centroids {
+            db.centroids = CentroidIndex::build(nodes.values());
+        }
+        db.labels = labels::LabelIndex::build(nodes.values());
+        db.nodes = nodes;
+        db.degrees = degree::DegreeIndex::build(graph.adjacency());
+        db.graph = graph;
+        db.decisions = decisions::DecisionLog::build(decisions, empty_vector_index(&db.options));
+        db.templates = templates;
+        db.removed_edges = removed_edges;
+        db.lamport = clocks
+            .values()
+            .map(NodeClock::max_counter)
+            .max()
+            .unwrap_or(0);
+        db.clocks = clocks;
+        db.models = models::ModelIndex::build(models);
+        db.recompute_memory_usage();
+        if had_snapshot && !db.options.in_memory {
+            db.checkpoint()?;
+        }
+
+        file.seek(SeekFrom::Start(skip_bytes))
+            .with_context(|| format!("Failed to seek WAL to offset {}", skip_bytes))?;
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        for line_num in 1.. {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .with_context(|| format!("Failed to read WAL line {}", line_num))?;
+            // Stop at EOF or at a record torn by a crash, as `open` does
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (record, _) = wal::decode_line(&line)
+                .with_context(|| format!("Failed to parse WAL record at line {}", line_num))?;
+
+            let embedding_dim = match &record {
+                WalRecord::Node { data } if !data.embedding.is_empty() => {
+                    Some(data.embedding.len())
+                }
+                WalRecord::Embedding { vec, .. } => Some(vec.len()),
+                _ => None,
+            };
+            if let Some(dim) = embedding_dim {
+                match report.dimension {
+                    Some(expected) if expected != dim => anyhow::bail!(
+                        "Dimension mismatch at WAL line {}: expected {}, found {}",
+                        line_num,
+                        expected,
+                        dim
+                    ),
+                    _ => report.dimension = Some(dim),
+                }
+                report.embeddings += 1;
+            }
+
+            match record {
+                WalRecord::Node { data } => {
+                    report.nodes += 1;
+                    db.append_node(data)?;
+                }
+                WalRecord::Edge {
+                    from,
+                    to,
+                    edge_type,
+                    weight,
+                    properties,
+                    timestamp,
+                } => {
+                    report.edges += 1;
+                    db.insert_edge(Edge {
+                        from,
+                        to,
+                        edge_type,
+                        weight,
+                        properties,
+                        timestamp,
+                    })?;
+                }
+                WalRecord::Edges { edges } => {
+                    report.edges += edges.len();
+                    db.insert_edges(edges)?;
+                }
+                WalRecord::Embedding { id, vec, model } => db.store_embedding(id, vec, model)?,
+                WalRecord::Decision { data } => {
+                    report.decisions += 1;
+                    db.log_decision(data)?;
+                }
+                WalRecord::Checkpoint { .. } => {}
+                WalRecord::Template { data } => db.put_template(data)?,
+                WalRecord::TemplateDeleted { name } => {
+                    db.delete_template(&name)?;
+                }
+                WalRecord::EdgeRemoved {
+                    from,
+                    to,
+                    edge_type,
+                } => {
+                    db.remove_edge(from, to, &edge_type)?;
+                }
+                WalRecord::Clock { id, clock } => db.commit_clock(id, clock)?,
+                WalRecord::NodeFields {
+                    id,
+                    label,
+                    agent_id,
+                    rule_tags,
+                    timestamp,
+                } => {
+                    let Some(mut fields) = db.get_node(id).cloned() else {
+                        continue;
+                    };
+                    fields.label = label;
+                    fields.agent_id = agent_id;
+                    fields.rule_tags = rule_tags;
+                    fields.timestamp = timestamp;
+                    db.update_node_fields(&fields)?;
+                }
+                WalRecord::EmbeddingRemoved { id } => {
+                    db.remove_embedding(id)?;
+                }
+                WalRecord::DecisionRedacted { id, path } => {
+                    db.redact_decision(id, path)?;
+                }
+            }
+            report.records += 1;
+        }
+
+        Ok((db, report))
+    }
+
+    /// Appends a node to the database.
+    ///
+    /// The node is written to the WAL for durability and added to the
+    /// in-memory index for fast lookups.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to append
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Serialization fails
+    /// - Writing to the WAL fails
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::Node;
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"));
+    /// let mut db = BarqGraphDb::open(opts).unwrap();
+    ///
+    /// let node = Node::new(1, "example".to_string());
+    /// db.append_node(node).unwrap();
+    /// ```
+    pub fn append_node(&mut self, mut node: Node) -> Result<()> {
+        let started = Instant::now();
+        self.prepare_embedding(&mut node.embedding);
+        // Only new nodes are bound to the partition; replicas may edit
+        // nodes other writers created
+        if let Some(p) = self.options.id_partition {
+            if !p.contains(node.id) && !self.nodes.contains_key(&node.id) {
+                return Err(BarqError::InvalidOperation(format!(
+                    "Node {} is outside the ID partition of writer {}",
+                    node.id, p.writer_id
+                ))
+                .into());
+            }
+        }
+
+        for weight in node.edges.iter().filter_map(|e| e.weight) {
+            check_weight(weight)?;
+        }
+        // Replacing a cold node needs its old payload for the bookkeeping below
+        self.promote(node.id)?;
+
+        let mut next = self.memory;
+        next.nodes = next.nodes + memory::node_bytes(&node)
+            - self.nodes.get(&node.id).map_or(0, memory::node_bytes);
+        next.adjacency += 2 * node.edges.len() * memory::ADJACENCY_ENTRY_BYTES
+            + self.new_adjacency_keys(node.edges.iter().flat_map(|e| [e.from, e.to]))
+                * memory::ADJACENCY_KEY_BYTES
+            + self.new_reverse_keys(node.edges.iter().map(|e| e.to)) * memory::ADJACENCY_KEY_BYTES
+            + self.new_weight_entries(
+                node.edges
+                    .iter()
+                    .filter(|e| e.weight.is_some())
+                    .map(|e| (e.from, e.to)),
+            ) * memory::EDGE_WEIGHT_BYTES;
+        if !node.embedding.is_empty() {
+            next.vectors = next.vectors + self.vector_bytes(node.embedding.len())
+                - self
+                    .indexed_dimension(node.id)
+                    .map_or(0, |d| self.vector_bytes(d));
+        }
+        self.check_memory_budget(&next)?;
+        let clock = self.stamp_put(&node);
+
+        let record = WalRecord::Node { data: node.clone() };
+
+        // Append to WAL for durability
+        self.write_wal(&record, "node", false)?;
+        self.memory = next;
+
+        // Rebuild adjacency from node edges
+        for edge in &node.edges {
+            self.graph
+                .add_typed_edge(edge.from, edge.to, &edge.edge_type, edge.weight);
+            self.degrees.add_edge(edge.from, edge.to);
+        }
+
+        // Add embedding to vector index if present
+        if !node.embedding.is_empty() {
+            if let Some(queue) = &self.batch_queue {
+                queue.push(node.clone());
+            } else {
+                self.vector_index.insert(node.id, &node.embedding);
+            }
+        }
+
+        if self.options.tag_centroids {
+            if let Some(old) = self.nodes.get(&node.id) {
+                self.centroids.remove(old);
+            }
+            self.centroids.add(&node);
+        }
+
+        if !node.embedding.is_empty() {
+            // A new vector from the node payload has no known model
+            let current = self
+                .nodes
+                .get(&node.id)
+                .map(|n| &n.embedding)
+                .or_else(|| self.orphan_embeddings.get(&node.id));
+            if current != Some(&node.embedding) {
+                self.models.set(node.id, None);
+            }
+            self.orphan_embeddings.remove(&node.id);
+        } else if let Some(old) = self.nodes.get_mut(&node.id) {
+            // The index keeps the replaced node's vector; track it as an orphan
+            if !old.embedding.is_empty() {
+                let embedding = std::mem::take(&mut old.embedding);
+                self.orphan_embeddings.insert(node.id, embedding);
+            }
+        }
+
+        // Keep the allocator ahead of explicitly chosen IDs in its own
+        // partition; IDs of other writers must not move it
+        let own_id = self
+            .options
+            .id_partition
+            .is_none_or(|p| p.contains(node.id));
+        if own_id && self.next_node_id.is_some_and(|next| node.id >= next) {
+            self.next_node_id = node.id.checked_add(1);
+        }
+
+        // Update in-memory index
+        let id = node.id;
+        if let Some(old) = self.nodes.get(&id) {
+            self.labels.remove(&old.label, id);
+        }
+        self.labels.insert(&node.label, id);
+        self.nodes.insert(id, node);
+        if let Some(clock) = clock {
+            self.commit_clock(id, clock)?;
+        }
+
+        self.observe(Operation::AppendNode, started);
+        Ok(())
+    }
+
+    /// Returns a reference to the in-memory node map.
+    ///
+    /// This is primarily used for testing and debugging. Nodes in the
+    /// cold tier appear without their label and embedding.
+    pub fn nodes(&self) -> &HashMap<NodeId, Node> {
+        &self.nodes
+    }
+
+    /// Gets a node by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The node ID to look up
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing a reference to the node if found. Nodes in
+    /// the cold tier are hydrated from disk on first access; `None` is
+    /// also returned if that read fails (see `TierStats::hydration_failures`).
+    pub fn get_node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(&id).and_then(|node| self.hydrate(node))
+    }
+
+    /// Returns the number of nodes in the database.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the database path.
+    pub fn path(&self) -> &PathBuf {
+        &self.options.path
+    }
+
+    /// Returns the location of the WAL file, or `None` for in-memory databases.
+    pub fn wal_path(&self) -> Option<PathBuf> {
+        if self.options.in_memory {
+            None
+        } else {
+            Some(self.options.wal_file())
+        }
+    }
+
+    /// Lists all nodes in the database.
+    ///
+    /// # Returns
+    ///
+    /// A vector of references to all nodes, leaving out cold nodes whose
+    /// payload cannot be read.
+    pub fn list_nodes(&self) -> Vec<&Node> {
+        self.nodes
+            .values()
+            .filter_map(|node| self.hydrate(node))
+            .collect()
+    }
+
+    /// Adds a directed edge between two nodes.
+    ///
+    /// The edge is written to the WAL for durability and the adjacency
+    /// list is updated for fast neighbor lookups.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Source node ID
+    /// * `to` - Target node ID
+    /// * `edge_type` - Type/label of the edge
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::Node;
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"));
+    /// let mut db = BarqGraphDb::open(opts).unwrap();
+    /// db.add_edge(1, 2, "CALLS").unwrap();
+    /// ```
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, edge_type: &str) -> Result<()> {
+        self.insert_edge(Edge::new(from, to, edge_type))
+    }
+
+    /// Adds many directed edges at once.
+    ///
+    /// All edges go into a single WAL record, so bulk loads pay for one
+    /// write (and one flush with `sync_writes`) instead of one per edge.
+    /// The batch is checked against the memory budget as a whole and is
+    /// either applied completely or not at all.
+    ///
+    /// # Arguments
+    ///
+    /// * `edges` - `(from, to, edge_type)` triples, applied in order
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch would exceed the memory budget or
+    /// the WAL write fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// db.add_edges(&[(1, 2, "CALLS"), (2, 3, "CALLS"), (1, 3, "IMPORTS")])
+    ///     .unwrap();
+    /// ```
+    pub fn add_edges(&mut self, edges: &[(NodeId, NodeId, &str)]) -> Result<()> {
+        let edges = edges
+            .iter()
+            .map(|&(from, to, edge_type)| Edge::new(from, to, edge_type))
+            .collect();
+        self.insert_edges(edges)
+    }
+
+    /// Adds a directed edge with a traversal cost.
+    ///
+    /// Weights are used by `shortest_path_weighted`; edges added with
+    /// `add_edge` cost 1.0. If the same pair is linked by several
+    /// weighted edges, the smallest weight applies.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Source node ID
+    /// * `to` - Target node ID
+    /// * `edge_type` - Type/label of the edge
+    /// * `weight` - Non-negative traversal cost
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the weight is negative or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// db.add_weighted_edge(1, 2, "ROAD", 4.5).unwrap();
+    /// ```
+    pub fn add_weighted_edge(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        edge_type: &str,
+        weight: f32,
+    ) -> Result<()> {
+        check_weight(weight)?;
+        self.insert_edge(Edge::new(from, to, edge_type).with_weight(weight))
+    }
+
+    /// Adds a fully specified edge, including weight and properties.
+    ///
+    /// Properties are stored with the edge in the WAL and returned by
+    /// `edges_between` and node lookups. They are kept on the source
+    /// node, so edges whose source node doesn't exist yet only
+    /// contribute to the adjacency list.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the edge's weight is negative or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::Edge;
+    /// use serde_json::json;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let edge = Edge::new(1, 2, "CITES").with_property("confidence", json!(0.8));
+    /// db.add_edge_data(edge).unwrap();
+    /// ```
+    pub fn add_edge_data(&mut self, edge: Edge) -> Result<()> {
+        if let Some(weight) = edge.weight {
+            check_weight(weight)?;
+        }
+        self.insert_edge(edge)
+    }
+
+    /// Returns all edges from `from` to `to`, in insertion order.
+    ///
+    /// Includes parallel edges of different types along with their
+    /// weights and properties.
+    pub fn edges_between(&self, from: NodeId, to: NodeId) -> Vec<&Edge> {
+        self.nodes
+            .get(&from)
+            .map(|n| n.edges.iter().filter(|e| e.to == to).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the explicit weight of the edge from `from` to `to`, if any.
+    pub fn edge_weight(&self, from: NodeId, to: NodeId) -> Option<f32> {
+        self.graph.weight(from, to)
+    }
+
+    /// Writes an edge to the WAL and the adjacency structures.
+    fn insert_edge(&mut self, edge: Edge) -> Result<()> {
+        let started = Instant::now();
+        let (from, to) = (edge.from, edge.to);
+        let mut next = self.memory;
+        next.adjacency += 2 * memory::ADJACENCY_ENTRY_BYTES
+            + (self.new_adjacency_keys([from, to]) + self.new_reverse_keys([to]))
+                * memory::ADJACENCY_KEY_BYTES;
+        if edge.weight.is_some() {
+            next.adjacency += self.new_weight_entries([(from, to)]) * memory::EDGE_WEIGHT_BYTES;
+        }
+        if self.nodes.contains_key(&from) {
+            next.nodes += memory::edge_bytes(&edge);
+        }
+        self.check_memory_budget(&next)?;
+
+        let record = WalRecord::Edge {
+            from,
+            to,
+            edge_type: edge.edge_type.clone(),
+            weight: edge.weight,
+            properties: edge.properties.clone(),
+            timestamp: edge.timestamp,
+        };
+
+        // Append to WAL for durability
+        self.write_wal(&record, "edge", false)?;
+        self.memory = next;
+
+        if let Some(clock) = self.link_edge(edge) {
+            self.commit_clock(from, clock)?;
+        }
+
+        self.observe(Operation::AddEdge, started);
+        Ok(())
+    }
+
+    /// Writes a batch of edges to the WAL as one record, then links them.
+    fn insert_edges(&mut self, edges: Vec<Edge>) -> Result<()> {
+        if edges.is_empty() {
+            return Ok(());
+        }
+        let mut next = self.memory;
+        next.adjacency += 2 * edges.len() * memory::ADJACENCY_ENTRY_BYTES
+            + (self.new_adjacency_keys(edges.iter().flat_map(|e| [e.from, e.to]))
+                + self.new_reverse_keys(edges.iter().map(|e| e.to)))
+                * memory::ADJACENCY_KEY_BYTES
+            + self.new_weight_entries(
+                edges
+                    .iter()
+                    .filter(|e| e.weight.is_some())
+                    .map(|e| (e.from, e.to)),
+            ) * memory::EDGE_WEIGHT_BYTES;
+        next.nodes += edges
+            .iter()
+            .filter(|e| self.nodes.contains_key(&e.from))
+            .map(memory::edge_bytes)
+            .sum::<usize>();
+        self.check_memory_budget(&next)?;
+
+        let record = WalRecord::Edges { edges };
+        self.write_wal(&record, "edge batch", false)?;
+        let WalRecord::Edges { edges } = record else {
+            unreachable!()
+        };
+        self.memory = next;
+
+        // Clocks accumulate across the batch and are logged once per node
+        let mut stamped = Vec::new();
+        for edge in edges {
+            let from = edge.from;
+            if let Some(clock) = self.link_edge(edge) {
+                self.clocks.insert(from, clock);
+                stamped.push(from);
+            }
+        }
+        stamped.sort_unstable();
+        stamped.dedup();
+        for id in stamped {
+            let clock = self.clocks[&id].clone();
+            self.commit_clock(id, clock)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a logged edge to the graph index and its source node.
+    ///
+    /// Returns the source's updated CRDT clock for the caller to commit.
+    fn link_edge(&mut self, edge: Edge) -> Option<NodeClock> {
+        let (from, to) = (edge.from, edge.to);
+        self.graph
+            .add_typed_edge(from, to, &edge.edge_type, edge.weight);
+        self.degrees.add_edge(from, to);
+        if self.removed_edges.remove(&(from, to)) {
+            self.memory.adjacency = self
+                .memory
+                .adjacency
+                .saturating_sub(memory::REMOVED_EDGE_BYTES);
+        }
+
+        // Also update the node's edges if the node exists
+        let clock = self.stamp_edge(&edge, true);
+        if let Some(node) = self.nodes.get_mut(&from) {
+            node.edges.push(edge);
+        }
+        self.invalidate_hydrated(from);
+        clock
+    }
+
+    /// Removes a directed edge.
+    ///
+    /// A tombstone is written to the WAL, so the removal survives
+    /// replay. The edge is dropped from the adjacency lists and from the
+    /// source node's `edges`. If several parallel edges share the same
+    /// type, only the oldest is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Source node ID
+    /// * `to` - Target node ID
+    /// * `edge_type` - Type/label of the edge
+    ///
+    /// # Returns
+    ///
+    /// `true` if a matching edge existed and was removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the WAL write fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// db.add_edge(1, 2, "CALLS").unwrap();
+    /// assert!(db.remove_edge(1, 2, "CALLS").unwrap());
+    /// ```
+    pub fn remove_edge(&mut self, from: NodeId, to: NodeId, edge_type: &str) -> Result<bool> {
+        let started = Instant::now();
+        let exists = match self.nodes.get(&from) {
+            Some(node) => node
+                .edges
+                .iter()
+                .any(|e| e.to == to && e.edge_type == edge_type),
+            None => self.graph.has_edge(from, to, edge_type),
+        };
+        if !exists {
+            return Ok(false);
+        }
+
+        let record = WalRecord::EdgeRemoved {
+            from,
+            to,
+            edge_type: edge_type.to_string(),
+        };
+        self.write_wal(&record, "edge removal", false)?;
+
+        let weights_before = self.graph.weights().get(&from).map_or(0, |w| w.len());
+        let targets_before = self.graph.target_count();
+        let removed = unlink_edge(&mut self.nodes, &mut self.graph, from, to, edge_type);
+        let weights_after = self.graph.weights().get(&from).map_or(0, |w| w.len());
+        let reverse_keys_removed = targets_before - self.graph.target_count();
+        if removed.is_some() {
+            self.degrees.remove_edge(from, to);
+            if self.removed_edges.insert((from, to)) {
+                self.memory.adjacency += memory::REMOVED_EDGE_BYTES;
+            }
+        }
+        self.invalidate_hydrated(from);
+
+        self.memory.adjacency = self.memory.adjacency.saturating_sub(
+            2 * memory::ADJACENCY_ENTRY_BYTES
+                + reverse_keys_removed * memory::ADJACENCY_KEY_BYTES
+                + (weights_before - weights_after) * memory::EDGE_WEIGHT_BYTES,
+        );
+        if let Some(Some(edge)) = &removed {
+            self.memory.nodes = self.memory.nodes.saturating_sub(memory::edge_bytes(edge));
+            if let Some(clock) = self.stamp_edge(edge, false) {
+                self.commit_clock(from, clock)?;
+            }
+        }
+
+        self.observe(Operation::RemoveEdge, started);
+        Ok(true)
+    }
+
+    /// Returns the neighbors (outgoing edges) of a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Node ID to look up
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing a slice of neighbor IDs, or `None` if
+    /// the node doesn't exist in the adjacency list.
+    pub fn neighbors(&self, id: NodeId) -> Option<&[NodeId]> {
+        self.graph.neighbors(id)
+    }
+
+    /// Returns the graph index holding every edge, with types and weights.
+    ///
+    /// Traversal algorithms that only need the graph structure can run
+    /// directly on it.
+    pub fn graph(&self) -> &GraphIndex {
+        &self.graph
+    }
+
+    /// Gets the sources of all edges pointing at a node.
+    ///
+    /// Served from the reverse adjacency list, so this is O(1) rather
+    /// than a scan over all edges.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Target node ID
+    ///
+    /// # Returns
+    ///
+    /// A slice of source node IDs (one entry per edge), or `None` if no
+    /// edge points at the node.
+    pub fn incoming_neighbors(&self, id: NodeId) -> Option<&[NodeId]> {
+        self.graph.incoming(id)
+    }
+
+    /// Gets the edges pointing at a node, with their types, weights and
+    /// properties, grouped by source node in order of first appearance.
+    ///
+    /// Edges whose source has no node record carry no data and are left
+    /// out; `incoming_neighbors` still lists their sources.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Target node ID
+    pub fn incoming_edges(&self, id: NodeId) -> Vec<&Edge> {
+        let mut sources = self.incoming_neighbors(id).unwrap_or_default().to_vec();
+        let mut seen = HashSet::new();
+        sources.retain(|source| seen.insert(*source));
+        sources
+            .into_iter()
+            .flat_map(|source| self.edges_between(source, id))
+            .collect()
+    }
+
+    /// Returns the neighbors of a node in the configured `NeighborOrder`,
+    /// skipping what `mask` removes.
+    pub(crate) fn ordered_neighbors(
+        &self,
+        id: NodeId,
+        direction: Direction,
+        mask: &Counterfactual,
+    ) -> Vec<NodeId> {
+        let mut neighbors: Vec<NodeId> = self.masked_neighbors(id, direction, mask).collect();
+        match self.options.neighbor_order {
+            NeighborOrder::Insertion => {}
+            NeighborOrder::NodeId => neighbors.sort_unstable(),
+            NeighborOrder::Weight => {
+                let cost = |n: NodeId| self.step_cost(id, n, direction);
+                neighbors.sort_by(|&a, &b| cost(a).total_cmp(&cost(b)).then(a.cmp(&b)));
+            }
+        }
+        neighbors
+    }
+
+    /// Returns the cost of stepping from `id` to its neighbor `n`.
+    ///
+    /// Uses the weight of the edge followed in `direction`, the cheaper
+    /// one if both directions connect them, and 1.0 for unweighted edges.
+    pub(crate) fn step_cost(&self, id: NodeId, n: NodeId, direction: Direction) -> f32 {
+        let outgoing = direction
+            .follows_outgoing()
+            .then(|| self.edge_weight(id, n))
+            .flatten();
+        let incoming = direction
+            .follows_incoming()
+            .then(|| self.edge_weight(n, id))
+            .flatten();
+        match (outgoing, incoming) {
+            (Some(a), Some(b)) => a.min(b),
+            (a, b) => a.or(b).unwrap_or(paths::DEFAULT_EDGE_WEIGHT),
+        }
+    }
+
+    /// Iterates the neighbors of a node, skipping what `mask` removes.
+    fn masked_neighbors<'a>(
+        &'a self,
+        id: NodeId,
+        direction: Direction,
+        mask: &'a Counterfactual,
+    ) -> impl Iterator<Item = NodeId> + 'a {
+        let outgoing = self
+            .graph
+            .neighbors(id)
+            .filter(|_| direction.follows_outgoing())
+            .into_iter()
+            .flatten()
+            .filter(move |&&to| !mask.removes_edge(id, to));
+        let incoming = self
+            .graph
+            .incoming(id)
+            .filter(|_| direction.follows_incoming())
+            .into_iter()
+            .flatten()
+            .filter(move |&&from| !mask.removes_edge(from, id));
+        outgoing
+            .chain(incoming)
+            .copied()
+            .filter(move |&n| !mask.removes_node(n))
+    }
+
+    /// Performs BFS traversal from a start node up to a maximum depth.
+    ///
+    /// Returns all nodes reachable within `max_hops` edges from the start.
+    /// The start node is included in the result if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Starting node ID for BFS
+    /// * `max_hops` - Maximum number of edges to traverse (depth limit)
+    ///
+    /// # Returns
+    ///
+    /// A vector of node IDs visited during BFS, in order of discovery.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"));
+    /// let db = BarqGraphDb::open(opts).unwrap();
+    /// let reachable = db.bfs_hops(1, 2); // All nodes within 2 hops of node 1
+    /// ```
+    pub fn bfs_hops(&self, start: NodeId, max_hops: usize) -> Vec<NodeId> {
+        self.bfs_hops_directed(start, max_hops, Direction::Outgoing)
+    }
+
+    /// Performs BFS traversal and reports the depth of each node found.
+    ///
+    /// Like `bfs_hops`, but each node comes with the number of edges on
+    /// the shortest path from `start`, so callers don't have to
+    /// re-derive distances.
+    ///
+    /// # Returns
+    ///
+    /// `(node ID, depth)` pairs in order of discovery, starting with
+    /// `(start, 0)` if the start node exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// for (id, depth) in db.bfs_with_depths(1, 3) {
+    ///     println!("node {} is {} hops away", id, depth);
+    /// }
+    /// ```
+    pub fn bfs_with_depths(&self, start: NodeId, max_hops: usize) -> Vec<(NodeId, usize)> {
+        self.bfs_tree(
+            &[start],
+            max_hops,
+            Direction::Outgoing,
+            &Counterfactual::default(),
+            None,
+            None,
+        )
+        .order
+    }
+
+    /// Performs BFS traversal backwards along incoming edges.
+    ///
+    /// Returns all nodes that can reach `start` within `max_hops` edges,
+    /// in order of discovery. Runs in O(edges visited) using the reverse
+    /// adjacency list.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Node ID to walk back from
+    /// * `max_hops` - Maximum number of edges to traverse (depth limit)
+    pub fn reverse_bfs_hops(&self, start: NodeId, max_hops: usize) -> Vec<NodeId> {
+        self.bfs_hops_directed(start, max_hops, Direction::Incoming)
+    }
+
+    /// Performs BFS traversal following edges in the given direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Starting node ID for BFS
+    /// * `max_hops` - Maximum number of edges to traverse (depth limit)
+    /// * `direction` - Edge direction to follow
+    pub fn bfs_hops_directed(
+        &self,
+        start: NodeId,
+        max_hops: usize,
+        direction: Direction,
+    ) -> Vec<NodeId> {
+        self.bfs_masked(start, max_hops, direction, &Counterfactual::default())
+    }
+
+    /// Performs BFS traversal over the graph as modified by `mask`.
+    pub(crate) fn bfs_masked(
+        &self,
+        start: NodeId,
+        max_hops: usize,
+        direction: Direction,
+        mask: &Counterfactual,
+    ) -> Vec<NodeId> {
+        self.bfs_tree(&[start], max_hops, direction, mask, None, None)
+            .order
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Runs the BFS shared by `bfs_hops` and hybrid queries.
+    ///
+    /// Explores from all `starts` at once, recording the depth and BFS
+    /// parent of every node discovered within `max_hops` of the closest
+    /// start, and skipping what `mask` removes. Traversal stops early
+    /// once `deadline` passes, and the tree is marked partial, or once
+    /// `max_nodes` nodes are discovered, and the tree is marked truncated.
+    pub(crate) fn bfs_tree(
+        &self,
+        starts: &[NodeId],
+        max_hops: usize,
+        direction: Direction,
+        mask: &Counterfactual,
+        deadline: Option<Instant>,
+        max_nodes: Option<usize>,
+    ) -> BfsTree {
+        use std::collections::VecDeque;
+
+        let mut tree = BfsTree::default();
+        // Queue entries: (node_id, current_depth)
+        let mut queue = VecDeque::new();
+        for &start in starts {
+            // Check if start exists in nodes or adjacency
+            if (!self.nodes.contains_key(&start) && !self.graph.contains_node(start))
+                || mask.removes_node(start)
+                || tree.parents.contains_key(&start)
+            {
+                continue;
+            }
+            if max_nodes.is_some_and(|max| tree.order.len() >= max) {
+                tree.truncated = true;
+                break;
+            }
+            queue.push_back((start, 0));
+            tree.parents.insert(start, None);
+            tree.order.push((start, 0));
+        }
+
+        while let Some((current, depth)) = queue.pop_front() {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                tree.partial = true;
+                break;
+            }
+            if tree.truncated {
+                break;
+            }
+
+            // Stop exploring further if we've reached max depth
+            if depth >= max_hops {
+                continue;
+            }
+
+            // Explore neighbors
+            for neighbor in self.ordered_neighbors(current, direction, mask) {
+                if let std::collections::hash_map::Entry::Vacant(slot) =
+                    tree.parents.entry(neighbor)
+                {
+                    if max_nodes.is_some_and(|max| tree.order.len() >= max) {
+                        tree.truncated = true;
+                        break;
+                    }
+                    slot.insert(Some(current));
+                    tree.order.push((neighbor, depth + 1));
+                    queue.push_back((neighbor, depth + 1));
+                }
+            }
+        }
+
+        tree
+    }
+
+    /// Returns the number of edges in the graph.
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Sets the vector embedding for a node.
+    ///
+    /// The embedding is written to the WAL for durability and added
+    /// to the vector index for similarity search.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Node ID to set embedding for
+    /// * `embedding` - Vector embedding to store
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"));
+    /// let mut db = BarqGraphDb::open(opts).unwrap();
+    /// db.set_embedding(1, vec![0.1, 0.2, 0.3]).unwrap();
+    /// ```
+    pub fn set_embedding(&mut self, id: NodeId, embedding: Vec<f32>) -> Result<()> {
+        self.store_embedding(id, embedding, None)
+    }
+
+    /// Writes an embedding tagged with the model that produced it, if known.
+    fn store_embedding(
+        &mut self,
+        id: NodeId,
+        mut embedding: Vec<f32>,
+        model: Option<String>,
+    ) -> Result<()> {
+        self.prepare_embedding(&mut embedding);
+        self.promote(id)?;
+        let mut next = self.memory;
+        next.vectors = next.vectors + self.vector_bytes(embedding.len())
+            - self
+                .indexed_dimension(id)
+                .map_or(0, |d| self.vector_bytes(d));
+        if let Some(node) = self.nodes.get(&id) {
+            next.nodes = next.nodes + embedding.len() * std::mem::size_of::<f32>()
+                - node.embedding.len() * std::mem::size_of::<f32>();
+        }
+        self.check_memory_budget(&next)?;
+
+        let record = WalRecord::Embedding {
+            id,
+            vec: embedding.clone(),
+            model: model.clone(),
+        };
+
+        // Append to WAL for durability
+        self.write_wal(&record, "embedding", false)?;
+        self.memory = next;
+
+        // Update vector index
+        // Update vector index
+        if let Some(queue) = &self.batch_queue {
+            let mut dummy_node = Node::new(id, String::new());
+            dummy_node.embedding = embedding.clone();
+            queue.push(dummy_node);
+        } else {
+            self.vector_index.insert(id, &embedding);
+        }
+
+        // Update node if it exists
+        if let Some(node) = self.nodes.get_mut(&id) {
+            if self.options.tag_centroids {
+                self.centroids.remove(node);
+                node.embedding = embedding;
+                self.centroids.add(node);
+            } else {
+                node.embedding = embedding;
+            }
+            self.orphan_embeddings.remove(&id);
+        } else {
+            self.orphan_embeddings.insert(id, embedding);
+        }
+        self.models.set(id, model);
+        if let Some(clock) = self.stamp_embedding(id) {
+            self.commit_clock(id, clock)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the embedding of a node from the store and the vector index.
+    ///
+    /// The node itself, its edges and its other fields are kept; it just
+    /// stops appearing in similarity searches. Embeddings still waiting
+    /// in the async indexing queue are indexed first, so a queued vector
+    /// can't resurface afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Node ID whose embedding to remove
+    ///
+    /// # Returns
+    ///
+    /// `true` if the node had an embedding.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// db.set_embedding(1, vec![0.1, 0.2, 0.3]).unwrap();
+    /// assert!(db.remove_embedding(1).unwrap());
+    /// assert!(db.knn_search(&[0.1, 0.2, 0.3], 1).is_empty());
+    /// ```
+    pub fn remove_embedding(&mut self, id: NodeId) -> Result<bool> {
+        self.promote(id)?;
+        self.drain_index_queue();
+        let Some(dimension) = self.indexed_dimension(id) else {
+            return Ok(false);
+        };
+
+        let record = WalRecord::EmbeddingRemoved { id };
+        self.write_wal(&record, "embedding removal", false)?;
+
+        self.vector_index.remove(id);
+        self.memory.vectors = self
+            .memory
+            .vectors
+            .saturating_sub(self.vector_bytes(dimension));
+        self.orphan_embeddings.remove(&id);
+        self.models.set(id, None);
+        if let Some(node) = self.nodes.get_mut(&id) {
+            let bytes = node.embedding.len() * std::mem::size_of::<f32>();
+            self.memory.nodes = self.memory.nodes.saturating_sub(bytes);
+            if self.options.tag_centroids {
+                self.centroids.remove(node);
+            }
+            node.embedding.clear();
+        }
+        if let Some(clock) = self.stamp_embedding(id) {
+            self.commit_clock(id, clock)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Compacts the vector index, dropping entries left by updates and removals.
+    ///
+    /// The HNSW index keeps a replaced or removed vector's point in its
+    /// graph and rebuilds itself once dead points outnumber live ones;
+    /// this rebuilds it right away, e.g. after a bulk re-embedding. Other
+    /// index types delete in place and report 0.
+    ///
+    /// # Returns
+    ///
+    /// The number of stale entries dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// println!("dropped {} stale vectors", db.compact_vector_index());
+    /// ```
+    pub fn compact_vector_index(&self) -> usize {
+        self.drain_index_queue();
+        self.vector_index.compact()
+    }
+
+    /// Indexes embeddings still waiting in the async indexing queue.
+    pub(super) fn drain_index_queue(&self) {
+        if let Some(queue) = &self.batch_queue {
+            for node in queue.flush() {
+                if !node.embedding.is_empty() {
+                    self.vector_index.insert(node.id, &node.embedding);
+                }
+            }
+        }
+    }
+
+    /// Finds the k nearest neighbors to a query vector.
+    ///
+    /// Distances are computed with the database's metric (see `metric`).
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query vector for similarity search
+    /// * `k` - Number of nearest neighbors to return
+    ///
+    /// # Returns
+    ///
+    /// A vector of (NodeId, distance) pairs sorted by distance ascending.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"));
+    /// let db = BarqGraphDb::open(opts).unwrap();
+    /// let results = db.knn_search(&[0.1, 0.2, 0.3], 5);
+    /// ```
+    pub fn knn_search(&self, query: &[f32], k: usize) -> Vec<(NodeId, f32)> {
+        let started = Instant::now();
+        let query = self.prepare_query(query);
+        let results = self.vector_index.knn(&query, k);
+        self.observe(Operation::Knn, started);
+        results
+    }
+
+    /// Finds up to k nearest neighbors no farther than `max_distance`.
+    ///
+    /// Unlike `knn_search`, which always returns k matches however poor,
+    /// this returns only those within the distance bar, possibly none.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query vector for similarity search
+    /// * `k` - Maximum number of nearest neighbors to return
+    /// * `max_distance` - Largest distance a match may have
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let close = db.knn_search_within(&[0.1, 0.2, 0.3], 5, 0.5);
+    /// ```
+    pub fn knn_search_within(
+        &self,
+        query: &[f32],
+        k: usize,
+        max_distance: f32,
+    ) -> Vec<(NodeId, f32)> {
+        let mut results = self.knn_search(query, k);
+        results.retain(|&(_, distance)| distance <= max_distance);
+        results
+    }
+
+    /// Finds the k nearest neighbors with a per-query HNSW search width.
+    ///
+    /// Overrides `HnswParams::ef_search` for this query only: raise it
+    /// for recall-critical lookups, lower it for latency-critical ones.
+    /// A linear index always searches exhaustively and ignores it.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query vector for similarity search
+    /// * `k` - Number of nearest neighbors to return
+    /// * `ef_search` - Candidate list size explored by the search
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let precise = db.knn_search_with_ef(&[0.1, 0.2, 0.3], 5, 800);
+    /// ```
+    pub fn knn_search_with_ef(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef_search: usize,
+    ) -> Vec<(NodeId, f32)> {
+        let started = Instant::now();
+        let query = self.prepare_query(query);
+        let results = self.vector_index.knn_with_ef(&query, k, ef_search);
+        self.observe(Operation::Knn, started);
+        results
+    }
+
+    /// Finds the k nearest neighbors together with their node data.
+    ///
+    /// Saves clients a `get_node` call per result. Embeddings set for IDs
+    /// that have no node are left out, so fewer than `k` matches may be
+    /// returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// for hit in db.knn_search_nodes(&[0.1, 0.2, 0.3], 5) {
+    ///     println!("{} {:?} at {}", hit.label, hit.rule_tags, hit.distance);
+    /// }
+    /// ```
+    pub fn knn_search_nodes(&self, query: &[f32], k: usize) -> Vec<NodeMatch> {
+        self.join_nodes(self.knn_search(query, k))
+    }
+
+    /// Attaches node data to (NodeId, distance) results, dropping IDs
+    /// without a node.
+    pub(crate) fn join_nodes(&self, results: Vec<(NodeId, f32)>) -> Vec<NodeMatch> {
+        results
+            .into_iter()
+            .filter_map(|(id, distance)| {
+                let node = self.get_node(id)?;
+                Some(NodeMatch {
+                    id,
+                    distance,
+                    label: node.label.clone(),
+                    rule_tags: node.rule_tags.clone(),
+                    timestamp: node.timestamp,
+                    agent_id: node.agent_id,
+                })
+            })
+            .collect()
+    }
+
+    /// Finds `k` nearest neighbors while steering away from `avoid` vectors.
+    ///
+    /// Fetches `avoid::CANDIDATES_PER_RESULT * k` neighbors and reranks
+    /// them by their distance to the query minus `weight` times their
+    /// distance to the closest avoided vector (see `crate::vector::avoid`),
+    /// so memories on already-covered subjects give way to the rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query vector for similarity search
+    /// * `avoid` - Vectors of subjects to steer away from
+    /// * `k` - Number of results to return
+    /// * `weight` - Strength of the steering; 0 is plain kNN
+    ///
+    /// # Returns
+    ///
+    /// (NodeId, distance to query) pairs, best adjusted score first.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let covered = vec![vec![0.3, 0.1, 0.0]];
+    /// let fresh = db.knn_search_avoiding(&[0.1, 0.2, 0.3], &covered, 5, 0.5);
+    /// ```
+    pub fn knn_search_avoiding(
+        &self,
+        query: &[f32],
+        avoid: &[Vec<f32>],
+        k: usize,
+        weight: f32,
+    ) -> Vec<(NodeId, f32)> {
+        let started = Instant::now();
+        let query = self.prepare_query(query);
+        let avoid: Vec<Cow<[f32]>> = avoid.iter().map(|a| self.prepare_query(a)).collect();
+        let avoid: Vec<&[f32]> = avoid.iter().map(|a| a.as_ref()).collect();
+        let candidates = self.vector_index.knn(
+            &query,
+            k.saturating_mul(vector::avoid::CANDIDATES_PER_RESULT),
+        );
+        let results =
+            vector::rerank_avoiding(&candidates, &avoid, k, weight, self.metric(), |id| {
+                self.indexed_embedding(id)
+            });
+        self.observe(Operation::Knn, started);
+        results
+    }
+
+    /// Finds `k` nearest neighbors diversified by maximal marginal relevance.
+    ///
+    /// Fetches `mmr::CANDIDATES_PER_RESULT * k` neighbors and greedily
+    /// picks those that are close to the query but far from the ones
+    /// already picked (see `crate::vector::mmr`), so near-duplicate
+    /// memories don't crowd out the rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query vector for similarity search
+    /// * `k` - Number of results to return
+    /// * `lambda` - Relevance weight in `[0, 1]`: 1 is plain kNN, lower
+    ///   values favor diversity
+    ///
+    /// # Returns
+    ///
+    /// (NodeId, distance) pairs in selection order.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let diverse = db.knn_search_mmr(&[0.1, 0.2, 0.3], 5, 0.7);
+    /// ```
+    pub fn knn_search_mmr(&self, query: &[f32], k: usize, lambda: f32) -> Vec<(NodeId, f32)> {
+        let started = Instant::now();
+        let query = self.prepare_query(query);
+        let candidates = self
+            .vector_index
+            .knn(&query, k.saturating_mul(vector::mmr::CANDIDATES_PER_RESULT));
+        let results = vector::mmr_select(&candidates, k, lambda, self.metric(), |id| {
+            self.indexed_embedding(id)
+        });
+        self.observe(Operation::Knn, started);
+        results
+    }
+
+    /// Finds the rule tags whose centroids are closest to a query.
+    ///
+    /// A cheap coarse routing step: comparing against one centroid per
+    /// tag tells which topic cluster a query belongs to before running a
+    /// fine-grained search. Requires `DbOptions::tag_centroids`; otherwise
+    /// no centroids are maintained and the result is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query vector
+    /// * `n` - Number of tags to return
+    ///
+    /// # Returns
+    ///
+    /// `(tag, L2 distance)` pairs sorted by distance ascending.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db")).with_tag_centroids(true);
+    /// let db = BarqGraphDb::open(opts).unwrap();
+    /// if let Some((topic, _)) = db.route_query(&[0.1, 0.2], 1).first() {
+    ///     println!("query belongs to {}", topic);
+    /// }
+    /// ```
+    pub fn route_query(&self, query: &[f32], n: usize) -> Vec<(String, f32)> {
+        self.centroids.nearest(&self.prepare_query(query), n)
+    }
+
+    /// Returns the centroid of the nodes carrying a rule tag.
+    pub fn tag_centroid(&self, tag: &str) -> Option<TagCentroid> {
+        self.centroids.get(tag)
+    }
+
+    /// Returns all maintained tag centroids, ordered by tag.
+    pub fn tag_centroids(&self) -> Vec<TagCentroid> {
+        self.centroids.all()
+    }
+
+    /// Returns the number of vectors in the index.
+    pub fn vector_count(&self) -> usize {
+        self.vector_index.len()
+    }
+
+    /// Returns element, stale-entry and memory statistics of the vector index.
+    pub fn vector_index_stats(&self) -> IndexStats {
+        self.vector_index.stats()
+    }
+
+    /// Gets the embedding for a node if it exists.
+    pub fn get_embedding(&self, id: NodeId) -> Option<&[f32]> {
+        self.get_node(id).and_then(|n| {
+            if n.embedding.is_empty() {
+                None
+            } else {
+                Some(n.embedding.as_slice())
+            }
+        })
+    }
+
+    /// Performs a hybrid query combining vector similarity and graph distance.
+    ///
+    /// Starting from a given node, explores the graph via BFS up to max_hops,
+    /// computes vector similarity for each visited node, and returns the top k
+    /// results ranked by hybrid score. `HybridQuery` builds the same query
+    /// with named options instead of positional arguments.
+    ///
+    /// The hybrid score combines:
+    /// - Vector similarity: `alpha * (1 - normalized_vector_distance)`
+    /// - Graph proximity: `beta * (1 / (1 + graph_distance))`
+    ///
+    /// # Arguments
+    ///
+    /// * `query_embedding` - Query vector for similarity comparison
+    /// * `start` - Starting node ID for BFS traversal
+    /// * `max_hops` - Maximum BFS depth to explore
+    /// * `k` - Number of top results to return
+    /// * `params` - Hybrid scoring parameters (alpha, beta weights)
+    ///
+    /// # Returns
+    ///
+    /// A vector of `HybridResult` sorted by score descending.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::hybrid::HybridParams;
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"));
+    /// let db = BarqGraphDb::open(opts).unwrap();
+    /// let params = HybridParams::new(0.7, 0.3);
+    /// let results = db.hybrid_query(&[0.1, 0.2], 1, 3, 5, params);
+    /// ```
+    pub fn hybrid_query(
+        &self,
+        query_embedding: &[f32],
+        start: NodeId,
+        max_hops: usize,
+        k: usize,
+        params: crate::hybrid::HybridParams,
+    ) -> Vec<crate::hybrid::HybridResult> {
+        self.hybrid_query_outcome(query_embedding, start, max_hops, k, params)
+            .results
+    }
+
+    /// Executes a hybrid query and reports whether it was cut short.
+    ///
+    /// Behaves like `hybrid_query`, but returns a `HybridOutcome` whose
+    /// `partial` and `truncated` flags tell whether
+    /// `HybridParams::timeout` or `HybridParams::max_candidates` stopped
+    /// the query before it explored all of `max_hops`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::hybrid::HybridParams;
+    /// use std::path::PathBuf;
+    /// use std::time::Duration;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let params = HybridParams::default()
+    ///     .with_max_candidates(10_000)
+    ///     .with_timeout(Duration::from_millis(50));
+    /// let outcome = db.hybrid_query_outcome(&[0.1, 0.2], 1, 4, 5, params);
+    /// if outcome.truncated || outcome.partial {
+    ///     println!("explored part of the neighborhood");
+    /// }
+    /// ```
+    pub fn hybrid_query_outcome(
+        &self,
+        query_embedding: &[f32],
+        start: NodeId,
+        max_hops: usize,
+        k: usize,
+        params: crate::hybrid::HybridParams,
+    ) -> crate::hybrid::HybridOutcome {
+        let started = Instant::now();
+        let outcome = self.run_hybrid_query(
+            query_embedding,
+            &[start],
+            max_hops,
+            k,
+            params,
+            None,
+            &Counterfactual::default(),
+        );
+        self.observe(Operation::Hybrid, started);
+        outcome
+    }
+
+    /// Executes a hybrid query within a latency budget.
+    ///
+    /// Behaves like `hybrid_query`, but stops traversal and scoring once
+    /// `budget` has elapsed and returns the best results found so far.
+    /// Nodes are scored in BFS discovery order, so a truncated run favors
+    /// candidates closest to the start node. The outcome is flagged as
+    /// partial when the budget expired before the query completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_embedding` - Query vector for similarity comparison
+    /// * `start` - Starting node ID for BFS traversal
+    /// * `max_hops` - Maximum BFS depth to explore
+    /// * `k` - Number of top results to return
+    /// * `params` - Hybrid scoring parameters (alpha, beta weights)
+    /// * `budget` - Maximum time to spend on the query
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::hybrid::HybridParams;
+    /// use std::path::PathBuf;
+    /// use std::time::Duration;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"));
+    /// let db = BarqGraphDb::open(opts).unwrap();
+    /// let outcome = db.hybrid_query_with_budget(
+    ///     &[0.1, 0.2], 1, 4, 5, HybridParams::default(), Duration::from_millis(20),
+    /// );
+    /// if outcome.partial {
+    ///     println!("deadline hit, {} results so far", outcome.results.len());
+    /// }
+    /// ```
+    pub fn hybrid_query_with_budget(
+        &self,
+        query_embedding: &[f32],
+        start: NodeId,
+        max_hops: usize,
+        k: usize,
+        params: crate::hybrid::HybridParams,
+        budget: Duration,
+    ) -> crate::hybrid::HybridOutcome {
+        let started = Instant::now();
+        let outcome = self.run_hybrid_query(
+            query_embedding,
+            &[start],
+            max_hops,
+            k,
+            params,
+            Some(started + budget),
+            &Counterfactual::default(),
+        );
+        self.observe(Operation::Hybrid, started);
+        outcome
+    }
+
+    /// Builds a landmark index for approximate graph distances.
+    ///
+    /// The index is a snapshot of the current graph; rebuild it after
+    /// significant structural changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Number of landmark nodes (highest-degree nodes are chosen)
+    pub fn build_landmark_index(&self, count: usize) -> LandmarkIndex {
+        LandmarkIndex::build(self.graph.adjacency(), count)
+    }
+
+    /// Performs a hybrid query using landmark-estimated graph distances.
+    ///
+    /// Instead of a live BFS from `start`, candidates come from the vector
+    /// index and their graph distance is estimated in constant time via
+    /// `landmarks`. Candidates whose estimated distance exceeds `max_hops`
+    /// (or that are unreachable) are dropped. Because no traversal is
+    /// performed, result paths are empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_embedding` - Query vector for similarity comparison
+    /// * `start` - Node from which graph proximity is measured
+    /// * `max_hops` - Maximum estimated hop distance to keep
+    /// * `k` - Number of top results to return
+    /// * `params` - Hybrid scoring parameters (alpha, beta weights)
+    /// * `landmarks` - Index from `build_landmark_index`
+    /// * `candidates` - Number of vector matches to score
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::hybrid::HybridParams;
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let landmarks = db.build_landmark_index(16);
+    /// let results =
+    ///     db.hybrid_query_landmarks(&[0.1, 0.2], 1, 3, 5, HybridParams::default(), &landmarks, 200);
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn hybrid_query_landmarks(
+        &self,
+        query_embedding: &[f32],
+        start: NodeId,
+        max_hops: usize,
+        k: usize,
+        params: crate::hybrid::HybridParams,
+        landmarks: &LandmarkIndex,
+        candidates: usize,
+    ) -> Vec<crate::hybrid::HybridResult> {
+        use crate::hybrid::{score_results, top_k, HybridResult};
+
+        let started = Instant::now();
+        let query_embedding = &*self.prepare_query(query_embedding);
+        let params = self.prepare_query_vectors(params, query_embedding.len());
+        let mut results: Vec<HybridResult> = self
+            .vector_index
+            .knn(query_embedding, candidates.max(k))
+            .into_iter()
+            .filter_map(|(node_id, _)| {
+                let graph_dist = landmarks
+                    .estimate_distance(start, node_id)
+                    .filter(|d| *d <= max_hops)?;
+                let node = self.get_node(node_id)?;
+                if node.embedding.len() != query_embedding.len() || !params.filter.matches(node) {
+                    return None;
+                }
+                let vec_dist = params
+                    .normalization
+                    .distance(query_embedding, &node.embedding);
+                let mut result = HybridResult::new(node_id, 0.0, vec_dist, graph_dist, Vec::new());
+                result.query_distances = params.query_distances(&node.embedding);
+                Some(result)
+            })
+            .collect();
+        score_results(&mut results, &params, |id| self.get_node(id));
+        results.retain(|r| params.accepts_score(r.score));
+
+        let mut results = top_k(results, k);
+        self.attach_result_details(&mut results, &params);
+        self.observe(Operation::Hybrid, started);
+        results
+    }
+
+    /// Performs a hybrid query seeded by the query's nearest neighbors.
+    ///
+    /// Behaves like `hybrid_query`, but instead of a caller-supplied start
+    /// node, the graph is explored from the `seeds` nodes closest to the
+    /// query in the vector index. A result's graph distance and path are
+    /// measured from the closest seed, and the seeds themselves are
+    /// candidates at distance 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_embedding` - Query vector for seeding and similarity
+    /// * `seeds` - Number of vector matches to expand from
+    /// * `max_hops` - Maximum BFS depth to explore from each seed
+    /// * `k` - Number of top results to return
+    /// * `params` - Hybrid scoring parameters (alpha, beta weights)
+    ///
+    /// # Returns
+    ///
+    /// A vector of `HybridResult` sorted by score descending.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::hybrid::HybridParams;
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let results = db.hybrid_query_seedless(&[0.1, 0.2], 5, 2, 10, HybridParams::default());
+    /// ```
+    pub fn hybrid_query_seedless(
+        &self,
+        query_embedding: &[f32],
+        seeds: usize,
+        max_hops: usize,
+        k: usize,
+        params: crate::hybrid::HybridParams,
+    ) -> Vec<crate::hybrid::HybridResult> {
+        self.hybrid_query_seedless_outcome(query_embedding, seeds, max_hops, k, params)
+            .results
+    }
+
+    /// Executes a seedless hybrid query and reports whether it was cut
+    /// short, like `hybrid_query_outcome` does for `hybrid_query`.
+    pub fn hybrid_query_seedless_outcome(
+        &self,
+        query_embedding: &[f32],
+        seeds: usize,
+        max_hops: usize,
+        k: usize,
+        params: crate::hybrid::HybridParams,
+    ) -> crate::hybrid::HybridOutcome {
+        let started = Instant::now();
+        let starts: Vec<NodeId> = self
+            .vector_index
+            .knn(&self.prepare_query(query_embedding), seeds)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        let outcome = self.run_hybrid_query(
+            query_embedding,
+            &starts,
+            max_hops,
+            k,
+            params,
+            None,
+            &Counterfactual::default(),
+        );
+        self.observe(Operation::Hybrid, started);
+        outcome
+    }
+
+    /// Executes a hybrid query without telemetry.
+    ///
+    /// Explores from every node in `starts`. When a deadline or
+    /// `params.timeout` is given, traversal and scoring stop as soon as
+    /// the earlier of them passes and the outcome is marked partial.
+    /// Nodes and edges removed by `mask` are neither traversed nor
+    /// returned.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn run_hybrid_query(
+        &self,
+        query_embedding: &[f32],
+        starts: &[NodeId],
+        max_hops: usize,
+        k: usize,
+        params: crate::hybrid::HybridParams,
+        deadline: Option<Instant>,
+        mask: &Counterfactual,
+    ) -> crate::hybrid::HybridOutcome {
+        use crate::hybrid::{
+            score_results, top_k, GraphProximity, HybridOutcome, HybridResult, PARALLEL_SCORE_MIN,
+        };
+        use rayon::prelude::*;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let timed_out = AtomicBool::new(false);
+        let deadline = params
+            .timeout
+            .map(|timeout| Instant::now() + timeout)
+            .into_iter()
+            .chain(deadline)
+            .min();
+        let query_embedding = &*self.prepare_query(query_embedding);
+        let params = self.prepare_query_vectors(params, query_embedding.len());
+
+        // Traverse along the requested direction, by BFS or beam search
+        let tree = match params.beam_width {
+            Some(_) => self.beam_tree(starts, max_hops, query_embedding, &params, mask, deadline),
+            None => self.bfs_tree(
+                starts,
+                max_hops,
+                params.direction,
+                mask,
+                deadline,
+                params.max_candidates,
+            ),
+        };
+
+        // Compute vector distances for all visited nodes with embeddings;
+        // scores follow once all are known
+        let candidate = |&(node_id, graph_dist): &(NodeId, usize)| {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                timed_out.store(true, Ordering::Relaxed);
+                return None;
+            }
+
+            // Get embedding for this node from authoritative storage
+            let node = self.get_node(node_id)?;
+
+            // Skip missing embeddings, mismatched dimensions and filtered nodes
+            if node.embedding.is_empty()
+                || node.embedding.len() != query_embedding.len()
+                || !params.filter.matches(node)
+            {
+                return None;
+            }
+
+            let vec_dist = params
+                .normalization
+                .distance(query_embedding, &node.embedding);
+            let mut result =
+                HybridResult::new(node_id, 0.0, vec_dist, graph_dist, tree.path_to(node_id));
+            result.query_distances = params.query_distances(&node.embedding);
+            Some(result)
+        };
+        let mut results: Vec<HybridResult> = if tree.order.len() < PARALLEL_SCORE_MIN {
+            tree.order.iter().filter_map(candidate).collect()
+        } else {
+            tree.order.par_iter().filter_map(candidate).collect()
+        };
+        let partial = tree.partial || timed_out.into_inner();
+        if params.weighted_edges {
+            let weighted = self.weighted_tree(&tree, params.direction, mask);
+            for result in &mut results {
+                result.path = weighted.path_to(result.id);
+                result.graph_distance = result.path.len() - 1;
+                result.graph_cost = weighted.cost(result.id);
+            }
+        }
+        if let GraphProximity::PersonalizedPageRank {
+            damping,
+            iterations,
+        } = params.graph_proximity
+        {
+            let ranks = self.personalized_pagerank(
+                &tree,
+                params.direction,
+                mask,
+                damping,
+                iterations,
+                params.weighted_edges,
+            );
+            let top = ranks.values().copied().fold(0.0, f64::max);
+            for result in &mut results {
+                // Graph similarity 1 / (1 + cost) becomes the relative rank
+                let rank = ranks.get(&result.id).map_or(0.0, |r| r / top);
+                result.graph_cost = if rank > 0.0 {
+                    (1.0 / rank - 1.0) as f32
+                } else {
+                    f32::INFINITY
+                };
+            }
+        }
+        score_results(&mut results, &params, |id| self.get_node(id));
+        results.retain(|r| params.accepts_score(r.score));
+
+        let mut results = top_k(results, k);
+        self.attach_result_details(&mut results, &params);
+        HybridOutcome {
+            results,
+            partial,
+            truncated: tree.truncated,
+        }
+    }
+
+    /// Prepares `params.query_vectors` like the query embedding, dropping
+    /// those whose dimension differs from it.
+    fn prepare_query_vectors(
+        &self,
+        mut params: crate::hybrid::HybridParams,
+        dimension: usize,
+    ) -> crate::hybrid::HybridParams {
+        params
+            .query_vectors
+            .retain(|v| v.embedding.len() == dimension);
+        for vector in &mut params.query_vectors {
+            vector.embedding = self.prepare_query(&vector.embedding).into_owned();
+        }
+        params
+    }
+
+    /// Fills in each result's `node` and `edge_types` when `params` asks
+    /// for them.
+    fn attach_result_details(
+        &self,
+        results: &mut [crate::hybrid::HybridResult],
+        params: &crate::hybrid::HybridParams,
+    ) {
+        for result in results {
+            if params.include_nodes {
+                result.node = self.get_node(result.id).map(Into::into);
+            }
+            if params.include_edge_types {
+                result.edge_types = result
+                    .path
+                    .windows(2)
+                    .map(|step| self.step_edge_type(step[0], step[1], params.direction))
+                    .collect();
+            }
+        }
+    }
+
+    /// Returns the type of an edge joining `from` to its neighbor `to`
+    /// along `direction`, preferring an outgoing edge, or `""` if unknown.
+    fn step_edge_type(&self, from: NodeId, to: NodeId, direction: Direction) -> String {
+        let outgoing = || {
+            direction
+                .follows_outgoing()
+                .then(|| self.edges_between(from, to).first().copied())
+                .flatten()
+        };
+        let incoming = || {
+            direction
+                .follows_incoming()
+                .then(|| self.edges_between(to, from).first().copied())
+                .flatten()
+        };
+        outgoing()
+            .or_else(incoming)
+            .map(|edge| edge.edge_type.clone())
+            .unwrap_or_default()
+    }
+
+    /// Records an agent decision to the database.
+    ///
+    /// The decision is written to the WAL for durability and stored
+    /// in memory for querying.
+    ///
+    /// A record built with `DecisionRecord::as_node` is also added to the
+    /// graph as a node tagged `decision`, carrying the agent, timestamp
+    /// and the mean embedding of its path nodes. It has a `DECIDED_AT`
+    /// edge to the last node of the path (the root if the path is empty)
+    /// and `VISITED` edges to the others, so traversals following
+    /// incoming edges reach it from the nodes it touched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the decision node's ID is already in use.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The decision record to store
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::agent::DecisionRecord;
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db"));
+    /// let mut db = BarqGraphDb::open(opts).unwrap();
+    ///
+    /// let decision = DecisionRecord::new(1, 42, 100, vec![100, 101], 0.95);
+    /// db.record_decision(decision).unwrap();
+    ///
+    /// let as_node = DecisionRecord::new(2, 42, 100, vec![100, 101], 0.8).as_node(1_000);
+    /// db.record_decision(as_node).unwrap();
+    /// ```
+    pub fn record_decision(&mut self, mut record: DecisionRecord) -> Result<()> {
+        self.prepare_embedding(&mut record.embedding);
+        if let Some(node_id) = record.node_id {
+            if self.get_node(node_id).is_some() {
+                return Err(BarqError::NodeAlreadyExists(node_id).into());
+            }
+            let node = self.decision_node(&record, node_id);
+            self.append_node(node)?;
+        }
+        self.log_decision(record)
+    }
+
+    /// Builds the graph node materializing `record`.
+    fn decision_node(&self, record: &DecisionRecord, node_id: NodeId) -> Node {
+        let mut node = Node::new(node_id, format!("decision {}", record.id));
+        node.timestamp = record.created_at;
+        node.agent_id = Some(record.agent_id);
+        node.rule_tags = vec![DECISION_TAG.to_string()];
+
+        let decided_at = record.path.last().copied().unwrap_or(record.root_node);
+        let mut targets = Vec::new();
+        for &id in &record.path {
+            if id != decided_at && !targets.contains(&id) {
+                targets.push(id);
+                node.edges.push(Edge::new(node_id, id, VISITED));
+            }
+        }
+        targets.push(decided_at);
+        node.edges.push(Edge::new(node_id, decided_at, DECIDED_AT));
+
+        // Mean of the path embeddings sharing the first one's dimension
+        let embeddings: Vec<&[f32]> = targets
+            .iter()
+            .filter_map(|&id| self.get_node(id))
+            .map(|n| n.embedding.as_slice())
+            .filter(|e| !e.is_empty())
+            .collect();
+        if let Some(dim) = embeddings.first().map(|e| e.len()) {
+            let same: Vec<&[f32]> = embeddings.into_iter().filter(|e| e.len() == dim).collect();
+            node.embedding = (0..dim)
+                .map(|i| same.iter().map(|e| e[i]).sum::<f32>() / same.len() as f32)
+                .collect();
+        }
+        node
+    }
+
+    /// Writes a decision to the WAL and stores it, without materializing
+    /// its node (WAL replay finds that as a node record of its own).
+    fn log_decision(&mut self, record: DecisionRecord) -> Result<()> {
+        let wal_record = WalRecord::Decision {
+            data: record.clone(),
+        };
+
+        // Append to WAL for durability
+        self.write_wal(&wal_record, "decision", true)?;
+
+        // Add to in-memory storage
+        self.decisions.insert(record);
+
+        Ok(())
+    }
+
+    /// Lists all decisions for a specific agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent_id` - ID of the agent to filter by
+    ///
+    /// # Returns
+    ///
+    /// A vector of references to decision records for the specified agent.
+    pub fn list_decisions_for_agent(&self, agent_id: u64) -> Vec<&DecisionRecord> {
+        self.decisions.for_agent(agent_id).collect()
+    }
+
+    /// Lists all decisions in the database.
+    ///
+    /// # Returns
+    ///
+    /// A vector of references to all decision records.
+    pub fn list_all_decisions(&self) -> Vec<&DecisionRecord> {
+        self.decisions.records().iter().collect()
+    }
+
+    /// Returns the total number of decisions in the database.
+    pub fn decision_count(&self) -> usize {
+        self.decisions.len()
+    }
+
+    /// Gets a decision by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The decision ID to look up
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing a reference to the decision if found.
+    pub fn get_decision(&self, id: u64) -> Option<&DecisionRecord> {
+        self.decisions.get(id)
+    }
+
+    /// Saves a query template, replacing any template with the same name.
+    ///
+    /// The stored version is one more than the replaced template's
+    /// version, or 1 for a new name; the version on `template` is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The template to save
+    ///
+    /// # Returns
+    ///
+    /// The version assigned to the saved template.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty or the WAL write fails.
+    pub fn save_template(&mut self, mut template: QueryTemplate) -> Result<u32> {
+        if template.name.trim().is_empty() {
+            return Err(
+                BarqError::InvalidOperation("Template name cannot be empty".to_string()).into(),
+            );
+        }
+        template.version = self
+            .templates
+            .get(&template.name)
+            .map_or(1, |t| t.version + 1);
+        let version = template.version;
+        self.put_template(template)?;
+        Ok(version)
+    }
+
+    /// Stores a template as-is, keeping its version.
+    fn put_template(&mut self, template: QueryTemplate) -> Result<()> {
+        let record = WalRecord::Template {
+            data: template.clone(),
+        };
+        self.write_wal(&record, "template", true)?;
+        self.templates.insert(template.name.clone(), template);
+        Ok(())
+    }
+
+    /// Gets a saved query template by name.
+    pub fn get_template(&self, name: &str) -> Option<&QueryTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Lists all saved query templates, ordered by name.
+    pub fn list_templates(&self) -> Vec<&QueryTemplate> {
+        let mut templates: Vec<_> = self.templates.values().collect();
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        templates
+    }
+
+    /// Deletes a saved query template.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a template with this name existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the WAL write fails.
+    pub fn delete_template(&mut self, name: &str) -> Result<bool> {
+        if !self.templates.contains_key(name) {
+            return Ok(false);
+        }
+        let record = WalRecord::TemplateDeleted {
+            name: name.to_string(),
+        };
+        self.write_wal(&record, "template deletion", true)?;
+        self.templates.remove(name);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hybrid::HybridParams;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_new_database() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.node_count(), 0);
+    }
+
+    #[test]
+    fn test_append_and_retrieve_node() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+
+        let mut db = BarqGraphDb::open(opts).unwrap();
+
+        let node = Node::new(1, "test_node".to_string());
+        db.append_node(node.clone()).unwrap();
+
+        assert_eq!(db.node_count(), 1);
+        let retrieved = db.get_node(1).unwrap();
+        assert_eq!(retrieved.id, 1);
+        assert_eq!(retrieved.label, "test_node");
+    }
+
+    #[test]
+    fn test_append_and_persist() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+
+        // Open, add nodes, close
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            let node = Node {
+                id: 1,
+                label: "test".to_string(),
+                embedding: vec![],
+                edges: vec![],
+                timestamp: 0,
+                agent_id: None,
+                rule_tags: vec![],
+            };
+            db.append_node(node).unwrap();
+        }
+
+        // Reopen and verify
+        let db2 = BarqGraphDb::open(opts).unwrap();
+        assert!(db2.nodes().contains_key(&1));
+        assert_eq!(db2.get_node(1).unwrap().label, "test");
+    }
+
+    #[test]
+    fn test_multiple_nodes() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+
+        let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+
+        for i in 1..=10 {
+            let node = Node::new(i, format!("node_{}", i));
+            db.append_node(node).unwrap();
+        }
+
+        assert_eq!(db.node_count(), 10);
+
+        // Reopen and verify all nodes persist
+        drop(db);
+        let db2 = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db2.node_count(), 10);
+
+        for i in 1..=10 {
+            let node = db2.get_node(i).unwrap();
+            assert_eq!(node.label, format!("node_{}", i));
+        }
+    }
+
+    #[test]
+    fn test_node_update_in_wal() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+
+        // Add node, then update it
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            let node = Node::new(1, "original".to_string());
+            db.append_node(node).unwrap();
+
+            // Update by appending again with same ID
+            let updated_node = Node::new(1, "updated".to_string());
+            db.append_node(updated_node).unwrap();
+        }
+
+        // Reopen and verify update persisted
+        let db2 = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db2.node_count(), 1);
+        assert_eq!(db2.get_node(1).unwrap().label, "updated");
+    }
+
+    #[test]
+    fn test_metrics_sink_receives_latencies() {
+        use crate::metrics::LatencyHistogram;
+
+        let dir = TempDir::new().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        let histogram = Arc::new(LatencyHistogram::new());
+        db.set_metrics_sink(Some(histogram.clone()));
+
+        db.append_node(Node::new(1, "a".to_string())).unwrap();
+        db.append_node(Node::new(2, "b".to_string())).unwrap();
+        db.add_edge(1, 2, "LINKS").unwrap();
+        db.knn_search(&[0.0], 1);
+        db.hybrid_query(&[0.0], 1, 1, 1, crate::hybrid::HybridParams::default());
+
+        assert_eq!(histogram.snapshot(Operation::AppendNode).count, 2);
+        assert_eq!(histogram.snapshot(Operation::AddEdge).count, 1);
+        assert_eq!(histogram.snapshot(Operation::Knn).count, 1);
+        assert_eq!(histogram.snapshot(Operation::Hybrid).count, 1);
+    }
+
+    #[test]
+    fn test_partitioned_id_allocation() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf()).with_id_partition(IdPartition::new(7));
+
+        let (a, b) = {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            let a = db.allocate_node_id().unwrap();
+            db.append_node(Node::new(a, "a".to_string())).unwrap();
+            let b = db.allocate_node_id().unwrap();
+            db.append_node(Node::new(b, "b".to_string())).unwrap();
+
+            // IDs outside the partition are rejected
+            assert!(db.append_node(Node::new(1, "foreign".to_string())).is_err());
+            (a, b)
+        };
+
+        assert_eq!(IdPartition::writer_of(a), 7);
+        assert_eq!(b, a + 1);
+
+        // Allocation resumes after reopen
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.allocate_node_id().unwrap(), b + 1);
+    }
+
+    #[test]
+    fn test_id_allocation_exhausted() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            db.append_node(Node::new(u64::MAX, "last".to_string()))
+                .unwrap();
+            assert!(db.allocate_node_id().is_err());
+            // Explicit IDs still work
+            db.append_node(Node::new(5, "five".to_string())).unwrap();
+        }
+
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.node_count(), 2);
+        assert!(db.allocate_node_id().is_err());
+
+        let partition = IdPartition::new(u16::MAX);
+        let mut db =
+            BarqGraphDb::open(DbOptions::in_memory().with_id_partition(partition)).unwrap();
+        db.append_node(Node::new(partition.last_id(), "last".to_string()))
+            .unwrap();
+        assert!(db.allocate_node_id().is_err());
+    }
+
+    #[test]
+    fn test_in_memory_database() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        db.append_node(Node::new(1, "a".to_string())).unwrap();
+        db.append_node(Node::new(2, "b".to_string())).unwrap();
+        db.add_edge(1, 2, "LINKS").unwrap();
+        db.set_embedding(2, vec![1.0, 0.0]).unwrap();
+        db.record_decision(DecisionRecord::new(1, 1, 1, vec![1, 2], 0.9))
+            .unwrap();
+
+        assert_eq!(db.node_count(), 2);
+        assert_eq!(db.bfs_hops(1, 1), vec![1, 2]);
+        assert_eq!(db.decision_count(), 1);
+        assert!(!db.path().join("wal.log").exists());
+    }
+
+    #[test]
+    fn test_record_decision_as_node() {
+        use crate::graph::Direction;
+        use crate::hybrid::HybridQuery;
+
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for id in 1..=3 {
+                let mut node = Node::new(id, format!("n{}", id));
+                node.embedding = vec![id as f32];
+                db.append_node(node).unwrap();
+            }
+            let decision = DecisionRecord::with_timestamp(7, 42, 500, 1, vec![1, 2, 1, 3], 0.9);
+            db.record_decision(decision.as_node(100)).unwrap();
+
+            // The ID is taken now, so the next decision is refused whole
+            let taken = DecisionRecord::new(8, 42, 1, vec![1], 0.5).as_node(100);
+            assert!(db.record_decision(taken).is_err());
+            assert_eq!(db.decision_count(), 1);
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!((db.node_count(), db.decision_count()), (4, 1));
+        assert_eq!(db.get_decision(7).unwrap().node_id, Some(100));
+
+        let node = db.get_node(100).unwrap();
+        assert_eq!(node.label, "decision 7");
+        assert_eq!((node.agent_id, node.timestamp), (Some(42), 500));
+        assert_eq!(node.rule_tags, vec!["decision".to_string()]);
+        assert_eq!(node.embedding, vec![2.0]);
+        let edges: Vec<_> = node
+            .edges
+            .iter()
+            .map(|e| (e.to, e.edge_type.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![(1, "VISITED"), (2, "VISITED"), (3, "DECIDED_AT")]
+        );
+
+        // Reached from a path node by following incoming edges
+        let results = HybridQuery::new(vec![2.0])
+            .start(3)
+            .max_hops(1)
+            .direction(Direction::Incoming)
+            .run(&db)
+            .results;
+        assert!(results
+            .iter()
+            .any(|r| r.id == 100 && r.path == vec![3, 100]));
+    }
+
+    #[test]
+    fn test_rebuild_with_new_metric() {
+        let src = TempDir::new().unwrap();
+        {
+            let mut db = BarqGraphDb::open(DbOptions::new(src.path().to_path_buf())).unwrap();
+            db.append_node(Node::new(1, "a".to_string())).unwrap();
+            db.append_node(Node::new(2, "b".to_string())).unwrap();
+            db.add_edge(1, 2, "LINKS").unwrap();
+            db.set_embedding(1, vec![10.0, 0.0]).unwrap();
+            db.set_embedding(2, vec![0.5, 0.5]).unwrap();
+        }
+
+        let dest = TempDir::new().unwrap();
+        let mut opts = DbOptions::new(dest.path().to_path_buf());
+        opts.index_type = IndexType::Linear;
+        opts.metric = DistanceMetric::Cosine;
+        let (db, report) =
+            BarqGraphDb::rebuild(&DbOptions::new(src.path().to_path_buf()), opts).unwrap();
+
+        assert_eq!(report.records, 5);
+        assert_eq!(report.dimension, Some(2));
+        assert_eq!(db.edge_count(), 1);
+        assert_eq!(db.knn_search(&[1.0, 0.1], 1)[0].0, 1);
+    }
+
+    #[test]
+    fn test_rebuild_rejects_dimension_mismatch() {
+        let src = TempDir::new().unwrap();
+        {
+            let mut opts = DbOptions::new(src.path().to_path_buf());
+            opts.index_type = IndexType::Linear;
+            let mut db = BarqGraphDb::open(opts).unwrap();
+            db.set_embedding(1, vec![1.0, 0.0]).unwrap();
+            db.set_embedding(2, vec![1.0, 0.0, 0.0]).unwrap();
+        }
+
+        let dest = TempDir::new().unwrap();
+        let opts = DbOptions::new(dest.path().to_path_buf());
+        let err = BarqGraphDb::rebuild(&DbOptions::new(src.path().to_path_buf()), opts)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Dimension mismatch"));
+    }
+
+    #[test]
+    fn test_rebuild_stops_at_torn_tail() {
+        let src = TempDir::new().unwrap();
+        let source = DbOptions::new(src.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(source.clone()).unwrap();
+            db.append_node(Node::new(1, "a".to_string())).unwrap();
+            db.append_node(Node::new(2, "b".to_string())).unwrap();
+        }
+        // A crash mid-append leaves the last record without its newline
+        let wal = fs::read(source.wal_file()).unwrap();
+        fs::write(source.wal_file(), &wal[..wal.len() - 5]).unwrap();
+
+        let dest = TempDir::new().unwrap();
+        let (db, report) =
+            BarqGraphDb::rebuild(&source, DbOptions::new(dest.path().to_path_buf())).unwrap();
+        assert_eq!(report.records, 1);
+        assert_eq!(db.node_count(), 1);
+        assert!(db.get_node(2).is_none());
+    }
+
+    #[test]
+    fn test_custom_wal_path() {
+        let data = TempDir::new().unwrap();
+        let logs = TempDir::new().unwrap();
+        let wal = logs.path().join("nvme").join("barq.wal");
+        let opts = DbOptions::new(data.path().to_path_buf()).with_wal_path(wal.clone());
+
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            db.append_node(Node::new(1, "a".to_string())).unwrap();
+            assert_eq!(db.wal_path(), Some(wal.clone()));
+        }
+
+        assert!(wal.exists());
+        assert!(!data.path().join("wal.log").exists());
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.node_count(), 1);
+    }
+
+    #[test]
+    fn test_hybrid_query_with_budget() {
+        use crate::hybrid::HybridParams;
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for i in 1..=3 {
+            let mut node = Node::new(i, format!("n{}", i));
+            node.embedding = vec![i as f32];
+            db.append_node(node).unwrap();
+        }
+        db.add_edge(1, 2, "NEXT").unwrap();
+        db.add_edge(2, 3, "NEXT").unwrap();
+
+        let outcome = db.hybrid_query_with_budget(
+            &[1.0],
+            1,
+            2,
+            3,
+            HybridParams::default(),
+            Duration::from_secs(5),
+        );
+        assert!(!outcome.partial);
+        assert_eq!(outcome.results.len(), 3);
+
+        // A zero budget expires immediately and is flagged as partial
+        let outcome =
+            db.hybrid_query_with_budget(&[1.0], 1, 2, 3, HybridParams::default(), Duration::ZERO);
+        assert!(outcome.partial);
+    }
+
+    #[test]
+    fn test_hybrid_query_seedless_expands_from_nearest_matches() {
+        use crate::hybrid::HybridParams;
+
+        let mut opts = DbOptions::in_memory();
+        opts.index_type = IndexType::Linear;
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        for (id, x) in [(1, 0.0), (2, 5.0), (3, 9.0), (10, 1.0), (11, 20.0)] {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![x];
+            db.append_node(node).unwrap();
+        }
+        db.add_edge(1, 2, "NEXT").unwrap();
+        db.add_edge(2, 3, "NEXT").unwrap();
+        db.add_edge(10, 11, "NEXT").unwrap();
+
+        // Seeds 1 and 10 are the two closest to the query
+        let results = db.hybrid_query_seedless(&[0.4], 2, 1, 10, HybridParams::default());
+        let mut ids: Vec<NodeId> = results.iter().map(|r| r.id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 2, 10, 11]);
+        let far = results.iter().find(|r| r.id == 11).unwrap();
+        assert_eq!((far.graph_distance, far.path.clone()), (1, vec![10, 11]));
+        assert_eq!(results[0].id, 1);
+
+        assert!(db
+            .hybrid_query_seedless(&[0.4], 0, 1, 10, HybridParams::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_hybrid_filter_applies_before_truncation() {
+        use crate::hybrid::{HybridFilter, HybridParams};
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for i in 1..=4 {
+            let mut node = Node::new(i, format!("n{}", i));
+            node.embedding = vec![i as f32 * 0.1];
+            node.agent_id = Some(i % 2);
+            node.timestamp = i * 10;
+            if i > 1 {
+                node.rule_tags = vec!["fact".to_string()];
+            }
+            db.append_node(node).unwrap();
+        }
+        db.add_edge(1, 2, "NEXT").unwrap();
+        db.add_edge(2, 3, "NEXT").unwrap();
+        db.add_edge(3, 4, "NEXT").unwrap();
+
+        // Node 2 is filtered out but still leads to 3 and 4
+        let filter = HybridFilter {
+            rule_tags_any: vec!["fact".to_string()],
+            agent_id: Some(1),
+            ..HybridFilter::default()
+        };
+        let params = HybridParams::default().with_filter(filter);
+        let ids: Vec<NodeId> = db
+            .hybrid_query(&[0.1], 1, 3, 1, params.clone())
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, vec![3]);
+
+        let window = HybridFilter {
+            min_timestamp: Some(20),
+            max_timestamp: Some(30),
+            ..HybridFilter::default()
+        };
+        let results = db.hybrid_query(&[0.1], 1, 3, 10, params.with_filter(window));
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_hybrid_query_with_custom_scorer() {
+        use crate::hybrid::{HybridParams, HybridScorer, ScoreInput};
+        use std::sync::Arc;
+
+        /// Ranks by agent ID alone, ignoring both distances.
+        struct ByAgent;
+
+        impl HybridScorer for ByAgent {
+            fn score(&self, input: &ScoreInput<'_>) -> f32 {
+                input.node.and_then(|n| n.agent_id).unwrap_or(0) as f32
+            }
+        }
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for (id, agent) in [(1, 5), (2, 9), (3, 7)] {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![id as f32 * 0.1];
+            node.agent_id = Some(agent);
+            db.append_node(node).unwrap();
+        }
+        db.add_edge(1, 2, "NEXT").unwrap();
+        db.add_edge(1, 3, "NEXT").unwrap();
+
+        let default: Vec<NodeId> = db
+            .hybrid_query(&[0.1], 1, 1, 3, HybridParams::default())
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(default[0], 1);
+
+        let params = HybridParams::default().with_scorer(Arc::new(ByAgent));
+        let results = db.hybrid_query(&[0.1], 1, 1, 3, params);
+        let ids: Vec<NodeId> = results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+        assert_eq!(results[0].score, 9.0);
+    }
+
+    #[test]
+    fn test_hybrid_query_weighted_edges() {
+        use crate::hybrid::HybridParams;
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for id in 1..=4 {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![if id == 1 { 0.2 } else { 0.5 }];
+            db.append_node(node).unwrap();
+        }
+        // 1 -> 2 is strong; 1 -> 3 is weak but 1 -> 4 -> 3 is cheaper
+        db.add_weighted_edge(1, 2, "REL", 0.1).unwrap();
+        db.add_weighted_edge(1, 3, "REL", 5.0).unwrap();
+        db.add_weighted_edge(1, 4, "REL", 1.0).unwrap();
+        db.add_weighted_edge(4, 3, "REL", 1.5).unwrap();
+
+        let hops = db.hybrid_query(&[0.5], 1, 2, 4, HybridParams::default());
+        let three = hops.iter().find(|r| r.id == 3).unwrap();
+        assert_eq!((three.graph_distance, three.graph_cost), (1, 1.0));
+
+        let params = HybridParams::default().with_weighted_edges(true);
+        let results = db.hybrid_query(&[0.5], 1, 2, 4, params);
+        let ids: Vec<NodeId> = results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![2, 1, 4, 3]);
+        let three = results.iter().find(|r| r.id == 3).unwrap();
+        assert_eq!(three.path, vec![1, 4, 3]);
+        assert_eq!((three.graph_distance, three.graph_cost), (2, 2.5));
+    }
+
+    #[test]
+    fn test_hybrid_query_returns_shortest_paths_with_edge_types() {
+        use crate::graph::Direction;
+        use crate::hybrid::HybridQuery;
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for id in 1..=5 {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![id as f32];
+            db.append_node(node).unwrap();
+        }
+        // A long route 1 -> 2 -> 3 -> 4 added before the shortcut 1 -> 4
+        db.add_edge(1, 2, "A").unwrap();
+        db.add_edge(2, 3, "B").unwrap();
+        db.add_edge(3, 4, "C").unwrap();
+        db.add_edge(1, 4, "SHORTCUT").unwrap();
+        db.add_edge(5, 4, "POINTS_AT").unwrap();
+
+        let query = HybridQuery::new(vec![4.0]).start(1).max_hops(3).k(5);
+        let plain = query.clone().run(&db);
+        let four = plain.results.iter().find(|r| r.id == 4).unwrap();
+        assert_eq!((four.path.clone(), four.graph_distance), (vec![1, 4], 1));
+        assert!(four.edge_types.is_empty());
+
+        let typed = query.direction(Direction::Both).edge_types(true).run(&db);
+        let edge_types = |id: NodeId| {
+            let result = typed.results.iter().find(|r| r.id == id).unwrap();
+            (result.path.clone(), result.edge_types.clone())
+        };
+        assert_eq!(edge_types(4), (vec![1, 4], vec!["SHORTCUT".to_string()]));
+        // Incoming edges keep their own type
+        assert_eq!(
+            edge_types(5),
+            (
+                vec![1, 4, 5],
+                vec!["SHORTCUT".to_string(), "POINTS_AT".to_string()]
+            )
+        );
+        assert!(edge_types(1).1.is_empty());
+    }
+
+    #[test]
+    fn test_hybrid_query_min_score_drops_weak_results() {
+        use crate::hybrid::HybridQuery;
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for id in 1..=4 {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![id as f32];
+            db.append_node(node).unwrap();
+        }
+        db.add_edge(1, 2, "NEXT").unwrap();
+        db.add_edge(2, 3, "NEXT").unwrap();
+        db.add_edge(3, 4, "NEXT").unwrap();
+
+        let query = HybridQuery::new(vec![1.0]).start(1).max_hops(3).k(4);
+        let all = query.clone().run(&db).results;
+        assert_eq!(all.len(), 4);
+
+        let bar = all[1].score;
+        let kept = query.clone().min_score(bar).run(&db).results;
+        assert_eq!(kept.len(), 2);
+        assert!(kept.iter().all(|r| r.score >= bar));
+        assert!(query.min_score(f32::MAX).run(&db).results.is_empty());
+    }
+
+    #[test]
+    fn test_hybrid_query_personalized_pagerank_rewards_many_paths() {
+        use crate::hybrid::{GraphProximity, HybridQuery};
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for id in 0..=6 {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![0.0];
+            db.append_node(node).unwrap();
+        }
+        // 4 is two hops away along three paths, 6 along one
+        for via in 1..=3 {
+            db.add_edge(0, via, "REL").unwrap();
+            db.add_edge(via, 4, "REL").unwrap();
+        }
+        db.add_edge(0, 5, "REL").unwrap();
+        db.add_edge(5, 6, "REL").unwrap();
+
+        let query = HybridQuery::new(vec![0.0])
+            .start(0)
+            .max_hops(2)
+            .k(7)
+            .alpha(0.0)
+            .beta(1.0);
+        let score = |outcome: &crate::hybrid::HybridOutcome, id: NodeId| {
+            outcome.results.iter().find(|r| r.id == id).unwrap().score
+        };
+
+        let hops = query.clone().run(&db);
+        assert_eq!(score(&hops, 4), score(&hops, 6));
+
+        let ppr = query
+            .graph_proximity(GraphProximity::personalized_pagerank())
+            .run(&db);
+        assert!(score(&ppr, 4) > score(&ppr, 6));
+        // The start ranks highest and keeps full graph similarity
+        assert_eq!(ppr.results[0].id, 0);
+        assert!((ppr.results[0].score - 1.0).abs() < 1e-6);
+        assert_eq!(ppr.results[0].graph_cost, 0.0);
+    }
+
+    #[test]
+    fn test_hybrid_query_with_several_query_vectors() {
+        use crate::hybrid::{HybridQuery, VectorAggregation, WeightedVector};
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        // 2 matches the question, 3 the summary, 4 sits halfway
+        for (id, x) in [(1, 5.0), (2, 0.0), (3, 1.0), (4, 0.5)] {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![x];
+            db.append_node(node).unwrap();
+            if id > 1 {
+                db.add_edge(1, id, "REL").unwrap();
+            }
+        }
+        let query = HybridQuery::new(vec![0.0])
+            .start(1)
+            .max_hops(1)
+            .k(4)
+            .alpha(1.0)
+            .beta(0.0);
+        let summary = || vec![WeightedVector::new(vec![1.0], 1.0)];
+        let ids = |query: HybridQuery| -> Vec<NodeId> {
+            query.run(&db).results.iter().map(|r| r.id).collect()
+        };
+
+        assert_eq!(ids(query.clone())[0], 2);
+        // Either vector is enough under max
+        let any = query
+            .clone()
+            .query_vectors(summary(), VectorAggregation::Max);
+        let results = any.clone().run(&db).results;
+        assert!(results[..2].iter().all(|r| r.id == 2 || r.id == 3));
+        assert_eq!(results[0].query_distances.len(), 1);
+        // Weights decide under the mean
+        let heavy = vec![WeightedVector::new(vec![1.0], 3.0)];
+        let mean = query.query_vectors(heavy, VectorAggregation::WeightedMean);
+        assert_eq!(ids(mean), vec![3, 4, 2, 1]);
+    }
+
+    #[test]
+    fn test_hybrid_query_beam_expands_best_nodes_only() {
+        use crate::hybrid::HybridQuery;
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        // 0 -> 1..=5 -> 11..=15, with 3 and 13 closest to the query
+        let mut add = |id: NodeId, x: f32| {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![x];
+            db.append_node(node).unwrap();
+        };
+        add(0, 5.0);
+        for id in 1..=5 {
+            add(id, if id == 3 { 0.5 } else { 3.0 });
+            add(id + 10, if id == 3 { 0.0 } else { 3.0 });
+        }
+        for id in 1..=5 {
+            db.add_edge(0, id, "REL").unwrap();
+            db.add_edge(id, id + 10, "REL").unwrap();
+        }
+
+        let query = HybridQuery::new(vec![0.0]).start(0).max_hops(2).k(20);
+        let full = query.clone().run(&db);
+        assert_eq!(full.results.len(), 11);
+
+        let beam = query.beam_width(1).run(&db);
+        let mut ids: Vec<NodeId> = beam.results.iter().map(|r| r.id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![0, 1, 2, 3, 4, 5, 13]);
+        let best = &beam.results[0];
+        assert_eq!((best.id, best.path.clone()), (13, vec![0, 3, 13]));
+    }
+
+    #[test]
+    fn test_hybrid_query_builder_matches_positional_calls() {
+        use crate::hybrid::{HybridParams, HybridQuery};
+
+        let mut opts = DbOptions::in_memory();
+        opts.index_type = IndexType::Linear;
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        for id in 1..=5 {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![id as f32 * 0.2];
+            db.append_node(node).unwrap();
+        }
+        for id in 1..5 {
+            db.add_edge(id, id + 1, "NEXT").unwrap();
+        }
+        let ids = |results: &[crate::hybrid::HybridResult]| -> Vec<NodeId> {
+            results.iter().map(|r| r.id).collect()
+        };
+
+        let params = HybridParams::new(0.7, 0.3);
+        let positional = db.hybrid_query(&[0.8], 1, 2, 3, params.clone());
+        let built = HybridQuery::new(vec![0.8])
+            .start(1)
+            .max_hops(2)
+            .k(3)
+            .alpha(0.7)
+            .beta(0.3)
+            .run(&db);
+        assert_eq!(ids(&built.results), ids(&positional));
+
+        let seedless = db.hybrid_query_seedless(&[0.8], 1, 1, 5, params.clone());
+        let built = HybridQuery::new(vec![0.8])
+            .seeds(1)
+            .max_hops(1)
+            .k(5)
+            .params(params)
+            .run(&db);
+        assert_eq!(ids(&built.results), ids(&seedless));
+    }
+
+    #[test]
+    fn test_hybrid_query_includes_node_payloads() {
+        use crate::hybrid::{HybridParams, NodePayload};
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for id in 1..=2 {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![id as f32];
+            node.rule_tags = vec!["fact".to_string()];
+            node.agent_id = Some(7);
+            node.timestamp = 1_700_000_000 + id;
+            db.append_node(node).unwrap();
+        }
+        db.add_edge(1, 2, "REL").unwrap();
+
+        let plain = db.hybrid_query(&[2.0], 1, 1, 2, HybridParams::default());
+        assert!(plain.iter().all(|r| r.node.is_none()));
+
+        let params = HybridParams::default().with_include_nodes(true);
+        let results = db.hybrid_query(&[2.0], 1, 1, 2, params);
+        assert_eq!(results[0].id, 2);
+        assert_eq!(
+            results[0].node,
+            Some(NodePayload {
+                label: "n2".to_string(),
+                rule_tags: vec!["fact".to_string()],
+                timestamp: 1_700_000_002,
+                agent_id: Some(7),
+            })
+        );
+    }
+
+    #[test]
+    fn test_hybrid_query_stops_at_candidate_and_time_limits() {
+        use crate::hybrid::HybridParams;
+
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        // A hub whose leaves each have a leaf of their own
+        for id in 0..=20 {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![id as f32 / 20.0];
+            db.append_node(node).unwrap();
+        }
+        for id in 1..=10 {
+            db.add_edge(0, id, "REL").unwrap();
+            db.add_edge(id, id + 10, "REL").unwrap();
+        }
+
+        let full = db.hybrid_query_outcome(&[1.0], 0, 2, 25, HybridParams::default());
+        assert_eq!(full.results.len(), 21);
+        assert!(!full.truncated && !full.partial);
+
+        // The cap keeps the nodes discovered first: the hub and its leaves
+        let params = HybridParams::default().with_max_candidates(11);
+        let capped = db.hybrid_query_outcome(&[1.0], 0, 2, 25, params);
+        assert!(capped.truncated && !capped.partial);
+        assert_eq!(capped.results.len(), 11);
+        assert!(capped.results.iter().all(|r| r.id <= 10));
+
+        let params = HybridParams::default().with_timeout(Duration::ZERO);
+        let timed_out = db.hybrid_query_seedless_outcome(&[1.0], 1, 2, 25, params);
+        assert!(timed_out.partial && !timed_out.truncated);
+        assert!(timed_out.results.len() < 21);
+    }
+
+    #[test]
+    fn test_hybrid_query_scores_large_expansions_in_parallel() {
+        use crate::hybrid::{compute_hybrid_score, HybridParams, PARALLEL_SCORE_MIN};
+
+        let mut opts = DbOptions::in_memory();
+        opts.index_type = IndexType::Linear;
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        // A hub with enough leaves to take the parallel path
+        let leaves = PARALLEL_SCORE_MIN as u64 * 2;
+        for id in 0..=leaves {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = vec![(id % 97) as f32 / 97.0, 1.0];
+            db.append_node(node).unwrap();
+            if id > 0 {
+                db.add_edge(0, id, "REL").unwrap();
+            }
+        }
+
+        let params = HybridParams::new(0.7, 0.3);
+        let results = db.hybrid_query(&[0.5, 1.0], 0, 1, 20, params.clone());
+        assert_eq!(results.len(), 20);
+
+        // Same ranking as scoring every candidate and sorting
+        let mut expected: Vec<(NodeId, f32)> = (0..=leaves)
+            .map(|id| {
+                let node = db.get_node(id).unwrap();
+                let dist = params.normalization.distance(&[0.5, 1.0], &node.embedding);
+                (id, compute_hybrid_score(dist, (id > 0) as usize, &params))
+            })
+            .collect();
+        expected.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (result, (id, score)) in results.iter().zip(&expected) {
+            assert!((result.score - score).abs() < 1e-6);
+            assert_eq!(result.id, *id);
+        }
+    }
+
+    #[test]
+    fn test_hybrid_query_landmarks_matches_bfs_ranking() {
+        use crate::hybrid::HybridParams;
+
+        let mut opts = DbOptions::in_memory();
+        opts.index_type = IndexType::Linear;
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        // 1 -> 2 -> 3 -> 4 with 2 as the hub
+        for i in 1..=4 {
+            let mut node = Node::new(i, format!("n{}", i));
+            node.embedding = vec![i as f32 * 0.1];
+            db.append_node(node).unwrap();
+        }
+        db.add_edge(1, 2, "NEXT").unwrap();
+        db.add_edge(2, 3, "NEXT").unwrap();
+        db.add_edge(3, 4, "NEXT").unwrap();
+
+        let landmarks = db.build_landmark_index(2);
+        let approx =
+            db.hybrid_query_landmarks(&[0.1], 1, 2, 4, HybridParams::default(), &landmarks, 10);
+        let exact = db.hybrid_query(&[0.1], 1, 2, 4, HybridParams::default());
+
+        let approx_ids: Vec<NodeId> = approx.iter().map(|r| r.id).collect();
+        let exact_ids: Vec<NodeId> = exact.iter().map(|r| r.id).collect();
+        assert_eq!(approx_ids, exact_ids);
+        assert!(approx.iter().all(|r| r.path.is_empty()));
+    }
+
+    #[test]
+    fn test_memory_budget_rejects_writes() {
+        let opts = DbOptions::in_memory().with_memory_budget(MemoryBudget::new(2_000));
+        let mut db = BarqGraphDb::open(opts).unwrap();
+
+        db.append_node(Node::new(1, "a".to_string())).unwrap();
+        db.add_edge(1, 2, "NEXT").unwrap();
+        let usage = db.memory_usage();
+        assert!(usage.nodes > 0 && usage.adjacency > 0);
+
+        let mut big = Node::new(2, "b".to_string());
+        big.embedding = vec![0.0; 1_000];
+        let err = db.append_node(big).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BarqError>(),
+            Some(BarqError::MemoryBudgetExceeded { .. })
+        ));
+        assert_eq!(db.node_count(), 1);
+        assert_eq!(db.memory_usage(), usage);
+
+        // The incremental estimate matches a full recount
+        db.set_embedding(1, vec![0.5; 8]).unwrap();
+        let incremental = db.memory_usage();
+        db.recompute_memory_usage();
+        assert_eq!(db.memory_usage(), incremental);
+    }
+
+    #[test]
+    fn test_reinserted_edge_forgets_its_removal() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            db.append_node(Node::new(1, "a".to_string())).unwrap();
+            db.add_edge(1, 2, "NEXT").unwrap();
+            let linked = db.memory_usage();
+
+            db.remove_edge(1, 2, "NEXT").unwrap();
+            assert!(db.removed_edges.contains(&(1, 2)));
+            let removed = db.memory_usage();
+            db.recompute_memory_usage();
+            assert_eq!(db.memory_usage(), removed);
+
+            db.add_edges(&[(1, 2, "NEXT")]).unwrap();
+            assert!(db.removed_edges.is_empty());
+            assert_eq!(db.memory_usage(), linked);
+
+            // Removed and re-added many times, the pair is held at most once
+            for _ in 0..10 {
+                db.remove_edge(1, 2, "NEXT").unwrap();
+                db.add_edge(1, 2, "NEXT").unwrap();
+            }
+            assert_eq!(db.memory_usage(), linked);
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert!(db.removed_edges.is_empty());
+        assert_eq!(db.edge_count(), 1);
+    }
+
+    #[test]
+    fn test_tag_centroids_follow_writes_and_reopen() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf()).with_tag_centroids(true);
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for (id, embedding, tag) in [
+                (1, vec![0.0, 0.0], "billing"),
+                (2, vec![2.0, 0.0], "billing"),
+                (3, vec![0.0, 8.0], "auth"),
+            ] {
+                let mut node = Node::new(id, format!("n{}", id));
+                node.embedding = embedding;
+                node.rule_tags = vec![tag.to_string()];
+                db.append_node(node).unwrap();
+            }
+            assert_eq!(db.tag_centroid("billing").unwrap().centroid, vec![1.0, 0.0]);
+
+            // Re-embedding a node moves its tag's centroid
+            db.set_embedding(2, vec![4.0, 0.0]).unwrap();
+            assert_eq!(db.tag_centroid("billing").unwrap().centroid, vec![2.0, 0.0]);
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.tag_centroid("billing").unwrap().centroid, vec![2.0, 0.0]);
+        assert_eq!(db.route_query(&[0.0, 7.0], 1)[0].0, "auth");
+        assert_eq!(db.tag_centroids().len(), 2);
+
+        let plain = BarqGraphDb::open(DbOptions::new(dir.path().join("plain"))).unwrap();
+        assert!(plain.route_query(&[0.0, 7.0], 1).is_empty());
+    }
+
+    #[test]
+    fn test_neighbor_order_makes_bfs_reproducible() {
+        let dir = TempDir::new().unwrap();
+        let build = |name: &str, order: NeighborOrder, targets: &[NodeId]| {
+            let opts = DbOptions::new(dir.path().join(name)).with_neighbor_order(order);
+            let mut db = BarqGraphDb::open(opts).unwrap();
+            for &to in targets {
+                db.add_weighted_edge(1, to, "X", 10.0 - to as f32).unwrap();
+            }
+            db
+        };
+
+        let a = build("a", NeighborOrder::NodeId, &[4, 2, 3]);
+        let b = build("b", NeighborOrder::NodeId, &[3, 4, 2]);
+        assert_eq!(a.bfs_hops(1, 1), vec![1, 2, 3, 4]);
+        assert_eq!(a.bfs_hops(1, 1), b.bfs_hops(1, 1));
+        assert_eq!(a.dfs_hops(1, 1), b.dfs_hops(1, 1));
+
+        // Cheapest edge first: 4 costs 6.0, 3 costs 7.0, 2 costs 8.0
+        let weighted = build("w", NeighborOrder::Weight, &[2, 3, 4]);
+        assert_eq!(weighted.bfs_hops(1, 1), vec![1, 4, 3, 2]);
+
+        let insertion = build("i", NeighborOrder::Insertion, &[4, 2, 3]);
+        assert_eq!(insertion.bfs_hops(1, 1), vec![1, 4, 2, 3]);
+    }
+
+    #[test]
+    fn test_bfs_with_depths_reports_shortest_hops() {
+        let dir = TempDir::new().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        // 1 -> 2 -> 3 -> 4, plus a shortcut 1 -> 3
+        db.add_edges(&[(1, 2, "X"), (2, 3, "X"), (3, 4, "X"), (1, 3, "X")])
+            .unwrap();
+
+        assert_eq!(
+            db.bfs_with_depths(1, 5),
+            vec![(1, 0), (2, 1), (3, 1), (4, 2)]
+        );
+        assert_eq!(db.bfs_with_depths(1, 1), vec![(1, 0), (2, 1), (3, 1)]);
+        assert!(db.bfs_with_depths(99, 3).is_empty());
+
+        let ids: Vec<NodeId> = db
+            .bfs_with_depths(1, 5)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, db.bfs_hops(1, 5));
+    }
+
+    #[test]
+    fn test_edge_properties_persist() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            db.append_node(Node::new(1, "a".to_string())).unwrap();
+            db.append_node(Node::new(2, "b".to_string())).unwrap();
+            db.add_edge(1, 2, "KNOWS").unwrap();
+            let edge = Edge::new(1, 2, "CITES")
+                .with_weight(2.0)
+                .with_property("confidence", serde_json::json!(0.8));
+            db.add_edge_data(edge).unwrap();
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        let edges = db.edges_between(1, 2);
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0].edge_type, "KNOWS");
+        assert!(edges[0].properties.is_empty());
+        assert_eq!(edges[1].weight, Some(2.0));
+        assert_eq!(edges[1].properties["confidence"], serde_json::json!(0.8));
+        assert!(db.edges_between(2, 1).is_empty());
+    }
+
+    #[test]
+    fn test_edge_timestamps_persist() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            db.append_node(Node::new(1, "a".to_string())).unwrap();
+            db.add_edge(1, 2, "KNOWS").unwrap();
+            db.add_edge_data(Edge::new(1, 3, "CITES").with_timestamp(42))
+                .unwrap();
+            db.checkpoint().unwrap();
+            db.add_edge_data(Edge::new(1, 4, "CITES").with_timestamp(43))
+                .unwrap();
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert!(db.edges_between(1, 2)[0].timestamp > 0);
+        assert_eq!(db.edges_between(1, 3)[0].timestamp, 42);
+        assert_eq!(db.edges_between(1, 4)[0].timestamp, 43);
+
+        // Records written before edges carried timestamps decode as 0
+        let legacy = r#"{"kind":"edge","from":1,"to":2,"edge_type":"KNOWS"}"#;
+        let (record, _) = wal::decode_line(legacy).unwrap();
+        assert!(matches!(record, WalRecord::Edge { timestamp: 0, .. }));
+    }
+
+    #[test]
+    fn test_add_edges_writes_one_record() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            db.append_node(Node::new(1, "a".to_string())).unwrap();
+            db.add_edges(&[(1, 2, "KNOWS"), (1, 3, "CITES"), (2, 3, "KNOWS")])
+                .unwrap();
+            db.add_edges(&[]).unwrap();
+            assert_eq!(db.edge_count(), 3);
+        }
+
+        let wal = fs::read_to_string(opts.wal_file()).unwrap();
+        assert_eq!(wal.lines().count(), 2);
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.neighbors(1), Some(&[2, 3][..]));
+        assert_eq!(db.incoming_neighbors(3), Some(&[1, 2][..]));
+        assert_eq!(db.edges_between(1, 3)[0].edge_type, "CITES");
+    }
+
+    #[test]
+    fn test_incoming_edges() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        for id in 1..=3 {
+            db.append_node(Node::new(id, format!("n{}", id))).unwrap();
+        }
+        db.add_edges(&[
+            (2, 1, "CITES"),
+            (3, 1, "KNOWS"),
+            (2, 1, "KNOWS"),
+            (1, 2, "CITES"),
+        ])
+        .unwrap();
+        // Node 4 has no record, so its edge carries no data
+        db.add_edge(4, 1, "CITES").unwrap();
+
+        let incoming: Vec<_> = db
+            .incoming_edges(1)
+            .iter()
+            .map(|e| (e.from, e.edge_type.as_str()))
+            .collect();
+        assert_eq!(incoming, vec![(2, "CITES"), (2, "KNOWS"), (3, "KNOWS")]);
+        assert!(db.incoming_edges(3).is_empty());
+    }
+
+    #[test]
+    fn test_remove_edge_survives_replay() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            db.append_node(Node::new(1, "a".to_string())).unwrap();
+            db.append_node(Node::new(2, "b".to_string())).unwrap();
+            db.add_weighted_edge(1, 2, "KNOWS", 1.0).unwrap();
+            db.add_weighted_edge(1, 2, "CITES", 3.0).unwrap();
+            db.add_edge(2, 1, "KNOWS").unwrap();
+            let before = db.memory_usage();
+
+            assert!(db.remove_edge(1, 2, "KNOWS").unwrap());
+            assert!(!db.remove_edge(1, 2, "KNOWS").unwrap());
+            assert!(!db.remove_edge(1, 3, "KNOWS").unwrap());
+            assert_eq!(db.edge_weight(1, 2), Some(3.0));
+            assert!(db.memory_usage().total() < before.total());
+        }
+
+        let db = BarqGraphDb::open(opts.clone()).unwrap();
+        let edges = db.edges_between(1, 2);
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].edge_type, "CITES");
+        assert_eq!(db.neighbors(1), Some(&[2][..]));
+        assert_eq!(db.incoming_neighbors(2), Some(&[1][..]));
+        assert_eq!(db.edge_weight(1, 2), Some(3.0));
+        assert_eq!(db.edge_count(), 2);
+
+        // Rebuild replays the tombstone too
+        let dest = DbOptions::new(dir.path().join("rebuilt"));
+        let (rebuilt, _) = BarqGraphDb::rebuild(&opts, dest).unwrap();
+        assert_eq!(rebuilt.edges_between(1, 2).len(), 1);
+        assert_eq!(rebuilt.edge_count(), 2);
+    }
+
+    #[test]
+    fn test_remove_embedding_survives_replay() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            let mut node = Node::new(1, "a".to_string());
+            node.embedding = vec![1.0, 0.0];
+            db.append_node(node).unwrap();
+            db.set_embedding(2, vec![0.0, 1.0]).unwrap();
+            db.set_embedding(3, vec![0.9, 0.1]).unwrap();
+            let before = db.memory_usage();
+
+            assert!(db.remove_embedding(1).unwrap());
+            assert!(db.remove_embedding(2).unwrap());
+            assert!(!db.remove_embedding(2).unwrap());
+            assert!(!db.remove_embedding(4).unwrap());
+            assert_eq!(db.vector_count(), 1);
+            assert_eq!(db.get_embedding(1), None);
+            assert!(db.memory_usage().vectors < before.vectors);
+        }
+
+        let db = BarqGraphDb::open(opts.clone()).unwrap();
+        assert_eq!(db.node_count(), 1);
+        assert_eq!(db.vector_count(), 1);
+        assert_eq!(db.knn_search(&[1.0, 0.0], 5)[0].0, 3);
+
+        let dest = DbOptions::new(dir.path().join("rebuilt"));
+        let (rebuilt, _) = BarqGraphDb::rebuild(&opts, dest).unwrap();
+        assert_eq!(rebuilt.vector_count(), 1);
+    }
+
+    #[test]
+    fn test_custom_hnsw_params() {
+        let dir = TempDir::new().unwrap();
+        let small = HnswParams {
+            m: 8,
+            ef_construction: 64,
+            ef_search: 16,
+            ..HnswParams::default()
+        };
+        let opts = DbOptions::new(dir.path().to_path_buf()).with_hnsw(small);
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for i in 0..50u64 {
+                db.set_embedding(i, vec![i as f32, 0.0]).unwrap();
+            }
+            assert_eq!(db.knn_search(&[10.2, 0.0], 1)[0].0, 10);
+            assert_eq!(db.knn_search_with_ef(&[10.2, 0.0], 3, 400).len(), 3);
+            db.checkpoint().unwrap();
+        }
+
+        // A different graph shape discards the saved index and rebuilds it
+        let db = BarqGraphDb::open(opts.with_hnsw(HnswParams::default())).unwrap();
+        assert_eq!(db.vector_count(), 50);
+        assert_eq!(db.knn_search(&[30.9, 0.0], 1)[0].0, 31);
+
+        let invalid = HnswParams {
+            m: HnswParams::MAX_M + 1,
+            ..HnswParams::default()
+        };
+        let in_memory = DbOptions::in_memory().with_hnsw(invalid);
+        assert!(BarqGraphDb::open(in_memory).is_err());
+    }
+
+    #[test]
+    fn test_int8_quantization_cuts_vector_memory() {
+        let mut usage = Vec::new();
+        for quantization in [Quantization::None, Quantization::Int8] {
+            let mut opts = DbOptions::in_memory().with_quantization(quantization);
+            opts.index_type = IndexType::Linear;
+            let mut db = BarqGraphDb::open(opts).unwrap();
+            for i in 0..20u64 {
+                db.set_embedding(i, vec![i as f32; 256]).unwrap();
+            }
+            assert_eq!(db.knn_search(&[7.1; 256], 1)[0].0, 7);
+            usage.push(db.memory_usage().vectors);
+        }
+        assert!(usage[1] * 2 < usage[0]);
+
+        let hnsw = DbOptions::in_memory().with_quantization(Quantization::Int8);
+        assert!(BarqGraphDb::open(hnsw).is_err());
+    }
+
+    #[test]
+    fn test_ivf_index_type() {
+        assert_eq!(
+            "ivf".parse::<IndexType>(),
+            Ok(IndexType::Ivf {
+                nlist: IvfVectorIndex::DEFAULT_NLIST,
+                nprobe: IvfVectorIndex::DEFAULT_NPROBE,
+            })
+        );
+        assert!("ivf:4:0".parse::<IndexType>().is_err());
+        assert!("hnsw:4".parse::<IndexType>().is_err());
+
+        let mut opts = DbOptions::in_memory();
+        opts.index_type = "IVF:4:4".parse().unwrap();
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        for i in 0..100u64 {
+            db.set_embedding(i, vec![i as f32, (i % 7) as f32]).unwrap();
+        }
+        // Probing every list is exact
+        assert_eq!(db.knn_search(&[42.2, 0.0], 1)[0].0, 42);
+    }
+
+    #[test]
+    fn test_normalized_embeddings() {
+        let dir = TempDir::new().unwrap();
+        let mut opts = DbOptions::new(dir.path().to_path_buf());
+        opts.index_type = IndexType::Linear;
+        {
+            // Written before normalization is enabled
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            db.set_embedding(1, vec![100.0, 1.0]).unwrap();
+            db.set_embedding(2, vec![0.7, 0.7]).unwrap();
+            assert_eq!(db.knn_search(&[1.0, 1.0], 1)[0].0, 2);
+        }
+
+        let mut db = BarqGraphDb::open(opts.with_normalized_embeddings(true)).unwrap();
+        let mut node = Node::new(3, "c".to_string());
+        node.embedding = vec![3.0, 4.0];
+        db.append_node(node).unwrap();
+        assert_eq!(db.get_embedding(3), Some(&[0.6, 0.8][..]));
+
+        // Direction decides, so the long vector 1 now beats 2 for a +x query
+        let results = db.knn_search(&[50.0, 0.0], 3);
+        assert_eq!(results[0].0, 1);
+        assert!(results[0].1 < 0.02);
+        assert_eq!(db.knn_search(&[6.0, 8.0], 1), vec![(3, 0.0)]);
+    }
+
+    #[test]
+    fn test_knn_search_nodes_joins_node_data() {
+        let dir = TempDir::new().unwrap();
+        let mut opts = DbOptions::new(dir.path().to_path_buf());
+        opts.index_type = IndexType::Linear;
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        let mut node = Node::new(1, "memory".to_string());
+        node.embedding = vec![1.0, 0.0];
+        node.rule_tags = vec!["fact".to_string()];
+        node.agent_id = Some(7);
+        node.timestamp = 42;
+        db.append_node(node).unwrap();
+        db.set_embedding(2, vec![1.0, 0.1]).unwrap();
+
+        let hits = db.knn_search_nodes(&[1.0, 0.0], 2);
+        assert_eq!(
+            hits,
+            vec![NodeMatch {
+                id: 1,
+                distance: 0.0,
+                label: "memory".to_string(),
+                rule_tags: vec!["fact".to_string()],
+                timestamp: 42,
+                agent_id: Some(7),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_knn_search_within_drops_distant_matches() {
+        let mut opts = DbOptions::in_memory();
+        opts.index_type = IndexType::Linear;
+        let mut db = BarqGraphDb::open(opts).unwrap();
+        db.set_embedding(1, vec![0.5]).unwrap();
+        db.set_embedding(2, vec![0.9]).unwrap();
+        db.set_embedding(3, vec![3.0]).unwrap();
+
+        let ids =
+            |results: Vec<(NodeId, f32)>| results.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(ids(db.knn_search(&[0.0], 3)), vec![1, 2, 3]);
+        assert_eq!(ids(db.knn_search_within(&[0.0], 3, 1.0)), vec![1, 2]);
+        assert_eq!(ids(db.knn_search_within(&[0.0], 1, 1.0)), vec![1]);
+        assert!(db.knn_search_within(&[10.0], 3, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_reverse_adjacency_and_bidirectional_hybrid() {
+        let dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for id in 1..=4 {
+                let mut node = Node::new(id, format!("n{}", id));
+                node.embedding = vec![id as f32, 0.0];
+                db.append_node(node).unwrap();
+            }
+            db.add_edge(1, 3, "X").unwrap();
+            db.add_edge(2, 3, "X").unwrap();
+            db.add_edge(3, 4, "X").unwrap();
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.incoming_neighbors(3), Some(&[1, 2][..]));
+        assert_eq!(db.incoming_neighbors(1), None);
+
+        let mut upstream = db.reverse_bfs_hops(4, 2);
+        upstream.sort_unstable();
+        assert_eq!(upstream, vec![1, 2, 3, 4]);
+
+        let query = [2.0, 0.0];
+        let forward = db.hybrid_query(&query, 3, 1, 10, HybridParams::new(0.5, 0.5));
+        assert!(forward.iter().all(|r| r.id != 2));
+        let params = HybridParams::new(0.5, 0.5).with_direction(Direction::Both);
+        let both = db.hybrid_query(&query, 3, 1, 10, params);
+        assert!(both.iter().any(|r| r.id == 2 && r.path == vec![3, 2]));
+    }
+}
@@ -0,0 +1,199 @@
+//! Embedding model metadata.
+//!
+//! Vectors written with `set_embedding_with_model` remember the name and
+//! version of the model that produced them. Embeddings from different
+//! models live in unrelated spaces, so by default an index holding one
+//! model's vectors rejects another's; `DbOptions::with_mixed_models`
+//! lifts that for a migration, during which `reembed_pending` lists the
+//! vectors still to be replaced. Vectors written without a model
+//! (`set_embedding`, node payloads) are untagged and never conflict.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use super::{BarqGraphDb, ModelMap};
+use crate::error::BarqError;
+use crate::NodeId;
+
+/// Model tags by node, with the number of vectors per model.
+#[derive(Debug, Default)]
+pub(super) struct ModelIndex {
+    by_node: ModelMap,
+    counts: HashMap<String, usize>,
+}
+
+impl ModelIndex {
+    /// Indexes recovered model tags.
+    pub(super) fn build(by_node: ModelMap) -> Self {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for model in by_node.values() {
+            *counts.entry(model.clone()).or_default() += 1;
+        }
+        Self { by_node, counts }
+    }
+
+    /// Tags the vector of `id`, or clears its tag if `model` is `None`.
+    pub(super) fn set(&mut self, id: NodeId, model: Option<String>) {
+        if let Some(old) = self.by_node.remove(&id) {
+            if let Some(count) = self.counts.get_mut(&old) {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(&old);
+                }
+            }
+        }
+        if let Some(model) = model {
+            *self.counts.entry(model.clone()).or_default() += 1;
+            self.by_node.insert(id, model);
+        }
+    }
+
+    pub(super) fn get(&self, id: NodeId) -> Option<&str> {
+        self.by_node.get(&id).map(String::as_str)
+    }
+
+    /// Returns a model other than `model` tagging a vector besides `id`'s.
+    fn conflicting(&self, id: NodeId, model: &str) -> Option<&str> {
+        let own = self.get(id);
+        self.counts
+            .iter()
+            .filter(|(name, &count)| {
+                name.as_str() != model && count > usize::from(own == Some(name.as_str()))
+            })
+            .map(|(name, _)| name.as_str())
+            .min()
+    }
+
+    pub(super) fn by_node(&self) -> &ModelMap {
+        &self.by_node
+    }
+}
+
+impl BarqGraphDb {
+    /// Sets the embedding of a node and records the model that produced it.
+    ///
+    /// Behaves like `set_embedding`, and the model name survives
+    /// checkpoints and WAL replay.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Node ID to set the embedding for
+    /// * `embedding` - Vector embedding
+    /// * `model` - Model name and version, e.g. `"text-embedding-3-small@1"`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BarqError::InvalidOperation` if another node's vector was
+    /// produced by a different model, unless `DbOptions::allow_mixed_models`
+    /// is set.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// db.set_embedding_with_model(1, vec![0.1, 0.2, 0.3], "minilm-l6@2").unwrap();
+    /// assert_eq!(db.embedding_model(1), Some("minilm-l6@2"));
+    /// ```
+    pub fn set_embedding_with_model(
+        &mut self,
+        id: NodeId,
+        embedding: Vec<f32>,
+        model: &str,
+    ) -> Result<()> {
+        if !self.options.allow_mixed_models {
+            if let Some(existing) = self.models.conflicting(id, model) {
+                return Err(BarqError::InvalidOperation(format!(
+                    "Embedding model '{}' differs from '{}' already in the index; \
+                     enable allow_mixed_models to migrate",
+                    model, existing
+                ))
+                .into());
+            }
+        }
+        self.store_embedding(id, embedding, Some(model.to_string()))
+    }
+
+    /// Returns the model recorded for a node's embedding, if any.
+    pub fn embedding_model(&self, id: NodeId) -> Option<&str> {
+        self.models.get(id)
+    }
+
+    /// Lists the nodes whose embeddings were not produced by `model`.
+    ///
+    /// Includes untagged vectors, whose model is unknown. Re-embedding
+    /// each listed node with `set_embedding_with_model` empties the list.
+    ///
+    /// # Returns
+    ///
+    /// Node IDs sorted ascending.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db")).with_mixed_models(true);
+    /// let db = BarqGraphDb::open(opts).unwrap();
+    /// for id in db.reembed_pending("minilm-l6@3") {
+    ///     println!("node {} needs a new embedding", id);
+    /// }
+    /// ```
+    pub fn reembed_pending(&self, model: &str) -> Vec<NodeId> {
+        let mut ids: Vec<NodeId> = self
+            .indexed_embeddings()
+            .map(|(id, _)| id)
+            .filter(|&id| self.models.get(id) != Some(model))
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::Node;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_models_are_tracked_and_not_mixed() {
+        let temp_dir = TempDir::new().unwrap();
+        let opts = DbOptions::new(temp_dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            let mut node = Node::new(1, "untagged".to_string());
+            node.embedding = vec![1.0, 0.0];
+            db.append_node(node).unwrap();
+            db.append_node(Node::new(2, "two".to_string())).unwrap();
+            db.set_embedding_with_model(2, vec![0.0, 1.0], "m@1")
+                .unwrap();
+            db.set_embedding_with_model(3, vec![0.5, 0.5], "m@1")
+                .unwrap();
+            assert!(db
+                .set_embedding_with_model(3, vec![0.5, 0.5], "m@2")
+                .is_err());
+            assert_eq!(db.reembed_pending("m@1"), vec![1]);
+            db.checkpoint().unwrap();
+            db.set_embedding(3, vec![0.4, 0.6]).unwrap();
+        }
+
+        let mut db = BarqGraphDb::open(opts.with_mixed_models(true)).unwrap();
+        assert_eq!(db.embedding_model(2), Some("m@1"));
+        assert_eq!(db.embedding_model(3), None);
+        db.set_embedding_with_model(2, vec![0.1, 0.9], "m@2")
+            .unwrap();
+        assert_eq!(db.reembed_pending("m@2"), vec![1, 3]);
+
+        // The only vector of a model may be replaced by another model
+        let mut strict = BarqGraphDb::open(DbOptions::new(temp_dir.path().join("s"))).unwrap();
+        strict.set_embedding_with_model(1, vec![1.0], "a").unwrap();
+        strict.set_embedding_with_model(1, vec![1.0], "b").unwrap();
+        assert_eq!(strict.reembed_pending("b"), Vec::<NodeId>::new());
+    }
+}
@@ -0,0 +1,381 @@
+//! Weighted path search over the adjacency list.
+//!
+//! Edges added with `add_weighted_edge` (or carrying `Edge::weight`)
+//! contribute their weight as traversal cost; all other edges cost 1.0,
+//! so on an unweighted graph the result matches a hop-count search.
+//! `all_paths` enumerates every simple path up to a hop limit instead.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+use super::{BarqGraphDb, BfsTree};
+use crate::counterfactual::Counterfactual;
+use crate::graph::Direction;
+use crate::NodeId;
+
+/// Cost of an unweighted edge.
+pub(super) const DEFAULT_EDGE_WEIGHT: f32 = 1.0;
+
+/// Frontier entry ordered so the cheapest node is popped first.
+struct Frontier {
+    cost: f32,
+    node: NodeId,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so `BinaryHeap` acts as a min-heap; ties broken by ID
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+/// Cheapest routes from the starts of a `BfsTree` to every node it found.
+pub(crate) struct WeightedTree {
+    costs: HashMap<NodeId, f32>,
+    previous: HashMap<NodeId, NodeId>,
+}
+
+impl WeightedTree {
+    /// Returns the total edge weight from the closest start to `id`.
+    pub(crate) fn cost(&self, id: NodeId) -> f32 {
+        self.costs.get(&id).copied().unwrap_or(f32::INFINITY)
+    }
+
+    /// Returns the cheapest path from a start to `id`, inclusive.
+    pub(crate) fn path_to(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path = vec![id];
+        let mut current = id;
+        while let Some(&prev) = self.previous.get(&current) {
+            path.push(prev);
+            current = prev;
+        }
+        path.reverse();
+        path
+    }
+}
+
+impl BarqGraphDb {
+    /// Finds the cheapest directed path between two nodes using Dijkstra.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Start node ID
+    /// * `to` - Target node ID
+    ///
+    /// # Returns
+    ///
+    /// The path (including both endpoints) and its total weight, or
+    /// `None` if `to` is unreachable from `from`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// db.add_weighted_edge(1, 2, "ROAD", 4.0).unwrap();
+    /// db.add_weighted_edge(2, 3, "ROAD", 1.5).unwrap();
+    /// if let Some((path, cost)) = db.shortest_path_weighted(1, 3) {
+    ///     println!("{:?} costs {}", path, cost);
+    /// }
+    /// ```
+    pub fn shortest_path_weighted(&self, from: NodeId, to: NodeId) -> Option<(Vec<NodeId>, f32)> {
+        self.dijkstra(from, to, &Counterfactual::default())
+    }
+
+    /// Enumerates distinct simple paths between two nodes.
+    ///
+    /// Paths follow edge direction, never revisit a node, and are returned
+    /// shortest first; paths of equal length follow the configured
+    /// neighbor order. Parallel edges between the same nodes yield a
+    /// single path.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Start node ID
+    /// * `to` - Target node ID
+    /// * `max_hops` - Maximum number of edges per path
+    /// * `max_paths` - Maximum number of paths to return
+    ///
+    /// # Returns
+    ///
+    /// Up to `max_paths` paths, each including both endpoints. Empty if
+    /// `to` is not reachable within `max_hops`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// // Every chain of reasoning connecting claim 1 to conclusion 9
+    /// for path in db.all_paths(1, 9, 4, 20) {
+    ///     println!("{:?}", path);
+    /// }
+    /// ```
+    pub fn all_paths(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        max_hops: usize,
+        max_paths: usize,
+    ) -> Vec<Vec<NodeId>> {
+        if max_paths == 0 {
+            return Vec::new();
+        }
+        if from == to {
+            return vec![vec![from]];
+        }
+
+        // Hops from each node to the target, used to prune dead branches
+        let mut remaining = HashMap::from([(to, 0)]);
+        let mut queue = VecDeque::from([to]);
+        while let Some(node) = queue.pop_front() {
+            let hops = remaining[&node];
+            if hops == max_hops {
+                continue;
+            }
+            for &prev in self.incoming_neighbors(node).unwrap_or_default() {
+                remaining.entry(prev).or_insert_with(|| {
+                    queue.push_back(prev);
+                    hops + 1
+                });
+            }
+        }
+        let Some(&shortest) = remaining.get(&from) else {
+            return Vec::new();
+        };
+
+        // Depth-first search per exact length keeps memory linear in max_hops
+        let mut paths = Vec::new();
+        for length in shortest..=max_hops {
+            let mut path = vec![from];
+            let mut on_path = HashSet::from([from]);
+            let mut stack = vec![self.path_successors(from, length, &remaining)];
+            while let Some(candidates) = stack.last_mut() {
+                let Some(next) = candidates.pop_front() else {
+                    stack.pop();
+                    on_path.remove(&path.pop().unwrap_or(from));
+                    continue;
+                };
+                if on_path.contains(&next) {
+                    continue;
+                }
+                if next == to {
+                    if path.len() == length {
+                        let mut found = path.clone();
+                        found.push(to);
+                        paths.push(found);
+                        if paths.len() == max_paths {
+                            return paths;
+                        }
+                    }
+                    continue;
+                }
+                path.push(next);
+                on_path.insert(next);
+                let left = length + 1 - path.len();
+                stack.push(self.path_successors(next, left, &remaining));
+            }
+        }
+        paths
+    }
+
+    /// Returns the distinct successors of `node` that can still reach the
+    /// target within `hops_left - 1` further hops.
+    fn path_successors(
+        &self,
+        node: NodeId,
+        hops_left: usize,
+        remaining: &HashMap<NodeId, usize>,
+    ) -> VecDeque<NodeId> {
+        let mut seen = HashSet::new();
+        self.ordered_neighbors(node, Direction::Outgoing, &Counterfactual::default())
+            .into_iter()
+            .filter(|next| remaining.get(next).is_some_and(|&r| r < hops_left))
+            .filter(|&next| seen.insert(next))
+            .collect()
+    }
+
+    /// Runs Dijkstra from the starts of `tree`, staying on its nodes.
+    ///
+    /// Every node the BFS found stays reachable, at the cost of the
+    /// cheapest route among the found nodes, which may take more hops
+    /// than the BFS path.
+    pub(crate) fn weighted_tree(
+        &self,
+        tree: &BfsTree,
+        direction: Direction,
+        mask: &Counterfactual,
+    ) -> WeightedTree {
+        let mut weighted = WeightedTree {
+            costs: HashMap::new(),
+            previous: HashMap::new(),
+        };
+        let mut heap = BinaryHeap::new();
+        for &(start, _) in tree.order.iter().filter(|(_, depth)| *depth == 0) {
+            weighted.costs.insert(start, 0.0);
+            heap.push(Frontier {
+                cost: 0.0,
+                node: start,
+            });
+        }
+
+        while let Some(Frontier { cost, node }) = heap.pop() {
+            // Skip stale entries superseded by a cheaper route
+            if weighted.costs.get(&node).is_some_and(|&b| cost > b) {
+                continue;
+            }
+            for next in self.masked_neighbors(node, direction, mask) {
+                if !tree.parents.contains_key(&next) {
+                    continue;
+                }
+                let candidate = cost + self.step_cost(node, next, direction);
+                if weighted.costs.get(&next).is_none_or(|&b| candidate < b) {
+                    weighted.costs.insert(next, candidate);
+                    weighted.previous.insert(next, node);
+                    heap.push(Frontier {
+                        cost: candidate,
+                        node: next,
+                    });
+                }
+            }
+        }
+        weighted
+    }
+
+    /// Runs Dijkstra over the graph as modified by `mask`.
+    pub(crate) fn dijkstra(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        mask: &Counterfactual,
+    ) -> Option<(Vec<NodeId>, f32)> {
+        if (!self.nodes.contains_key(&from) && !self.graph.contains_node(from))
+            || mask.removes_node(from)
+        {
+            return None;
+        }
+
+        let mut best: HashMap<NodeId, f32> = HashMap::from([(from, 0.0)]);
+        let mut previous: HashMap<NodeId, NodeId> = HashMap::new();
+        let mut heap = BinaryHeap::from([Frontier {
+            cost: 0.0,
+            node: from,
+        }]);
+
+        while let Some(Frontier { cost, node }) = heap.pop() {
+            if node == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while let Some(&prev) = previous.get(&current) {
+                    path.push(prev);
+                    current = prev;
+                }
+                path.reverse();
+                return Some((path, cost));
+            }
+            // Skip stale entries superseded by a cheaper route
+            if best.get(&node).is_some_and(|&b| cost > b) {
+                continue;
+            }
+
+            for &next in self.graph.neighbors(node).into_iter().flatten() {
+                if mask.removes_node(next) || mask.removes_edge(node, next) {
+                    continue;
+                }
+                let weight = self.edge_weight(node, next).unwrap_or(DEFAULT_EDGE_WEIGHT);
+                let candidate = cost + weight;
+                if best.get(&next).is_none_or(|&b| candidate < b) {
+                    best.insert(next, candidate);
+                    previous.insert(next, node);
+                    heap.push(Frontier {
+                        cost: candidate,
+                        node: next,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::{BarqGraphDb, DbOptions};
+    use crate::Node;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_dijkstra_prefers_cheaper_longer_route() {
+        let dir = tempdir().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for id in 1..=4 {
+                db.append_node(Node::new(id, format!("n{}", id))).unwrap();
+            }
+            db.add_weighted_edge(1, 4, "DIRECT", 10.0).unwrap();
+            db.add_weighted_edge(1, 2, "HOP", 1.0).unwrap();
+            db.add_weighted_edge(2, 3, "HOP", 2.0).unwrap();
+            db.add_edge(3, 4, "HOP").unwrap();
+            assert!(db.add_weighted_edge(1, 3, "BAD", -1.0).is_err());
+        }
+
+        // Weights survive a reopen via the WAL
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.edge_weight(1, 4), Some(10.0));
+        let (path, cost) = db.shortest_path_weighted(1, 4).unwrap();
+        assert_eq!(path, vec![1, 2, 3, 4]);
+        assert_eq!(cost, 4.0);
+
+        assert_eq!(db.shortest_path_weighted(1, 1), Some((vec![1], 0.0)));
+        assert_eq!(db.shortest_path_weighted(4, 1), None);
+    }
+
+    #[test]
+    fn test_all_paths_shortest_first_with_limits() {
+        let dir = tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        for id in 1..=5 {
+            db.append_node(Node::new(id, format!("n{}", id))).unwrap();
+        }
+        // Two two-hop routes, one three-hop route and a cycle back to 1
+        db.add_edge(1, 2, "A").unwrap();
+        db.add_edge(1, 2, "B").unwrap();
+        db.add_edge(2, 5, "A").unwrap();
+        db.add_edge(1, 3, "A").unwrap();
+        db.add_edge(3, 5, "A").unwrap();
+        db.add_edge(3, 4, "A").unwrap();
+        db.add_edge(4, 5, "A").unwrap();
+        db.add_edge(4, 1, "A").unwrap();
+
+        let paths = db.all_paths(1, 5, 4, 10);
+        assert_eq!(paths, vec![vec![1, 2, 5], vec![1, 3, 5], vec![1, 3, 4, 5]]);
+        assert_eq!(db.all_paths(1, 5, 2, 10).len(), 2);
+        assert_eq!(db.all_paths(1, 5, 4, 1), vec![vec![1, 2, 5]]);
+        assert_eq!(db.all_paths(1, 5, 1, 10), Vec::<Vec<u64>>::new());
+        assert_eq!(db.all_paths(5, 1, 4, 10), Vec::<Vec<u64>>::new());
+        assert_eq!(db.all_paths(3, 3, 4, 10), vec![vec![3]]);
+    }
+}
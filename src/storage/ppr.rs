@@ -0,0 +1,103 @@
+//! Personalized PageRank over the subgraph explored by a hybrid query.
+//!
+//! A random walk from the start nodes follows an edge with probability
+//! `damping` and restarts otherwise; a node's rank is how often the walk
+//! is found there. Unlike hop count, this rewards nodes reachable along
+//! many paths.
+
+use std::collections::HashMap;
+
+use super::{BarqGraphDb, BfsTree};
+use crate::counterfactual::Counterfactual;
+use crate::graph::Direction;
+use crate::NodeId;
+
+/// Change in total rank below which the power iteration stops early.
+const CONVERGENCE: f64 = 1e-9;
+
+impl BarqGraphDb {
+    /// Computes personalized PageRank from the starts of `tree` over the
+    /// nodes it discovered.
+    ///
+    /// The walk restarts uniformly at the starts (the tree's depth-0
+    /// nodes) and follows `direction`, skipping what `mask` removes.
+    /// With `weighted`, a step's probability is proportional to
+    /// `1 / step_cost`, so cheap edges are likelier; otherwise all edges
+    /// are equally likely. Nodes without a way out hand their rank back
+    /// to the starts. Ranks sum to 1.
+    pub(super) fn personalized_pagerank(
+        &self,
+        tree: &BfsTree,
+        direction: Direction,
+        mask: &Counterfactual,
+        damping: f32,
+        iterations: usize,
+        weighted: bool,
+    ) -> HashMap<NodeId, f64> {
+        let index: HashMap<NodeId, usize> = tree
+            .order
+            .iter()
+            .enumerate()
+            .map(|(i, &(id, _))| (id, i))
+            .collect();
+        let starts: Vec<usize> = (0..tree.order.len())
+            .filter(|&i| tree.order[i].1 == 0)
+            .collect();
+        if starts.is_empty() {
+            return HashMap::new();
+        }
+
+        // Transition probabilities within the explored subgraph
+        let steps: Vec<Vec<(usize, f64)>> = tree
+            .order
+            .iter()
+            .map(|&(id, _)| {
+                let mut step: Vec<(usize, f64)> = self
+                    .masked_neighbors(id, direction, mask)
+                    .filter_map(|n| {
+                        let &j = index.get(&n)?;
+                        let weight = if weighted {
+                            1.0 / f64::from(self.step_cost(id, n, direction)).max(f64::EPSILON)
+                        } else {
+                            1.0
+                        };
+                        Some((j, weight))
+                    })
+                    .collect();
+                let total: f64 = step.iter().map(|(_, w)| w).sum();
+                step.iter_mut().for_each(|(_, w)| *w /= total);
+                step
+            })
+            .collect();
+
+        let damping = f64::from(damping.clamp(0.0, 1.0));
+        let restart = 1.0 / starts.len() as f64;
+        let mut ranks = vec![0.0; tree.order.len()];
+        starts.iter().for_each(|&s| ranks[s] = restart);
+        for _ in 0..iterations {
+            let mut next = vec![0.0; ranks.len()];
+            let mut returned = 1.0 - damping;
+            for (i, step) in steps.iter().enumerate() {
+                if step.is_empty() {
+                    returned += damping * ranks[i];
+                }
+                for &(j, p) in step {
+                    next[j] += damping * ranks[i] * p;
+                }
+            }
+            starts.iter().for_each(|&s| next[s] += returned * restart);
+
+            let change: f64 = next.iter().zip(&ranks).map(|(a, b)| (a - b).abs()).sum();
+            ranks = next;
+            if change < CONVERGENCE {
+                break;
+            }
+        }
+
+        tree.order
+            .iter()
+            .zip(ranks)
+            .map(|(&(id, _), rank)| (id, rank))
+            .collect()
+    }
+}
@@ -0,0 +1,120 @@
+//! Vector searches that run without holding the database.
+//!
+//! Servers share one `BarqGraphDb` behind a mutex, so a kNN search on a
+//! large HNSW index would hold every other request up. A `VectorSearcher`
+//! carries its own handle to the thread-safe vector index: a handler
+//! takes one under the lock, releases the lock and searches on tokio's
+//! blocking pool, letting concurrent queries proceed in parallel.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+
+use super::BarqGraphDb;
+use crate::metrics::{MetricsSink, Operation};
+use crate::vector::{self, VectorIndex};
+use crate::NodeId;
+
+/// Handle for kNN searches independent of the database lock.
+///
+/// Sees the index as it is at search time, including writes made after
+/// the handle was taken.
+#[derive(Clone)]
+pub struct VectorSearcher {
+    index: Arc<dyn VectorIndex>,
+    normalize: bool,
+    metrics: Option<Arc<dyn MetricsSink>>,
+}
+
+impl VectorSearcher {
+    /// Finds the k nearest neighbors, like `BarqGraphDb::knn_search`.
+    pub fn knn(&self, query: &[f32], k: usize) -> Vec<(NodeId, f32)> {
+        let started = Instant::now();
+        let results = if self.normalize {
+            let mut normalized = query.to_vec();
+            vector::normalize(&mut normalized);
+            self.index.knn(&normalized, k)
+        } else {
+            self.index.knn(query, k)
+        };
+        if let Some(sink) = &self.metrics {
+            sink.record(Operation::Knn, started.elapsed());
+        }
+        results
+    }
+
+    /// Finds the k nearest neighbors on tokio's blocking thread pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search task panicked or was cancelled.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    /// use std::sync::Arc;
+    /// use tokio::sync::Mutex;
+    ///
+    /// # async fn run() -> anyhow::Result<()> {
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db")))?;
+    /// let db = Arc::new(Mutex::new(db));
+    /// let searcher = db.lock().await.searcher();
+    /// let results = searcher.knn_async(vec![0.1, 0.2, 0.3], 5).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn knn_async(&self, query: Vec<f32>, k: usize) -> Result<Vec<(NodeId, f32)>> {
+        let searcher = self.clone();
+        tokio::task::spawn_blocking(move || searcher.knn(&query, k))
+            .await
+            .with_context(|| "kNN search task failed")
+    }
+}
+
+impl BarqGraphDb {
+    /// Returns a handle for kNN searches that don't need the database.
+    ///
+    /// Query normalization and the metrics sink follow the database
+    /// settings at the time of the call.
+    pub fn searcher(&self) -> VectorSearcher {
+        VectorSearcher {
+            index: Arc::clone(&self.vector_index),
+            normalize: self.options.normalize_embeddings,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{DbOptions, IndexType};
+    use tempfile::TempDir;
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_searcher_runs_without_the_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut opts =
+            DbOptions::new(temp_dir.path().to_path_buf()).with_normalized_embeddings(true);
+        opts.index_type = IndexType::Linear;
+        let db = Arc::new(Mutex::new(BarqGraphDb::open(opts).unwrap()));
+        {
+            let mut db = db.lock().await;
+            db.set_embedding(1, vec![1.0, 0.0]).unwrap();
+            db.set_embedding(2, vec![0.0, 3.0]).unwrap();
+        }
+
+        let searcher = db.lock().await.searcher();
+        // Holding the lock doesn't block the search, and later writes are visible
+        let mut guard = db.lock().await;
+        guard.set_embedding(3, vec![0.0, -1.0]).unwrap();
+        let results = searcher.knn_async(vec![0.0, 5.0], 3).await.unwrap();
+        assert_eq!(results, guard.knn_search(&[0.0, 5.0], 3));
+        assert_eq!(results[0], (2, 0.0));
+        assert_eq!(results.len(), 3);
+    }
+}
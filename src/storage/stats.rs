@@ -0,0 +1,144 @@
+//! Structural statistics of the stored graph.
+//!
+//! A cheap health check for agent memory: a falling density or a growing
+//! share of isolated nodes usually means ingestion stopped linking new
+//! knowledge to what is already stored.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use super::BarqGraphDb;
+use crate::NodeId;
+
+/// Distribution of node degrees (incoming plus outgoing edges).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DegreeStats {
+    /// Smallest degree.
+    pub min: usize,
+    /// Median degree.
+    pub p50: usize,
+    /// 90th percentile degree.
+    pub p90: usize,
+    /// 99th percentile degree.
+    pub p99: usize,
+    /// Largest degree.
+    pub max: usize,
+    /// Mean degree.
+    pub mean: f64,
+}
+
+impl DegreeStats {
+    /// Summarizes a list of degrees using nearest-rank percentiles.
+    fn from_degrees(mut degrees: Vec<usize>) -> Self {
+        if degrees.is_empty() {
+            return Self::default();
+        }
+        degrees.sort_unstable();
+        let n = degrees.len();
+        let percentile = |p: usize| degrees[(p * n).div_ceil(100).max(1) - 1];
+        Self {
+            min: degrees[0],
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: degrees[n - 1],
+            mean: degrees.iter().sum::<usize>() as f64 / n as f64,
+        }
+    }
+}
+
+/// Summary returned by `BarqGraphDb::graph_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GraphStats {
+    /// Stored nodes.
+    pub nodes: usize,
+    /// Directed edges, counting parallel edges.
+    pub edges: usize,
+    /// Edges divided by the number of possible directed edges between
+    /// stored nodes (0.0 to 1.0 without parallel edges).
+    pub density: f64,
+    /// Stored nodes without incoming or outgoing edges.
+    pub isolated_nodes: usize,
+    /// Distinct edge types on stored nodes' outgoing edges.
+    pub edge_types: usize,
+    /// Degree distribution over stored nodes.
+    pub degree: DegreeStats,
+}
+
+impl BarqGraphDb {
+    /// Computes structural statistics of the graph.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let stats = db.graph_stats();
+    /// if stats.isolated_nodes * 10 > stats.nodes {
+    ///     println!("over 10% of memories are unlinked");
+    /// }
+    /// ```
+    pub fn graph_stats(&self) -> GraphStats {
+        let degree_of = |id: &NodeId| {
+            self.graph.neighbors(*id).map_or(0, <[_]>::len)
+                + self.graph.incoming(*id).map_or(0, <[_]>::len)
+        };
+        let degrees: Vec<usize> = self.nodes.keys().map(degree_of).collect();
+        let edge_types: HashSet<&str> = self
+            .nodes
+            .values()
+            .flat_map(|n| n.edges.iter().map(|e| e.edge_type.as_str()))
+            .collect();
+
+        let nodes = self.nodes.len();
+        let edges = self.edge_count();
+        let possible = nodes.saturating_mul(nodes.saturating_sub(1));
+        GraphStats {
+            nodes,
+            edges,
+            density: if possible == 0 {
+                0.0
+            } else {
+                edges as f64 / possible as f64
+            },
+            isolated_nodes: degrees.iter().filter(|&&d| d == 0).count(),
+            edge_types: edge_types.len(),
+            degree: DegreeStats::from_degrees(degrees),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::Node;
+
+    #[test]
+    fn test_graph_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        assert_eq!(db.graph_stats(), GraphStats::default());
+
+        for id in 1..=5 {
+            db.append_node(Node::new(id, format!("n{}", id))).unwrap();
+        }
+        // Star around 1; node 5 is isolated
+        db.add_edge(1, 2, "KNOWS").unwrap();
+        db.add_edge(1, 3, "KNOWS").unwrap();
+        db.add_edge(4, 1, "CITES").unwrap();
+
+        let stats = db.graph_stats();
+        assert_eq!((stats.nodes, stats.edges), (5, 3));
+        assert!((stats.density - 3.0 / 20.0).abs() < 1e-9);
+        assert_eq!(stats.isolated_nodes, 1);
+        assert_eq!(stats.edge_types, 2);
+        assert_eq!(stats.degree.min, 0);
+        assert_eq!(stats.degree.p50, 1);
+        assert_eq!(stats.degree.max, 3);
+        assert!((stats.degree.mean - 1.2).abs() < 1e-9);
+    }
+}
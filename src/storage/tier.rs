@@ -0,0 +1,459 @@
+//! Cold tier storage and hydration.
+//!
+//! A tiering pass rewrites `cold.bin` with one gzip-compressed JSON
+//! payload (label and embedding) per cold node and strips those fields
+//! from the in-memory node. Reads through `get_node` hydrate a cold node
+//! into a per-slot `OnceLock`, so hydration works behind `&self`; the next
+//! pass promotes hydrated nodes back to the hot tier. Writes that touch a
+//! cold node's payload promote it first, and edge changes drop its
+//! hydrated copy so it is rebuilt from the current stub.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, Weak};
+use std::thread::JoinHandle;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::BarqGraphDb;
+use crate::memory;
+use crate::metrics::{BackgroundTask, Operation};
+use crate::tier::{TierReport, TierStats};
+use crate::{Node, NodeId};
+
+/// Cold-tier bookkeeping held by the database.
+#[derive(Default)]
+pub(super) struct ColdStore {
+    /// Location of each cold payload in the cold file.
+    slots: HashMap<NodeId, ColdSlot>,
+    /// When nodes were last promoted back from the cold tier.
+    promoted_at: HashMap<NodeId, u64>,
+    /// Hydrations since open.
+    hydrations: AtomicU64,
+    /// Hydrations that failed to read the cold file since open.
+    hydration_failures: AtomicU64,
+    /// Size of the cold file.
+    file_bytes: u64,
+}
+
+impl ColdStore {
+    /// Returns the embedding dimensions held by cold payloads.
+    pub(super) fn dims(&self) -> impl Iterator<Item = usize> + '_ {
+        self.slots.values().map(|s| s.dims).filter(|&d| d > 0)
+    }
+}
+
+/// A cold payload and, once read, the hydrated node.
+struct ColdSlot {
+    offset: u64,
+    len: u64,
+    /// Embedding dimension, kept for memory accounting.
+    dims: usize,
+    hydrated: OnceLock<Node>,
+}
+
+/// Fields moved out of a node by a tiering pass.
+#[derive(Serialize, Deserialize)]
+struct ColdPayload {
+    label: String,
+    embedding: Vec<f32>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl BarqGraphDb {
+    /// Moves nodes that have been untouched for longer than the
+    /// configured `TierPolicy` to the cold tier.
+    ///
+    /// A node counts as touched when it is written (its `timestamp`) and
+    /// when it is hydrated from the cold tier; cold nodes hydrated since
+    /// the previous pass are promoted back to the hot tier first. Does
+    /// nothing without a tier policy or for in-memory databases.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cold file cannot be read or rewritten; the
+    /// previous tiers are left intact.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::tier::TierPolicy;
+    /// use std::path::PathBuf;
+    ///
+    /// let opts = DbOptions::new(PathBuf::from("./my_db")).with_tier_policy(TierPolicy::days(30));
+    /// let mut db = BarqGraphDb::open(opts).unwrap();
+    /// let report = db.run_tiering().unwrap();
+    /// println!("{} nodes moved to cold storage", report.demoted);
+    /// ```
+    pub fn run_tiering(&mut self) -> Result<TierReport> {
+        let Some(policy) = self.options.tier_policy.filter(|_| !self.options.in_memory) else {
+            return Ok(TierReport::default());
+        };
+        let now = unix_now();
+        let cutoff = now.saturating_sub(policy.cold_after.as_secs());
+
+        // Nodes read since the last pass are warm again
+        let hydrated: Vec<NodeId> = self
+            .cold
+            .slots
+            .iter()
+            .filter(|(_, slot)| slot.hydrated.get().is_some())
+            .map(|(&id, _)| id)
+            .collect();
+        for &id in &hydrated {
+            self.promote(id)?;
+        }
+
+        let mut demote: Vec<NodeId> = self
+            .nodes
+            .values()
+            .filter(|n| !self.cold.slots.contains_key(&n.id))
+            .filter(|n| !n.label.is_empty() || !n.embedding.is_empty())
+            .filter(|n| {
+                let promoted = self.cold.promoted_at.get(&n.id).copied().unwrap_or(0);
+                n.timestamp.max(promoted) < cutoff
+            })
+            .map(|n| n.id)
+            .collect();
+        demote.sort_unstable();
+        if demote.is_empty() && hydrated.is_empty() {
+            return Ok(TierReport {
+                cold_file_bytes: self.cold.file_bytes,
+                ..Default::default()
+            });
+        }
+
+        // Rewrite the cold file, dropping payloads of promoted nodes
+        let path = self.options.cold_file();
+        let tmp = path.with_extension("bin.tmp");
+        let mut out = BufWriter::new(
+            File::create(&tmp).with_context(|| format!("Failed to create cold file: {:?}", tmp))?,
+        );
+        let mut offset = 0;
+        let mut moved: Vec<(NodeId, u64)> = Vec::with_capacity(self.cold.slots.len());
+        if !self.cold.slots.is_empty() {
+            let mut old = File::open(&path)
+                .with_context(|| format!("Failed to open cold file: {:?}", path))?;
+            for (&id, slot) in &self.cold.slots {
+                let mut buf = vec![0; slot.len as usize];
+                old.seek(SeekFrom::Start(slot.offset))?;
+                old.read_exact(&mut buf)
+                    .with_context(|| format!("Failed to read cold payload of node {}", id))?;
+                out.write_all(&buf)?;
+                moved.push((id, offset));
+                offset += slot.len;
+            }
+        }
+        let mut added = Vec::with_capacity(demote.len());
+        for &id in &demote {
+            let node = &self.nodes[&id];
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            serde_json::to_writer(
+                &mut encoder,
+                &ColdPayload {
+                    label: node.label.clone(),
+                    embedding: node.embedding.clone(),
+                },
+            )?;
+            let bytes = encoder.finish()?;
+            out.write_all(&bytes)?;
+            added.push((
+                id,
+                ColdSlot {
+                    offset,
+                    len: bytes.len() as u64,
+                    dims: node.embedding.len(),
+                    hydrated: OnceLock::new(),
+                },
+            ));
+            offset += bytes.len() as u64;
+        }
+        let file = out.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()
+            .with_context(|| "Failed to sync cold file")?;
+        fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to replace cold file: {:?}", path))?;
+
+        // The new file is in place; only now update offsets and strip nodes
+        for (id, new_offset) in moved {
+            if let Some(slot) = self.cold.slots.get_mut(&id) {
+                slot.offset = new_offset;
+            }
+        }
+        for (id, slot) in added {
+            if let Some(node) = self.nodes.get_mut(&id) {
+                node.label = String::new();
+                node.embedding = Vec::new();
+            }
+            self.cold.promoted_at.remove(&id);
+            self.cold.slots.insert(id, slot);
+        }
+        self.cold.file_bytes = offset;
+        self.recompute_memory_usage();
+
+        Ok(TierReport {
+            demoted: demote.len(),
+            promoted: hydrated.len(),
+            cold_file_bytes: offset,
+        })
+    }
+
+    /// Returns node counts per tier and hydration statistics.
+    pub fn tier_stats(&self) -> TierStats {
+        let cold_nodes = self.cold.slots.len();
+        TierStats {
+            hot_nodes: self.nodes.len() - cold_nodes,
+            cold_nodes,
+            hydrated_nodes: self
+                .cold
+                .slots
+                .values()
+                .filter(|s| s.hydrated.get().is_some())
+                .count(),
+            hydrations: self.cold.hydrations.load(Ordering::Relaxed),
+            hydration_failures: self.cold.hydration_failures.load(Ordering::Relaxed),
+            cold_file_bytes: self.cold.file_bytes,
+        }
+    }
+
+    /// Spawns a thread that calls `run_tiering` at the policy's interval.
+    ///
+    /// Failed passes are reported to the metrics sink's
+    /// `background_error`; `tier_stats` shows the effect of passes that
+    /// succeed. The thread exits once the database is dropped, or
+    /// immediately if the database has no tier policy.
+    pub fn spawn_tiering_thread(db: &Arc<Mutex<BarqGraphDb>>) -> JoinHandle<()> {
+        let weak = Arc::downgrade(db);
+        std::thread::spawn(move || run_tiering_thread(weak))
+    }
+
+    /// Returns the full node for an in-memory node, hydrating it from the
+    /// cold tier if needed.
+    ///
+    /// Returns `None` if the cold payload cannot be read, rather than the
+    /// stub without its label and embedding; the failure is counted in
+    /// `TierStats::hydration_failures` and the next access retries.
+    pub(super) fn hydrate<'a>(&'a self, stub: &'a Node) -> Option<&'a Node> {
+        let Some(slot) = self.cold.slots.get(&stub.id) else {
+            return Some(stub);
+        };
+        if let Some(node) = slot.hydrated.get() {
+            return Some(node);
+        }
+        let started = Instant::now();
+        match self.read_cold(stub, slot) {
+            Ok(node) => {
+                self.cold.hydrations.fetch_add(1, Ordering::Relaxed);
+                self.observe(Operation::Hydrate, started);
+                Some(slot.hydrated.get_or_init(|| node))
+            }
+            Err(_) => {
+                self.cold.hydration_failures.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Returns the full node without caching it, for snapshots.
+    pub(super) fn load_full(&self, stub: &Node) -> Result<Cow<'_, Node>> {
+        match self.cold.slots.get(&stub.id) {
+            Some(slot) => match slot.hydrated.get() {
+                Some(node) => Ok(Cow::Borrowed(node)),
+                None => self.read_cold(stub, slot).map(Cow::Owned),
+            },
+            None => Ok(Cow::Owned(stub.clone())),
+        }
+    }
+
+    /// Moves a cold node back to the hot tier.
+    pub(super) fn promote(&mut self, id: NodeId) -> Result<()> {
+        let Some(slot) = self.cold.slots.get(&id) else {
+            return Ok(());
+        };
+        let full = match slot.hydrated.get() {
+            Some(node) => node.clone(),
+            None => self.read_cold(&self.nodes[&id], slot)?,
+        };
+        self.cold.slots.remove(&id);
+        self.cold.promoted_at.insert(id, unix_now());
+        // Vector memory already counts cold embeddings
+        self.memory.nodes += memory::node_bytes(&full) - memory::node_bytes(&self.nodes[&id]);
+        self.nodes.insert(id, full);
+        Ok(())
+    }
+
+    /// Drops the hydrated copy of a cold node after its edges changed.
+    pub(super) fn invalidate_hydrated(&mut self, id: NodeId) {
+        if let Some(slot) = self.cold.slots.get_mut(&id) {
+            slot.hydrated.take();
+        }
+    }
+
+    /// Reads a cold payload and merges it into a copy of the stub.
+    fn read_cold(&self, stub: &Node, slot: &ColdSlot) -> Result<Node> {
+        let path = self.options.cold_file();
+        let mut file =
+            File::open(&path).with_context(|| format!("Failed to open cold file: {:?}", path))?;
+        file.seek(SeekFrom::Start(slot.offset))?;
+        let payload: ColdPayload = serde_json::from_reader(GzDecoder::new(file.take(slot.len)))
+            .with_context(|| format!("Failed to decode cold payload of node {}", stub.id))?;
+        let mut node = stub.clone();
+        node.label = payload.label;
+        node.embedding = payload.embedding;
+        Ok(node)
+    }
+}
+
+/// Body of the background tiering thread.
+fn run_tiering_thread(db: Weak<Mutex<BarqGraphDb>>) {
+    let Some(interval) = db
+        .upgrade()
+        .and_then(|db| db.blocking_lock().options.tier_policy)
+        .map(|p| p.check_interval)
+    else {
+        return;
+    };
+
+    loop {
+        std::thread::sleep(interval);
+        let Some(db) = db.upgrade() else {
+            break;
+        };
+        let mut db = db.blocking_lock();
+        if let Err(e) = db.run_tiering() {
+            db.report_background_error(BackgroundTask::Tiering, &e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::tier::TierPolicy;
+
+    #[test]
+    fn test_cold_nodes_hydrate_and_promote() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf())
+            .with_tier_policy(TierPolicy::new(Duration::from_secs(3600)));
+        let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+        for id in 1..=3 {
+            let mut node = Node::new(id, format!("memory {}", id));
+            node.embedding = vec![id as f32, 0.0];
+            if id < 3 {
+                node.timestamp = 0;
+            }
+            db.append_node(node).unwrap();
+        }
+        db.add_edge(1, 2, "RELATES").unwrap();
+        let hot_memory = db.memory_usage().nodes;
+
+        let report = db.run_tiering().unwrap();
+        assert_eq!(report.demoted, 2);
+        assert!(opts.cold_file().exists());
+        assert!(db.memory_usage().nodes < hot_memory);
+        assert_eq!(db.nodes()[&1].label, "");
+        assert_eq!(db.bfs_hops(1, 1), vec![1, 2]);
+        assert_eq!(db.knn_search(&[2.0, 0.0], 1)[0].0, 2);
+        db.checkpoint().unwrap();
+
+        // Reads hydrate transparently; edge changes stay visible
+        assert_eq!(db.get_node(1).unwrap().label, "memory 1");
+        db.add_edge(1, 3, "RELATES").unwrap();
+        let node = db.get_node(1).unwrap();
+        assert_eq!(node.embedding, vec![1.0, 0.0]);
+        assert_eq!(node.edges.len(), 2);
+        let stats = db.tier_stats();
+        assert_eq!((stats.hot_nodes, stats.cold_nodes), (1, 2));
+        assert_eq!(stats.hydrations, 2);
+
+        // Writing a cold node promotes it; hydrated nodes are promoted by the next pass
+        db.set_embedding(2, vec![5.0, 5.0]).unwrap();
+        assert_eq!(db.get_node(2).unwrap().label, "memory 2");
+        let report = db.run_tiering().unwrap();
+        assert_eq!((report.demoted, report.promoted), (0, 1));
+        assert_eq!(db.tier_stats().cold_nodes, 0);
+
+        // The checkpoint taken while nodes were cold kept their payloads
+        drop(db);
+        let db = BarqGraphDb::open(opts).unwrap();
+        assert_eq!(db.tier_stats().cold_nodes, 0);
+        assert_eq!(db.nodes()[&1].label, "memory 1");
+        assert_eq!(db.get_node(2).unwrap().embedding, vec![5.0, 5.0]);
+        assert_eq!(db.get_node(1).unwrap().edges.len(), 2);
+    }
+
+    #[test]
+    fn test_unreadable_cold_payload_is_not_served_blank() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf())
+            .with_tier_policy(TierPolicy::new(Duration::from_secs(3600)));
+        let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+        db.append_node(Node::with_timestamp(1, "old".to_string(), 0))
+            .unwrap();
+        db.append_node(Node::new(2, "new".to_string())).unwrap();
+        assert_eq!(db.run_tiering().unwrap().demoted, 1);
+
+        fs::write(opts.cold_file(), b"garbage").unwrap();
+        assert!(db.get_node(1).is_none());
+        let labels: Vec<_> = db.list_nodes().iter().map(|n| n.label.clone()).collect();
+        assert_eq!(labels, vec!["new"]);
+        let stats = db.tier_stats();
+        assert_eq!((stats.hydrations, stats.hydration_failures), (0, 2));
+    }
+
+    #[test]
+    fn test_tiering_thread_reports_failures_to_sink() {
+        #[derive(Default)]
+        struct FailureLog(std::sync::Mutex<Vec<BackgroundTask>>);
+
+        impl crate::metrics::MetricsSink for FailureLog {
+            fn record(&self, _op: Operation, _elapsed: Duration) {}
+
+            fn background_error(&self, task: BackgroundTask, _error: &anyhow::Error) {
+                self.0.lock().unwrap().push(task);
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let policy = TierPolicy::new(Duration::from_secs(3600))
+            .with_check_interval(Duration::from_millis(10));
+        let opts = DbOptions::new(dir.path().to_path_buf()).with_tier_policy(policy);
+        let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+        db.append_node(Node::with_timestamp(1, "old".to_string(), 0))
+            .unwrap();
+        let log = Arc::new(FailureLog::default());
+        db.set_metrics_sink(Some(log.clone()));
+        // A directory in the way of the rewritten cold file
+        fs::create_dir(opts.cold_file().with_extension("bin.tmp")).unwrap();
+
+        let db = Arc::new(Mutex::new(db));
+        let handle = BarqGraphDb::spawn_tiering_thread(&db);
+        while log.0.lock().unwrap().is_empty() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(log.0.lock().unwrap()[0], BackgroundTask::Tiering);
+        assert_eq!(db.blocking_lock().tier_stats().cold_nodes, 0);
+        drop(db);
+        handle.join().unwrap();
+    }
+}
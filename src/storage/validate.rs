@@ -0,0 +1,148 @@
+//! Dry-run validation of bulk imports.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use super::BarqGraphDb;
+use crate::{Edge, Node, NodeId};
+
+/// Problems found by `BarqGraphDb::validate_batch`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchValidation {
+    /// IDs that appear more than once within the batch.
+    pub duplicate_ids: Vec<NodeId>,
+    /// Batch IDs that already exist and would be overwritten.
+    pub existing_ids: Vec<NodeId>,
+    /// Edges whose endpoints are neither in the batch nor in the database.
+    pub dangling_edges: Vec<Edge>,
+    /// Nodes whose embedding length differs from `expected_dimension`.
+    pub dimension_mismatches: Vec<(NodeId, usize)>,
+    /// Embedding dimension of the database, or of the batch if the database has none.
+    pub expected_dimension: Option<usize>,
+}
+
+impl BatchValidation {
+    /// Returns true if the batch can be imported without integrity problems.
+    ///
+    /// Overwrites of existing IDs are reported but not treated as errors.
+    pub fn is_valid(&self) -> bool {
+        self.duplicate_ids.is_empty()
+            && self.dangling_edges.is_empty()
+            && self.dimension_mismatches.is_empty()
+    }
+}
+
+impl BarqGraphDb {
+    /// Validates a bulk import without writing anything.
+    ///
+    /// Checks the batch for duplicate node IDs, edges (standalone or
+    /// embedded in nodes) pointing at unknown nodes, and embeddings
+    /// whose dimension differs from the data already stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `nodes` - Nodes that would be appended
+    /// * `edges` - Edges that would be added after the nodes
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::{Edge, Node};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let nodes = vec![Node::new(1, "a".to_string())];
+    /// let edges = vec![Edge::new(1, 2, "NEXT")];
+    /// let report = db.validate_batch(&nodes, &edges);
+    /// assert!(!report.is_valid());
+    /// ```
+    pub fn validate_batch(&self, nodes: &[Node], edges: &[Edge]) -> BatchValidation {
+        let mut report = BatchValidation::default();
+
+        let mut seen = HashSet::new();
+        for node in nodes {
+            if !seen.insert(node.id) {
+                if !report.duplicate_ids.contains(&node.id) {
+                    report.duplicate_ids.push(node.id);
+                }
+            } else if self.nodes.contains_key(&node.id) {
+                report.existing_ids.push(node.id);
+            }
+        }
+
+        let known = |id: NodeId| seen.contains(&id) || self.nodes.contains_key(&id);
+        let embedded_edges = nodes.iter().flat_map(|n| n.edges.iter());
+        for edge in embedded_edges.chain(edges.iter()) {
+            if !known(edge.from) || !known(edge.to) {
+                report.dangling_edges.push(edge.clone());
+            }
+        }
+
+        report.expected_dimension = self
+            .nodes
+            .values()
+            .map(|n| n.embedding.len())
+            .chain(self.orphan_embeddings.values().map(|v| v.len()))
+            .chain(nodes.iter().map(|n| n.embedding.len()))
+            .find(|&len| len > 0);
+        if let Some(expected) = report.expected_dimension {
+            for node in nodes {
+                let dim = node.embedding.len();
+                if dim != 0 && dim != expected {
+                    report.dimension_mismatches.push((node.id, dim));
+                }
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+
+    fn embedded(id: NodeId, dim: usize) -> Node {
+        let mut node = Node::new(id, format!("n{}", id));
+        node.embedding = vec![0.5; dim];
+        node
+    }
+
+    #[test]
+    fn test_validate_batch_reports_problems() {
+        let mut db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        db.append_node(embedded(1, 3)).unwrap();
+
+        let nodes = vec![
+            embedded(1, 3),
+            embedded(2, 3),
+            embedded(2, 3),
+            embedded(3, 4),
+        ];
+        let edges = vec![Edge::new(1, 3, "OK"), Edge::new(2, 9, "DANGLING")];
+        let report = db.validate_batch(&nodes, &edges);
+
+        assert!(!report.is_valid());
+        assert_eq!(report.duplicate_ids, vec![2]);
+        assert_eq!(report.existing_ids, vec![1]);
+        assert_eq!(report.dangling_edges.len(), 1);
+        assert_eq!(report.dangling_edges[0].to, 9);
+        assert_eq!(report.expected_dimension, Some(3));
+        assert_eq!(report.dimension_mismatches, vec![(3, 4)]);
+
+        // Nothing was written
+        assert_eq!(db.node_count(), 1);
+        assert_eq!(db.edge_count(), 0);
+    }
+
+    #[test]
+    fn test_validate_clean_batch() {
+        let db = BarqGraphDb::open(DbOptions::in_memory()).unwrap();
+        let nodes = vec![embedded(1, 2), embedded(2, 2)];
+        let edges = vec![Edge::new(1, 2, "NEXT")];
+        assert!(db.validate_batch(&nodes, &edges).is_valid());
+    }
+}
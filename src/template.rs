@@ -0,0 +1,224 @@
+//! Saved, parameterized query templates.
+//!
+//! A template names a kNN, hybrid or traversal query together with its
+//! default parameters. Templates are persisted in the WAL alongside the
+//! graph, so retrieval behavior can be tuned and versioned without
+//! redeploying the agents that call it. Callers run a template by name
+//! and may override any default per request.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::error::BarqError;
+use crate::hybrid::{HybridParams, HybridResult};
+use crate::storage::BarqGraphDb;
+use crate::NodeId;
+
+/// Query shape and default parameters of a template.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TemplateQuery {
+    /// Vector similarity search.
+    Knn {
+        /// Default number of results.
+        k: usize,
+    },
+    /// Hybrid vector + graph query.
+    Hybrid {
+        /// Default BFS depth from the start node.
+        max_hops: usize,
+        /// Default number of results.
+        k: usize,
+        /// Default weight of vector similarity.
+        alpha: f32,
+        /// Default weight of graph proximity.
+        beta: f32,
+    },
+    /// BFS traversal from a start node.
+    Traversal {
+        /// Default BFS depth.
+        max_hops: usize,
+    },
+}
+
+/// A named query stored in the database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryTemplate {
+    /// Unique name the template is executed by.
+    pub name: String,
+    /// Optional human-readable description.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Version, starting at 1 and incremented each time the template is saved.
+    #[serde(default)]
+    pub version: u32,
+    /// Query shape and default parameters.
+    pub query: TemplateQuery,
+}
+
+impl QueryTemplate {
+    /// Creates an unsaved template; its version is assigned on save.
+    pub fn new(name: impl Into<String>, query: TemplateQuery) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            version: 0,
+            query,
+        }
+    }
+}
+
+/// Per-request inputs and overrides for running a template.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemplateParams {
+    /// Query vector (required for kNN and hybrid templates).
+    pub vector: Option<Vec<f32>>,
+    /// Start node (required for hybrid and traversal templates).
+    pub start: Option<NodeId>,
+    /// Overrides the template's `k`.
+    pub k: Option<usize>,
+    /// Overrides the template's `max_hops`.
+    pub max_hops: Option<usize>,
+    /// Overrides the template's `alpha`.
+    pub alpha: Option<f32>,
+    /// Overrides the template's `beta`.
+    pub beta: Option<f32>,
+}
+
+/// Results of running a template.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "results", rename_all = "snake_case")]
+pub enum TemplateOutput {
+    /// `(node ID, distance)` pairs, closest first.
+    Knn(Vec<(NodeId, f32)>),
+    /// Hybrid results, best score first.
+    Hybrid(Vec<HybridResult>),
+    /// Node IDs in BFS order.
+    Traversal(Vec<NodeId>),
+}
+
+/// Returns a required parameter or an error naming it.
+fn require<T>(value: Option<T>, name: &str, template: &str) -> Result<T> {
+    value.ok_or_else(|| {
+        BarqError::InvalidOperation(format!(
+            "Template '{}' requires parameter '{}'",
+            template, name
+        ))
+        .into()
+    })
+}
+
+impl BarqGraphDb {
+    /// Runs a saved template, applying per-request overrides.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the template to run
+    /// * `params` - Query inputs and overrides of the template defaults
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no template has this name or a parameter the
+    /// query needs (vector or start node) is missing.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::template::{QueryTemplate, TemplateParams, TemplateQuery};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// db.save_template(QueryTemplate::new("similar", TemplateQuery::Knn { k: 5 }))
+    ///     .unwrap();
+    /// let params = TemplateParams {
+    ///     vector: Some(vec![0.1, 0.2]),
+    ///     ..Default::default()
+    /// };
+    /// let output = db.run_template("similar", &params).unwrap();
+    /// ```
+    pub fn run_template(&self, name: &str, params: &TemplateParams) -> Result<TemplateOutput> {
+        let template = self
+            .get_template(name)
+            .ok_or_else(|| BarqError::TemplateNotFound(name.to_string()))?;
+
+        Ok(match template.query {
+            TemplateQuery::Knn { k } => {
+                let vector = require(params.vector.as_deref(), "vector", name)?;
+                TemplateOutput::Knn(self.knn_search(vector, params.k.unwrap_or(k)))
+            }
+            TemplateQuery::Hybrid {
+                max_hops,
+                k,
+                alpha,
+                beta,
+            } => {
+                let vector = require(params.vector.as_deref(), "vector", name)?;
+                let start = require(params.start, "start", name)?;
+                let hybrid =
+                    HybridParams::new(params.alpha.unwrap_or(alpha), params.beta.unwrap_or(beta));
+                TemplateOutput::Hybrid(self.hybrid_query(
+                    vector,
+                    start,
+                    params.max_hops.unwrap_or(max_hops),
+                    params.k.unwrap_or(k),
+                    hybrid,
+                ))
+            }
+            TemplateQuery::Traversal { max_hops } => {
+                let start = require(params.start, "start", name)?;
+                TemplateOutput::Traversal(self.bfs_hops(start, params.max_hops.unwrap_or(max_hops)))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::Node;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_templates_persist_and_run_with_overrides() {
+        let dir = tempdir().unwrap();
+        let opts = DbOptions::new(dir.path().to_path_buf());
+        {
+            let mut db = BarqGraphDb::open(opts.clone()).unwrap();
+            for id in 1..=3 {
+                db.append_node(Node::new(id, format!("n{}", id))).unwrap();
+            }
+            db.add_edge(1, 2, "X").unwrap();
+            db.add_edge(2, 3, "X").unwrap();
+            let template = QueryTemplate::new("walk", TemplateQuery::Traversal { max_hops: 1 });
+            assert_eq!(db.save_template(template.clone()).unwrap(), 1);
+            assert_eq!(db.save_template(template).unwrap(), 2);
+            db.save_template(QueryTemplate::new("tmp", TemplateQuery::Knn { k: 1 }))
+                .unwrap();
+            assert!(db.delete_template("tmp").unwrap());
+        }
+
+        let db = BarqGraphDb::open(opts).unwrap();
+        let names: Vec<_> = db.list_templates().iter().map(|t| &t.name).collect();
+        assert_eq!(names, vec!["walk"]);
+        assert_eq!(db.get_template("walk").unwrap().version, 2);
+
+        let mut params = TemplateParams {
+            start: Some(1),
+            ..Default::default()
+        };
+        match db.run_template("walk", &params).unwrap() {
+            TemplateOutput::Traversal(ids) => assert_eq!(ids.len(), 2),
+            other => panic!("unexpected output: {:?}", other),
+        }
+        params.max_hops = Some(2);
+        match db.run_template("walk", &params).unwrap() {
+            TemplateOutput::Traversal(ids) => assert_eq!(ids.len(), 3),
+            other => panic!("unexpected output: {:?}", other),
+        }
+        assert!(db.run_template("walk", &TemplateParams::default()).is_err());
+        assert!(db.run_template("tmp", &params).is_err());
+    }
+}
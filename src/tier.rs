@@ -0,0 +1,90 @@
+//! Cold storage tiering.
+//!
+//! Long-lived agent memories are mostly written once and rarely read
+//! again. With a `TierPolicy` configured, nodes untouched for longer than
+//! `cold_after` have their label and embedding moved to a compressed
+//! cold file in the database directory. IDs, tags, edges and adjacency
+//! stay in memory, so traversals never touch disk, and the vector index
+//! keeps its own copy of each embedding, so kNN is unaffected.
+//!
+//! Cold nodes are hydrated transparently by `BarqGraphDb::get_node` and
+//! promoted back to the hot tier on the next tiering pass. The cold file
+//! only caches state already held by the WAL and snapshot; it is
+//! discarded on open and rewritten by every pass.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Default interval of the background tiering thread.
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// When nodes move to the cold tier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TierPolicy {
+    /// Age after which an untouched node moves to the cold tier.
+    pub cold_after: Duration,
+    /// Interval of the background thread started by `spawn_tiering_thread`.
+    pub check_interval: Duration,
+}
+
+impl TierPolicy {
+    /// Creates a policy that moves nodes untouched for `cold_after`.
+    pub fn new(cold_after: Duration) -> Self {
+        Self {
+            cold_after,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+        }
+    }
+
+    /// Creates a policy that moves nodes untouched for `days` days.
+    pub fn days(days: u64) -> Self {
+        Self::new(Duration::from_secs(days * 24 * 60 * 60))
+    }
+
+    /// Sets how often the background thread runs a tiering pass.
+    pub fn with_check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = interval;
+        self
+    }
+}
+
+/// Summary of one tiering pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TierReport {
+    /// Nodes moved to the cold tier by this pass.
+    pub demoted: usize,
+    /// Hydrated cold nodes moved back to the hot tier.
+    pub promoted: usize,
+    /// Size of the rewritten cold file.
+    pub cold_file_bytes: u64,
+}
+
+/// Current state of the storage tiers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TierStats {
+    /// Nodes whose payload is held in memory.
+    pub hot_nodes: usize,
+    /// Nodes whose payload lives in the cold file.
+    pub cold_nodes: usize,
+    /// Cold nodes hydrated since the last tiering pass.
+    pub hydrated_nodes: usize,
+    /// Total hydrations since the database was opened.
+    pub hydrations: u64,
+    /// Hydrations that could not read the cold file since the database
+    /// was opened; the affected nodes were left out of the reads.
+    pub hydration_failures: u64,
+    /// Size of the cold file.
+    pub cold_file_bytes: u64,
+}
+
+impl TierStats {
+    /// Returns the fraction of cold nodes hydrated since the last pass.
+    pub fn hydration_rate(&self) -> f64 {
+        if self.cold_nodes == 0 {
+            0.0
+        } else {
+            self.hydrated_nodes as f64 / self.cold_nodes as f64
+        }
+    }
+}
@@ -0,0 +1,492 @@
+//! Lazy, iterator-based graph traversal.
+//!
+//! `BarqGraphDb::traverse` returns a builder that configures the
+//! traversal order, depth limit and edge direction. Nodes are discovered
+//! one at a time as the iterator is advanced, so callers can stop early
+//! or filter on the fly without materializing the whole visited set the
+//! way `bfs_hops` does. `random_walk` samples stochastic paths instead,
+//! and `is_reachable`, `descendants` and `ancestors` answer connectivity
+//! questions without listing paths.
+
+use std::collections::{HashSet, VecDeque};
+
+use rand::Rng;
+
+use crate::counterfactual::Counterfactual;
+use crate::graph::Direction;
+use crate::storage::BarqGraphDb;
+use crate::NodeId;
+
+/// Order in which a traversal visits nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraversalOrder {
+    /// Breadth-first: all nodes at depth `d` before any at `d + 1`.
+    #[default]
+    BreadthFirst,
+    /// Depth-first (pre-order), following neighbors in the database's
+    /// configured `NeighborOrder`.
+    DepthFirst,
+}
+
+/// Traversal configuration created by `BarqGraphDb::traverse`.
+#[derive(Clone, Copy)]
+pub struct Traversal<'a> {
+    db: &'a BarqGraphDb,
+    start: NodeId,
+    order: TraversalOrder,
+    max_hops: Option<usize>,
+    direction: Direction,
+    window: Option<(u64, u64)>,
+}
+
+impl<'a> Traversal<'a> {
+    /// Visits nodes breadth-first (the default).
+    pub fn bfs(mut self) -> Self {
+        self.order = TraversalOrder::BreadthFirst;
+        self
+    }
+
+    /// Visits nodes depth-first.
+    pub fn dfs(mut self) -> Self {
+        self.order = TraversalOrder::DepthFirst;
+        self
+    }
+
+    /// Limits the traversal to nodes within `max_hops` edges of the start.
+    pub fn max_hops(mut self, max_hops: usize) -> Self {
+        self.max_hops = Some(max_hops);
+        self
+    }
+
+    /// Sets the edge direction to follow (outgoing by default).
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Only visits nodes whose timestamp lies in `from..=to` (Unix seconds).
+    ///
+    /// The start node is always yielded. The traversal never passes
+    /// through nodes outside the window, so a node reachable only via an
+    /// older node is not found; nodes without a stored record are skipped.
+    pub fn time_window(mut self, from: u64, to: u64) -> Self {
+        self.window = Some((from, to));
+        self
+    }
+
+    /// Iterates `(node ID, depth)` pairs instead of bare node IDs.
+    ///
+    /// The depth is the number of edges on the path the traversal took
+    /// to reach the node.
+    pub fn with_depths(self) -> TraversalIter<'a> {
+        let exists = self.db.get_node(self.start).is_some()
+            || self.db.neighbors(self.start).is_some()
+            || self.db.incoming_neighbors(self.start).is_some();
+        let mut visited = HashSet::new();
+        let mut frontier = VecDeque::new();
+        if exists {
+            frontier.push_back((self.start, 0));
+            if self.order == TraversalOrder::BreadthFirst {
+                visited.insert(self.start);
+            }
+        }
+        TraversalIter {
+            traversal: self,
+            visited,
+            frontier,
+        }
+    }
+}
+
+impl<'a> IntoIterator for Traversal<'a> {
+    type Item = NodeId;
+    type IntoIter = std::iter::Map<TraversalIter<'a>, fn((NodeId, usize)) -> NodeId>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.with_depths().map(|(id, _)| id)
+    }
+}
+
+/// Lazy iterator over `(node ID, depth)` pairs of a traversal.
+pub struct TraversalIter<'a> {
+    traversal: Traversal<'a>,
+    /// BFS marks nodes when queued; DFS marks them when visited.
+    visited: HashSet<NodeId>,
+    /// Queue (BFS) or stack (DFS) of nodes still to visit.
+    frontier: VecDeque<(NodeId, usize)>,
+}
+
+impl TraversalIter<'_> {
+    /// Returns the neighbors of a node along the traversal's direction,
+    /// in the database's configured `NeighborOrder`.
+    fn neighbors(&self, id: NodeId) -> Vec<NodeId> {
+        let Traversal {
+            db,
+            direction,
+            window,
+            ..
+        } = self.traversal;
+        let mut neighbors = db.ordered_neighbors(id, direction, &Counterfactual::default());
+        if let Some((from, to)) = window {
+            // Timestamps stay on cold-tier stubs, so no hydration is needed
+            neighbors.retain(|n| {
+                db.nodes()
+                    .get(n)
+                    .is_some_and(|node| (from..=to).contains(&node.timestamp))
+            });
+        }
+        neighbors
+    }
+}
+
+impl Iterator for TraversalIter<'_> {
+    type Item = (NodeId, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (id, depth) = match self.traversal.order {
+                TraversalOrder::BreadthFirst => self.frontier.pop_front()?,
+                TraversalOrder::DepthFirst => self.frontier.pop_back()?,
+            };
+            let expand = self.traversal.max_hops.is_none_or(|max| depth < max);
+
+            match self.traversal.order {
+                TraversalOrder::BreadthFirst => {
+                    if expand {
+                        for next in self.neighbors(id) {
+                            if self.visited.insert(next) {
+                                self.frontier.push_back((next, depth + 1));
+                            }
+                        }
+                    }
+                }
+                TraversalOrder::DepthFirst => {
+                    if !self.visited.insert(id) {
+                        continue;
+                    }
+                    if expand {
+                        // Push in reverse so the first neighbor is visited first
+                        let mut fresh = self.neighbors(id);
+                        fresh.retain(|n| !self.visited.contains(n));
+                        self.frontier
+                            .extend(fresh.into_iter().rev().map(|n| (n, depth + 1)));
+                    }
+                }
+            }
+            return Some((id, depth));
+        }
+    }
+}
+
+impl BarqGraphDb {
+    /// Starts configuring a lazy traversal from `start`.
+    ///
+    /// Defaults to an unbounded breadth-first traversal along outgoing
+    /// edges. The start node is yielded first if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// // First node within 4 hops labelled "root cause", without visiting the rest
+    /// let found = db
+    ///     .traverse(1)
+    ///     .bfs()
+    ///     .max_hops(4)
+    ///     .into_iter()
+    ///     .find(|id| db.get_node(*id).is_some_and(|n| n.label == "root cause"));
+    /// ```
+    pub fn traverse(&self, start: NodeId) -> Traversal<'_> {
+        Traversal {
+            db: self,
+            start,
+            order: TraversalOrder::default(),
+            max_hops: None,
+            direction: Direction::Outgoing,
+            window: None,
+        }
+    }
+
+    /// Performs a BFS that only visits nodes created within a time window.
+    ///
+    /// Answers questions like "what did I learn in the last hour that
+    /// connects to X": the start node may be of any age, every other node
+    /// on the way must have a timestamp in `from..=to`.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Starting node ID
+    /// * `max_hops` - Maximum number of edges to traverse
+    /// * `from` - Earliest node timestamp (Unix seconds, inclusive)
+    /// * `to` - Latest node timestamp (Unix seconds, inclusive)
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    /// use std::time::{SystemTime, UNIX_EPOCH};
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    /// let recent = db.bfs_time_window(1, 3, now - 3600, now);
+    /// ```
+    pub fn bfs_time_window(
+        &self,
+        start: NodeId,
+        max_hops: usize,
+        from: u64,
+        to: u64,
+    ) -> Vec<NodeId> {
+        self.traverse(start)
+            .max_hops(max_hops)
+            .time_window(from, to)
+            .into_iter()
+            .collect()
+    }
+
+    /// Performs a depth-first traversal up to a maximum depth.
+    ///
+    /// Each node is visited once, at the depth of the first path the
+    /// search takes to it. A node first reached along a long branch may
+    /// therefore be cut off even if a shorter path exists; use `bfs_hops`
+    /// when every node within the limit must be found.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Starting node ID
+    /// * `max_depth` - Maximum number of edges to traverse
+    ///
+    /// # Returns
+    ///
+    /// Node IDs in depth-first pre-order, starting with `start`.
+    pub fn dfs_hops(&self, start: NodeId, max_depth: usize) -> Vec<NodeId> {
+        self.traverse(start)
+            .dfs()
+            .max_hops(max_depth)
+            .into_iter()
+            .collect()
+    }
+
+    /// Samples a random walk with restarts along outgoing edges.
+    ///
+    /// Each step moves to a uniformly chosen outgoing edge of the current
+    /// node (parallel edges count once each), or jumps back to `start`
+    /// with probability `restart_prob`. A walk that reaches a node
+    /// without outgoing edges also restarts. Walks are the sampling step
+    /// of node2vec-style embeddings and give agents a cheap stochastic
+    /// exploration of a neighborhood.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Starting node ID
+    /// * `length` - Number of nodes in the walk, including `start`
+    /// * `restart_prob` - Probability of jumping back to `start` at each step,
+    ///   clamped to 0.0..=1.0
+    ///
+    /// # Returns
+    ///
+    /// The visited node sequence, or an empty vector if `start` is not in
+    /// the graph.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// // Ten walks of 20 steps as a node2vec corpus for node 1
+    /// let corpus: Vec<Vec<u64>> = (0..10).map(|_| db.random_walk(1, 20, 0.15)).collect();
+    /// ```
+    pub fn random_walk(&self, start: NodeId, length: usize, restart_prob: f64) -> Vec<NodeId> {
+        self.random_walk_with_rng(start, length, restart_prob, &mut rand::thread_rng())
+    }
+
+    /// Samples a random walk using the given random number generator.
+    ///
+    /// Behaves like `random_walk`; pass a seeded generator for
+    /// reproducible walks.
+    pub fn random_walk_with_rng<R: Rng + ?Sized>(
+        &self,
+        start: NodeId,
+        length: usize,
+        restart_prob: f64,
+        rng: &mut R,
+    ) -> Vec<NodeId> {
+        if self.get_node(start).is_none() && self.neighbors(start).is_none() {
+            return Vec::new();
+        }
+        let restart_prob = restart_prob.clamp(0.0, 1.0);
+        let mut walk = Vec::with_capacity(length);
+        let mut current = start;
+        while walk.len() < length {
+            walk.push(current);
+            let neighbors = self.neighbors(current).unwrap_or_default();
+            current = if neighbors.is_empty() || rng.gen_bool(restart_prob) {
+                start
+            } else {
+                neighbors[rng.gen_range(0..neighbors.len())]
+            };
+        }
+        walk
+    }
+
+    /// Checks whether `to` can be reached from `from` along outgoing edges
+    /// within `max_hops` edges.
+    ///
+    /// The search runs from both ends and stops as soon as they meet, so
+    /// validating that a proposed path is connected stays cheap even in
+    /// dense neighborhoods. A node always reaches itself.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// let path = [1, 4, 9];
+    /// let connected = path.windows(2).all(|w| db.is_reachable(w[0], w[1], 1));
+    /// ```
+    pub fn is_reachable(&self, from: NodeId, to: NodeId, max_hops: usize) -> bool {
+        self.graph().is_reachable(from, to, max_hops)
+    }
+
+    /// Returns the nodes reachable from `id` along outgoing edges within
+    /// `max_hops` edges, excluding `id`.
+    pub fn descendants(&self, id: NodeId, max_hops: usize) -> HashSet<NodeId> {
+        self.graph()
+            .reachable_set(id, max_hops, Direction::Outgoing)
+    }
+
+    /// Returns the nodes that reach `id` along outgoing edges within
+    /// `max_hops` edges, excluding `id`.
+    pub fn ancestors(&self, id: NodeId, max_hops: usize) -> HashSet<NodeId> {
+        self.graph()
+            .reachable_set(id, max_hops, Direction::Incoming)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DbOptions;
+    use crate::Node;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_dfs_and_lazy_bfs_match_eager_traversal() {
+        let dir = tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        for id in 1..=6 {
+            db.append_node(Node::new(id, format!("n{}", id))).unwrap();
+        }
+        // 1 -> 2 -> 4 -> 6, 1 -> 3 -> 5
+        db.add_edge(1, 2, "X").unwrap();
+        db.add_edge(1, 3, "X").unwrap();
+        db.add_edge(2, 4, "X").unwrap();
+        db.add_edge(3, 5, "X").unwrap();
+        db.add_edge(4, 6, "X").unwrap();
+
+        assert_eq!(db.dfs_hops(1, 10), vec![1, 2, 4, 6, 3, 5]);
+        assert_eq!(db.dfs_hops(1, 1), vec![1, 2, 3]);
+
+        let lazy: Vec<_> = db.traverse(1).bfs().max_hops(2).into_iter().collect();
+        assert_eq!(lazy, db.bfs_hops(1, 2));
+
+        let depths: Vec<_> = db
+            .traverse(6)
+            .direction(Direction::Incoming)
+            .with_depths()
+            .collect();
+        assert_eq!(depths, vec![(6, 0), (4, 1), (2, 2), (1, 3)]);
+
+        // Stops as soon as a match is found
+        let first_leaf = db
+            .traverse(1)
+            .into_iter()
+            .find(|&id| db.neighbors(id).is_some_and(|n| n.is_empty()));
+        assert_eq!(first_leaf, Some(5));
+        assert_eq!(db.traverse(99).into_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_random_walk_follows_edges_and_restarts() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let dir = tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        for id in 1..=4 {
+            db.append_node(Node::new(id, format!("n{}", id))).unwrap();
+        }
+        // 1 -> 2 -> 3 dead end; 4 is isolated
+        db.add_edge(1, 2, "X").unwrap();
+        db.add_edge(2, 3, "X").unwrap();
+
+        let walk = db.random_walk(1, 7, 0.0);
+        assert_eq!(walk, vec![1, 2, 3, 1, 2, 3, 1]);
+        assert_eq!(db.random_walk(1, 5, 1.0), vec![1; 5]);
+        assert_eq!(db.random_walk(4, 3, 0.5), vec![4; 3]);
+        assert!(db.random_walk(99, 3, 0.5).is_empty());
+        assert!(db.random_walk(1, 0, 0.5).is_empty());
+
+        let mut a = StdRng::seed_from_u64(7);
+        let mut b = StdRng::seed_from_u64(7);
+        assert_eq!(
+            db.random_walk_with_rng(1, 50, 0.3, &mut a),
+            db.random_walk_with_rng(1, 50, 0.3, &mut b)
+        );
+    }
+
+    #[test]
+    fn test_time_window_skips_nodes_outside_window() {
+        let dir = tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        // Old hub 1 links to recent 2 and old 3; old 3 links to recent 4
+        for (id, ts) in [(1, 100), (2, 5_000), (3, 200), (4, 5_100), (5, 5_200)] {
+            db.append_node(Node::with_timestamp(id, format!("n{}", id), ts))
+                .unwrap();
+        }
+        db.add_edge(1, 2, "X").unwrap();
+        db.add_edge(1, 3, "X").unwrap();
+        db.add_edge(3, 4, "X").unwrap();
+        db.add_edge(2, 5, "X").unwrap();
+
+        assert_eq!(db.bfs_time_window(1, 5, 4_000, 6_000), vec![1, 2, 5]);
+        assert_eq!(db.bfs_time_window(1, 1, 4_000, 6_000), vec![1, 2]);
+        assert_eq!(db.bfs_time_window(1, 5, 0, 300), vec![1, 3]);
+        let dfs: Vec<_> = db
+            .traverse(1)
+            .dfs()
+            .time_window(0, u64::MAX)
+            .into_iter()
+            .collect();
+        assert_eq!(dfs, vec![1, 2, 5, 3, 4]);
+    }
+
+    #[test]
+    fn test_reachability_and_ancestry() {
+        let dir = tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        // 1 -> 2 -> 3 -> 4, 5 -> 3, and 6 on its own
+        db.add_edges(&[(1, 2, "X"), (2, 3, "X"), (3, 4, "X"), (5, 3, "X")])
+            .unwrap();
+        db.append_node(Node::new(6, "lonely".to_string())).unwrap();
+
+        assert!(db.is_reachable(1, 4, 3));
+        assert!(!db.is_reachable(1, 4, 2));
+        assert!(!db.is_reachable(4, 1, 10));
+        assert!(!db.is_reachable(1, 5, 10));
+        assert!(db.is_reachable(6, 6, 0));
+
+        assert_eq!(db.descendants(2, 10), HashSet::from([3, 4]));
+        assert_eq!(db.descendants(1, 1), HashSet::from([2]));
+        assert_eq!(db.ancestors(3, 10), HashSet::from([1, 2, 5]));
+        assert!(db.ancestors(6, 10).is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+//! Steering kNN results away from "avoid" vectors.
+//!
+//! An agent that has already covered some subjects can pass their
+//! vectors along with the query. Each nearest-neighbor candidate is
+//! then ranked by
+//!
+//! `score = distance(query, x) - weight * min distance(avoid, x)`
+//!
+//! so candidates close to any avoided vector drop down the list. Only
+//! the query's own neighborhood is reranked, which keeps the reward for
+//! being far from an avoided subject bounded. `weight = 0` reproduces
+//! the kNN order.
+
+use super::DistanceMetric;
+use crate::NodeId;
+
+/// Nearest neighbors fetched per requested result as rerank candidates.
+pub const CANDIDATES_PER_RESULT: usize = 4;
+
+/// Reranks nearest-neighbor candidates away from `avoid` vectors.
+///
+/// # Arguments
+///
+/// * `candidates` - (NodeId, distance to query) pairs, e.g. from `knn`
+/// * `avoid` - Vectors whose neighborhood to steer away from
+/// * `k` - Number of results to keep
+/// * `weight` - Strength of the penalty; negative values are treated as 0
+/// * `metric` - Metric used to compare candidates with avoided vectors
+/// * `embedding` - Looks up a candidate's vector; candidates without one,
+///   or of another dimension, are not penalized
+///
+/// # Returns
+///
+/// Up to `k` (NodeId, distance to query) pairs, best adjusted score first.
+pub fn rerank_avoiding<'a>(
+    candidates: &[(NodeId, f32)],
+    avoid: &[&[f32]],
+    k: usize,
+    weight: f32,
+    metric: DistanceMetric,
+    embedding: impl Fn(NodeId) -> Option<&'a [f32]>,
+) -> Vec<(NodeId, f32)> {
+    let weight = weight.max(0.0);
+    let mut scored: Vec<(f32, NodeId, f32)> = candidates
+        .iter()
+        .map(|&(id, distance)| {
+            let closest = embedding(id).and_then(|v| {
+                avoid
+                    .iter()
+                    .filter(|a| a.len() == v.len())
+                    .map(|a| metric.distance(a, v))
+                    .min_by(f32::total_cmp)
+            });
+            (distance - weight * closest.unwrap_or(0.0), id, distance)
+        })
+        .collect();
+    scored.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+    scored
+        .into_iter()
+        .take(k)
+        .map(|(_, id, distance)| (id, distance))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_avoided_topic_drops_down() {
+        let vectors: Vec<(NodeId, Vec<f32>)> = vec![
+            (1, vec![1.0, 0.1]),
+            (2, vec![1.0, -0.1]),
+            (3, vec![0.9, 0.5]),
+            (4, vec![0.9, -0.5]),
+        ];
+        let query = [1.0, 0.0];
+        let mut candidates: Vec<(NodeId, f32)> = vectors
+            .iter()
+            .map(|(id, v)| (*id, DistanceMetric::L2.distance(&query, v)))
+            .collect();
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        let lookup = |id: NodeId| {
+            vectors
+                .iter()
+                .find(|(v, _)| *v == id)
+                .map(|(_, v)| v.as_slice())
+        };
+
+        let avoid: [&[f32]; 1] = [&[0.0, 1.0]];
+        let plain = rerank_avoiding(&candidates, &avoid, 4, 0.0, DistanceMetric::L2, lookup);
+        assert_eq!(plain, candidates);
+
+        // Steering away from +y prefers the results below the axis
+        let steered = rerank_avoiding(&candidates, &avoid, 2, 2.0, DistanceMetric::L2, lookup);
+        let ids: Vec<NodeId> = steered.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![4, 2]);
+        assert_eq!(steered[1], candidates[1]);
+    }
+}
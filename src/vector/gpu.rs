@@ -0,0 +1,429 @@
+//! Batch distance computation on the GPU.
+//!
+//! Exact search over tens of millions of vectors is bound by CPU cores
+//! and memory bandwidth. Built with the `wgpu` feature, batches of at
+//! least `GPU_BATCH_MIN` vectors are scored by a compute shader on the
+//! first adapter wgpu finds (Vulkan, Metal or DX12), and
+//! `LinearVectorIndex` keeps a copy of its vectors in GPU memory so a
+//! query only uploads itself. Without the feature, with smaller batches,
+//! or when no adapter is available or a GPU call fails, distances are
+//! computed on the CPU with the same results.
+
+#[cfg(feature = "wgpu")]
+pub(super) use device::DeviceMatrix;
+
+use rayon::prelude::*;
+
+use super::{DistanceMetric, PARALLEL_SCAN_MIN};
+
+/// Vectors below which a batch is scored on the CPU even with a GPU.
+pub const GPU_BATCH_MIN: usize = 65_536;
+
+/// Returns `true` if batches can be offloaded to a GPU.
+///
+/// Always `false` unless built with the `wgpu` feature.
+pub fn gpu_available() -> bool {
+    #[cfg(feature = "wgpu")]
+    {
+        device::context().is_some()
+    }
+    #[cfg(not(feature = "wgpu"))]
+    {
+        false
+    }
+}
+
+/// Computes the distance from `query` to each of `vectors`.
+///
+/// Meant for exact re-ranking of large candidate sets; results equal
+/// `metric.distance` up to floating-point rounding.
+///
+/// # Arguments
+///
+/// * `metric` - Distance metric to apply
+/// * `query` - Query vector
+/// * `vectors` - Vectors to score, all of the query's dimension
+///
+/// # Returns
+///
+/// One distance per vector, in input order.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use barq_graphdb::vector::{batch_distances, DistanceMetric};
+///
+/// let candidates: Vec<Vec<f32>> = vec![vec![0.1, 0.2], vec![0.3, 0.4]];
+/// let rows: Vec<&[f32]> = candidates.iter().map(Vec::as_slice).collect();
+/// let distances = batch_distances(DistanceMetric::Cosine, &[0.2, 0.2], &rows);
+/// ```
+pub fn batch_distances(metric: DistanceMetric, query: &[f32], vectors: &[&[f32]]) -> Vec<f32> {
+    #[cfg(feature = "wgpu")]
+    if vectors.len() >= GPU_BATCH_MIN && vectors.iter().all(|v| v.len() == query.len()) {
+        let distances = device::context().and_then(|ctx| {
+            let rows = vectors.iter().enumerate().map(|(i, v)| (i as u64, *v));
+            DeviceMatrix::upload(ctx, query.len(), rows)?.distances(ctx, metric, query)
+        });
+        if let Some(distances) = distances {
+            return distances;
+        }
+    }
+    if vectors.len() < PARALLEL_SCAN_MIN {
+        vectors.iter().map(|v| metric.distance(query, v)).collect()
+    } else {
+        vectors
+            .par_iter()
+            .map(|v| metric.distance(query, v))
+            .collect()
+    }
+}
+
+#[cfg(feature = "wgpu")]
+pub(super) mod device {
+    use std::sync::{mpsc, OnceLock};
+
+    use wgpu::util::DeviceExt;
+
+    use super::super::DistanceMetric;
+    use crate::NodeId;
+
+    /// Threads per workgroup, matching `@workgroup_size` in the shader.
+    const WORKGROUP_SIZE: u32 = 256;
+
+    const SHADER: &str = r#"
+struct Params {
+    dim: u32,
+    rows: u32,
+    metric: u32,
+    _pad: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> query: array<f32>;
+@group(0) @binding(2) var<storage, read> matrix: array<f32>;
+@group(0) @binding(3) var<storage, read_write> out: array<f32>;
+
+@compute @workgroup_size(256)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let row = gid.x;
+    if (row >= params.rows) {
+        return;
+    }
+    let base = row * params.dim;
+    var dot = 0.0;
+    var norm_q = 0.0;
+    var norm_v = 0.0;
+    var diff_sq = 0.0;
+    for (var i = 0u; i < params.dim; i = i + 1u) {
+        let q = query[i];
+        let v = matrix[base + i];
+        dot = dot + q * v;
+        norm_q = norm_q + q * q;
+        norm_v = norm_v + v * v;
+        diff_sq = diff_sq + (q - v) * (q - v);
+    }
+    var distance = 0.0;
+    if (params.metric == 0u) {
+        distance = sqrt(diff_sq);
+    } else if (params.metric == 1u) {
+        let magnitude = sqrt(norm_q * norm_v);
+        if (magnitude == 0.0) {
+            distance = 1.0;
+        } else {
+            distance = 1.0 - dot / magnitude;
+        }
+    } else {
+        distance = -dot;
+    }
+    out[row] = distance;
+}
+"#;
+
+    /// Device, queue and compiled distance pipeline, created once.
+    pub(crate) struct GpuContext {
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        pipeline: wgpu::ComputePipeline,
+        limits: wgpu::Limits,
+    }
+
+    /// Returns the shared GPU context, or `None` if no adapter was found.
+    pub(crate) fn context() -> Option<&'static GpuContext> {
+        static CONTEXT: OnceLock<Option<GpuContext>> = OnceLock::new();
+        CONTEXT
+            .get_or_init(|| pollster::block_on(GpuContext::new()))
+            .as_ref()
+    }
+
+    impl GpuContext {
+        async fn new() -> Option<Self> {
+            let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    ..Default::default()
+                })
+                .await?;
+            let limits = adapter.limits();
+            let (device, queue) = adapter
+                .request_device(
+                    &wgpu::DeviceDescriptor {
+                        label: Some("barq distances"),
+                        required_limits: limits.clone(),
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+                .ok()?;
+            let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("barq distances"),
+                source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+            });
+            let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("barq distances"),
+                layout: None,
+                module: &module,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                cache: None,
+            });
+            Some(Self {
+                device,
+                queue,
+                pipeline,
+                limits,
+            })
+        }
+
+        /// Runs `f`, returning `None` if it raised a wgpu error.
+        ///
+        /// Out-of-memory and validation errors would otherwise panic in
+        /// the default error handler instead of falling back to the CPU.
+        fn checked<T>(&self, f: impl FnOnce() -> Option<T>) -> Option<T> {
+            self.device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+            self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let result = f();
+            let validation = pollster::block_on(self.device.pop_error_scope());
+            let memory = pollster::block_on(self.device.pop_error_scope());
+            if validation.is_some() || memory.is_some() {
+                return None;
+            }
+            result
+        }
+
+        /// Largest number of rows of `dim` floats one dispatch can score.
+        fn rows_per_chunk(&self, dim: usize) -> usize {
+            let binding = self
+                .limits
+                .max_storage_buffer_binding_size
+                .min(self.limits.max_buffer_size.min(u32::MAX as u64) as u32)
+                as usize;
+            let groups = self.limits.max_compute_workgroups_per_dimension as usize;
+            (binding / (dim * 4)).min(groups * WORKGROUP_SIZE as usize)
+        }
+    }
+
+    /// A slice of the matrix small enough for one storage binding.
+    #[derive(Debug)]
+    struct Chunk {
+        buffer: wgpu::Buffer,
+        rows: usize,
+    }
+
+    /// Vectors of one dimension held in GPU memory.
+    #[derive(Debug)]
+    pub(crate) struct DeviceMatrix {
+        chunks: Vec<Chunk>,
+        ids: Vec<NodeId>,
+        dim: usize,
+    }
+
+    impl DeviceMatrix {
+        /// Copies `rows`, all of dimension `dim`, to the GPU.
+        pub(crate) fn upload<'a>(
+            ctx: &GpuContext,
+            dim: usize,
+            rows: impl Iterator<Item = (NodeId, &'a [f32])>,
+        ) -> Option<Self> {
+            if dim == 0 {
+                return None;
+            }
+            let per_chunk = ctx.rows_per_chunk(dim);
+            if per_chunk == 0 {
+                return None;
+            }
+            ctx.checked(|| {
+                let mut matrix = Self {
+                    chunks: Vec::new(),
+                    ids: Vec::new(),
+                    dim,
+                };
+                let mut flat: Vec<f32> = Vec::new();
+                for (id, row) in rows {
+                    matrix.ids.push(id);
+                    flat.extend_from_slice(row);
+                    if flat.len() == per_chunk * dim {
+                        matrix.push_chunk(ctx, &flat);
+                        flat.clear();
+                    }
+                }
+                if !flat.is_empty() {
+                    matrix.push_chunk(ctx, &flat);
+                }
+                Some(matrix)
+            })
+        }
+
+        fn push_chunk(&mut self, ctx: &GpuContext, flat: &[f32]) {
+            let buffer = ctx
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("barq vectors"),
+                    contents: bytemuck::cast_slice(flat),
+                    usage: wgpu::BufferUsages::STORAGE,
+                });
+            self.chunks.push(Chunk {
+                buffer,
+                rows: flat.len() / self.dim,
+            });
+        }
+
+        /// Dimension of the stored vectors.
+        pub(crate) fn dim(&self) -> usize {
+            self.dim
+        }
+
+        /// IDs of the stored vectors, in row order.
+        pub(crate) fn ids(&self) -> &[NodeId] {
+            &self.ids
+        }
+
+        /// Computes the distance from `query` to every row, in row order.
+        pub(crate) fn distances(
+            &self,
+            ctx: &GpuContext,
+            metric: DistanceMetric,
+            query: &[f32],
+        ) -> Option<Vec<f32>> {
+            if query.len() != self.dim {
+                return None;
+            }
+            let metric = match metric {
+                DistanceMetric::L2 => 0u32,
+                DistanceMetric::Cosine => 1,
+                DistanceMetric::DotProduct => 2,
+            };
+            ctx.checked(|| {
+                let device = &ctx.device;
+                let query = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("barq query"),
+                    contents: bytemuck::cast_slice(query),
+                    usage: wgpu::BufferUsages::STORAGE,
+                });
+                let mut distances = Vec::with_capacity(self.ids.len());
+                for chunk in &self.chunks {
+                    let params = [self.dim as u32, chunk.rows as u32, metric, 0];
+                    let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("barq params"),
+                        contents: bytemuck::cast_slice(&params),
+                        usage: wgpu::BufferUsages::UNIFORM,
+                    });
+                    let size = (chunk.rows * 4) as u64;
+                    let out = device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("barq distances"),
+                        size,
+                        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                        mapped_at_creation: false,
+                    });
+                    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("barq readback"),
+                        size,
+                        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    });
+                    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: None,
+                        layout: &ctx.pipeline.get_bind_group_layout(0),
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: params.as_entire_binding(),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: query.as_entire_binding(),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 2,
+                                resource: chunk.buffer.as_entire_binding(),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 3,
+                                resource: out.as_entire_binding(),
+                            },
+                        ],
+                    });
+
+                    let mut encoder = device.create_command_encoder(&Default::default());
+                    {
+                        let mut pass = encoder.begin_compute_pass(&Default::default());
+                        pass.set_pipeline(&ctx.pipeline);
+                        pass.set_bind_group(0, &bind_group, &[]);
+                        pass.dispatch_workgroups(
+                            (chunk.rows as u32).div_ceil(WORKGROUP_SIZE),
+                            1,
+                            1,
+                        );
+                    }
+                    encoder.copy_buffer_to_buffer(&out, 0, &staging, 0, size);
+                    ctx.queue.submit([encoder.finish()]);
+
+                    let slice = staging.slice(..);
+                    let (tx, rx) = mpsc::channel();
+                    slice.map_async(wgpu::MapMode::Read, move |result| {
+                        let _ = tx.send(result);
+                    });
+                    device.poll(wgpu::Maintain::Wait);
+                    rx.recv().ok()?.ok()?;
+                    distances.extend_from_slice(bytemuck::cast_slice(&slice.get_mapped_range()));
+                    staging.unmap();
+                }
+                Some(distances)
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_distances_match_metric() {
+        let vectors: Vec<Vec<f32>> = (0..GPU_BATCH_MIN)
+            .map(|i| {
+                let x = i as f32 * 0.01;
+                vec![x.sin(), x.cos(), (i % 7) as f32]
+            })
+            .collect();
+        let rows: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        let query = [0.3, -0.2, 1.0];
+
+        for metric in [
+            DistanceMetric::L2,
+            DistanceMetric::Cosine,
+            DistanceMetric::DotProduct,
+        ] {
+            // Both the small CPU batch and, if available, the GPU batch
+            for rows in [&rows[..10], &rows[..]] {
+                let distances = batch_distances(metric, &query, rows);
+                assert_eq!(distances.len(), rows.len());
+                for (row, distance) in rows.iter().zip(&distances) {
+                    assert!((metric.distance(&query, row) - distance).abs() < 1e-4);
+                }
+            }
+        }
+        assert!(batch_distances(DistanceMetric::L2, &query, &[]).is_empty());
+    }
+}
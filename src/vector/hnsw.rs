@@ -1,50 +1,532 @@
+use anyhow::{Context, Result};
 use dashmap::DashMap;
 use hnsw_rs::prelude::*;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 
-use super::VectorIndex;
+use super::{select_top_k, DistanceMetric, IndexStats, Quantization, VectorIndex, MAP_ENTRY_BYTES};
 use crate::NodeId;
 
+/// Adapter exposing a `DistanceMetric` to `hnsw_rs`.
+///
+/// HNSW requires non-negative distances, so dot-product scores are
+/// mapped through a positive, strictly monotone transform and mapped
+/// back when results are reported.
+#[derive(Debug, Clone, Copy, Default)]
+struct MetricDistance {
+    metric: DistanceMetric,
+}
+
+impl MetricDistance {
+    /// Converts a metric distance into the non-negative HNSW space.
+    fn encode(&self, d: f32) -> f32 {
+        match self.metric {
+            DistanceMetric::L2 => d,
+            DistanceMetric::Cosine => d.max(0.0),
+            // d = -dot; map dot >= 0 into (0, 1] and dot < 0 into (1, inf)
+            DistanceMetric::DotProduct => {
+                let dot = -d;
+                if dot >= 0.0 {
+                    1.0 / (1.0 + dot)
+                } else {
+                    1.0 - dot
+                }
+            }
+        }
+    }
+
+    /// Converts an HNSW distance back into the metric's own scale.
+    fn decode(&self, d: f32) -> f32 {
+        match self.metric {
+            DistanceMetric::L2 | DistanceMetric::Cosine => d,
+            DistanceMetric::DotProduct => {
+                let dot = if d <= 1.0 { 1.0 / d - 1.0 } else { 1.0 - d };
+                -dot
+            }
+        }
+    }
+}
+
+impl Distance<f32> for MetricDistance {
+    fn eval(&self, va: &[f32], vb: &[f32]) -> f32 {
+        self.encode(self.metric.distance(va, vb))
+    }
+}
+
+/// Tuning knobs of the HNSW graph, trading recall against latency and memory.
+///
+/// # Example
+///
+/// ```rust
+/// use barq_graphdb::vector::HnswParams;
+///
+/// // Fewer links and a smaller search beam: faster, lower recall
+/// let fast = HnswParams {
+///     m: 12,
+///     ef_search: 50,
+///     ..HnswParams::default()
+/// };
+/// assert!(fast.validate().is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HnswParams {
+    /// Points the graph is sized for up front; it grows in the
+    /// background as this fills up.
+    pub capacity: usize,
+    /// Links kept per node and layer (`M`); more links raise recall and memory use.
+    pub m: usize,
+    /// Candidate list size while inserting; larger builds a better graph, more slowly.
+    pub ef_construction: usize,
+    /// Maximum number of graph layers (at most 16).
+    pub max_layer: usize,
+    /// Default candidate list size while searching; larger raises recall and latency.
+    pub ef_search: usize,
+    /// Below this many vectors, searches scan them exactly instead of
+    /// walking the graph, which is both faster and exact at that size.
+    /// The graph is still kept up to date, so crossing the threshold
+    /// switches over at once. 0 always uses the graph.
+    pub exact_below: usize,
+}
+
+impl Default for HnswParams {
+    fn default() -> Self {
+        // Generous M and ef_construction keep recall stable on small datasets
+        Self {
+            capacity: 1_000_000,
+            m: 32,
+            ef_construction: 400,
+            max_layer: 16,
+            ef_search: 200,
+            exact_below: 1_000,
+        }
+    }
+}
+
+impl HnswParams {
+    /// Largest `m` supported by the graph implementation.
+    pub const MAX_M: usize = 256;
+
+    /// Checks that every parameter is within the supported range.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first out-of-range parameter.
+    pub fn validate(&self) -> Result<()> {
+        if self.capacity == 0 {
+            anyhow::bail!("HNSW capacity must be positive");
+        }
+        if self.m == 0 || self.m > Self::MAX_M {
+            anyhow::bail!(
+                "HNSW m must be between 1 and {}, got {}",
+                Self::MAX_M,
+                self.m
+            );
+        }
+        if self.ef_construction == 0 || self.ef_search == 0 {
+            anyhow::bail!("HNSW ef_construction and ef_search must be positive");
+        }
+        if self.max_layer == 0 || self.max_layer > 16 {
+            anyhow::bail!(
+                "HNSW max_layer must be between 1 and 16, got {}",
+                self.max_layer
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Dead points tolerated before an update or removal triggers a rebuild.
+const REBUILD_MIN_STALE: usize = 1024;
+
+/// Estimated bytes of one graph link: a shared point reference and its distance.
+const LINK_BYTES: usize = 16;
+
+/// Estimated bytes of one entry in an internal ID mapping.
+const ID_MAPPING_BYTES: usize = size_of::<NodeId>() + size_of::<usize>() + 16;
+
+/// Returns whether `points` is close enough to `capacity` to grow.
+fn nearly_full(points: usize, capacity: usize) -> bool {
+    points >= capacity - capacity / 10
+}
+
+/// State shared with the background growth thread.
+#[derive(Debug)]
+struct Growth {
+    /// Points the current graph was sized for.
+    capacity: AtomicUsize,
+    /// Whether a growth thread is running.
+    growing: AtomicBool,
+    /// Held while the graph is being replaced, so growth and stale
+    /// rebuilds never race each other.
+    maintenance: Mutex<()>,
+}
+
+/// ID mappings written next to a dumped HNSW graph.
+#[derive(Serialize, Deserialize)]
+struct SavedIds {
+    metric: DistanceMetric,
+    next_internal_id: usize,
+    /// Current internal ID of every indexed node.
+    nodes: Vec<(NodeId, usize)>,
+}
+
+/// Returns `<base><suffix>`, e.g. `hnsw` + `.ids.json`.
+fn with_suffix(base: &Path, suffix: &str) -> PathBuf {
+    let mut path = base.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
 /// HNSW-based vector index implementation.
 /// Uses logical-to-physical ID mapping to support updates via append-only strategy.
 /// Thread-safe implementation using DashMap and AtomicUsize.
+///
+/// Removed and replaced vectors stay in the graph as unmapped points
+/// until they outnumber the live ones, at which point the graph is
+/// rebuilt from the live vectors alone.
+///
+/// When the graph nears its capacity, a background thread copies the
+/// live points into one twice as large and swaps it in; searches and
+/// inserts keep using the old graph until then.
 pub struct HnswVectorIndex {
-    /// The underlying HNSW index (thread-safe).
-    index: Hnsw<'static, f32, DistL2>,
+    /// The underlying HNSW index (thread-safe); write-locked only to
+    /// swap in a rebuilt graph.
+    index: Arc<RwLock<Hnsw<'static, f32, MetricDistance>>>,
+    /// Capacity tracking for background growth.
+    growth: Arc<Growth>,
+    /// Build and search parameters.
+    params: HnswParams,
+    /// Distance adapter shared with the HNSW graph.
+    distance: MetricDistance,
     /// Maps NodeId (logical) to the current valid Internal ID (physical) in HNSW.
     node_to_internal: DashMap<NodeId, usize>,
     /// Maps Internal ID (physical) back to NodeId (logical).
-    internal_to_node: DashMap<usize, NodeId>,
+    internal_to_node: Arc<DashMap<usize, NodeId>>,
     /// Counter for assigning new internal IDs.
     next_internal_id: AtomicUsize,
+    /// Copy of each live vector for `get`, as the graph can't be read
+    /// back by node.
+    vectors: DashMap<NodeId, Vec<f32>>,
 }
 
 impl HnswVectorIndex {
-    /// Creates a new HNSW index.
+    /// Creates a new HNSW index using L2 distance.
     pub fn new(max_elements: usize) -> Self {
-        // Increased M and ef_construction to improve recall on small datasets and stability
-        let max_nb_connection = 32; // M
-        let ef_construction = 400; // build quality
+        Self::with_metric(max_elements, DistanceMetric::L2)
+    }
 
-        let index = Hnsw::new(
-            max_nb_connection,
-            max_elements,
-            16, // max_layer
-            ef_construction,
-            DistL2 {},
-        );
+    /// Creates a new HNSW index using the given distance metric.
+    pub fn with_metric(max_elements: usize, metric: DistanceMetric) -> Self {
+        let params = HnswParams {
+            capacity: max_elements,
+            ..HnswParams::default()
+        };
+        Self::with_params(metric, params)
+    }
 
+    /// Creates a new HNSW index with explicit build and search parameters.
+    ///
+    /// `params` should have passed `HnswParams::validate`.
+    pub fn with_params(metric: DistanceMetric, params: HnswParams) -> Self {
+        let distance = MetricDistance { metric };
+        let graph = Self::empty_graph(params.capacity, distance, &params);
+        Self::from_graph(graph, params.capacity, params, distance)
+    }
+
+    fn from_graph(
+        graph: Hnsw<'static, f32, MetricDistance>,
+        capacity: usize,
+        params: HnswParams,
+        distance: MetricDistance,
+    ) -> Self {
         Self {
-            index,
+            index: Arc::new(RwLock::new(graph)),
+            growth: Arc::new(Growth {
+                capacity: AtomicUsize::new(capacity),
+                growing: AtomicBool::new(false),
+                maintenance: Mutex::new(()),
+            }),
+            params,
+            distance,
             node_to_internal: DashMap::new(),
-            internal_to_node: DashMap::new(),
+            internal_to_node: Arc::new(DashMap::new()),
             next_internal_id: AtomicUsize::new(1),
+            vectors: DashMap::new(),
         }
     }
-}
 
-impl VectorIndex for HnswVectorIndex {
-    fn insert(&self, id: NodeId, embedding: &[f32]) {
+    fn empty_graph(
+        max_elements: usize,
+        distance: MetricDistance,
+        params: &HnswParams,
+    ) -> Hnsw<'static, f32, MetricDistance> {
+        Hnsw::new(
+            params.m,
+            max_elements,
+            params.max_layer,
+            params.ef_construction,
+            distance,
+        )
+    }
+
+    /// Returns the build and search parameters.
+    pub fn params(&self) -> HnswParams {
+        self.params
+    }
+
+    /// Returns the number of points the current graph is sized for.
+    ///
+    /// Starts at `HnswParams::capacity` and doubles with each growth.
+    pub fn capacity(&self) -> usize {
+        self.growth.capacity.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether a background growth is in progress.
+    pub fn is_growing(&self) -> bool {
+        self.growth.growing.load(Ordering::SeqCst)
+    }
+
+    /// Starts a background growth unless one is already running.
+    fn spawn_growth(&self) {
+        if self.growth.growing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let index = Arc::clone(&self.index);
+        let growth = Arc::clone(&self.growth);
+        let live = Arc::clone(&self.internal_to_node);
+        let (params, distance) = (self.params, self.distance);
+        std::thread::spawn(move || run_growth(&index, &growth, &live, &params, distance));
+    }
+
+    /// Returns the number of points in the graph that no node maps to.
+    pub fn stale_count(&self) -> usize {
+        let points = self.index.read().unwrap().get_nb_point();
+        points.saturating_sub(self.node_to_internal.len())
+    }
+
+    /// Replaces the graph with one holding only the live vectors.
+    ///
+    /// Internal IDs are reassigned from 1, so searches stop paying for
+    /// points left behind by updates and removals.
+    ///
+    /// # Returns
+    ///
+    /// The number of dead points dropped; 0 if a background growth is
+    /// replacing the graph at the same time.
+    fn rebuild(&self) -> usize {
+        // A running growth would swap its copy over the renumbered graph
+        let Ok(_maintenance) = self.growth.maintenance.try_lock() else {
+            return 0;
+        };
+        let mut index = self.index.write().unwrap();
+        let points = index.get_nb_point();
+        let live: Vec<(NodeId, Vec<f32>)> = index
+            .get_point_indexation()
+            .into_iter()
+            .filter_map(|point| {
+                let node = *self.internal_to_node.get(&point.get_origin_id())?;
+                Some((node, point.get_v().to_vec()))
+            })
+            .collect();
+
+        let graph = Self::empty_graph(self.capacity().max(live.len()), self.distance, &self.params);
+        self.node_to_internal.clear();
+        self.internal_to_node.clear();
+        for (internal_id, (node, embedding)) in (1..).zip(live) {
+            graph.insert((&embedding, internal_id));
+            self.node_to_internal.insert(node, internal_id);
+            self.internal_to_node.insert(internal_id, node);
+        }
+        self.next_internal_id
+            .store(self.node_to_internal.len() + 1, Ordering::SeqCst);
+        *index = graph;
+        points.saturating_sub(self.node_to_internal.len())
+    }
+
+    /// Writes the index to disk so it can be reloaded without rebuilding.
+    ///
+    /// Produces `<base>.hnsw.graph` and `<base>.hnsw.data` with the HNSW
+    /// structure, then `<base>.ids.json` with the node ID mappings. The
+    /// mappings are written last, so a crash mid-save never pairs them
+    /// with a torn graph. An empty index removes any previous save.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the files cannot be written.
+    pub fn save(&self, base: &Path) -> Result<()> {
+        let ids_path = with_suffix(base, ".ids.json");
+        match fs::remove_file(&ids_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("Failed to remove {:?}", ids_path));
+            }
+            _ => {}
+        }
+        if self.node_to_internal.is_empty() {
+            return Ok(());
+        }
+
+        // An absolute base replaces the working directory `file_dump` writes to
+        self.index
+            .read()
+            .unwrap()
+            .file_dump(&base.to_string_lossy().into_owned())
+            .map_err(|e| anyhow::anyhow!("{:#}", e))
+            .with_context(|| format!("Failed to dump HNSW graph to {:?}", base))?;
+
+        let saved = SavedIds {
+            metric: self.distance.metric,
+            next_internal_id: self.next_internal_id.load(Ordering::SeqCst),
+            nodes: self
+                .node_to_internal
+                .iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect(),
+        };
+        let tmp_path = with_suffix(base, ".ids.json.tmp");
+        let data = serde_json::to_vec(&saved).with_context(|| "Failed to serialize HNSW ids")?;
+        fs::write(&tmp_path, data)
+            .with_context(|| format!("Failed to write HNSW ids: {:?}", tmp_path))?;
+        fs::rename(&tmp_path, &ids_path)
+            .with_context(|| format!("Failed to install HNSW ids: {:?}", ids_path))?;
+        Ok(())
+    }
+
+    /// Loads an index written by `save`.
+    ///
+    /// Returns `Ok(None)` when nothing was saved at `base` or the save
+    /// used a different metric or graph shape (`m`, `ef_construction`,
+    /// `max_layer`). Call `sync` afterwards to bring the loaded index up
+    /// to date with the current embeddings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the saved files are unreadable or corrupt.
+    pub fn load(base: &Path, metric: DistanceMetric, params: HnswParams) -> Result<Option<Self>> {
+        let ids_path = with_suffix(base, ".ids.json");
+        if !ids_path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(&ids_path)
+            .with_context(|| format!("Failed to read HNSW ids: {:?}", ids_path))?;
+        let saved: SavedIds = serde_json::from_slice(&data)
+            .with_context(|| format!("Failed to parse HNSW ids: {:?}", ids_path))?;
+        if saved.metric != metric {
+            return Ok(None);
+        }
+
+        let dir = match base.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let basename = base
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // The loaded graph borrows from its loader; the loader itself is
+        // small and lives as long as the index
+        let loader: &'static HnswIo = Box::leak(Box::new(HnswIo::new(dir, basename)));
+        let distance = MetricDistance { metric };
+        let index = loader
+            .load_hnsw_with_dist(distance)
+            .map_err(|e| anyhow::anyhow!("{:#}", e))
+            .with_context(|| format!("Failed to load HNSW graph from {:?}", base))?;
+        // The dump stores m as a u8
+        if index.get_max_nb_connection() != params.m as u8
+            || index.get_ef_construction() != params.ef_construction
+            || index.get_max_level() != params.max_layer
+        {
+            return Ok(None);
+        }
+
+        // Capacity isn't part of the dump; a full graph grows on the next insert
+        let loaded = Self::from_graph(index, params.capacity, params, distance);
+        for (node, internal) in saved.nodes {
+            loaded.node_to_internal.insert(node, internal);
+            loaded.internal_to_node.insert(internal, node);
+        }
+        loaded
+            .next_internal_id
+            .store(saved.next_internal_id, Ordering::SeqCst);
+        Ok(Some(loaded))
+    }
+
+    /// Reconciles the index with the authoritative embeddings.
+    ///
+    /// Vectors that are missing or differ from the indexed copy are
+    /// re-inserted, and nodes absent from `vectors` are dropped. This is
+    /// a single pass over the stored points, far cheaper than a rebuild.
+    ///
+    /// # Returns
+    ///
+    /// The number of vectors that had to be re-inserted.
+    pub fn sync<'a>(&self, vectors: impl IntoIterator<Item = (NodeId, &'a [f32])>) -> usize {
+        let index = self.index.read().unwrap();
+        let stored: HashMap<usize, _> = index
+            .get_point_indexation()
+            .into_iter()
+            .map(|point| (point.get_origin_id(), point))
+            .collect();
+
+        let mut present = HashSet::new();
+        let mut outdated = Vec::new();
+        for (id, embedding) in vectors {
+            present.insert(id);
+            let current = self
+                .node_to_internal
+                .get(&id)
+                .and_then(|internal| stored.get(internal.value()));
+            if current.is_none_or(|point| point.get_v() != embedding) {
+                outdated.push((id, embedding));
+            } else {
+                self.vectors.insert(id, embedding.to_vec());
+            }
+        }
+        drop(stored);
+        drop(index);
+        let reinserted = outdated.len();
+        self.build_from(&outdated);
+        self.node_to_internal.retain(|id, internal| {
+            let keep = present.contains(id);
+            if !keep {
+                self.internal_to_node.remove(internal);
+                self.vectors.remove(id);
+            }
+            keep
+        });
+        self.rebuild_if_mostly_stale();
+        reinserted
+    }
+
+    /// Scans the live vectors exactly, for indexes below `exact_below`.
+    fn exact_knn(&self, query: &[f32], k: usize) -> Vec<(NodeId, f32)> {
+        let metric = self.distance.metric;
+        let scored = self.vectors.iter().filter_map(|entry| {
+            let v = entry.value();
+            (v.len() == query.len()).then(|| (*entry.key(), metric.distance(query, v)))
+        });
+        select_top_k(scored, k)
+    }
+
+    /// Rebuilds the graph once dead points outnumber live ones.
+    fn rebuild_if_mostly_stale(&self) {
+        let stale = self.stale_count();
+        if stale >= REBUILD_MIN_STALE && stale > self.node_to_internal.len() {
+            self.rebuild();
+        }
+    }
+
+    fn insert_into(
+        &self,
+        index: &Hnsw<'static, f32, MetricDistance>,
+        id: NodeId,
+        embedding: &[f32],
+    ) {
         // Assign a new internal ID atomically
         // Relaxed ordering is fine as unique IDs matters, strict time ordering is loose in distrib DBs,
         // but SeqCst is safer for logic if needed. Relaxed is enough for counter.
@@ -52,20 +534,87 @@ impl VectorIndex for HnswVectorIndex {
 
         // Insert into HNSW (internal locking)
         let embedding_vec = embedding.to_vec();
-        self.index.insert((&embedding_vec, internal_id));
+        index.insert((&embedding_vec, internal_id));
+        self.vectors.insert(id, embedding_vec);
+        if nearly_full(index.get_nb_point(), self.capacity()) {
+            self.spawn_growth();
+        }
 
         // Update mappings (DashMap handles concurrency)
-        self.node_to_internal.insert(id, internal_id);
+        if let Some(old) = self.node_to_internal.insert(id, internal_id) {
+            self.internal_to_node.remove(&old);
+        }
         self.internal_to_node.insert(internal_id, id);
     }
+}
+
+impl VectorIndex for HnswVectorIndex {
+    fn insert(&self, id: NodeId, embedding: &[f32]) {
+        self.insert_into(&self.index.read().unwrap(), id, embedding);
+        // Updates leave the old point behind
+        self.rebuild_if_mostly_stale();
+    }
+
+    fn build_from(&self, vectors: &[(NodeId, &[f32])]) {
+        let index = self.index.read().unwrap();
+        let first = self
+            .next_internal_id
+            .fetch_add(vectors.len(), Ordering::SeqCst);
+        let points: Vec<(&[f32], usize)> = vectors
+            .iter()
+            .zip(first..)
+            .map(|(&(_, embedding), internal_id)| (embedding, internal_id))
+            .collect();
+        // Points are linked into the graph concurrently on the rayon pool
+        index.parallel_insert_slice(&points);
+
+        for (&(id, embedding), internal_id) in vectors.iter().zip(first..) {
+            self.vectors.insert(id, embedding.to_vec());
+            if let Some(old) = self.node_to_internal.insert(id, internal_id) {
+                self.internal_to_node.remove(&old);
+            }
+            self.internal_to_node.insert(internal_id, id);
+        }
+        let full = nearly_full(index.get_nb_point(), self.capacity());
+        drop(index);
+        if full {
+            self.spawn_growth();
+        }
+        self.rebuild_if_mostly_stale();
+    }
+
+    fn remove(&self, id: NodeId) -> bool {
+        // The point stays in the graph; without a mapping, searches skip it
+        let Some((_, internal_id)) = self.node_to_internal.remove(&id) else {
+            return false;
+        };
+        self.internal_to_node.remove(&internal_id);
+        self.vectors.remove(&id);
+        self.rebuild_if_mostly_stale();
+        true
+    }
 
     fn knn(&self, query: &[f32], k: usize) -> Vec<(NodeId, f32)> {
+        self.knn_with_ef(query, k, self.params.ef_search)
+    }
+
+    fn compact(&self) -> usize {
+        if self.stale_count() == 0 {
+            return 0;
+        }
+        self.rebuild()
+    }
+
+    fn knn_with_ef(&self, query: &[f32], k: usize, ef_search: usize) -> Vec<(NodeId, f32)> {
+        if self.node_to_internal.len() < self.params.exact_below {
+            return self.exact_knn(query, k);
+        }
         // Increased ef_search and fetch_k to handle stale entries from append-only updates (soft deletes)
-        let ef_search = 200.max(k * 2);
+        let ef_search = ef_search.max(k * 2);
         let fetch_k = (k * 20).max(100); // Fetch more candidates to filter out stale ones
 
         // HNSW search is thread-safe
-        let results = self.index.search(query, fetch_k, ef_search);
+        let results = self.index.read().unwrap().search(query, fetch_k, ef_search);
 
         let mut final_results = Vec::with_capacity(k);
         // We use a small local set to dedup results for this query
@@ -83,7 +632,7 @@ impl VectorIndex for HnswVectorIndex {
                     if *current_ref.value() == internal_id {
                         // It's valid!
                         if seen_nodes.insert(node_id) {
-                            final_results.push((node_id, neighbor.distance));
+                            final_results.push((node_id, self.distance.decode(neighbor.distance)));
                             if final_results.len() >= k {
                                 break;
                             }
@@ -103,4 +652,222 @@ impl VectorIndex for HnswVectorIndex {
     fn contains(&self, id: NodeId) -> bool {
         self.node_to_internal.contains_key(&id)
     }
+
+    fn get(&self, id: NodeId) -> Option<Vec<f32>> {
+        self.vectors.get(&id).map(|v| v.clone())
+    }
+
+    fn stats(&self) -> IndexStats {
+        let index = self.index.read().unwrap();
+        let points = index.get_nb_point();
+        let dimension = index.get_point_indexation().get_data_dimension();
+        let elements = self.node_to_internal.len();
+        let vector = Quantization::None.stored_bytes(dimension);
+        IndexStats {
+            elements,
+            stale: points.saturating_sub(elements),
+            dimension,
+            // Graph points with their layer-0 links, plus the copies kept
+            // for `get` and both ID mappings of every live vector
+            memory_bytes: points * (vector + 2 * self.params.m * LINK_BYTES)
+                + elements * (vector + MAP_ENTRY_BYTES + 2 * ID_MAPPING_BYTES),
+        }
+    }
+
+    fn persist(&self, base: &Path) -> Result<()> {
+        self.save(base)
+    }
+}
+
+/// Body of the background growth thread.
+///
+/// Copies the live points into a graph twice as large, keeping their
+/// internal IDs so the node mappings stay valid, then swaps it in after
+/// catching up with points inserted meanwhile. Repeats while the new
+/// graph is itself nearly full.
+fn run_growth(
+    index: &RwLock<Hnsw<'static, f32, MetricDistance>>,
+    growth: &Growth,
+    live: &DashMap<usize, NodeId>,
+    params: &HnswParams,
+    distance: MetricDistance,
+) {
+    let _maintenance = growth.maintenance.lock().unwrap();
+    loop {
+        let capacity = growth
+            .capacity
+            .load(Ordering::SeqCst)
+            .saturating_mul(2)
+            .max(1);
+        let points: Vec<(usize, Vec<f32>)> = index
+            .read()
+            .unwrap()
+            .get_point_indexation()
+            .into_iter()
+            .filter(|point| live.contains_key(&point.get_origin_id()))
+            .map(|point| (point.get_origin_id(), point.get_v().to_vec()))
+            .collect();
+
+        let graph = HnswVectorIndex::empty_graph(capacity, distance, params);
+        let mut copied = HashSet::with_capacity(points.len());
+        for (internal_id, embedding) in &points {
+            graph.insert((embedding, *internal_id));
+            copied.insert(*internal_id);
+        }
+
+        let mut current = index.write().unwrap();
+        for point in current.get_point_indexation() {
+            let internal_id = point.get_origin_id();
+            if !copied.contains(&internal_id) && live.contains_key(&internal_id) {
+                graph.insert((&point.get_v().to_vec(), internal_id));
+            }
+        }
+        *current = graph;
+        growth.capacity.store(capacity, Ordering::SeqCst);
+        if !nearly_full(current.get_nb_point(), capacity) {
+            break;
+        }
+    }
+    growth.growing.store(false, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::LinearVectorIndex;
+
+    #[test]
+    fn test_remove_tombstones_then_rebuilds() {
+        let index = HnswVectorIndex::new(10_000);
+        let live = REBUILD_MIN_STALE / 2;
+        for id in 0..(REBUILD_MIN_STALE + live) as NodeId {
+            index.insert(id, &[id as f32, 1.0]);
+        }
+        assert!(index.remove(3));
+        assert!(!index.remove(3));
+        assert_eq!(index.stale_count(), 1);
+        assert!(!index.knn(&[3.0, 1.0], 5).iter().any(|(id, _)| *id == 3));
+
+        // Once dead points outnumber live ones the graph is rebuilt
+        for id in live as NodeId..(REBUILD_MIN_STALE + live - 1) as NodeId {
+            index.remove(id);
+        }
+        assert_eq!(index.stale_count(), 0);
+        assert_eq!(index.len(), live);
+        assert_eq!(index.knn(&[7.0, 1.0], 1)[0].0, 7);
+        assert_eq!(index.knn(&[900.0, 1.0], 1)[0].0, live as NodeId - 1);
+    }
+
+    #[test]
+    fn test_compact_drops_replaced_points_and_get_survives() {
+        let index = HnswVectorIndex::new(1_000);
+        for id in 0..50 as NodeId {
+            index.insert(id, &[id as f32, 0.0]);
+        }
+        for id in 0..20 as NodeId {
+            index.insert(id, &[id as f32, 1.0]);
+        }
+        assert_eq!(index.stale_count(), 20);
+
+        let stats = index.stats();
+        assert_eq!((stats.elements, stats.stale, stats.dimension), (50, 20, 2));
+        assert_eq!(index.get(5), Some(vec![5.0, 1.0]));
+        assert_eq!(index.get(30), Some(vec![30.0, 0.0]));
+
+        assert_eq!(index.compact(), 20);
+        assert_eq!(index.stale_count(), 0);
+        assert!(index.stats().memory_bytes < stats.memory_bytes);
+        assert_eq!(index.compact(), 0);
+        assert!(index.remove(30));
+        assert_eq!(index.get(30), None);
+        assert_eq!(index.len(), 49);
+        // The removed point stays in the graph as a tombstone
+        let stats = index.stats();
+        assert_eq!((stats.elements, stats.stale, stats.dimension), (49, 1, 2));
+        assert_eq!(index.knn(&[5.0, 1.0], 1), vec![(5, 0.0)]);
+        assert_eq!(index.knn(&[31.0, 0.0], 1), vec![(31, 0.0)]);
+    }
+
+    #[test]
+    fn test_small_index_scans_exactly() {
+        let params = HnswParams {
+            exact_below: 64,
+            ..HnswParams::default()
+        };
+        let index = HnswVectorIndex::with_params(DistanceMetric::Cosine, params);
+        let exact = LinearVectorIndex::with_metric(DistanceMetric::Cosine);
+        for id in 0..100 as NodeId {
+            let x = id as f32 * 0.41;
+            index.insert(id, &[x.sin(), x.cos()]);
+            exact.insert(id, &[x.sin(), x.cos()]);
+            if id == 62 {
+                assert_eq!(index.knn(&[0.3, 0.7], 63), exact.knn(&[0.3, 0.7], 63));
+                assert!(index.knn(&[0.3, 0.7, 0.1], 5).is_empty());
+            }
+        }
+        assert_eq!(index.knn(&[0.3, 0.7], 1), exact.knn(&[0.3, 0.7], 1));
+    }
+
+    #[test]
+    fn test_build_from_inserts_in_parallel() {
+        let params = HnswParams {
+            exact_below: 0,
+            ..HnswParams::default()
+        };
+        let index = HnswVectorIndex::with_params(DistanceMetric::L2, params);
+        // Scattered points; a regular grid's ties make approximate search miss
+        let point = |id: u64| {
+            let x = id as f32;
+            vec![(x * 0.618).fract() * 100.0, (x * 0.377).sin() * 100.0]
+        };
+        let vectors: Vec<(NodeId, Vec<f32>)> = (0..3000u64).map(|id| (id, point(id))).collect();
+        let mut batch: Vec<(NodeId, &[f32])> =
+            vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        // A repeated node keeps its last vector
+        let moved = point(7777);
+        batch.push((7, &moved));
+        index.build_from(&batch);
+
+        assert_eq!(index.len(), 3000);
+        assert_eq!(index.stale_count(), 1);
+        assert_eq!(index.get(7), Some(moved.clone()));
+        assert_ne!(index.knn(&point(7), 1)[0], (7, 0.0));
+        // Graph search is approximate, so allow for a rare unreachable point
+        let found = (0..3000u64)
+            .step_by(30)
+            .filter(|&id| id != 7 && index.knn(&point(id), 1)[0] == (id, 0.0))
+            .count();
+        assert!(found >= 95, "found {} of 99", found);
+
+        // Later inserts continue after the batch's internal IDs
+        index.insert(5000, &point(5000));
+        assert_eq!(index.len(), 3001);
+        assert_eq!(index.stale_count(), 1);
+        assert_eq!(index.knn(&point(5000), 5).len(), 5);
+    }
+
+    #[test]
+    fn test_grows_past_initial_capacity() {
+        let params = HnswParams {
+            capacity: 32,
+            ..HnswParams::default()
+        };
+        let index = HnswVectorIndex::with_params(DistanceMetric::L2, params);
+        for id in 0..300u64 {
+            index.insert(id, &[id as f32, 0.0]);
+        }
+        index.insert(7, &[-5.0, 0.0]);
+        index.remove(8);
+
+        let started = std::time::Instant::now();
+        while index.is_growing() {
+            assert!(started.elapsed().as_secs() < 30, "growth never finished");
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(index.capacity() > 300);
+        assert_eq!(index.len(), 299);
+        assert_eq!(index.knn(&[-5.0, 0.0], 1)[0].0, 7);
+        assert_eq!(index.knn(&[250.2, 0.0], 1)[0].0, 250);
+        assert!(!index.knn(&[8.0, 0.0], 3).iter().any(|(id, _)| *id == 8));
+    }
 }
@@ -0,0 +1,325 @@
+//! Inverted file (IVF) vector index.
+//!
+//! Vectors are partitioned into `nlist` clusters by k-means and a query
+//! scans only the `nprobe` lists whose centroids are closest to it. It
+//! sits between the linear scan and HNSW: building takes a few k-means
+//! passes instead of a graph insertion per vector, and an insert just
+//! appends to one list, which suits write-heavy workloads. Recall grows
+//! with `nprobe`; probing every list is an exact search.
+//!
+//! Until `TRAIN_PER_LIST` vectors per list have arrived, everything is
+//! scanned exactly. Clusters are retrained whenever the index has doubled
+//! since the last training, so centroids follow the data as it grows
+//! while the training cost stays amortized.
+
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::RwLock;
+
+use rayon::prelude::*;
+
+use super::{map_stats, scan_top_k, DistanceMetric, IndexStats, Quantization, VectorIndex};
+use crate::NodeId;
+
+/// Vectors per list needed before the first training.
+const TRAIN_PER_LIST: usize = 8;
+
+/// Lloyd iterations per training.
+const KMEANS_ITERATIONS: usize = 10;
+
+/// Clusters and their members.
+#[derive(Debug, Default)]
+struct Lists {
+    centroids: Vec<Vec<f32>>,
+    lists: Vec<HashMap<NodeId, Vec<f32>>>,
+    /// Vectors outside any list: everything before the first training,
+    /// and vectors whose dimension differs from the centroids.
+    unassigned: HashMap<NodeId, Vec<f32>>,
+    /// List of every assigned node.
+    assignment: HashMap<NodeId, usize>,
+    /// Number of vectors at the last training (0 if untrained).
+    trained_at: usize,
+}
+
+impl Lists {
+    fn len(&self) -> usize {
+        self.assignment.len() + self.unassigned.len()
+    }
+
+    fn take(&mut self, id: NodeId) -> Option<Vec<f32>> {
+        match self.assignment.remove(&id) {
+            Some(list) => self.lists[list].remove(&id),
+            None => self.unassigned.remove(&id),
+        }
+    }
+
+    fn place(&mut self, id: NodeId, embedding: Vec<f32>, metric: DistanceMetric) {
+        match nearest_centroid(&self.centroids, &embedding, metric) {
+            Some(list) => {
+                self.lists[list].insert(id, embedding);
+                self.assignment.insert(id, list);
+            }
+            None => {
+                self.unassigned.insert(id, embedding);
+            }
+        }
+    }
+
+    /// Reruns k-means over all vectors and redistributes them.
+    fn train(&mut self, nlist: usize, metric: DistanceMetric) {
+        let mut all: Vec<(NodeId, Vec<f32>)> = self
+            .lists
+            .drain(..)
+            .flatten()
+            .chain(self.unassigned.drain())
+            .collect();
+        self.assignment.clear();
+        all.sort_unstable_by_key(|(id, _)| *id);
+
+        // Cluster the most common dimension; other vectors stay unassigned
+        let mut dims: HashMap<usize, usize> = HashMap::new();
+        for (_, v) in &all {
+            *dims.entry(v.len()).or_default() += 1;
+        }
+        let dimension = dims
+            .into_iter()
+            .max_by_key(|&(dim, count)| (count, dim))
+            .map_or(0, |(dim, _)| dim);
+        let samples: Vec<&[f32]> = all
+            .iter()
+            .map(|(_, v)| v.as_slice())
+            .filter(|v| v.len() == dimension)
+            .collect();
+
+        self.centroids = kmeans(&samples, nlist, metric);
+        self.lists = vec![HashMap::new(); self.centroids.len()];
+        self.trained_at = all.len();
+        for (id, embedding) in all {
+            self.place(id, embedding, metric);
+        }
+    }
+}
+
+/// Index of the centroid closest to `v`, if `v` has the centroids' dimension.
+fn nearest_centroid(centroids: &[Vec<f32>], v: &[f32], metric: DistanceMetric) -> Option<usize> {
+    centroids
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.len() == v.len())
+        .map(|(i, c)| (i, metric.distance(v, c)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+/// Lloyd's k-means with evenly spaced samples as initial centroids.
+///
+/// Deterministic for a given sample order. A cluster that loses all
+/// its members keeps its previous centroid.
+fn kmeans(samples: &[&[f32]], k: usize, metric: DistanceMetric) -> Vec<Vec<f32>> {
+    let k = k.min(samples.len());
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut centroids: Vec<Vec<f32>> = (0..k)
+        .map(|i| samples[i * samples.len() / k].to_vec())
+        .collect();
+    let dimension = centroids[0].len();
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let assigned: Vec<usize> = samples
+            .par_iter()
+            .map(|v| nearest_centroid(&centroids, v, metric).unwrap_or(0))
+            .collect();
+        let mut sums = vec![vec![0.0f32; dimension]; k];
+        let mut counts = vec![0usize; k];
+        for (v, &cluster) in samples.iter().zip(&assigned) {
+            counts[cluster] += 1;
+            for (sum, x) in sums[cluster].iter_mut().zip(v.iter()) {
+                *sum += x;
+            }
+        }
+        for ((centroid, sum), count) in centroids.iter_mut().zip(sums).zip(counts) {
+            if count > 0 {
+                *centroid = sum.into_iter().map(|s| s / count as f32).collect();
+            }
+        }
+    }
+    centroids
+}
+
+/// IVF index: k-means clusters with exact search inside probed lists.
+#[derive(Debug)]
+pub struct IvfVectorIndex {
+    state: RwLock<Lists>,
+    /// Number of clusters.
+    nlist: usize,
+    /// Clusters scanned per query.
+    nprobe: usize,
+    metric: DistanceMetric,
+}
+
+impl IvfVectorIndex {
+    /// Default number of clusters.
+    pub const DEFAULT_NLIST: usize = 256;
+
+    /// Default number of clusters scanned per query.
+    pub const DEFAULT_NPROBE: usize = 8;
+
+    /// Creates an empty IVF index.
+    ///
+    /// # Arguments
+    ///
+    /// * `nlist` - Number of k-means clusters
+    /// * `nprobe` - Clusters scanned per query; `nlist` makes search exact
+    /// * `metric` - Distance metric for clustering and ranking
+    pub fn new(nlist: usize, nprobe: usize, metric: DistanceMetric) -> Self {
+        Self {
+            state: RwLock::new(Lists::default()),
+            nlist: nlist.max(1),
+            nprobe: nprobe.max(1),
+            metric,
+        }
+    }
+
+    /// Returns whether clusters have been trained yet.
+    pub fn is_trained(&self) -> bool {
+        !self.state.read().unwrap().centroids.is_empty()
+    }
+}
+
+impl VectorIndex for IvfVectorIndex {
+    fn insert(&self, id: NodeId, embedding: &[f32]) {
+        let mut state = self.state.write().unwrap();
+        state.take(id);
+        state.place(id, embedding.to_vec(), self.metric);
+
+        let len = state.len();
+        let due = if state.trained_at == 0 {
+            len >= self.nlist * TRAIN_PER_LIST
+        } else {
+            len >= state.trained_at * 2
+        };
+        if due {
+            state.train(self.nlist, self.metric);
+        }
+    }
+
+    fn remove(&self, id: NodeId) -> bool {
+        self.state.write().unwrap().take(id).is_some()
+    }
+
+    fn knn(&self, query: &[f32], k: usize) -> Vec<(NodeId, f32)> {
+        let state = self.state.read().unwrap();
+        let distance =
+            |v: &Vec<f32>| (v.len() == query.len()).then(|| self.metric.distance(query, v));
+
+        let mut probed: Vec<(usize, f32)> = state
+            .centroids
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.len() == query.len())
+            .map(|(i, c)| (i, self.metric.distance(query, c)))
+            .collect();
+        probed.sort_by(|a, b| a.1.total_cmp(&b.1));
+        probed.truncate(self.nprobe);
+
+        let mut results = scan_top_k(&state.unassigned, k, distance);
+        for (list, _) in probed {
+            results.extend(scan_top_k(&state.lists[list], k, distance));
+        }
+        results.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        results.truncate(k);
+        results
+    }
+
+    fn len(&self) -> usize {
+        self.state.read().unwrap().len()
+    }
+
+    fn contains(&self, id: NodeId) -> bool {
+        let state = self.state.read().unwrap();
+        state.assignment.contains_key(&id) || state.unassigned.contains_key(&id)
+    }
+
+    fn get(&self, id: NodeId) -> Option<Vec<f32>> {
+        let state = self.state.read().unwrap();
+        match state.assignment.get(&id) {
+            Some(&list) => state.lists[list].get(&id).cloned(),
+            None => state.unassigned.get(&id).cloned(),
+        }
+    }
+
+    fn stats(&self) -> IndexStats {
+        let state = self.state.read().unwrap();
+        let vectors = state.lists.iter().flatten().chain(&state.unassigned);
+        let mut stats = map_stats(vectors.map(|(_, v)| v.len()), |d| {
+            Quantization::None.stored_bytes(d)
+        });
+        // Centroids plus the list assignment of every clustered vector
+        stats.memory_bytes += state
+            .centroids
+            .iter()
+            .map(|c| Quantization::None.stored_bytes(c.len()))
+            .sum::<usize>()
+            + state.assignment.len() * (size_of::<NodeId>() + size_of::<usize>());
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::LinearVectorIndex;
+
+    #[test]
+    fn test_probed_lists_match_exact_search() {
+        let ivf = IvfVectorIndex::new(8, 3, DistanceMetric::L2);
+        let exact = LinearVectorIndex::new();
+        assert!(!ivf.is_trained());
+
+        // Four well separated blobs
+        for id in 0..400u64 {
+            let center = [(id % 4) as f32 * 100.0, (id % 4 / 2) as f32 * 100.0];
+            let jitter = (id as f32 * 0.618).fract() * 4.0;
+            let v = [center[0] + jitter, center[1] - jitter];
+            ivf.insert(id, &v);
+            exact.insert(id, &v);
+        }
+        ivf.insert(999, &[1.0, 2.0, 3.0]);
+        assert!(ivf.is_trained());
+        assert_eq!(ivf.len(), 401);
+
+        for query in [[0.5, 0.5], [101.0, -1.0], [298.0, 99.0]] {
+            assert_eq!(ivf.knn(&query, 10), exact.knn(&query, 10));
+        }
+        assert_eq!(ivf.knn(&[1.0, 2.0, 3.0], 1)[0].0, 999);
+
+        assert!(ivf.remove(4));
+        assert!(!ivf.remove(4));
+        assert!(!ivf.contains(4));
+        assert!(!ivf.knn(&[0.0, 0.0], 400).iter().any(|(id, _)| *id == 4));
+    }
+
+    #[test]
+    fn test_get_and_stats_track_updates() {
+        let ivf = IvfVectorIndex::new(2, 1, DistanceMetric::L2);
+        // Before training vectors sit unassigned, afterwards in lists
+        ivf.insert(1, &[0.0, 0.0]);
+        assert_eq!(ivf.get(1), Some(vec![0.0, 0.0]));
+        for id in 2..40u64 {
+            ivf.insert(id, &[(id % 2) as f32 * 50.0, id as f32 * 0.1]);
+        }
+        assert!(ivf.is_trained());
+
+        // Moving a vector to the other cluster keeps a single copy
+        ivf.insert(1, &[50.0, 1.0]);
+        assert_eq!(ivf.get(1), Some(vec![50.0, 1.0]));
+        assert_eq!(ivf.knn(&[50.0, 1.0], 1), vec![(1, 0.0)]);
+        let stats = ivf.stats();
+        assert_eq!((stats.elements, stats.stale, stats.dimension), (39, 0, 2));
+
+        assert!(ivf.remove(1));
+        assert_eq!(ivf.get(1), None);
+        assert_eq!(ivf.stats().elements, 38);
+    }
+}
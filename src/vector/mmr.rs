@@ -0,0 +1,114 @@
+//! Maximal marginal relevance (MMR) reranking.
+//!
+//! Plain kNN returns the vectors closest to the query, which for agent
+//! memories are often near-duplicates of each other. MMR picks results
+//! greedily, trading closeness to the query against distance to the
+//! results already picked:
+//!
+//! `score = lambda * relevance - (1 - lambda) * redundancy`
+//!
+//! where relevance is the negated distance to the query and redundancy
+//! the negated distance to the closest picked result, both under the
+//! index metric. `lambda = 1` reproduces the kNN order; lower values
+//! favor diversity.
+
+use super::DistanceMetric;
+use crate::NodeId;
+
+/// Nearest neighbors fetched per requested result as MMR candidates.
+pub const CANDIDATES_PER_RESULT: usize = 4;
+
+/// Selects up to `k` diverse results from nearest-neighbor candidates.
+///
+/// # Arguments
+///
+/// * `candidates` - (NodeId, distance to query) pairs, e.g. from `knn`
+/// * `k` - Number of results to select
+/// * `lambda` - Relevance weight in `[0, 1]`; clamped into that range
+/// * `metric` - Metric used to compare candidates with each other
+/// * `embedding` - Looks up a candidate's vector; candidates without
+///   one are never considered redundant
+///
+/// # Returns
+///
+/// Selected (NodeId, distance to query) pairs in selection order.
+pub fn mmr_select<'a>(
+    candidates: &[(NodeId, f32)],
+    k: usize,
+    lambda: f32,
+    metric: DistanceMetric,
+    embedding: impl Fn(NodeId) -> Option<&'a [f32]>,
+) -> Vec<(NodeId, f32)> {
+    let lambda = lambda.clamp(0.0, 1.0);
+    let mut remaining: Vec<(NodeId, f32, Option<&[f32]>)> = candidates
+        .iter()
+        .map(|&(id, distance)| (id, distance, embedding(id)))
+        .collect();
+    // Distance from each remaining candidate to its closest selected one
+    let mut closest = vec![f32::INFINITY; remaining.len()];
+    let mut selected = Vec::with_capacity(k.min(remaining.len()));
+
+    while selected.len() < k && !remaining.is_empty() {
+        let score = |i: usize| {
+            let redundancy = if closest[i].is_finite() {
+                (1.0 - lambda) * closest[i]
+            } else {
+                0.0
+            };
+            redundancy - lambda * remaining[i].1
+        };
+        let best = (0..remaining.len())
+            .max_by(|&a, &b| score(a).total_cmp(&score(b)).then(b.cmp(&a)))
+            .expect("remaining is not empty");
+
+        let (id, distance, picked) = remaining.remove(best);
+        closest.remove(best);
+        selected.push((id, distance));
+        if let Some(picked) = picked {
+            for ((_, _, other), closest) in remaining.iter().zip(closest.iter_mut()) {
+                if let Some(other) = other.filter(|o| o.len() == picked.len()) {
+                    *closest = closest.min(metric.distance(picked, other));
+                }
+            }
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mmr_skips_near_duplicates() {
+        let vectors: Vec<(NodeId, Vec<f32>)> = vec![
+            (1, vec![1.0, 0.0]),
+            (2, vec![1.0, 0.01]),
+            (3, vec![1.0, -0.01]),
+            (4, vec![0.6, 0.8]),
+        ];
+        let query = [0.9, 0.1];
+        let mut candidates: Vec<(NodeId, f32)> = vectors
+            .iter()
+            .map(|(id, v)| (*id, DistanceMetric::L2.distance(&query, v)))
+            .collect();
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let lookup = |id: NodeId| {
+            vectors
+                .iter()
+                .find(|(v, _)| *v == id)
+                .map(|(_, v)| v.as_slice())
+        };
+
+        let relevant = mmr_select(&candidates, 2, 1.0, DistanceMetric::L2, lookup);
+        assert_eq!(relevant, candidates[..2].to_vec());
+
+        let diverse = mmr_select(&candidates, 2, 0.5, DistanceMetric::L2, lookup);
+        assert_eq!(diverse[0], candidates[0]);
+        assert_eq!(diverse[1].0, 4);
+        assert_eq!(
+            mmr_select(&candidates, 10, 0.5, DistanceMetric::L2, lookup).len(),
+            4
+        );
+    }
+}
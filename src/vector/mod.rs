@@ -1,15 +1,36 @@
 //! Vector index for similarity search.
 //!
 //! This module provides vector indexing and k-nearest neighbor (kNN) search
-//! functionality using L2 (Euclidean) distance.
-
-use std::collections::HashMap;
+//! functionality under a configurable `DistanceMetric`.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::mem::size_of;
+use std::str::FromStr;
+#[cfg(feature = "wgpu")]
+use std::sync::Mutex;
 use std::sync::RwLock;
 
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
 use crate::NodeId;
 
+pub mod avoid;
+pub mod gpu;
 pub mod hnsw;
-pub use hnsw::HnswVectorIndex;
+pub mod ivf;
+pub mod mmr;
+pub mod quantized;
+pub mod recall;
+pub use avoid::rerank_avoiding;
+pub use gpu::{batch_distances, gpu_available, GPU_BATCH_MIN};
+pub use hnsw::{HnswParams, HnswVectorIndex};
+pub use ivf::IvfVectorIndex;
+pub use mmr::mmr_select;
+pub use quantized::{Quantization, QuantizedVectorIndex};
+pub use recall::{evaluate_recall, RecallReport};
 
 /// Trait for vector index implementations.
 ///
@@ -24,6 +45,24 @@ pub trait VectorIndex: Send + Sync {
     /// * `embedding` - Vector embedding to store
     fn insert(&self, id: NodeId, embedding: &[f32]);
 
+    /// Inserts many embeddings at once, e.g. when a database is loaded.
+    ///
+    /// The default inserts them one by one; indexes with a faster bulk
+    /// path, like parallel HNSW construction, override it. A node listed
+    /// twice keeps its last embedding.
+    fn build_from(&self, vectors: &[(NodeId, &[f32])]) {
+        for &(id, embedding) in vectors {
+            self.insert(id, embedding);
+        }
+    }
+
+    /// Removes the embedding of a node.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the node was indexed.
+    fn remove(&self, id: NodeId) -> bool;
+
     /// Finds the k nearest neighbors to a query vector.
     ///
     /// # Arguments
@@ -36,6 +75,15 @@ pub trait VectorIndex: Send + Sync {
     /// A vector of (NodeId, distance) pairs sorted by distance ascending.
     fn knn(&self, query: &[f32], k: usize) -> Vec<(NodeId, f32)>;
 
+    /// Finds the k nearest neighbors with an explicit search beam width.
+    ///
+    /// Approximate indexes explore `ef_search` candidates instead of
+    /// their configured default; exact indexes ignore it.
+    fn knn_with_ef(&self, query: &[f32], k: usize, ef_search: usize) -> Vec<(NodeId, f32)> {
+        let _ = ef_search;
+        self.knn(query, k)
+    }
+
     /// Returns the number of vectors in the index.
     fn len(&self) -> usize;
 
@@ -46,6 +94,69 @@ pub trait VectorIndex: Send + Sync {
 
     /// Checks if a node exists in the index.
     fn contains(&self, id: NodeId) -> bool;
+
+    /// Returns a copy of the vector indexed for a node.
+    ///
+    /// Quantized indexes return the dequantized approximation.
+    fn get(&self, id: NodeId) -> Option<Vec<f32>>;
+
+    /// Reports the size and estimated memory footprint of the index.
+    fn stats(&self) -> IndexStats;
+
+    /// Drops entries left behind by updates and removals.
+    ///
+    /// Indexes that delete in place have nothing to drop and keep the
+    /// default.
+    ///
+    /// # Returns
+    ///
+    /// The number of stale entries dropped.
+    fn compact(&self) -> usize {
+        0
+    }
+
+    /// Saves the index under the path prefix `base` so it can be reloaded
+    /// instead of rebuilt.
+    ///
+    /// Indexes that are cheap to rebuild keep the default, which writes
+    /// nothing.
+    fn persist(&self, base: &std::path::Path) -> anyhow::Result<()> {
+        let _ = base;
+        Ok(())
+    }
+}
+
+/// Size and memory estimate of a vector index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct IndexStats {
+    /// Live vectors that searches can return.
+    pub elements: usize,
+    /// Entries still held but no longer mapped to a node, e.g. HNSW
+    /// points left behind by updates until the next compaction.
+    pub stale: usize,
+    /// Dimension of the indexed vectors (the largest, if mixed; 0 if empty).
+    pub dimension: usize,
+    /// Estimated bytes held by the index.
+    pub memory_bytes: usize,
+}
+
+/// Estimated bytes of one map entry around a stored vector.
+const MAP_ENTRY_BYTES: usize = size_of::<NodeId>() + size_of::<Vec<f32>>() + 16;
+
+/// Stats of an index holding one map entry per vector.
+///
+/// `stored_bytes` gives the payload size of a vector of a dimension.
+fn map_stats(
+    dimensions: impl Iterator<Item = usize>,
+    stored_bytes: impl Fn(usize) -> usize,
+) -> IndexStats {
+    let mut stats = IndexStats::default();
+    for dimension in dimensions {
+        stats.elements += 1;
+        stats.dimension = stats.dimension.max(dimension);
+        stats.memory_bytes += stored_bytes(dimension) + MAP_ENTRY_BYTES;
+    }
+    stats
 }
 
 /// Computes the L2 (Euclidean) distance between two vectors.
@@ -89,7 +200,6 @@ pub fn l2_distance(a: &[f32], b: &[f32]) -> f32 {
 /// # Returns
 ///
 /// The cosine distance (0 = identical, 2 = opposite).
-#[allow(dead_code)]
 pub fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
     debug_assert_eq!(
         a.len(),
@@ -115,46 +225,268 @@ pub fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
     1.0 - (dot / magnitude)
 }
 
+/// Scales a vector to unit L2 norm in place; zero vectors are left as is.
+///
+/// On unit vectors `l2² = 2 - 2·cos`, so L2, cosine and dot-product
+/// rankings all agree.
+pub fn normalize(v: &mut [f32]) {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in v.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+/// Distance metric used to compare embeddings.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    /// Euclidean distance.
+    #[default]
+    L2,
+    /// Cosine distance (`1 - cosine_similarity`).
+    Cosine,
+    /// Negated inner product, so that lower is still better.
+    DotProduct,
+}
+
+impl DistanceMetric {
+    /// Computes the distance between two vectors under this metric.
+    ///
+    /// Lower values always mean more similar vectors.
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            DistanceMetric::L2 => l2_distance(a, b),
+            DistanceMetric::Cosine => cosine_distance(a, b),
+            DistanceMetric::DotProduct => -dot_product(a, b),
+        }
+    }
+}
+
+impl fmt::Display for DistanceMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DistanceMetric::L2 => "l2",
+            DistanceMetric::Cosine => "cosine",
+            DistanceMetric::DotProduct => "dot",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for DistanceMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "l2" | "euclidean" => Ok(DistanceMetric::L2),
+            "cosine" => Ok(DistanceMetric::Cosine),
+            "dot" | "dot_product" | "ip" => Ok(DistanceMetric::DotProduct),
+            other => Err(format!("Unknown distance metric: {}", other)),
+        }
+    }
+}
+
+/// Computes the inner product of two vectors.
+pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+    debug_assert_eq!(
+        a.len(),
+        b.len(),
+        "Vectors must have same length for dot product"
+    );
+
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+/// Vectors below which a linear scan stays on the calling thread.
+const PARALLEL_SCAN_MIN: usize = 4096;
+
+/// A scored candidate, ordered by distance and then ID so the top of a
+/// `BinaryHeap` is the worst one kept.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    id: NodeId,
+    distance: f32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+/// Adds a candidate to a heap holding the `k` best seen so far.
+fn push_bounded(
+    mut heap: BinaryHeap<Candidate>,
+    candidate: Candidate,
+    k: usize,
+) -> BinaryHeap<Candidate> {
+    if heap.len() < k {
+        heap.push(candidate);
+    } else if heap.peek().is_some_and(|worst| candidate < *worst) {
+        heap.pop();
+        heap.push(candidate);
+    }
+    heap
+}
+
+/// Returns the `k` lowest-distance pairs of `scored`, sorted ascending.
+fn select_top_k(scored: impl Iterator<Item = (NodeId, f32)>, k: usize) -> Vec<(NodeId, f32)> {
+    if k == 0 {
+        return Vec::new();
+    }
+    scored
+        .map(|(id, distance)| Candidate { id, distance })
+        .fold(BinaryHeap::new(), |heap, c| push_bounded(heap, c, k))
+        .into_sorted_vec()
+        .into_iter()
+        .map(|c| (c.id, c.distance))
+        .collect()
+}
+
+/// Returns the `k` entries with the lowest distance, sorted ascending.
+///
+/// `distance` returns `None` for entries to skip. Maps with at least
+/// `PARALLEL_SCAN_MIN` entries are scored in parallel; either way only
+/// a bounded max-heap of the best `k` is kept instead of sorting all.
+fn scan_top_k<T: Sync>(
+    entries: &HashMap<NodeId, T>,
+    k: usize,
+    distance: impl Fn(&T) -> Option<f32> + Sync,
+) -> Vec<(NodeId, f32)> {
+    if entries.len() < PARALLEL_SCAN_MIN {
+        let scored = entries
+            .iter()
+            .filter_map(|(&id, entry)| Some((id, distance(entry)?)));
+        return select_top_k(scored, k);
+    }
+    if k == 0 {
+        return Vec::new();
+    }
+    let score = |(&id, entry): (&NodeId, &T)| {
+        Some(Candidate {
+            id,
+            distance: distance(entry)?,
+        })
+    };
+    let best = entries
+        .par_iter()
+        .filter_map(score)
+        .fold(BinaryHeap::new, |heap, c| push_bounded(heap, c, k))
+        .reduce(BinaryHeap::new, |a, b| {
+            b.into_iter().fold(a, |heap, c| push_bounded(heap, c, k))
+        });
+
+    best.into_sorted_vec()
+        .into_iter()
+        .map(|c| (c.id, c.distance))
+        .collect()
+}
+
 /// Linear scan vector index implementation.
 ///
-/// This is a simple brute-force implementation that computes
-/// distances to all vectors for each query. Suitable for small
-/// datasets (< 10,000 vectors).
+/// This is a brute-force implementation that computes distances to all
+/// vectors for each query, so results are exact. Large indexes are
+/// scanned in parallel and only the best `k` candidates are kept, which
+/// keeps exact search practical up to around a million vectors. With
+/// the `wgpu` feature, indexes of at least `GPU_BATCH_MIN` vectors are
+/// scored on the GPU instead.
 #[derive(Debug, Default)]
 pub struct LinearVectorIndex {
     /// Storage mapping node IDs to their embeddings.
     vectors: RwLock<HashMap<NodeId, Vec<f32>>>,
+    /// Metric used to rank results.
+    metric: DistanceMetric,
+    /// Copy of the vectors in GPU memory, dropped on every write.
+    #[cfg(feature = "wgpu")]
+    resident: Mutex<Option<gpu::DeviceMatrix>>,
 }
 
 impl LinearVectorIndex {
-    /// Creates a new empty linear vector index.
+    /// Creates a new empty linear vector index using L2 distance.
     pub fn new() -> Self {
+        Self::with_metric(DistanceMetric::L2)
+    }
+
+    /// Creates a new empty linear vector index using the given metric.
+    pub fn with_metric(metric: DistanceMetric) -> Self {
         Self {
             vectors: RwLock::new(HashMap::new()),
+            metric,
+            #[cfg(feature = "wgpu")]
+            resident: Mutex::new(None),
         }
     }
+
+    /// Scores every vector of the query's dimension on the GPU.
+    ///
+    /// Uploads the vectors first if they changed since the last query or
+    /// had another dimension. Returns `None` to fall back to the CPU.
+    #[cfg(feature = "wgpu")]
+    fn knn_on_gpu(
+        &self,
+        vectors: &HashMap<NodeId, Vec<f32>>,
+        query: &[f32],
+        k: usize,
+    ) -> Option<Vec<(NodeId, f32)>> {
+        let ctx = gpu::device::context()?;
+        let mut resident = self.resident.lock().unwrap();
+        if resident.as_ref().is_none_or(|m| m.dim() != query.len()) {
+            let rows = vectors
+                .iter()
+                .filter(|(_, v)| v.len() == query.len())
+                .map(|(&id, v)| (id, v.as_slice()));
+            *resident = gpu::DeviceMatrix::upload(ctx, query.len(), rows);
+        }
+        let matrix = resident.as_ref()?;
+        let distances = matrix.distances(ctx, self.metric, query)?;
+        Some(select_top_k(matrix.ids().iter().copied().zip(distances), k))
+    }
 }
 
 impl VectorIndex for LinearVectorIndex {
     fn insert(&self, id: NodeId, embedding: &[f32]) {
-        self.vectors.write().unwrap().insert(id, embedding.to_vec());
+        let mut vectors = self.vectors.write().unwrap();
+        #[cfg(feature = "wgpu")]
+        self.resident.lock().unwrap().take();
+        vectors.insert(id, embedding.to_vec());
+    }
+
+    fn remove(&self, id: NodeId) -> bool {
+        let mut vectors = self.vectors.write().unwrap();
+        #[cfg(feature = "wgpu")]
+        self.resident.lock().unwrap().take();
+        vectors.remove(&id).is_some()
     }
 
     fn knn(&self, query: &[f32], k: usize) -> Vec<(NodeId, f32)> {
-        // Compute distances to all vectors
         let vectors = self.vectors.read().unwrap();
-        let mut distances: Vec<(NodeId, f32)> = vectors
-            .iter()
-            .filter(|(_, vec)| vec.len() == query.len())
-            .map(|(&id, vec)| (id, l2_distance(query, vec)))
-            .collect();
-
-        // Sort by distance (ascending)
-        distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-
-        // Return top k
-        distances.truncate(k);
-        distances
+        #[cfg(feature = "wgpu")]
+        if vectors.len() >= GPU_BATCH_MIN {
+            if let Some(results) = self.knn_on_gpu(&vectors, query, k) {
+                return results;
+            }
+        }
+        scan_top_k(&vectors, k, |vec| {
+            (vec.len() == query.len()).then(|| self.metric.distance(query, vec))
+        })
     }
 
     fn len(&self) -> usize {
@@ -164,6 +496,17 @@ impl VectorIndex for LinearVectorIndex {
     fn contains(&self, id: NodeId) -> bool {
         self.vectors.read().unwrap().contains_key(&id)
     }
+
+    fn get(&self, id: NodeId) -> Option<Vec<f32>> {
+        self.vectors.read().unwrap().get(&id).cloned()
+    }
+
+    fn stats(&self) -> IndexStats {
+        let vectors = self.vectors.read().unwrap();
+        map_stats(vectors.values().map(Vec::len), |d| {
+            Quantization::None.stored_bytes(d)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -205,6 +548,48 @@ mod tests {
         assert!((cosine_distance(&a, &b) - 1.0).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_normalize_makes_l2_follow_cosine() {
+        let mut a = vec![3.0, 4.0];
+        let mut b = vec![10.0, 0.0];
+        let mut zero = vec![0.0, 0.0];
+        let cosine = cosine_distance(&a, &b);
+        normalize(&mut a);
+        normalize(&mut b);
+        normalize(&mut zero);
+        assert!((l2_distance(&a, &[0.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!((l2_distance(&a, &b).powi(2) - 2.0 * cosine).abs() < 1e-6);
+        assert_eq!(zero, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_metric_parse_and_distance() {
+        assert_eq!(
+            "cosine".parse::<DistanceMetric>(),
+            Ok(DistanceMetric::Cosine)
+        );
+        assert_eq!(
+            "DOT".parse::<DistanceMetric>(),
+            Ok(DistanceMetric::DotProduct)
+        );
+        assert!("manhattan".parse::<DistanceMetric>().is_err());
+
+        let a = [1.0, 2.0];
+        let b = [3.0, 4.0];
+        assert!((DistanceMetric::DotProduct.distance(&a, &b) + 11.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_linear_index_cosine_metric() {
+        let index = LinearVectorIndex::with_metric(DistanceMetric::Cosine);
+        index.insert(1, &[10.0, 0.0]);
+        index.insert(2, &[0.5, 0.5]);
+
+        // Direction matters, not magnitude
+        let results = index.knn(&[1.0, 0.1], 2);
+        assert_eq!(results[0].0, 1);
+    }
+
     #[test]
     fn test_linear_index_new() {
         let index = LinearVectorIndex::new();
@@ -233,6 +618,23 @@ mod tests {
         assert_eq!(retrieved, &embedding);
     }
 
+    #[test]
+    fn test_linear_index_get_and_stats_track_updates() {
+        let index = LinearVectorIndex::new();
+        index.insert(1, &[1.0, 2.0, 3.0]);
+        index.insert(2, &[4.0, 5.0, 6.0]);
+        assert_eq!(index.get(1), Some(vec![1.0, 2.0, 3.0]));
+
+        index.insert(1, &[7.0, 8.0, 9.0]);
+        assert_eq!(index.get(1), Some(vec![7.0, 8.0, 9.0]));
+        let stats = index.stats();
+        assert_eq!((stats.elements, stats.stale, stats.dimension), (2, 0, 3));
+
+        assert!(index.remove(2));
+        assert_eq!(index.get(2), None);
+        assert_eq!(index.stats().elements, 1);
+    }
+
     #[test]
     fn test_knn_simple() {
         let index = LinearVectorIndex::new();
@@ -265,6 +667,32 @@ mod tests {
         assert_eq!(results.len(), 2); // Only 2 vectors exist
     }
 
+    #[test]
+    fn test_parallel_knn_matches_full_sort() {
+        let index = LinearVectorIndex::new();
+        let count = PARALLEL_SCAN_MIN as u64 * 2;
+        for id in 0..count {
+            // Pairs of IDs share a vector, so ties must break by ID
+            let x = (id / 2) as f32;
+            index.insert(id, &[x.sin() * 100.0, x.cos() * 100.0]);
+        }
+        index.insert(count, &[1.0]); // wrong dimension, skipped
+
+        let query = [3.0, 50.0];
+        let mut expected: Vec<(NodeId, f32)> = (0..count)
+            .map(|id| {
+                let x = (id / 2) as f32;
+                (id, l2_distance(&query, &[x.sin() * 100.0, x.cos() * 100.0]))
+            })
+            .collect();
+        expected.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        expected.truncate(25);
+
+        assert_eq!(index.knn(&query, 25), expected);
+        assert!(index.knn(&query, 0).is_empty());
+        assert_eq!(index.knn(&query, usize::MAX).len(), count as usize);
+    }
+
     #[test]
     fn test_knn_empty_index() {
         let index = LinearVectorIndex::new();
@@ -0,0 +1,216 @@
+//! Int8 scalar-quantized vector storage.
+//!
+//! Each embedding is stored as one `i8` per component plus a per-vector
+//! scale (`max |x| / 127`), a quarter of the `f32` footprint. Queries
+//! stay in full precision and are compared against the codes directly
+//! (asymmetric distance computation), so no vector is ever dequantized
+//! into a temporary buffer. Distances carry a small quantization error,
+//! which mainly reorders near-ties.
+
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::{map_stats, scan_top_k, DistanceMetric, IndexStats, VectorIndex};
+use crate::NodeId;
+
+/// How the vector index stores embedding components.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    /// Full-precision `f32` components.
+    #[default]
+    None,
+    /// One signed byte per component with a per-vector scale.
+    Int8,
+}
+
+impl Quantization {
+    /// Returns the bytes used to store a vector of `dimension` components.
+    pub fn stored_bytes(&self, dimension: usize) -> usize {
+        match self {
+            Quantization::None => dimension * size_of::<f32>(),
+            Quantization::Int8 => dimension + 2 * size_of::<f32>(),
+        }
+    }
+}
+
+/// An embedding quantized to int8 codes.
+#[derive(Debug, Clone, PartialEq)]
+struct QuantizedVector {
+    /// Value of one code step; the component is `code as f32 * scale`.
+    scale: f32,
+    codes: Vec<i8>,
+    /// Squared norm of the dequantized vector, for L2 and cosine.
+    norm_sq: f32,
+}
+
+impl QuantizedVector {
+    fn encode(embedding: &[f32]) -> Self {
+        let max_abs = embedding.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+        let scale = if max_abs > 0.0 { max_abs / 127.0 } else { 0.0 };
+        let codes: Vec<i8> = embedding
+            .iter()
+            .map(|x| {
+                if scale > 0.0 {
+                    (x / scale).round().clamp(-127.0, 127.0) as i8
+                } else {
+                    0
+                }
+            })
+            .collect();
+        let norm_sq = codes.iter().map(|&c| (c as f32 * scale).powi(2)).sum();
+        Self {
+            scale,
+            codes,
+            norm_sq,
+        }
+    }
+
+    /// Inner product of a full-precision query with the dequantized vector.
+    fn dot(&self, query: &[f32]) -> f32 {
+        let raw: f32 = query
+            .iter()
+            .zip(&self.codes)
+            .map(|(q, &c)| q * c as f32)
+            .sum();
+        raw * self.scale
+    }
+
+    /// Distance from a query whose squared norm is `query_norm_sq`.
+    fn distance(&self, metric: DistanceMetric, query: &[f32], query_norm_sq: f32) -> f32 {
+        let dot = self.dot(query);
+        match metric {
+            DistanceMetric::L2 => (query_norm_sq - 2.0 * dot + self.norm_sq).max(0.0).sqrt(),
+            DistanceMetric::Cosine => {
+                let magnitude = (query_norm_sq * self.norm_sq).sqrt();
+                if magnitude == 0.0 {
+                    1.0
+                } else {
+                    1.0 - dot / magnitude
+                }
+            }
+            DistanceMetric::DotProduct => -dot,
+        }
+    }
+}
+
+/// Exact linear scan over int8-quantized embeddings.
+///
+/// Behaves like `LinearVectorIndex`, including the parallel bounded
+/// top-k scan, while holding roughly a quarter of the memory.
+#[derive(Debug, Default)]
+pub struct QuantizedVectorIndex {
+    vectors: RwLock<HashMap<NodeId, QuantizedVector>>,
+    metric: DistanceMetric,
+}
+
+impl QuantizedVectorIndex {
+    /// Creates a new empty quantized index using the given metric.
+    pub fn with_metric(metric: DistanceMetric) -> Self {
+        Self {
+            vectors: RwLock::new(HashMap::new()),
+            metric,
+        }
+    }
+}
+
+impl VectorIndex for QuantizedVectorIndex {
+    fn insert(&self, id: NodeId, embedding: &[f32]) {
+        let vector = QuantizedVector::encode(embedding);
+        self.vectors.write().unwrap().insert(id, vector);
+    }
+
+    fn remove(&self, id: NodeId) -> bool {
+        self.vectors.write().unwrap().remove(&id).is_some()
+    }
+
+    fn knn(&self, query: &[f32], k: usize) -> Vec<(NodeId, f32)> {
+        let query_norm_sq = query.iter().map(|x| x * x).sum();
+        let vectors = self.vectors.read().unwrap();
+        scan_top_k(&vectors, k, |vector| {
+            (vector.codes.len() == query.len())
+                .then(|| vector.distance(self.metric, query, query_norm_sq))
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.vectors.read().unwrap().len()
+    }
+
+    fn contains(&self, id: NodeId) -> bool {
+        self.vectors.read().unwrap().contains_key(&id)
+    }
+
+    fn get(&self, id: NodeId) -> Option<Vec<f32>> {
+        let vectors = self.vectors.read().unwrap();
+        let vector = vectors.get(&id)?;
+        Some(
+            vector
+                .codes
+                .iter()
+                .map(|&c| c as f32 * vector.scale)
+                .collect(),
+        )
+    }
+
+    fn stats(&self) -> IndexStats {
+        let vectors = self.vectors.read().unwrap();
+        map_stats(vectors.values().map(|v| v.codes.len()), |d| {
+            Quantization::Int8.stored_bytes(d)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::LinearVectorIndex;
+
+    #[test]
+    fn test_int8_ranking_tracks_full_precision() {
+        let exact = LinearVectorIndex::with_metric(DistanceMetric::Cosine);
+        let quantized = QuantizedVectorIndex::with_metric(DistanceMetric::Cosine);
+        for id in 0..200u64 {
+            let x = id as f32 * 0.37;
+            let embedding = [x.sin(), x.cos(), (2.0 * x).sin() * 0.5, 0.1];
+            exact.insert(id, &embedding);
+            quantized.insert(id, &embedding);
+        }
+        quantized.insert(999, &[0.0; 4]);
+
+        let query = [0.3, 0.9, -0.2, 0.1];
+        let want = exact.knn(&query, 5);
+        let got = quantized.knn(&query, 5);
+        assert_eq!(got[0].0, want[0].0);
+        for ((_, a), (_, b)) in want.iter().zip(&got) {
+            assert!((a - b).abs() < 0.01);
+        }
+
+        let restored = quantized.get(7).unwrap();
+        let x = 7.0f32 * 0.37;
+        assert!((restored[0] - x.sin()).abs() < 0.01);
+        assert_eq!(quantized.get(999), Some(vec![0.0; 4]));
+        assert!(Quantization::Int8.stored_bytes(768) * 3 < Quantization::None.stored_bytes(768));
+    }
+
+    #[test]
+    fn test_get_and_stats_track_updates() {
+        let index = QuantizedVectorIndex::default();
+        index.insert(1, &[1.0, 0.0, -1.0]);
+        index.insert(2, &[0.5, 0.5, 0.5]);
+        let close = |a: Vec<f32>, b: [f32; 3]| a.iter().zip(b).all(|(x, y)| (x - y).abs() < 0.01);
+        assert!(close(index.get(1).unwrap(), [1.0, 0.0, -1.0]));
+
+        index.insert(1, &[-2.0, 2.0, 0.0]);
+        assert!(close(index.get(1).unwrap(), [-2.0, 2.0, 0.0]));
+        let stats = index.stats();
+        assert_eq!((stats.elements, stats.stale, stats.dimension), (2, 0, 3));
+
+        assert!(index.remove(2));
+        assert_eq!(index.get(2), None);
+        assert_eq!(index.stats().elements, 1);
+    }
+}
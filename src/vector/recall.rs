@@ -0,0 +1,168 @@
+//! Recall evaluation for approximate vector search.
+//!
+//! Runs a query set through the configured index and through an exact
+//! scan of the same embeddings, then reports how many of the true
+//! nearest neighbors the index found and how long each search took.
+//! Rerunning it after changing `HnswParams` (or IVF `nprobe`) shows the
+//! recall/latency trade-off on the caller's own data.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use super::scan_top_k;
+use crate::storage::BarqGraphDb;
+use crate::NodeId;
+
+/// Search quality and latency measured by `evaluate_recall`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecallReport {
+    /// Number of queries evaluated.
+    pub queries: usize,
+    /// Neighbors requested per query.
+    pub k: usize,
+    /// Mean recall@k over all queries, between 0 and 1.
+    pub recall: f64,
+    /// Lowest recall@k of any single query.
+    pub min_recall: f64,
+    /// Mean index search latency in microseconds.
+    pub mean_us: f64,
+    /// 99th percentile index search latency in microseconds.
+    pub p99_us: u64,
+    /// Mean exact-scan latency in microseconds, for comparison.
+    pub exact_mean_us: f64,
+}
+
+/// Measures recall@k of the database's vector index.
+///
+/// Ground truth is an exhaustive scan over every indexed embedding with
+/// the database's metric. A query's recall is the fraction of its true
+/// top `k` (or fewer, if fewer vectors match its dimension) that the
+/// index returned. Queries go through `knn_search`, so they are timed
+/// and normalized exactly as in production.
+///
+/// # Arguments
+///
+/// * `db` - Database whose index is evaluated
+/// * `queries` - Query vectors, ideally drawn from real traffic
+/// * `k` - Number of neighbors per query
+///
+/// # Returns
+///
+/// A `RecallReport`; recall is 1.0 for an empty query set.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+/// use barq_graphdb::vector::evaluate_recall;
+/// use std::path::PathBuf;
+///
+/// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+/// let report = evaluate_recall(&db, &[vec![0.1, 0.2, 0.3]], 10);
+/// println!("recall@10 = {:.3}, p99 = {}us", report.recall, report.p99_us);
+/// ```
+pub fn evaluate_recall(db: &BarqGraphDb, queries: &[Vec<f32>], k: usize) -> RecallReport {
+    let metric = db.metric();
+    let embeddings: HashMap<NodeId, &[f32]> = db.indexed_embeddings().collect();
+
+    let mut recalls = Vec::with_capacity(queries.len());
+    let mut latencies = Vec::with_capacity(queries.len());
+    let mut exact_total = 0u64;
+    for query in queries {
+        let started = Instant::now();
+        let found = db.knn_search(query, k);
+        latencies.push(started.elapsed().as_micros() as u64);
+
+        let started = Instant::now();
+        let prepared = db.prepare_query(query);
+        let truth = scan_top_k(&embeddings, k, |v| {
+            (v.len() == prepared.len()).then(|| metric.distance(&prepared, v))
+        });
+        exact_total += started.elapsed().as_micros() as u64;
+
+        let recall = if truth.is_empty() {
+            1.0
+        } else {
+            let hits = truth
+                .iter()
+                .filter(|(id, _)| found.iter().any(|(f, _)| f == id))
+                .count();
+            hits as f64 / truth.len() as f64
+        };
+        recalls.push(recall);
+    }
+
+    let count = queries.len();
+    let mean = |sum: f64| if count == 0 { 0.0 } else { sum / count as f64 };
+    latencies.sort_unstable();
+    let p99_us = latencies
+        .get((count * 99).div_ceil(100).saturating_sub(1))
+        .copied()
+        .unwrap_or(0);
+    RecallReport {
+        queries: count,
+        k,
+        recall: if count == 0 {
+            1.0
+        } else {
+            mean(recalls.iter().sum())
+        },
+        min_recall: recalls.iter().copied().fold(1.0, f64::min),
+        mean_us: mean(latencies.iter().sum::<u64>() as f64),
+        p99_us,
+        exact_mean_us: mean(exact_total as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{DbOptions, IndexType};
+    use crate::vector::HnswParams;
+    use crate::Node;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_recall_of_exact_and_approximate_indexes() {
+        let queries: Vec<Vec<f32>> = (0..20)
+            .map(|i| vec![(i as f32 * 0.7).sin(), (i as f32 * 0.3).cos()])
+            .collect();
+
+        for (index_type, exact) in [(IndexType::Linear, true), (IndexType::Hnsw, false)] {
+            let temp_dir = TempDir::new().unwrap();
+            // Always walk the graph, however small
+            let hnsw = HnswParams {
+                exact_below: 0,
+                ..HnswParams::default()
+            };
+            let mut opts = DbOptions::new(temp_dir.path().to_path_buf()).with_hnsw(hnsw);
+            opts.index_type = index_type;
+            let mut db = BarqGraphDb::open(opts).unwrap();
+            for id in 1..=300u64 {
+                let x = id as f32 * 0.37;
+                let mut node = Node::new(id, format!("n{}", id));
+                node.embedding = vec![x.sin(), x.cos()];
+                db.append_node(node).unwrap();
+            }
+            db.set_embedding(999, vec![0.0, 1.0]).unwrap();
+
+            let report = evaluate_recall(&db, &queries, 10);
+            assert_eq!(report.queries, 20);
+            assert_eq!(report.k, 10);
+            assert!(report.min_recall <= report.recall);
+            assert!(report.p99_us as f64 >= report.mean_us.floor());
+            if exact {
+                assert_eq!(report.recall, 1.0);
+            } else {
+                assert!(report.recall > 0.8, "recall {}", report.recall);
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let db = BarqGraphDb::open(DbOptions::new(temp_dir.path().to_path_buf())).unwrap();
+        let report = evaluate_recall(&db, &[], 5);
+        assert_eq!((report.queries, report.recall, report.p99_us), (0, 1.0, 0));
+    }
+}
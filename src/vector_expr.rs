@@ -0,0 +1,150 @@
+//! Query vectors built from stored embeddings.
+//!
+//! A `VectorExpr` describes a query vector in terms of node IDs, such as
+//! the analogy `vec(A) - vec(B) + vec(C)` or the centroid of a few anchor
+//! nodes. The database resolves it against its own embeddings, so clients
+//! don't have to fetch large vectors only to send them straight back.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::error::BarqError;
+use crate::storage::BarqGraphDb;
+use crate::NodeId;
+
+/// Arithmetic over stored node embeddings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum VectorExpr {
+    /// Sum of the `add` embeddings minus the `subtract` embeddings.
+    Sum {
+        /// Nodes whose embeddings are added.
+        add: Vec<NodeId>,
+        /// Nodes whose embeddings are subtracted.
+        #[serde(default)]
+        subtract: Vec<NodeId>,
+    },
+    /// Element-wise mean of the embeddings.
+    Mean {
+        /// Anchor nodes to average.
+        nodes: Vec<NodeId>,
+    },
+}
+
+impl VectorExpr {
+    /// Builds the analogy `vec(a) - vec(b) + vec(c)` ("b is to a as c is to ?").
+    pub fn analogy(a: NodeId, b: NodeId, c: NodeId) -> Self {
+        VectorExpr::Sum {
+            add: vec![a, c],
+            subtract: vec![b],
+        }
+    }
+
+    /// Builds the centroid of the given nodes.
+    pub fn centroid(nodes: impl IntoIterator<Item = NodeId>) -> Self {
+        VectorExpr::Mean {
+            nodes: nodes.into_iter().collect(),
+        }
+    }
+}
+
+impl BarqGraphDb {
+    /// Resolves a vector expression into a query vector.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NodeNotFound` if a referenced node has no embedding, and
+    /// `InvalidOperation` if the expression references no nodes or the
+    /// embeddings have different dimensions.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use barq_graphdb::storage::{BarqGraphDb, DbOptions};
+    /// use barq_graphdb::vector_expr::VectorExpr;
+    /// use std::path::PathBuf;
+    ///
+    /// let db = BarqGraphDb::open(DbOptions::new(PathBuf::from("./my_db"))).unwrap();
+    /// // king - man + woman
+    /// let query = db.resolve_vector(&VectorExpr::analogy(1, 2, 3)).unwrap();
+    /// let results = db.knn_search(&query, 5);
+    /// ```
+    pub fn resolve_vector(&self, expr: &VectorExpr) -> Result<Vec<f32>> {
+        let terms: Vec<(NodeId, f32)> = match expr {
+            VectorExpr::Sum { add, subtract } => add
+                .iter()
+                .map(|&id| (id, 1.0))
+                .chain(subtract.iter().map(|&id| (id, -1.0)))
+                .collect(),
+            VectorExpr::Mean { nodes } => {
+                let weight = 1.0 / nodes.len().max(1) as f32;
+                nodes.iter().map(|&id| (id, weight)).collect()
+            }
+        };
+        if terms.is_empty() {
+            return Err(BarqError::InvalidOperation(
+                "Vector expression references no nodes".to_string(),
+            )
+            .into());
+        }
+
+        let mut result: Option<Vec<f32>> = None;
+        for (id, weight) in terms {
+            let embedding = self.get_embedding(id).ok_or(BarqError::NodeNotFound(id))?;
+            let acc = result.get_or_insert_with(|| vec![0.0; embedding.len()]);
+            if acc.len() != embedding.len() {
+                return Err(BarqError::InvalidOperation(format!(
+                    "Embedding of node {} has dimension {}, expected {}",
+                    id,
+                    embedding.len(),
+                    acc.len()
+                ))
+                .into());
+            }
+            for (a, x) in acc.iter_mut().zip(embedding) {
+                *a += weight * x;
+            }
+        }
+        Ok(result.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::classify;
+    use crate::storage::DbOptions;
+    use crate::Node;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_analogy_and_centroid() {
+        let dir = tempdir().unwrap();
+        let mut db = BarqGraphDb::open(DbOptions::new(dir.path().to_path_buf())).unwrap();
+        for (id, embedding) in [
+            (1, vec![1.0, 1.0]),
+            (2, vec![1.0, 0.0]),
+            (3, vec![0.0, 0.0]),
+            (4, vec![0.0, 1.0]),
+        ] {
+            let mut node = Node::new(id, format!("n{}", id));
+            node.embedding = embedding;
+            db.append_node(node).unwrap();
+        }
+        db.append_node(Node::new(5, "no vector".to_string()))
+            .unwrap();
+
+        // 1 - 2 + 3 lands on 4
+        let query = db.resolve_vector(&VectorExpr::analogy(1, 2, 3)).unwrap();
+        assert_eq!(query, vec![0.0, 1.0]);
+        assert_eq!(db.knn_search(&query, 1)[0].0, 4);
+
+        let centroid = db.resolve_vector(&VectorExpr::centroid([1, 3])).unwrap();
+        assert_eq!(centroid, vec![0.5, 0.5]);
+
+        let expr: VectorExpr = serde_json::from_str(r#"{"op":"sum","add":[5]}"#).unwrap();
+        let err = db.resolve_vector(&expr).unwrap_err();
+        assert_eq!(classify(&err).code, "node_not_found");
+        assert!(db.resolve_vector(&VectorExpr::centroid([])).is_err());
+    }
+}